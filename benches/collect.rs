@@ -0,0 +1,15 @@
+//! How long reading all processes of this machine takes: `cargo bench`.
+//! `brt bench` also counts the allocations.
+
+use brt_core::{model, source};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn collect(c: &mut Criterion) {
+    let source = source::system_source();
+    c.bench_function("get_processes", |b| {
+        b.iter(|| model::get_processes(source.as_ref()))
+    });
+}
+
+criterion_group!(benches, collect);
+criterion_main!(benches);
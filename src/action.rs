@@ -1,7 +1,13 @@
+use std::time::{Duration, SystemTime};
+
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
+use crate::components::process::{Order, SortKey};
+use crate::control::{CommandTemplate, IoPriority, Tracer};
+use crate::journal::{Entry, Unit};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Display, Deserialize)]
 pub enum Action {
     Tick,
     Render,
@@ -9,6 +15,8 @@ pub enum Action {
     Suspend,
     Resume,
     Quit,
+    /// Clear the terminal and draw everything again.
+    ClearScreen,
     Refresh,
     Error(String),
     Help,
@@ -16,7 +24,9 @@ pub enum Action {
     Increment(usize),
     Decrement(usize),
     CompleteInput(String),
+    /// Let the global keybindings act again.
     EnterNormal,
+    /// Send every key to the component being typed in.
     EnterInsert,
     EnterProcessing,
     ExitProcessing,
@@ -26,5 +36,125 @@ pub enum Action {
     PageDown,
     Left,
     Right,
-    Update,
+    /// Scroll the columns of the process table sideways, keeping the pid and
+    /// program in view.
+    ScrollLeft,
+    ScrollRight,
+    /// Sort by the visible column at this position, counting from one.
+    SortBy(usize),
+    /// Sort by this order, from the highest value when `true`.
+    OrderBy(Order, bool),
+    /// Rearrange the orders and their directions.
+    PickOrder,
+    /// Sort by the first key, and the ties in it by the next ones in turn.
+    SetSortKeys(Vec<SortKey>),
+    Update(UpdatePayload),
+    TogglePause,
+    SeekForward,
+    SeekBackward,
+    SpeedUp,
+    SpeedDown,
+    ToggleMark,
+    /// Show or hide the details of the selected process.
+    ToggleDetails,
+    /// Show or hide the journal of the systemd unit of the selected process.
+    ToggleUnitLog,
+    /// The latest entries of the journal of a unit, or why they couldn't be
+    /// read.
+    UnitLogRead(Unit, Result<Vec<Entry>, String>),
+    Terminate,
+    Kill,
+    Renice(i32),
+    /// Pick the io priority of the selected or marked processes.
+    PickIoPriority,
+    SetIoPriority(IoPriority),
+    /// Attach a tracer to the selected process.
+    Attach(Tracer),
+    /// Run this command line in the terminal, with the TUI suspended until it
+    /// exits.
+    RunInTerminal(Vec<String>),
+    /// Start this command line on its own, without waiting for it.
+    RunDetached(Vec<String>),
+    /// Run this command line for the selected process, after replacing
+    /// `{pid}`, `{comm}` and `{user}` in its arguments with its pid, program
+    /// and user.
+    RunCommand(CommandTemplate),
+    Confirm,
+    Cancel,
+    CopyPid,
+    CopyCommand,
+    /// Copy the pending action as a command line for a shell instead of
+    /// applying it.
+    CopyShellCommand,
+    /// Start the stopwatch with this name, or stop it when it runs.
+    ToggleStopwatch(String),
+    /// Print this line once brt exits.
+    PrintOnExit(String),
+    /// Search the programs, users and command lines of the processes.
+    Search,
+    /// Type the expression the processes are filtered with.
+    EditFilter,
+    /// List only the processes in this cgroup and below it, e.g.
+    /// `/system.slice`.
+    FilterCgroup(String),
+    /// Select the next or previous process matching the search.
+    NextMatch,
+    PreviousMatch,
+    /// Select the parent of the selected process.
+    JumpToParent,
+    /// Select the first listed child of the selected process.
+    JumpToChild,
+    Toast(String),
+    /// Set the title of the terminal window.
+    SetTitle(String),
+    /// Write the current view as plain text, see `text_dump` in the config.
+    DumpText,
+    ToggleLogs,
+    /// Show or hide the huge pages and slab caches in the memory pane.
+    ToggleMemoryDetails,
+    /// Show or hide the estimates of the time the battery lasts or charges.
+    ToggleBatteryDetails,
+    /// Switch to the next power profile, where brt is permitted to.
+    CyclePowerProfile,
+    /// Open the popup that turns the collectors on and off.
+    PickCollectors,
+    /// Show the tab at this position, counting from one.
+    SelectTab(usize),
+    NextTab,
+    PreviousTab,
+    /// The time the components took to handle a tick.
+    Sampled(std::time::Duration),
+}
+
+/// What a refresh of the process list collected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdatePayload {
+    /// When the processes were read.
+    pub timestamp: SystemTime,
+    /// How long reading them took.
+    pub duration: Duration,
+    pub processes: usize,
+    /// The number of processes that started and exited since the previous
+    /// refresh.
+    pub started: usize,
+    pub exited: usize,
+    /// The number of processes whose details brt isn't allowed to read.
+    pub restricted: usize,
+    pub top: TopProcesses,
+}
+
+/// The processes using the most memory and cpu.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TopProcesses {
+    pub by_memory: Vec<TopProcess>,
+    pub by_cpu: Vec<TopProcess>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopProcess {
+    pub pid: i32,
+    pub program: String,
+    pub resident_memory: u64,
+    /// The cpu usage in percent.
+    pub cpu: f64,
 }
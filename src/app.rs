@@ -1,166 +1,832 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
 use color_eyre::eyre::Result;
 use crossterm::event::KeyEvent;
-use ratatui::prelude::Rect;
+use ratatui::prelude::{Constraint, Direction, Layout, Rect};
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
+use strum::{Display, EnumIter, IntoEnumIterator};
+use tokio::sync::{mpsc, watch};
 
 use crate::{
     action::Action,
-    components::{fps::FpsCounter, process::Process, Component},
+    collector::Collectors,
+    components::{
+        cgroups::Cgroups, collectors::CollectorMetrics, cores::Cores, cpufreq::CpuFrequency,
+        fps::FpsCounter, header::Header, interfaces::Interfaces, kernel_log::KernelLog, logs::Logs,
+        memory::Memory, numa::Numa, process::Process, sensors::Sensors, shm::SharedMemory,
+        stopwatch::Stopwatch, tabs::TabBar, toast::Toast, top::Top, Component,
+    },
     config::Config,
-    tui,
+    control,
+    hints::{self, Hint},
+    i18n,
+    proc_events::ProcEvents,
+    recording::{ActionRecorder, ActionReplay, LoggedEvent, Recorder, Replay},
+    rpc::RpcServer,
+    session::Session,
+    source::system_source,
+    staleness::{self, Sample},
+    symbols, text_dump,
+    tui::{self, Frame},
 };
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Mode {
     #[default]
     Process,
+    /// Text is typed, e.g. a search, so no key is bound.
+    Insert,
+}
+
+/// The part of the screen a component is drawn in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum Pane {
+    Header,
+    Main,
+    /// A column to the right of the main pane.
+    Side,
+    Footer,
+}
+
+/// The full screen views, switched between with the tab bar.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Display, EnumIter, Serialize, Deserialize)]
+pub enum Tab {
+    #[default]
+    Processes,
+    Performance,
+    Sensors,
+    Network,
+    Cgroups,
+}
+
+impl Tab {
+    /// The position of the tab, counting from one.
+    pub fn number(&self) -> usize {
+        *self as usize + 1
+    }
+
+    pub fn from_number(number: usize) -> Option<Self> {
+        Self::iter().nth(number.checked_sub(1)?)
+    }
+
+    /// The tab `steps` further, wrapping around at either end.
+    fn step(&self, steps: isize) -> Self {
+        let count = Self::iter().count() as isize;
+        let index = (*self as isize + steps).rem_euclid(count);
+        Self::iter().nth(index as usize).unwrap_or_default()
+    }
+}
+
+const SIDE_WIDTH: u16 = 32;
+/// The height of the side pane when it is stacked below the main pane.
+const SIDE_HEIGHT: u16 = 8;
+
+/// Splits `area` into the header, main, side and footer panes. The header
+/// and footer get a row per component. The side pane is put to the right of
+/// the main pane, or below it when `area` is narrower than `stack_width`.
+fn panes(
+    area: Rect,
+    has_side: bool,
+    header_rows: u16,
+    footer_rows: u16,
+    stack_width: u16,
+) -> [Rect; 4] {
+    let stacked = area.width < stack_width;
+    let side_height = if has_side && stacked { SIDE_HEIGHT } else { 0 };
+    let rows = Layout::new(
+        Direction::Vertical,
+        [
+            Constraint::Length(header_rows),
+            Constraint::Fill(1),
+            Constraint::Length(side_height),
+            Constraint::Length(footer_rows),
+        ],
+    )
+    .split(area);
+    let side_width = if has_side && !stacked { SIDE_WIDTH } else { 0 };
+    let middle = Layout::new(
+        Direction::Horizontal,
+        [Constraint::Fill(1), Constraint::Length(side_width)],
+    )
+    .split(rows[1]);
+    let side = if stacked { rows[2] } else { middle[1] };
+    [rows[0], middle[0], side, rows[3]]
+}
+
+/// Shares the side pane between `count` components: one above the other, or
+/// side by side when the pane is stacked below the main pane.
+fn side_slots(side: Rect, count: usize, stacked: bool) -> Vec<Rect> {
+    let direction = if stacked {
+        Direction::Horizontal
+    } else {
+        Direction::Vertical
+    };
+    Layout::new(direction, vec![Constraint::Fill(1); count])
+        .split(side)
+        .to_vec()
+}
+
+/// A row of `pane` for each of `count` components.
+fn rows(pane: Rect, count: usize) -> Vec<Rect> {
+    Layout::new(Direction::Vertical, vec![Constraint::Length(1); count])
+        .split(pane)
+        .to_vec()
 }
 
 pub struct App {
     pub config: Config,
     pub tick_rate: f64,
     pub frame_rate: f64,
-    pub components: Vec<Box<dyn Component>>,
+    /// The components, the pane they are drawn in and the tab they are
+    /// shown on, or `None` for all tabs.
+    pub components: Vec<(Pane, Option<Tab>, Box<dyn Component>)>,
+    pub tab: Tab,
+    /// Whether the whole screen should be drawn on the next frame, e.g.
+    /// after a key press or another tab was selected.
+    pub dirty: bool,
     pub should_quit: bool,
     pub should_suspend: bool,
     pub mode: Mode,
     pub last_tick_key_events: Vec<KeyEvent>,
+    /// The control socket, when brt was asked to listen on one.
+    pub rpc: Option<RpcServer>,
+    /// The title of the terminal window, set again on a new terminal.
+    pub title: Option<String>,
+    /// What is printed once the terminal is restored.
+    pub print_on_exit: Vec<String>,
+    /// The data sources sampled on their own thread besides the components;
+    /// more can be registered before the app runs.
+    pub collectors: Collectors,
+    /// Where the keys and actions are logged, for a bug report.
+    pub action_recorder: Option<ActionRecorder>,
+    /// The logged keys being typed again.
+    pub action_replay: Option<ActionReplay>,
 }
 
 impl App {
-    pub fn new(tick_rate: f64, frame_rate: f64, debug: bool) -> Result<Self> {
+    /// Without a `tick_rate` the app ticks as often as its most frequent
+    /// collector needs.
+    pub fn new(
+        tick_rate: Option<f64>,
+        frame_rate: f64,
+        debug: bool,
+        record: Option<PathBuf>,
+        replay: Option<PathBuf>,
+        ascii: bool,
+        socket: Option<PathBuf>,
+    ) -> Result<Self> {
+        let config = Config::new()?;
+        i18n::init(config.config.locale.as_deref());
+        symbols::init(ascii || config.config.ascii);
+        let tick_rate = tick_rate.unwrap_or_else(|| config.config.intervals.tick_rate());
         let mut process = Process::new();
+        if let Some(path) = record {
+            process = process.with_recorder(Recorder::new(&path)?);
+        }
+        match replay {
+            Some(path) => process = process.with_replay(Replay::from_file(&path)?),
+            None => match ProcEvents::listen() {
+                Ok(events) => process = process.with_events(events),
+                Err(e) => tracing::debug!("Not listening to process events: {e}"),
+            },
+        }
+        let mut rpc = None;
+        if let Some(path) = socket {
+            let (publisher, processes) = watch::channel(Arc::default());
+            process = process.with_publisher(publisher);
+            rpc = Some(RpcServer::new(path, processes));
+        }
         process.refresh();
 
-        let components: Vec<Box<dyn Component>> = if debug {
-            let fps = FpsCounter::new();
-            vec![Box::new(process), Box::new(fps)]
-        } else {
-            vec![Box::new(process)]
-        };
-        let config = Config::new()?;
+        let mut collectors = Collectors::builtin(system_source());
+        for name in &config.config.disabled_collectors {
+            if !collectors.set_enabled(name, false) {
+                tracing::warn!("There is no collector called {name}");
+            }
+        }
+
+        let processes = Some(Tab::Processes);
+        let mut components: Vec<(Pane, Option<Tab>, Box<dyn Component>)> = vec![
+            (Pane::Header, None, Box::new(Header::new())),
+            (Pane::Header, None, Box::new(TabBar::new())),
+            (Pane::Main, processes, Box::new(process)),
+            (Pane::Main, Some(Tab::Performance), Box::new(Cores::new())),
+            (Pane::Side, Some(Tab::Performance), Box::new(Memory::new())),
+            (
+                Pane::Side,
+                Some(Tab::Performance),
+                Box::new(SharedMemory::new()),
+            ),
+            (
+                Pane::Side,
+                Some(Tab::Performance),
+                Box::new(CollectorMetrics::new(collectors.subscribe())),
+            ),
+            (Pane::Main, Some(Tab::Sensors), Box::new(Sensors::new())),
+            (Pane::Main, Some(Tab::Network), Box::new(Interfaces::new())),
+            (Pane::Main, Some(Tab::Cgroups), Box::new(Cgroups::new())),
+            (Pane::Main, None, Box::new(Logs::new())),
+            (Pane::Main, None, Box::new(Toast::new())),
+        ];
+        let frequency = CpuFrequency::new();
+        if frequency.is_available() {
+            components.push((Pane::Side, processes, Box::new(frequency)));
+        }
+        let numa = Numa::new();
+        if numa.is_available() {
+            components.push((Pane::Side, processes, Box::new(numa)));
+        }
+        if let Some(pane) = config.config.top_processes {
+            components.push((pane, None, Box::new(Top::new())));
+        }
+        if let Some(pane) = config.config.kernel_log {
+            components.push((pane, None, Box::new(KernelLog::new())));
+        }
+        if let Some(pane) = config.config.stopwatch {
+            components.push((pane, None, Box::new(Stopwatch::new())));
+        }
+        if debug {
+            components.push((Pane::Footer, None, Box::new(FpsCounter::new())));
+        }
         let mode = Mode::Process;
         Ok(Self {
             tick_rate,
             frame_rate,
             components,
+            tab: Tab::default(),
+            dirty: true,
             should_quit: false,
             should_suspend: false,
             config,
             mode,
             last_tick_key_events: Vec::new(),
+            rpc,
+            title: None,
+            print_on_exit: vec![],
+            collectors,
+            action_recorder: None,
+            action_replay: None,
         })
     }
 
+    pub fn with_action_recorder(mut self, recorder: ActionRecorder) -> Self {
+        self.action_recorder = Some(recorder);
+        self
+    }
+
+    pub fn with_action_replay(mut self, replay: ActionReplay) -> Self {
+        self.action_replay = Some(replay);
+        self
+    }
+
+    /// Adds `event` to the action log, if there is one; it is given up on
+    /// after the first error.
+    fn log(&mut self, event: LoggedEvent) {
+        let Some(recorder) = self.action_recorder.as_mut() else {
+            return;
+        };
+        if let Err(e) = recorder.record(event) {
+            tracing::warn!("Unable to log the action: {e}");
+            self.action_recorder = None;
+        }
+    }
+
+    /// A new terminal, after the previous one was given to the shell or
+    /// another program.
+    fn restart_tui(&mut self) -> Result<tui::Tui> {
+        let mut tui = tui::Tui::new()?;
+        tui.tick_rate(self.tick_rate);
+        tui.frame_rate(self.frame_rate);
+        tui.unfocused_frame_rate(self.config.config.unfocused_frame_rate);
+        tui.enter()?;
+        if let Some(title) = &self.title {
+            tui.set_title(title)?;
+        }
+        self.dirty = true;
+        Ok(tui)
+    }
+
     pub async fn run(&mut self) -> Result<()> {
         let (action_tx, mut action_rx) = mpsc::unbounded_channel();
 
         let mut tui = tui::Tui::new()?;
         tui.tick_rate(self.tick_rate);
         tui.frame_rate(self.frame_rate);
+        tui.unfocused_frame_rate(self.config.config.unfocused_frame_rate);
         tui.enter()?;
 
-        for component in self.components.iter_mut() {
+        for (_, _, component) in self.components.iter_mut() {
             component.register_action_handler(action_tx.clone())?;
         }
 
-        for component in self.components.iter_mut() {
+        for (_, _, component) in self.components.iter_mut() {
             component.register_config_handler(self.config.clone())?;
         }
 
-        for component in self.components.iter_mut() {
+        for (_, _, component) in self.components.iter_mut() {
             component.init()?;
         }
 
+        if let Some(rpc) = self.rpc.as_mut() {
+            rpc.listen(action_tx.clone())?;
+        }
+
+        let session_path = Session::path();
+        self.restore_session(&Session::load(&session_path));
+        self.collectors.start();
+
         loop {
             if let Some(e) = tui.next().await {
-                match e {
-                    tui::Event::Quit => action_tx.send(Action::Quit)?,
-                    tui::Event::Tick => action_tx.send(Action::Tick)?,
-                    tui::Event::Render => action_tx.send(Action::Render)?,
-                    tui::Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
-                    tui::Event::Key(key) => {
-                        if let Some(keymap) = self.config.keybindings.get(&self.mode) {
-                            if let Some(action) = keymap.get(&vec![key]) {
-                                log::info!("Got action: {action:?}");
-                                action_tx.send(action.clone())?;
-                            } else {
-                                // If the key was not handled as a single key action,
-                                // then consider it for multi-key combinations.
-                                self.last_tick_key_events.push(key);
-
-                                // Check for multi-key combinations
-                                if let Some(action) = keymap.get(&self.last_tick_key_events) {
-                                    log::info!("Got action: {action:?}");
-                                    action_tx.send(action.clone())?;
-                                }
-                            }
-                        };
-                    }
-                    _ => {}
+                let replayed = match (&e, self.action_replay.as_mut()) {
+                    (tui::Event::Tick, Some(replay)) => replay.due(Instant::now()),
+                    _ => vec![],
+                };
+                self.handle_event(e, &action_tx)?;
+                for key in replayed {
+                    self.handle_event(tui::Event::Key(key), &action_tx)?;
                 }
-                for component in self.components.iter_mut() {
-                    if let Some(action) = component.handle_events(Some(e.clone()))? {
-                        action_tx.send(action)?;
-                    }
+                if self
+                    .action_replay
+                    .as_ref()
+                    .is_some_and(ActionReplay::is_done)
+                {
+                    self.action_replay = None;
+                    action_tx.send(Action::Toast("Replayed the action log".to_string()))?;
                 }
             }
 
             while let Ok(action) = action_rx.try_recv() {
+                self.log(LoggedEvent::Action(action.clone()));
                 match action {
                     Action::Tick => {
                         self.last_tick_key_events.drain(..);
+                        let collected = self.collectors.receive();
+                        // the ages on the staleness line change with every tick
+                        self.dirty |= collected && self.collectors.is_picking()
+                            || self.config.config.staleness;
+                    }
+                    Action::PickCollectors => {
+                        self.collectors.toggle_picker();
+                        self.dirty = true;
                     }
                     Action::Quit => self.should_quit = true,
                     Action::Suspend => self.should_suspend = true,
                     Action::Resume => self.should_suspend = false,
+                    Action::EnterInsert => self.mode = Mode::Insert,
+                    Action::EnterNormal => self.mode = Mode::Process,
+                    Action::RunInTerminal(ref command) => {
+                        tui.exit()?;
+                        let message = match control::run_in_terminal(command) {
+                            Ok(status) if status.success() => None,
+                            Ok(status) => Some(format!("{} exited with {status}", command[0])),
+                            Err(e) => Some(format!("Unable to run {}: {e}", command[0])),
+                        };
+                        tui = self.restart_tui()?;
+                        if let Some(message) = message {
+                            action_tx.send(Action::Toast(message))?;
+                        }
+                    }
+                    Action::RunDetached(ref command) => {
+                        if let Err(e) = control::spawn_detached(command) {
+                            action_tx.send(Action::Toast(format!(
+                                "Unable to run {}: {e}",
+                                command[0]
+                            )))?;
+                        }
+                    }
+                    Action::DumpText => {
+                        let message = match text_dump::dump(
+                            &self.describe(),
+                            &self.config.config.text_dump,
+                        ) {
+                            Ok(message) => message,
+                            Err(e) => {
+                                tracing::warn!("Unable to dump the view: {e}");
+                                format!("Unable to dump the view: {e}")
+                            }
+                        };
+                        action_tx.send(Action::Toast(message))?;
+                    }
+                    Action::PrintOnExit(ref line) => {
+                        self.print_on_exit.push(line.clone());
+                        action_tx.send(Action::Toast(format!("`{line}` is printed on exit")))?;
+                    }
+                    Action::SetTitle(ref title) => {
+                        tui.set_title(title)?;
+                        self.title = Some(title.clone());
+                    }
                     Action::Resize(w, h) => {
                         tui.resize(Rect::new(0, 0, w, h))?;
-                        tui.draw(|f| {
-                            for component in self.components.iter_mut() {
-                                let r = component.draw(f, f.size());
-                                if let Err(e) = r {
-                                    action_tx
-                                        .send(Action::Error(format!("Failed to draw: {:?}", e)))
-                                        .unwrap();
-                                }
-                            }
-                        })?;
+                        tui.draw(|f| self.draw(f, &action_tx))?;
                     }
-                    Action::Render => {
-                        tui.draw(|f| {
-                            for component in self.components.iter_mut() {
-                                let r = component.draw(f, f.size());
-                                if let Err(e) = r {
-                                    action_tx
-                                        .send(Action::Error(format!("Failed to draw: {:?}", e)))
-                                        .unwrap();
-                                }
-                            }
-                        })?;
+                    Action::Render if self.take_dirty() => {
+                        tui.draw(|f| self.draw(f, &action_tx))?;
+                    }
+                    Action::ClearScreen => {
+                        tui.clear()?;
+                        tui.draw(|f| self.draw(f, &action_tx))?;
+                    }
+                    Action::SelectTab(number) => {
+                        if let Some(tab) = Tab::from_number(number) {
+                            self.tab = tab;
+                            self.tell_visibility();
+                            self.dirty = true;
+                        }
+                    }
+                    Action::NextTab => {
+                        action_tx.send(Action::SelectTab(self.tab.step(1).number()))?
+                    }
+                    Action::PreviousTab => {
+                        action_tx.send(Action::SelectTab(self.tab.step(-1).number()))?
+                    }
+                    // the processes of the cgroup are listed on their tab
+                    Action::FilterCgroup(_) => {
+                        action_tx.send(Action::SelectTab(Tab::Processes.number()))?
                     }
                     _ => {}
                 }
-                for component in self.components.iter_mut() {
+                let started = Instant::now();
+                for (_, _, component) in self.components.iter_mut() {
                     if let Some(action) = component.update(action.clone())? {
                         action_tx.send(action)?
                     };
                 }
+                if action == Action::Tick {
+                    action_tx.send(Action::Sampled(started.elapsed()))?;
+                }
             }
             if self.should_suspend {
                 tui.suspend()?;
                 action_tx.send(Action::Resume)?;
-                tui = tui::Tui::new()?;
-                tui.tick_rate(self.tick_rate);
-                tui.frame_rate(self.frame_rate);
-                tui.enter()?;
+                tui = self.restart_tui()?;
             } else if self.should_quit {
                 tui.stop()?;
                 break;
             }
         }
         tui.exit()?;
+        if let Some(rpc) = self.rpc.as_mut() {
+            rpc.close();
+        }
+        for line in &self.print_on_exit {
+            println!("{line}");
+        }
+
+        if let Err(e) = self.session().save(&session_path) {
+            tracing::warn!("Unable to save the session: {e}");
+        }
         Ok(())
     }
+
+    /// Turns an event of the terminal into actions, through the keymap and
+    /// the components on the current tab.
+    fn handle_event(
+        &mut self,
+        e: tui::Event,
+        action_tx: &mpsc::UnboundedSender<Action>,
+    ) -> Result<()> {
+        // the keys of the collectors popup go to it alone, the one that
+        // closes it included
+        let picking = self.collectors.is_picking();
+        match e {
+            tui::Event::Quit => action_tx.send(Action::Quit)?,
+            tui::Event::Tick => action_tx.send(Action::Tick)?,
+            tui::Event::Render => action_tx.send(Action::Render)?,
+            tui::Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
+            tui::Event::Key(key) if picking => {
+                self.dirty = true;
+                self.log(LoggedEvent::Key(key));
+                self.collectors.handle_key(key);
+            }
+            tui::Event::Key(key) => {
+                self.dirty = true;
+                self.log(LoggedEvent::Key(key));
+                if let Some(keymap) = self.config.keybindings.get(&self.mode) {
+                    if let Some(action) = keymap.get(&vec![key]) {
+                        tracing::info!("Got action: {action:?}");
+                        action_tx.send(action.clone())?;
+                    } else {
+                        // If the key was not handled as a single key action,
+                        // then consider it for multi-key combinations.
+                        self.last_tick_key_events.push(key);
+
+                        // Check for multi-key combinations
+                        if let Some(action) = keymap.get(&self.last_tick_key_events) {
+                            tracing::info!("Got action: {action:?}");
+                            action_tx.send(action.clone())?;
+                        }
+                    }
+                };
+            }
+            tui::Event::Mouse(_) | tui::Event::Paste(_) | tui::Event::FocusGained => {
+                self.dirty = true
+            }
+            _ => {}
+        }
+        let tab = self.tab;
+        for (_, _, component) in self.visible_components(tab) {
+            if picking && matches!(e, tui::Event::Key(_)) {
+                break;
+            }
+            if let Some(action) = component.handle_events(Some(e.clone()))? {
+                action_tx.send(action)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether anything changed since the last frame, clearing the flags of
+    /// the app and of all visible components.
+    fn take_dirty(&mut self) -> bool {
+        let tab = self.tab;
+        let dirty = std::mem::take(&mut self.dirty);
+        self.visible_components(tab)
+            .fold(dirty, |dirty, (_, _, component)| {
+                component.take_dirty() | dirty
+            })
+    }
+
+    /// Hands the session to the components and goes back to the tab it was
+    /// left on.
+    fn restore_session(&mut self, session: &Session) {
+        for (_, _, component) in self.components.iter_mut() {
+            component.restore_session(session);
+        }
+        self.tab = session.tab;
+        self.tell_visibility();
+        self.dirty = true;
+    }
+
+    /// What the components and the current tab leave for the next launch.
+    fn session(&self) -> Session {
+        let mut session = Session {
+            tab: self.tab,
+            ..Session::default()
+        };
+        for (_, _, component) in self.components.iter() {
+            component.save_session(&mut session);
+        }
+        session
+    }
+
+    /// Tells every component whether it is shown on the current tab.
+    fn tell_visibility(&mut self) {
+        let tab = self.tab;
+        for (_, shown_on, component) in self.components.iter_mut() {
+            component.set_visible(shown_on.map_or(true, |shown_on| shown_on == tab));
+        }
+    }
+
+    /// The components shown on `tab`.
+    fn visible_components(
+        &mut self,
+        tab: Tab,
+    ) -> impl Iterator<Item = &mut (Pane, Option<Tab>, Box<dyn Component>)> {
+        self.components
+            .iter_mut()
+            .filter(move |(_, shown_on, _)| shown_on.map_or(true, |shown_on| shown_on == tab))
+    }
+
+    pub fn draw(&mut self, f: &mut Frame<'_>, action_tx: &mpsc::UnboundedSender<Action>) {
+        let tab = self.tab;
+        let count = |wanted: Pane| {
+            self.components
+                .iter()
+                .filter(|(pane, shown_on, _)| {
+                    *pane == wanted && shown_on.map_or(true, |shown_on| shown_on == tab)
+                })
+                .count()
+        };
+        let (headers, sides, mut footers) =
+            (count(Pane::Header), count(Pane::Side), count(Pane::Footer));
+        let show_staleness = self.config.config.staleness;
+        if show_staleness {
+            footers += 1;
+        }
+        let show_hints = self.config.config.hints;
+        if show_hints {
+            footers += 1;
+        }
+        let stack_width = self.config.config.stack_width;
+        let (stacked, [header, main, side, footer]) = match &self.config.config.layout {
+            Some(layout) => {
+                let panes = layout.panes(f.size(), headers as u16, footers as u16);
+                // side by side when the side pane looks wider than high,
+                // with cells about twice as high as wide
+                (panes[2].width > panes[2].height.saturating_mul(2), panes)
+            }
+            None => (
+                f.size().width < stack_width,
+                panes(
+                    f.size(),
+                    sides > 0,
+                    headers as u16,
+                    footers as u16,
+                    stack_width,
+                ),
+            ),
+        };
+        let mut headers = rows(header, headers).into_iter();
+        let mut sides = side_slots(side, sides, stacked).into_iter();
+        let mut footers = rows(footer, footers).into_iter();
+        for (pane, _, component) in self.visible_components(tab) {
+            let rect = match pane {
+                Pane::Header => headers.next().unwrap_or_default(),
+                Pane::Main => main,
+                Pane::Side => sides.next().unwrap_or_default(),
+                Pane::Footer => footers.next().unwrap_or_default(),
+            };
+            // a pane the layout leaves out has no room to draw in
+            if rect.area() == 0 {
+                continue;
+            }
+            let r = component.draw(f, rect);
+            if let Err(e) = r {
+                action_tx
+                    .send(Action::Error(format!("Failed to draw: {:?}", e)))
+                    .unwrap();
+            }
+        }
+        if let Some(rect) = show_staleness.then(|| footers.next()).flatten() {
+            f.render_widget(staleness::line(&self.samples(), Instant::now()), rect);
+        }
+        if let Some(rect) = footers.next().filter(|_| show_hints) {
+            f.render_widget(hints::line(&self.hints(), rect.width), rect);
+        }
+        if let Some(popup) = self.collectors.popup() {
+            f.render_widget(popup, main);
+        }
+    }
+
+    /// The components on the current tab as plain text, a sentence per line.
+    fn describe(&mut self) -> String {
+        let tab = self.tab;
+        let mut text = format!("Tab {tab}.\n");
+        for (_, _, component) in self.visible_components(tab) {
+            for line in component.describe() {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+        for line in self.collectors.describe() {
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text
+    }
+
+    /// When the components on the current tab and the collectors last
+    /// sampled.
+    fn samples(&mut self) -> Vec<Sample> {
+        let tab = self.tab;
+        let mut samples: Vec<Sample> = self
+            .visible_components(tab)
+            .filter_map(|(_, _, component)| component.sampled())
+            .collect();
+        samples.extend(self.collectors.sampled());
+        samples
+    }
+
+    /// The keys of the components on the current tab, then those bound
+    /// globally in the current mode.
+    fn hints(&mut self) -> Vec<Hint> {
+        if self.collectors.is_picking() {
+            return self.collectors.hints();
+        }
+        let tab = self.tab;
+        let mut hints: Vec<Hint> = self
+            .visible_components(tab)
+            .flat_map(|(_, _, component)| component.hints())
+            .collect();
+        if let Some(keymap) = self.config.keybindings.get(&self.mode) {
+            hints.extend(hints::binding_hints(keymap));
+        }
+        hints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panes() {
+        let wide = Rect::new(0, 0, 160, 40);
+        assert_eq!(
+            panes(wide, true, 1, 0, 120),
+            [
+                Rect::new(0, 0, 160, 1),
+                Rect::new(0, 1, 128, 39),
+                Rect::new(128, 1, 32, 39),
+                Rect::new(0, 40, 160, 0),
+            ]
+        );
+
+        let narrow = Rect::new(0, 0, 80, 24);
+        let [header, main, side, footer] = panes(narrow, true, 1, 1, 120);
+        assert_eq!(header, Rect::new(0, 0, 80, 1));
+        assert_eq!(main, Rect::new(0, 1, 80, 14));
+        assert_eq!(side, Rect::new(0, 15, 80, 8));
+        assert_eq!(footer, Rect::new(0, 23, 80, 1));
+
+        let [_, main, side, _] = panes(narrow, false, 1, 0, 120);
+        assert_eq!(main, Rect::new(0, 1, 80, 23));
+        assert_eq!(side.area(), 0);
+
+        let [header, main, _, footer] = panes(narrow, false, 2, 2, 120);
+        assert_eq!(header, Rect::new(0, 0, 80, 2));
+        assert_eq!(main, Rect::new(0, 2, 80, 20));
+        assert_eq!(
+            rows(footer, 2),
+            vec![Rect::new(0, 22, 80, 1), Rect::new(0, 23, 80, 1)]
+        );
+    }
+
+    #[test]
+    fn test_tabs() {
+        assert_eq!(Tab::from_number(1), Some(Tab::Processes));
+        assert_eq!(Tab::from_number(4), Some(Tab::Network));
+        assert_eq!(Tab::from_number(0), None);
+        assert_eq!(Tab::from_number(5), Some(Tab::Cgroups));
+        assert_eq!(Tab::from_number(6), None);
+        assert_eq!(Tab::Sensors.number(), 3);
+        assert_eq!(Tab::Cgroups.step(1), Tab::Processes);
+        assert_eq!(Tab::Processes.step(-1), Tab::Cgroups);
+        assert_eq!(Tab::Performance.step(1), Tab::Sensors);
+    }
+
+    #[test]
+    fn test_take_dirty() {
+        let mut app = App {
+            config: Config::default(),
+            tick_rate: 4.0,
+            frame_rate: 60.0,
+            components: vec![
+                (Pane::Header, None, Box::new(TabBar::new())),
+                (Pane::Main, Some(Tab::Network), Box::new(Top::new())),
+            ],
+            tab: Tab::Processes,
+            dirty: true,
+            should_quit: false,
+            should_suspend: false,
+            mode: Mode::Process,
+            last_tick_key_events: vec![],
+            rpc: None,
+            title: None,
+            print_on_exit: vec![],
+            collectors: Collectors::new(),
+            action_recorder: None,
+            action_replay: None,
+        };
+        assert!(app.take_dirty());
+        assert!(!app.take_dirty());
+
+        // the top processes aren't on this tab
+        let update = Action::Update(crate::action::UpdatePayload {
+            timestamp: std::time::SystemTime::UNIX_EPOCH,
+            duration: std::time::Duration::ZERO,
+            processes: 0,
+            started: 0,
+            exited: 0,
+            restricted: 0,
+            top: Default::default(),
+        });
+        for action in [Action::Tick, Action::Render, update] {
+            for (_, _, component) in app.components.iter_mut() {
+                component.update(action.clone()).unwrap();
+            }
+        }
+        assert!(!app.take_dirty());
+        app.tab = Tab::Network;
+        assert!(app.take_dirty());
+
+        app.components[0].2.update(Action::SelectTab(2)).unwrap();
+        assert!(app.take_dirty());
+        assert!(!app.take_dirty());
+
+        app.restore_session(&Session {
+            tab: Tab::Cgroups,
+            ..Session::default()
+        });
+        assert_eq!(app.tab, Tab::Cgroups);
+        assert!(app.take_dirty());
+        assert_eq!(app.session().tab, Tab::Cgroups);
+    }
+
+    #[test]
+    fn test_side_slots() {
+        let side = Rect::new(128, 1, 32, 39);
+        assert_eq!(
+            side_slots(side, 2, false),
+            vec![Rect::new(128, 1, 32, 20), Rect::new(128, 21, 32, 19)]
+        );
+        let side = Rect::new(0, 15, 80, 8);
+        assert_eq!(
+            side_slots(side, 2, true),
+            vec![Rect::new(0, 15, 40, 8), Rect::new(40, 15, 40, 8)]
+        );
+        assert_eq!(side_slots(side, 1, true), vec![side]);
+    }
 }
@@ -0,0 +1,148 @@
+//! `brt bench`: how long reading all processes takes and how much it
+//! allocates, so regressions in the sampling path show up in numbers. The
+//! allocations are only counted when brt is built with the `bench` feature.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::model::get_processes;
+use crate::source::ProcSource;
+
+/// Whether [`CountingAllocator`] is the global allocator.
+const COUNTING: bool = cfg!(any(test, feature = "bench"));
+
+thread_local! {
+    /// The number of allocations of this thread, so those of the other
+    /// threads, such as tokio's, don't add to the benchmark's.
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_allocation() {
+    // fails only while the thread exits
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// The system allocator, counting the allocations and reallocations.
+pub struct CountingAllocator;
+
+// SAFETY: passes everything on to the system allocator.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// The latency and allocations of a number of runs of the process collector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub runs: usize,
+    /// The number of processes read by the last run.
+    pub processes: usize,
+    pub latency: (Duration, Duration),
+    /// `None` when brt is built without the `bench` feature.
+    pub allocations: Option<(usize, usize)>,
+}
+
+/// Reads all processes `runs` times.
+pub fn run(source: &dyn ProcSource, runs: usize) -> Report {
+    let mut latencies = Vec::with_capacity(runs);
+    let mut allocations = Vec::with_capacity(runs);
+    let mut processes = 0;
+    for _ in 0..runs.max(1) {
+        let allocated = self::allocations();
+        let started = Instant::now();
+        processes = get_processes(source).len();
+        latencies.push(started.elapsed());
+        allocations.push(self::allocations() - allocated);
+    }
+    latencies.sort();
+    allocations.sort();
+    Report {
+        runs: latencies.len(),
+        processes,
+        latency: (percentile(&latencies, 50), percentile(&latencies, 95)),
+        allocations: COUNTING.then(|| (percentile(&allocations, 50), percentile(&allocations, 95))),
+    }
+}
+
+/// The nearest rank percentile of sorted, non-empty samples.
+fn percentile<T: Copy>(sorted: &[T], percent: usize) -> T {
+    let rank = (sorted.len() * percent).div_ceil(100);
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1_000.0;
+        writeln!(f, "{} runs over {} processes", self.runs, self.processes)?;
+        writeln!(
+            f,
+            "latency      p50 {:>8.2}ms  p95 {:>8.2}ms",
+            ms(self.latency.0),
+            ms(self.latency.1)
+        )?;
+        match self.allocations {
+            Some((p50, p95)) => write!(f, "allocations  p50 {p50:>10}  p95 {p95:>10}"),
+            None => write!(
+                f,
+                "allocations  not counted, build brt with the bench feature"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::{FakeProcSource, FakeProcess};
+
+    #[test]
+    fn test_percentile() {
+        let samples: Vec<usize> = (1..=20).collect();
+        assert_eq!(percentile(&samples, 50), 10);
+        assert_eq!(percentile(&samples, 95), 19);
+        assert_eq!(percentile(&[7], 95), 7);
+        assert_eq!(percentile(&[3, 4], 0), 3);
+    }
+
+    #[test]
+    fn test_run() {
+        let source = FakeProcSource::default()
+            .with(1, FakeProcess::running(1, "init", 0, 0))
+            .with(2, FakeProcess::zombie(2, "defunct"));
+        let report = run(&source, 10);
+        assert_eq!((report.runs, report.processes), (10, 2));
+        assert!(report.latency.0 <= report.latency.1);
+        assert!(report.allocations.unwrap().0 > 0);
+        assert!(report.to_string().starts_with("10 runs over 2 processes\n"));
+
+        let report = Report {
+            allocations: None,
+            ..report
+        };
+        assert!(report
+            .to_string()
+            .ends_with("not counted, build brt with the bench feature"));
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+/// Tells a collector when it is time to sample again, so each one can run at
+/// its own pace on the shared tick.
+#[derive(Debug, Clone)]
+pub struct Cadence {
+    period: Duration,
+    last: Option<Instant>,
+}
+
+impl Cadence {
+    pub fn new(period: Duration) -> Self {
+        Self { period, last: None }
+    }
+
+    pub fn from_millis(millis: u64) -> Self {
+        Self::new(Duration::from_millis(millis))
+    }
+
+    /// Whether a sample is due; the first one always is. A due sample is
+    /// assumed to be taken.
+    pub fn due(&mut self) -> bool {
+        self.due_at(Instant::now())
+    }
+
+    fn due_at(&mut self, now: Instant) -> bool {
+        let due = self
+            .last
+            .map_or(true, |last| now.duration_since(last) >= self.period);
+        if due {
+            self.last = Some(now);
+        }
+        due
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// When the last sample was due, if ever.
+    pub fn last(&self) -> Option<Instant> {
+        self.last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cadence() {
+        let mut cadence = Cadence::from_millis(500);
+        let start = Instant::now();
+        assert!(cadence.due_at(start));
+        assert!(!cadence.due_at(start + Duration::from_millis(100)));
+        assert!(cadence.due_at(start + Duration::from_millis(500)));
+        assert!(!cadence.due_at(start + Duration::from_millis(900)));
+        assert!(cadence.due_at(start + Duration::from_millis(1000)));
+    }
+
+    #[test]
+    fn test_zero_cadence() {
+        let mut cadence = Cadence::new(Duration::ZERO);
+        assert!(cadence.due());
+        assert!(cadence.due());
+    }
+}
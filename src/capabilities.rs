@@ -0,0 +1,95 @@
+use crate::symbols::symbols;
+
+/// The names of the capabilities, by bit number, from `linux/capability.h`.
+const NAMES: [&str; 41] = [
+    "CAP_CHOWN",
+    "CAP_DAC_OVERRIDE",
+    "CAP_DAC_READ_SEARCH",
+    "CAP_FOWNER",
+    "CAP_FSETID",
+    "CAP_KILL",
+    "CAP_SETGID",
+    "CAP_SETUID",
+    "CAP_SETPCAP",
+    "CAP_LINUX_IMMUTABLE",
+    "CAP_NET_BIND_SERVICE",
+    "CAP_NET_BROADCAST",
+    "CAP_NET_ADMIN",
+    "CAP_NET_RAW",
+    "CAP_IPC_LOCK",
+    "CAP_IPC_OWNER",
+    "CAP_SYS_MODULE",
+    "CAP_SYS_RAWIO",
+    "CAP_SYS_CHROOT",
+    "CAP_SYS_PTRACE",
+    "CAP_SYS_PACCT",
+    "CAP_SYS_ADMIN",
+    "CAP_SYS_BOOT",
+    "CAP_SYS_NICE",
+    "CAP_SYS_RESOURCE",
+    "CAP_SYS_TIME",
+    "CAP_SYS_TTY_CONFIG",
+    "CAP_MKNOD",
+    "CAP_LEASE",
+    "CAP_AUDIT_WRITE",
+    "CAP_AUDIT_CONTROL",
+    "CAP_SETFCAP",
+    "CAP_MAC_OVERRIDE",
+    "CAP_MAC_ADMIN",
+    "CAP_SYSLOG",
+    "CAP_WAKE_ALARM",
+    "CAP_BLOCK_SUSPEND",
+    "CAP_AUDIT_READ",
+    "CAP_PERFMON",
+    "CAP_BPF",
+    "CAP_CHECKPOINT_RESTORE",
+];
+
+pub const CAP_SYS_ADMIN: u64 = 1 << 21;
+
+/// The names of the capabilities in an effective set, e.g. `CapEff` of
+/// `/proc/<pid>/status`. Unknown bits are shown as their number.
+pub fn names(capabilities: u64) -> Vec<String> {
+    (0..64)
+        .filter(|bit| capabilities & (1 << bit) != 0)
+        .map(|bit| match NAMES.get(bit) {
+            Some(name) => name.to_string(),
+            None => format!("CAP_{bit}"),
+        })
+        .collect()
+}
+
+/// `⚷` for processes that can administer the system, `+` for other
+/// privileged processes, and nothing for the others.
+pub fn indicator(capabilities: u64) -> &'static str {
+    if capabilities & CAP_SYS_ADMIN != 0 {
+        symbols().admin
+    } else if capabilities != 0 {
+        "+"
+    } else {
+        ""
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert!(names(0).is_empty());
+        assert_eq!(
+            names(1 << 10 | 1 << 13),
+            vec!["CAP_NET_BIND_SERVICE", "CAP_NET_RAW"]
+        );
+        assert_eq!(names(1 << 62), vec!["CAP_62"]);
+        assert_eq!(names(0x1ff_ffff_ffff).len(), 41);
+    }
+
+    #[test]
+    fn test_indicator() {
+        assert_eq!(indicator(0), "");
+        assert_eq!(indicator(1 << 10), "+");
+        assert_eq!(indicator(CAP_SYS_ADMIN | 1), "⚷");
+    }
+}
@@ -1,6 +1,8 @@
-use clap::Parser;
+use std::path::PathBuf;
 
-use crate::utils::version;
+use clap::{Parser, Subcommand};
+
+use crate::{snapshot::OutputFormat, utils::version};
 
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
@@ -9,10 +11,9 @@ pub struct Cli {
         short,
         long,
         value_name = "FLOAT",
-        help = "Tick rate, i.e. number of ticks per second",
-        default_value_t = 0.2
+        help = "Tick rate, i.e. number of ticks per second [default: enough for the shortest interval in the config]"
     )]
-    pub tick_rate: f64,
+    pub tick_rate: Option<f64>,
 
     #[arg(
         short,
@@ -31,4 +32,147 @@ pub struct Cli {
         default_value_t = false
     )]
     pub debug: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Record every refresh of the process list to a file",
+        conflicts_with = "replay"
+    )]
+    pub record: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Replay a recorded file instead of reading the live system"
+    )]
+    pub replay: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Log the keys and actions with their times to a file, e.g. for a bug report \
+                with a --record of the processes",
+        conflicts_with_all = ["once", "statusline"]
+    )]
+    pub record_actions: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Type the keys of an action log again, at the pace they were typed, over the \
+                --replay of the processes they were typed at",
+        requires = "replay",
+        conflicts_with_all = ["once", "statusline"]
+    )]
+    pub replay_actions: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Accept JSON-RPC commands on a Unix socket [default: $XDG_RUNTIME_DIR/brt.sock]",
+        conflicts_with = "once"
+    )]
+    pub socket: Option<Option<PathBuf>>,
+
+    #[arg(
+        long,
+        help = "Print a single sample of the system to stdout and exit",
+        conflicts_with_all = ["record", "replay"]
+    )]
+    pub once: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        help = "The output format of --once",
+        default_value_t = OutputFormat::Table,
+        requires = "once"
+    )]
+    pub format: OutputFormat,
+
+    #[arg(
+        long,
+        help = "Print a line with the cpu, memory, load, battery and uptime, for tmux or i3bar",
+        conflicts_with_all = ["once", "record", "replay", "socket"]
+    )]
+    pub statusline: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Print another status line every so many seconds, instead of once",
+        requires = "statusline",
+        value_parser = positive_seconds
+    )]
+    pub interval: Option<f64>,
+
+    #[arg(
+        long,
+        help = "Draw with ASCII instead of braille, block and arrow glyphs"
+    )]
+    pub ascii: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// A number of seconds above zero, which `Duration::from_secs_f64` takes
+/// without panicking.
+fn positive_seconds(value: &str) -> Result<f64, String> {
+    let seconds: f64 = value.parse().map_err(|e| format!("{e}"))?;
+    if seconds.is_finite() && seconds > 0.0 {
+        Ok(seconds)
+    } else {
+        Err(format!("{value} is not a positive number of seconds"))
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Read all processes a number of times and print the latency and
+    /// allocations of doing so.
+    #[command(hide = true)]
+    Bench {
+        #[arg(
+            short,
+            long,
+            value_name = "COUNT",
+            help = "The number of times to read the processes",
+            default_value_t = 100
+        )]
+        runs: usize,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_action_logs() {
+        Cli::command().debug_assert();
+        let parse = |args: &[&str]| Cli::try_parse_from([&["brt"], args].concat());
+        assert!(parse(&["--record-actions", "keys.log", "--record", "ps.rec"]).is_ok());
+        assert!(parse(&["--replay-actions", "keys.log", "--replay", "ps.rec"]).is_ok());
+        // keys typed at other processes don't do the same again
+        assert!(parse(&["--replay-actions", "keys.log"]).is_err());
+        assert!(parse(&["--record-actions", "keys.log", "--once"]).is_err());
+        assert!(parse(&["--record-actions", "keys.log", "--statusline"]).is_err());
+    }
+
+    #[test]
+    fn test_interval() {
+        let parse = |interval: &str| {
+            Cli::try_parse_from(["brt", "--statusline", "--interval", interval])
+                .map(|cli| cli.interval)
+        };
+        assert_eq!(parse("2.5").unwrap(), Some(2.5));
+        for interval in ["0", "-1", "inf", "NaN", "soon"] {
+            assert!(parse(interval).is_err(), "{interval}");
+        }
+    }
 }
@@ -0,0 +1,43 @@
+use std::fmt::Debug;
+
+use color_eyre::eyre::{eyre, Result};
+
+/// On X11 the copied text is served by the owner of the clipboard, so it is
+/// kept around for as long as brt runs.
+#[cfg(feature = "clipboard")]
+static CLIPBOARD: std::sync::Mutex<Option<arboard::Clipboard>> = std::sync::Mutex::new(None);
+
+/// Copies text to the system clipboard.
+#[cfg(feature = "clipboard")]
+pub fn copy(text: &str) -> Result<()> {
+    let mut clipboard = CLIPBOARD
+        .lock()
+        .map_err(|_| eyre!("The clipboard lock is poisoned"))?;
+    let clipboard = match clipboard.as_mut() {
+        Some(clipboard) => clipboard,
+        None => clipboard.insert(arboard::Clipboard::new()?),
+    };
+    clipboard.set_text(text)?;
+    Ok(())
+}
+
+/// Copies text to the system clipboard.
+#[cfg(not(feature = "clipboard"))]
+pub fn copy(_text: &str) -> Result<()> {
+    Err(eyre!("brt was built without the clipboard feature"))
+}
+
+/// Somewhere to copy text to.
+pub trait Clipboard: Debug + Send + Sync {
+    fn copy(&self, text: &str) -> Result<()>;
+}
+
+/// The system clipboard.
+#[derive(Debug, Default)]
+pub struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    fn copy(&self, text: &str) -> Result<()> {
+        copy(text)
+    }
+}
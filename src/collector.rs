@@ -0,0 +1,582 @@
+//! Data sources sampled on a thread of their own, each at its own interval,
+//! which can be registered with the app and turned on and off while it runs.
+
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::{eyre, Result};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+use tokio::sync::watch;
+
+use crate::cadence::Cadence;
+use crate::components::sensors::{SensorSource, SysfsSensorSource};
+use crate::hints::Hint;
+use crate::source::ProcSource;
+use crate::staleness::Sample;
+use crate::symbols::symbols;
+use crate::widgets::popup::Popup;
+
+/// How often the collector thread looks for collectors that are due.
+const POLL: Duration = Duration::from_millis(100);
+
+/// A named value a collector read, e.g. the load over a minute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    pub name: String,
+    pub value: f64,
+    /// E.g. `%`, or empty for a plain number.
+    pub unit: &'static str,
+}
+
+impl Metric {
+    pub fn new(name: impl Into<String>, value: f64, unit: &'static str) -> Self {
+        Self {
+            name: name.into(),
+            value,
+            unit,
+        }
+    }
+}
+
+pub type Metrics = Vec<Metric>;
+
+/// A data source such as sensors, a GPU or the network, sampled every
+/// `interval` while it is enabled.
+pub trait Collector: Debug + Send {
+    /// Short and unique, as it is listed and named in the config by it.
+    fn name(&self) -> &str;
+    fn interval(&self) -> Duration;
+    fn collect(&mut self) -> Result<Metrics>;
+}
+
+/// The system load averaged over one, five and fifteen minutes.
+#[derive(Debug)]
+pub struct LoadAverage {
+    source: Box<dyn ProcSource>,
+}
+
+impl LoadAverage {
+    pub fn new(source: Box<dyn ProcSource>) -> Self {
+        Self { source }
+    }
+}
+
+impl Collector for LoadAverage {
+    fn name(&self) -> &str {
+        "load"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+
+    fn collect(&mut self) -> Result<Metrics> {
+        let [one, five, fifteen] = self.source.load_average()?;
+        Ok(vec![
+            Metric::new("1m", one.into(), ""),
+            Metric::new("5m", five.into(), ""),
+            Metric::new("15m", fifteen.into(), ""),
+        ])
+    }
+}
+
+/// The share of the last ten seconds some tasks stalled on the cpu, memory
+/// and io, from the pressure stall information of Linux 4.20 and later.
+#[derive(Debug)]
+pub struct Pressure {
+    root: PathBuf,
+}
+
+impl Default for Pressure {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pressure {
+    pub fn new() -> Self {
+        Self::with_root(Path::new("/proc/pressure"))
+    }
+
+    fn with_root(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+}
+
+/// The `avg10` of the `some` line, e.g. of
+/// `some avg10=1.50 avg60=0.80 avg300=0.20 total=123456`.
+fn parse_pressure(text: &str) -> Option<f64> {
+    text.lines()
+        .find_map(|line| line.strip_prefix("some "))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
+
+impl Collector for Pressure {
+    fn name(&self) -> &str {
+        "pressure"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    fn collect(&mut self) -> Result<Metrics> {
+        ["cpu", "memory", "io"]
+            .into_iter()
+            .map(|resource| {
+                let path = self.root.join(resource);
+                let text = fs::read_to_string(&path)?;
+                let value = parse_pressure(&text)
+                    .ok_or_else(|| eyre!("Unexpected contents of {}", path.display()))?;
+                Ok(Metric::new(resource, value, "%"))
+            })
+            .collect()
+    }
+}
+
+/// The temperature of every sensor, the data of the sensors tab.
+#[derive(Debug)]
+pub struct SensorCollector {
+    source: Box<dyn SensorSource>,
+}
+
+impl Default for SensorCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorCollector {
+    pub fn new() -> Self {
+        Self::with_source(Box::<SysfsSensorSource>::default())
+    }
+
+    pub fn with_source(source: Box<dyn SensorSource>) -> Self {
+        Self { source }
+    }
+}
+
+impl Collector for SensorCollector {
+    fn name(&self) -> &str {
+        "sensors"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    fn collect(&mut self) -> Result<Metrics> {
+        let temperatures = self.source.read();
+        if temperatures.is_empty() {
+            return Err(eyre!("No temperature sensors found"));
+        }
+        Ok(temperatures
+            .into_iter()
+            .map(|t| Metric::new(format!("{} {}", t.chip, t.label), t.current, "°C"))
+            .collect())
+    }
+}
+
+/// A collector as the registry sees it; the collector itself runs on the
+/// collector thread.
+#[derive(Debug)]
+struct Entry {
+    name: String,
+    interval: Duration,
+    /// Shared with the collector thread, which skips it while off.
+    enabled: Arc<AtomicBool>,
+    /// What the last sample read, or why it failed.
+    latest: Option<std::result::Result<Metrics, String>>,
+    /// When a sample last succeeded.
+    collected: Option<Instant>,
+}
+
+impl Entry {
+    fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+}
+
+/// What the collector thread sends: the index of the entry, what its
+/// collector read or why it failed, and when.
+type Collected = (usize, std::result::Result<Metrics, String>, Instant);
+
+/// The registered collectors, in the order they were registered, and the
+/// popup that turns them on and off. They are sampled on a thread of their
+/// own, so a slow one never holds up the tick.
+#[derive(Debug)]
+pub struct Collectors {
+    entries: Vec<Entry>,
+    /// Registered but not running yet.
+    pending: Vec<(Box<dyn Collector>, Arc<AtomicBool>)>,
+    tx: Sender<Collected>,
+    results: Receiver<Collected>,
+    /// The summaries of the enabled collectors, for the pane showing them.
+    summaries: watch::Sender<Vec<String>>,
+    /// The selected row, while the popup is open.
+    picking: Option<usize>,
+}
+
+impl Default for Collectors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collectors {
+    pub fn new() -> Self {
+        let (tx, results) = mpsc::channel();
+        Self {
+            entries: vec![],
+            pending: vec![],
+            tx,
+            results,
+            summaries: watch::channel(vec![]).0,
+            picking: None,
+        }
+    }
+
+    /// The collectors brt comes with.
+    pub fn builtin(source: Box<dyn ProcSource>) -> Self {
+        let mut collectors = Self::new();
+        collectors.register(Box::new(LoadAverage::new(source)));
+        collectors.register(Box::new(Pressure::new()));
+        collectors.register(Box::new(SensorCollector::new()));
+        collectors
+    }
+
+    /// Adds `collector`, enabled; it runs once the collectors are started.
+    pub fn register(&mut self, collector: Box<dyn Collector>) {
+        let enabled = Arc::new(AtomicBool::new(true));
+        self.entries.push(Entry {
+            name: collector.name().to_string(),
+            interval: collector.interval(),
+            enabled: Arc::clone(&enabled),
+            latest: None,
+            collected: None,
+        });
+        self.pending.push((collector, enabled));
+    }
+
+    /// Runs the collectors registered since the last start on a thread,
+    /// each when it is due, until the registry is dropped.
+    pub fn start(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let first = self.entries.len() - self.pending.len();
+        let mut running: Vec<(Cadence, Box<dyn Collector>, Arc<AtomicBool>)> = self
+            .pending
+            .drain(..)
+            .map(|(collector, enabled)| (Cadence::new(collector.interval()), collector, enabled))
+            .collect();
+        let tx = self.tx.clone();
+        thread::spawn(move || loop {
+            for (i, (cadence, collector, enabled)) in running.iter_mut().enumerate() {
+                if !enabled.load(Ordering::Relaxed) || !cadence.due() {
+                    continue;
+                }
+                let latest = collector.collect().map_err(|e| {
+                    tracing::debug!("Unable to collect {}: {e}", collector.name());
+                    e.to_string()
+                });
+                if tx.send((first + i, latest, Instant::now())).is_err() {
+                    return;
+                }
+            }
+            thread::sleep(POLL);
+        });
+    }
+
+    /// The summaries of the enabled collectors, sent again whenever one
+    /// changes.
+    pub fn subscribe(&self) -> watch::Receiver<Vec<String>> {
+        self.summaries.subscribe()
+    }
+
+    fn publish(&self) {
+        self.summaries.send_replace(
+            self.entries
+                .iter()
+                .filter(|entry| entry.is_enabled())
+                .map(Self::summary)
+                .collect(),
+        );
+    }
+
+    /// Turns the collector called `name` on or off; false when there is
+    /// none.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let Some(entry) = self.entries.iter_mut().find(|entry| entry.name == name) else {
+            return false;
+        };
+        entry.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            entry.latest = None;
+        }
+        self.publish();
+        true
+    }
+
+    /// Takes in the samples taken since the last tick, returning whether
+    /// there were any.
+    pub fn receive(&mut self) -> bool {
+        let mut received = false;
+        while let Ok(collected) = self.results.try_recv() {
+            received |= self.apply(collected);
+        }
+        if received {
+            self.publish();
+        }
+        received
+    }
+
+    /// Keeps a sample, unless its collector was turned off since.
+    fn apply(&mut self, (i, latest, at): Collected) -> bool {
+        let Some(entry) = self.entries.get_mut(i).filter(|entry| entry.is_enabled()) else {
+            return false;
+        };
+        if latest.is_ok() {
+            entry.collected = Some(at);
+        }
+        entry.latest = Some(latest);
+        true
+    }
+
+    /// When each enabled collector last succeeded, so one that keeps failing
+    /// grows stale.
+    pub fn sampled(&self) -> Vec<Sample> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_enabled())
+            .filter_map(|entry| {
+                Some(Sample {
+                    name: entry.name.clone(),
+                    at: entry.collected?,
+                    period: entry.interval,
+                })
+            })
+            .collect()
+    }
+
+    /// E.g. `load 1m 0.52, 5m 0.40, 15m 0.31`, or why it failed.
+    fn summary(entry: &Entry) -> String {
+        let name = &entry.name;
+        match &entry.latest {
+            None if entry.is_enabled() => format!("{name} not read yet"),
+            None => format!("{name} off"),
+            Some(Err(e)) => format!("{name} failed: {e}"),
+            Some(Ok(metrics)) => {
+                let metrics: Vec<String> = metrics
+                    .iter()
+                    .map(|metric| format!("{} {:.2}{}", metric.name, metric.value, metric.unit))
+                    .collect();
+                format!("{name} {}", metrics.join(", "))
+            }
+        }
+    }
+
+    pub fn is_picking(&self) -> bool {
+        self.picking.is_some()
+    }
+
+    /// Opens the popup, or closes it when it is open.
+    pub fn toggle_picker(&mut self) {
+        self.picking = match self.picking {
+            Some(_) => None,
+            None => Some(0),
+        };
+    }
+
+    /// Handles a key while the popup is open: up and down select, space or
+    /// enter turn the selected collector on or off, and esc closes it.
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        let Some(selected) = self.picking else {
+            return;
+        };
+        let last = self.entries.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Up => self.picking = Some(selected.saturating_sub(1)),
+            KeyCode::Down => self.picking = Some((selected + 1).min(last)),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if let Some(entry) = self.entries.get(selected) {
+                    let name = entry.name.clone();
+                    let enabled = !entry.is_enabled();
+                    self.set_enabled(&name, enabled);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => self.picking = None,
+            _ => (),
+        }
+    }
+
+    /// The collectors with a check mark when enabled and their latest
+    /// metrics, while the popup is open.
+    pub fn popup(&self) -> Option<Popup<'static>> {
+        let selected = self.picking?;
+        let symbols = symbols();
+        let mut lines: Vec<Line> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let mark = if entry.is_enabled() { "x" } else { " " };
+                let mut line = Line::from(format!("[{mark}] {}", Self::summary(entry)));
+                if !entry.is_enabled() {
+                    line = line.dim();
+                }
+                if i == selected {
+                    line = line.reversed();
+                }
+                line
+            })
+            .collect();
+        lines.push(Line::default());
+        lines.push(Line::from(
+            format!("{}/{} select, space toggles", symbols.up, symbols.down).dim(),
+        ));
+        lines.push(Line::from("esc to close".dim()));
+        Some(Popup::new("collectors", lines))
+    }
+
+    pub fn hints(&self) -> Vec<Hint> {
+        let symbols = symbols();
+        vec![
+            Hint::new(format!("{}{}", symbols.up, symbols.down), "select"),
+            Hint::new("space", "toggle"),
+            Hint::new("esc", "close"),
+        ]
+    }
+
+    /// A sentence per enabled collector.
+    pub fn describe(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_enabled())
+            .map(|entry| format!("{}.", Self::summary(entry)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::sensors::Temperature;
+    use crate::source::fake::FakeProcSource;
+
+    #[derive(Debug)]
+    struct Failing;
+
+    impl Collector for Failing {
+        fn name(&self) -> &str {
+            "gpu"
+        }
+
+        fn interval(&self) -> Duration {
+            Duration::ZERO
+        }
+
+        fn collect(&mut self) -> Result<Metrics> {
+            Err(eyre!("no gpu"))
+        }
+    }
+
+    #[test]
+    fn test_parse_pressure() {
+        let text = "some avg10=1.50 avg60=0.80 avg300=0.20 total=123456\n\
+                    full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n";
+        assert_eq!(parse_pressure(text), Some(1.5));
+        assert_eq!(parse_pressure("full avg10=2.00\n"), None);
+    }
+
+    /// Takes in samples until the collector called `name` has one.
+    fn wait_for(collectors: &mut Collectors, name: &str) {
+        while collectors
+            .entries
+            .iter()
+            .any(|entry| entry.name == name && entry.latest.is_none())
+        {
+            let collected = collectors
+                .results
+                .recv_timeout(Duration::from_secs(5))
+                .unwrap();
+            collectors.apply(collected);
+        }
+    }
+
+    #[test]
+    fn test_sensor_collector() {
+        #[derive(Debug)]
+        struct FakeSensors(Vec<Temperature>);
+
+        impl SensorSource for FakeSensors {
+            fn read(&self) -> Vec<Temperature> {
+                self.0.clone()
+            }
+        }
+
+        let core = Temperature {
+            chip: "coretemp".to_string(),
+            label: "Core 0".to_string(),
+            current: 45.0,
+            high: None,
+            critical: None,
+        };
+        let metrics = SensorCollector::with_source(Box::new(FakeSensors(vec![core])))
+            .collect()
+            .unwrap();
+        assert_eq!(metrics, [Metric::new("coretemp Core 0", 45.0, "°C")]);
+        assert!(SensorCollector::with_source(Box::new(FakeSensors(vec![])))
+            .collect()
+            .is_err());
+    }
+
+    #[test]
+    fn test_collectors() {
+        let mut collectors = Collectors::new();
+        collectors.register(Box::new(LoadAverage::new(Box::<FakeProcSource>::default())));
+        collectors.register(Box::new(Failing));
+        let summaries = collectors.subscribe();
+        collectors.start();
+        wait_for(&mut collectors, "load");
+        wait_for(&mut collectors, "gpu");
+        assert_eq!(
+            collectors.describe(),
+            ["load 1m 1.50, 5m 1.00, 15m 0.50.", "gpu failed: no gpu."]
+        );
+        let sampled: Vec<String> = collectors.sampled().into_iter().map(|s| s.name).collect();
+        assert_eq!(sampled, ["load"]);
+
+        assert!(collectors.set_enabled("gpu", false));
+        assert!(!collectors.set_enabled("wifi", false));
+        // the samples of the gpu still on their way are dropped
+        collectors.receive();
+        assert_eq!(collectors.describe().len(), 1);
+        assert_eq!(*summaries.borrow(), ["load 1m 1.50, 5m 1.00, 15m 0.50"]);
+
+        collectors.toggle_picker();
+        assert!(collectors.is_picking());
+        collectors.handle_key(KeyCode::Down.into());
+        collectors.handle_key(KeyCode::Char(' ').into());
+        wait_for(&mut collectors, "gpu");
+        assert_eq!(
+            collectors.describe(),
+            ["load 1m 1.50, 5m 1.00, 15m 0.50.", "gpu failed: no gpu."]
+        );
+        assert!(collectors.popup().is_some());
+        collectors.handle_key(KeyCode::Esc.into());
+        assert!(collectors.popup().is_none());
+    }
+}
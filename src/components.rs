@@ -6,12 +6,33 @@ use tokio::sync::mpsc::UnboundedSender;
 use crate::{
     action::Action,
     config::Config,
+    hints::Hint,
+    session::Session,
+    staleness::Sample,
     tui::{Event, Frame},
 };
 
 pub mod battery;
+pub mod cgroups;
+pub mod collectors;
+pub mod cores;
+pub mod cpufreq;
 pub mod fps;
+pub mod header;
+pub mod interfaces;
+pub mod kernel_log;
+pub mod logs;
+pub mod memory;
+pub mod numa;
+pub mod power;
 pub mod process;
+pub mod sensors;
+pub mod shm;
+pub mod stopwatch;
+pub mod tabs;
+pub mod toast;
+pub mod top;
+pub mod wifi;
 
 pub trait Component {
     #[allow(unused_variables)]
@@ -45,5 +66,34 @@ pub trait Component {
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         Ok(None)
     }
+    /// Stores the state that should survive a restart in `session`.
+    #[allow(unused_variables)]
+    fn save_session(&self, session: &mut Session) {}
+    /// Restores the state saved by [`Component::save_session`].
+    #[allow(unused_variables)]
+    fn restore_session(&mut self, session: &Session) {}
+    /// Whether the component changed since it was last drawn, clearing the
+    /// flag. Frames in which no component changed aren't drawn; components
+    /// that don't track their changes are drawn every frame.
+    fn take_dirty(&mut self) -> bool {
+        true
+    }
+    /// The keys the component handles in its current state, for the footer.
+    fn hints(&self) -> Vec<Hint> {
+        Vec::new()
+    }
+    /// What the component shows, as plain sentences for a screen reader.
+    fn describe(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// When the component last sampled what it shows, for the staleness
+    /// line.
+    fn sampled(&self) -> Option<Sample> {
+        None
+    }
+    /// Whether the component is shown on the current tab, told on start and
+    /// whenever the tab changes, so costly reads can wait until it is.
+    #[allow(unused_variables)]
+    fn set_visible(&mut self, visible: bool) {}
     fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()>;
 }
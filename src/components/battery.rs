@@ -1,15 +1,172 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
 use battery as battery_model;
+use battery::units::time::second;
 use battery::State;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::text::Line;
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
 
 use crate::action::Action;
+use crate::cadence::Cadence;
 use crate::components::Component;
+use crate::config::{BarConfig, Config, Intervals};
+use crate::i18n::{tr, Text};
+use crate::model::format_elapsed;
+use crate::symbols::symbols;
 use crate::tui::Frame;
+use crate::widgets::bar::gradient_bar;
+
+/// A single reading of the battery.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerReading {
+    pub state: State,
+    /// The state of charge, from 0.0 to 1.0.
+    pub state_of_charge: f32,
+    /// The percentage at which charging stops, when it stops before full.
+    pub charge_limit: Option<u32>,
+    /// The time until it is empty or full at the current rate, as the
+    /// battery tells.
+    pub time_left: Option<Duration>,
+}
+
+/// Where the kernel lists the batteries and chargers.
+pub const POWER_SUPPLY_ROOT: &str = "/sys/class/power_supply";
+/// The files in which the vendor drivers keep the percentage at which
+/// charging stops.
+const CHARGE_LIMIT_FILES: [&str; 3] = [
+    "charge_control_end_threshold",
+    "charge_stop_threshold",
+    "charge_end_threshold",
+];
+
+/// The charge limit of the first battery under `root` that has one, unless
+/// it lets the battery charge to 100%.
+fn read_charge_limit(root: &Path) -> Option<u32> {
+    let mut supplies: Vec<_> = fs::read_dir(root)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    supplies.sort();
+    supplies
+        .iter()
+        .filter(|supply| {
+            fs::read_to_string(supply.join("type")).is_ok_and(|kind| kind.trim() == "Battery")
+        })
+        .find_map(|supply| {
+            CHARGE_LIMIT_FILES.iter().find_map(|file| {
+                fs::read_to_string(supply.join(file))
+                    .ok()?
+                    .trim()
+                    .parse()
+                    .ok()
+            })
+        })
+        .filter(|limit| *limit < 100)
+}
+
+/// Something that can tell the charge of the battery, if there is one.
+pub trait PowerSource: Debug {
+    fn read(&mut self) -> Option<PowerReading>;
+}
+
+/// Reads the first battery through the `battery` crate, keeping the manager
+/// and the battery between readings.
+#[derive(Debug, Default)]
+pub struct SystemPowerSource {
+    manager: Option<battery_model::Manager>,
+    battery: Option<battery_model::Battery>,
+}
+
+impl SystemPowerSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refreshes the known battery, or looks for one when there is none or
+    /// it went away.
+    fn battery(&mut self) -> Option<&battery_model::Battery> {
+        if self.manager.is_none() {
+            self.manager = battery_model::Manager::new().ok();
+        }
+        let manager = self.manager.as_ref()?;
+        let refreshed = match self.battery.as_mut() {
+            Some(battery) => manager.refresh(battery).is_ok(),
+            None => false,
+        };
+        if !refreshed {
+            self.battery = manager.batteries().ok()?.next()?.ok();
+        }
+        self.battery.as_ref()
+    }
+}
+
+impl PowerSource for SystemPowerSource {
+    fn read(&mut self) -> Option<PowerReading> {
+        let battery = self.battery()?;
+        let time_left = match battery.state() {
+            State::Discharging => battery.time_to_empty(),
+            State::Charging => battery.time_to_full(),
+            _ => None,
+        };
+        Some(PowerReading {
+            state: battery.state(),
+            state_of_charge: battery.state_of_charge().value,
+            charge_limit: read_charge_limit(Path::new(POWER_SUPPLY_ROOT)),
+            time_left: time_left
+                .and_then(|time| Duration::try_from_secs_f32(time.get::<second>()).ok()),
+        })
+    }
+}
+
+/// How worried to be about the charge of the battery.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Charge {
+    #[default]
+    Normal,
+    Low,
+    Critical,
+}
+
+#[cfg(feature = "notifications")]
+fn notify(message: String) {
+    // showing a notification waits for the notification daemon
+    std::thread::spawn(move || {
+        if let Err(e) = notify_rust::Notification::new()
+            .appname("brt")
+            .summary(&message)
+            .show()
+        {
+            tracing::warn!("Unable to show a notification: {e}");
+        }
+    });
+}
+
+#[cfg(not(feature = "notifications"))]
+fn notify(_message: String) {}
 
 #[derive(Debug)]
 pub struct Battery {
-    battery: Option<battery_model::Battery>,
+    source: Box<dyn PowerSource>,
+    reading: Option<PowerReading>,
+    charge: Charge,
+    /// Percentages at which the charge is low and critical.
+    low: u32,
+    critical: u32,
+    /// The readings in the same state over the last `window`, to estimate
+    /// the time left at the average rate.
+    history: VecDeque<(Instant, PowerReading)>,
+    window: Duration,
+    /// Show the estimates of the time left.
+    expanded: bool,
+    bar: BarConfig,
+    cadence: Cadence,
+    /// Changed since it was last drawn.
+    dirty: bool,
 }
 
 impl Default for Battery {
@@ -20,65 +177,482 @@ impl Default for Battery {
 
 impl Battery {
     pub fn new() -> Self {
-        Self { battery: None }
+        Self::with_source(Box::new(SystemPowerSource::new()))
+    }
+
+    pub fn with_source(source: Box<dyn PowerSource>) -> Self {
+        Self {
+            source,
+            reading: None,
+            charge: Charge::Normal,
+            low: 20,
+            critical: 10,
+            history: VecDeque::new(),
+            window: Duration::from_secs(600),
+            expanded: false,
+            bar: BarConfig::default(),
+            cadence: Cadence::from_millis(Intervals::default().battery),
+            dirty: true,
+        }
+    }
+
+    fn percentage(reading: &PowerReading) -> u32 {
+        (reading.state_of_charge * 100.0).round() as u32
+    }
+
+    /// Only a discharging battery is a reason to worry.
+    fn charge(&self) -> Charge {
+        match self.reading {
+            Some(reading) if reading.state == State::Discharging => {
+                let percentage = Self::percentage(&reading);
+                if percentage <= self.critical {
+                    Charge::Critical
+                } else if percentage <= self.low {
+                    Charge::Low
+                } else {
+                    Charge::Normal
+                }
+            }
+            _ => Charge::Normal,
+        }
+    }
+
+    /// Keeps the reading for the average rate, forgetting the ones before
+    /// the window or in another state.
+    fn record(&mut self, now: Instant) {
+        let Some(reading) = self.reading else {
+            self.history.clear();
+            return;
+        };
+        if self
+            .history
+            .back()
+            .is_some_and(|(_, last)| last.state != reading.state)
+        {
+            self.history.clear();
+        }
+        self.history.push_back((now, reading));
+        while self
+            .history
+            .front()
+            .is_some_and(|(at, _)| now.saturating_duration_since(*at) > self.window)
+        {
+            self.history.pop_front();
+        }
+    }
+
+    /// The time until empty or full at the rate averaged over the window.
+    fn averaged_time_left(&self) -> Option<Duration> {
+        let ((first_at, first), (last_at, last)) = (self.history.front()?, self.history.back()?);
+        let seconds = last_at.saturating_duration_since(*first_at).as_secs_f32();
+        let rate = (last.state_of_charge - first.state_of_charge) / seconds;
+        let left = match last.state {
+            State::Discharging if rate < 0.0 => last.state_of_charge / -rate,
+            State::Charging if rate > 0.0 => {
+                let full = last.charge_limit.map_or(1.0, |limit| limit as f32 / 100.0);
+                (full - last.state_of_charge).max(0.0) / rate
+            }
+            _ => return None,
+        };
+        Duration::try_from_secs_f32(left).ok()
+    }
+
+    /// E.g. `empty in 2h10m avg, 1h50m now`, while it discharges or charges.
+    fn estimate(&self, reading: &PowerReading) -> Option<String> {
+        let label = match reading.state {
+            State::Discharging => "empty in",
+            State::Charging => "full in",
+            _ => return None,
+        };
+        let format = |time: Option<Duration>| {
+            time.map_or_else(|| "?".to_string(), |time| format_elapsed(time.as_secs()))
+        };
+        Some(format!(
+            "{label} {} avg, {} now",
+            format(self.averaged_time_left()),
+            format(reading.time_left)
+        ))
+    }
+
+    /// Reads the battery and warns when the charge got more worrying.
+    fn refresh(&mut self) -> Option<Action> {
+        self.reading = self.source.read();
+        self.record(Instant::now());
+        self.dirty = true;
+        let charge = self.charge();
+        let worse = charge > self.charge;
+        self.charge = charge;
+        let percentage = Self::percentage(&self.reading?);
+        let message = match charge {
+            Charge::Low if worse => format!("Battery low: {percentage}%"),
+            Charge::Critical if worse => format!("Battery critical: {percentage}%"),
+            _ => return None,
+        };
+        tracing::warn!("{message}");
+        notify(message.clone());
+        Some(Action::Toast(message))
+    }
+
+    /// The battery segment, e.g. `BAT▼ 85% ■■■■■■■■■□`, or
+    /// `BAT○ 80% ≤80% ■■■■■■■■□□` when charging stops at 80%. Expanded, the
+    /// estimates of the time left follow.
+    pub fn line(&self) -> Line<'static> {
+        let Some(reading) = self.reading else {
+            return Line::from(
+                format!("{}{}", tr(Text::Battery), get_state_symbol(State::Unknown)).dim(),
+            );
+        };
+        let style = match self.charge {
+            Charge::Normal => Style::default(),
+            Charge::Low => Style::default().fg(Color::Red),
+            Charge::Critical => Style::default()
+                .fg(Color::Red)
+                .add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK),
+        };
+        let mut spans = vec![Span::styled(
+            format!(
+                "{}{} {}% ",
+                tr(Text::Battery),
+                get_state_symbol(reading.state),
+                Self::percentage(&reading)
+            ),
+            style,
+        )];
+        if let Some(limit) = reading.charge_limit {
+            spans.push(Span::raw(format!("{}{limit}% ", symbols().at_most)).dim());
+        }
+        let symbols = symbols();
+        let glyphs = (
+            self.bar.glyph.as_deref().unwrap_or(symbols.bar),
+            self.bar.empty_glyph.as_deref().unwrap_or(symbols.bar_empty),
+        );
+        spans.extend(
+            gradient_bar(
+                reading.state_of_charge,
+                self.bar.length,
+                &self.bar.gradient,
+                glyphs,
+            )
+            .spans,
+        );
+        if let Some(estimate) = self.expanded.then(|| self.estimate(&reading)).flatten() {
+            spans.push(Span::raw(format!(" {estimate}")).dim());
+        }
+        Line::from(spans)
+    }
+}
+
+pub fn get_state_symbol(state: State) -> &'static str {
+    let symbols = symbols();
+    match state {
+        State::Charging => symbols.rising,
+        State::Discharging => symbols.falling,
+        State::Full => symbols.full,
+        _ => symbols.unknown,
     }
 }
 
 impl Component for Battery {
+    fn register_config_handler(&mut self, config: Config) -> color_eyre::Result<()> {
+        self.low = config.config.battery_low;
+        self.critical = config.config.battery_critical;
+        self.window = Duration::from_secs(config.config.battery_window);
+        self.bar = config.config.battery_bar.clone();
+        self.cadence = Cadence::from_millis(config.config.intervals.battery);
+        Ok(())
+    }
+
     fn init(&mut self) -> color_eyre::Result<()> {
-        let batteries = battery_model::Manager::new().unwrap().batteries();
-        if batteries.is_ok() {
-            let b = batteries.unwrap().next().unwrap().unwrap();
-            self.battery = Some(b);
-        }
+        self.reading = self.source.read();
+        self.record(Instant::now());
         Ok(())
     }
 
-    fn update(&mut self, _action: Action) -> color_eyre::Result<Option<Action>> {
-        let _ = self.init();
+    fn update(&mut self, action: Action) -> color_eyre::Result<Option<Action>> {
+        match action {
+            Action::Tick if self.cadence.due() => return Ok(self.refresh()),
+            Action::ToggleBatteryDetails => {
+                self.expanded = !self.expanded;
+                self.dirty = true;
+            }
+            _ => (),
+        }
         Ok(None)
     }
 
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
     fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> color_eyre::Result<()> {
         let layout =
             Layout::new(Direction::Horizontal, vec![Constraint::Percentage(100)]).split(rect);
-        let mut state = "○";
-        if self.battery.is_some() {
-            state = match self.battery.as_mut().unwrap().state() {
-                State::Charging => "▲",
-                State::Discharging => "▼",
-                State::Full => "■",
-                State::Unknown => "○",
-                State::Empty => "○",
-                _ => "○",
-            };
-        }
-        let soc = self.battery.as_mut().unwrap().state_of_charge().value * 100.0;
-        let percentage = format!("{}%", soc as u32);
-        let status = format!("{}{} {}", "BAT", state, percentage);
-        let line = Line::from(status);
-        f.render_widget(line, layout[0]);
+        f.render_widget(self.line(), layout[0]);
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::*;
-    use log::info;
-    use ratatui::{backend::TestBackend, prelude::*};
+    use crate::widgets::bar::{EMPTY_COLOR, RED_TO_GREEN};
+    use ratatui::{backend::TestBackend, buffer::Buffer, prelude::*};
+
+    #[derive(Debug)]
+    struct FakePowerSource(Option<PowerReading>);
+
+    impl PowerSource for FakePowerSource {
+        fn read(&mut self) -> Option<PowerReading> {
+            self.0
+        }
+    }
+
+    fn battery(reading: Option<PowerReading>) -> Battery {
+        let mut battery = Battery::with_source(Box::new(FakePowerSource(reading)));
+        battery.init().unwrap();
+        battery
+    }
+
+    fn render(battery: &mut Battery) -> Buffer {
+        let backend = TestBackend::new(20, 1);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| battery.draw(frame, frame.size()).unwrap())
+            .unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    fn text(buffer: &Buffer) -> String {
+        buffer.content().iter().map(|c| c.symbol()).collect()
+    }
+
+    #[test]
+    fn test_get_state_symbol() {
+        assert_eq!(get_state_symbol(State::Charging), "▲");
+        assert_eq!(get_state_symbol(State::Discharging), "▼");
+        assert_eq!(get_state_symbol(State::Full), "■");
+        assert_eq!(get_state_symbol(State::Empty), "○");
+    }
+
+    #[test]
+    fn test_battery_empty() {
+        let mut battery = battery(Some(PowerReading {
+            state: State::Discharging,
+            state_of_charge: 0.0,
+            charge_limit: None,
+            time_left: None,
+        }));
+        let buffer = render(&mut battery);
+        assert_eq!(text(&buffer), "BAT▼ 0% ■■■■■■■■■■  ");
+        assert_eq!(buffer.get(8, 0).fg, EMPTY_COLOR);
+    }
 
     #[test]
-    fn test_battery() {
-        let mut battery = Battery::default();
-        let _ = battery.init();
-        let backend = TestBackend::new(40, 20);
+    fn test_battery_full() {
+        let mut battery = battery(Some(PowerReading {
+            state: State::Full,
+            state_of_charge: 1.0,
+            charge_limit: None,
+            time_left: None,
+        }));
+        let buffer = render(&mut battery);
+        assert_eq!(text(&buffer), "BAT■ 100% ■■■■■■■■■■");
+        assert_eq!(buffer.get(19, 0).fg, RED_TO_GREEN[9]);
+    }
+
+    #[test]
+    fn test_battery_bar_config() {
+        let mut battery = battery(Some(PowerReading {
+            state: State::Full,
+            state_of_charge: 1.0,
+            charge_limit: None,
+            time_left: None,
+        }));
+        battery.bar = BarConfig {
+            length: 4,
+            glyph: Some("=".to_string()),
+            empty_glyph: Some(".".to_string()),
+            gradient: vec![Color::Red, Color::Green],
+        };
+        let buffer = render(&mut battery);
+        assert_eq!(text(&buffer), "BAT■ 100% ====      ");
+        assert_eq!(buffer.get(10, 0).fg, Color::Red);
+        assert_eq!(buffer.get(13, 0).fg, Color::Green);
+
+        battery.reading = battery.reading.map(|reading| PowerReading {
+            state_of_charge: 0.5,
+            ..reading
+        });
+        assert_eq!(battery.line().to_string(), "BAT■ 50% ==..");
+    }
+
+    #[test]
+    fn test_battery_charging() {
+        let mut battery = battery(Some(PowerReading {
+            state: State::Charging,
+            state_of_charge: 0.42,
+            charge_limit: None,
+            time_left: None,
+        }));
+        let buffer = render(&mut battery);
+        assert_eq!(text(&buffer), "BAT▲ 42% ■■■■■■■■■■ ");
+        assert_eq!(buffer.get(9, 0).fg, RED_TO_GREEN[0]);
+        assert_eq!(buffer.get(13, 0).fg, EMPTY_COLOR);
+    }
+
+    #[test]
+    fn test_battery_charge_limit() {
+        let mut battery = battery(Some(PowerReading {
+            state: State::Unknown,
+            state_of_charge: 0.8,
+            charge_limit: Some(80),
+            time_left: None,
+        }));
+        let backend = TestBackend::new(25, 1);
         let mut terminal = Terminal::new(backend).unwrap();
-        let _ = terminal.draw(|frame| {
-            let _r = battery.draw(frame, Rect::new(3, 3, 10, 1));
-            let b = frame.buffer_mut();
-            info!("{:#?}", b);
+        terminal
+            .draw(|frame| battery.draw(frame, frame.size()).unwrap())
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        assert_eq!(text(buffer), "BAT○ 80% ≤80% ■■■■■■■■■■ ");
+        assert!(buffer.get(9, 0).modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn test_read_charge_limit() {
+        let root = std::env::temp_dir().join(format!("brt-power-supply-{}", std::process::id()));
+        for (supply, kind, limit) in [
+            ("AC", "Mains", None),
+            (
+                "BAT0",
+                "Battery",
+                Some(("charge_control_end_threshold", "80\n")),
+            ),
+            ("BAT1", "Battery", Some(("charge_stop_threshold", "60\n"))),
+        ] {
+            let path = root.join(supply);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("type"), format!("{kind}\n")).unwrap();
+            if let Some((file, value)) = limit {
+                fs::write(path.join(file), value).unwrap();
+            }
+        }
+        let limit = read_charge_limit(&root);
+        fs::write(root.join("BAT0/charge_control_end_threshold"), "100\n").unwrap();
+        let unlimited = read_charge_limit(&root);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(limit, Some(80));
+        assert_eq!(unlimited, None);
+        assert_eq!(read_charge_limit(&root), None);
+    }
+
+    #[derive(Debug)]
+    struct DrainingPowerSource(f32);
+
+    impl PowerSource for DrainingPowerSource {
+        fn read(&mut self) -> Option<PowerReading> {
+            self.0 -= 0.05;
+            Some(PowerReading {
+                state: State::Discharging,
+                state_of_charge: self.0,
+                charge_limit: None,
+                time_left: None,
+            })
+        }
+    }
+
+    #[test]
+    fn test_battery_warnings() {
+        let mut battery = Battery::with_source(Box::new(DrainingPowerSource(0.3)));
+        battery.cadence = Cadence::new(Duration::ZERO);
+        assert_eq!(battery.update(Action::Tick).unwrap(), None);
+        assert_eq!(
+            battery.update(Action::Tick).unwrap(),
+            Some(Action::Toast("Battery low: 20%".to_string()))
+        );
+        assert_eq!(battery.update(Action::Tick).unwrap(), None);
+        assert_eq!(
+            battery.update(Action::Tick).unwrap(),
+            Some(Action::Toast("Battery critical: 10%".to_string()))
+        );
+        assert_eq!(battery.update(Action::Tick).unwrap(), None);
+
+        let buffer = render(&mut battery);
+        assert_eq!(buffer.get(0, 0).fg, Color::Red);
+        assert!(buffer.get(0, 0).modifier.contains(Modifier::SLOW_BLINK));
+    }
+
+    #[test]
+    fn test_battery_charging_is_no_worry() {
+        let mut battery = battery(Some(PowerReading {
+            state: State::Charging,
+            state_of_charge: 0.05,
+            charge_limit: None,
+            time_left: None,
+        }));
+        assert_eq!(battery.update(Action::Tick).unwrap(), None);
+        assert_eq!(battery.charge, Charge::Normal);
+    }
+
+    #[test]
+    fn test_battery_estimates() {
+        let reading = |state, state_of_charge| PowerReading {
+            state,
+            state_of_charge,
+            charge_limit: None,
+            time_left: Some(Duration::from_secs(3_000)),
+        };
+        let mut battery = battery(None);
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        // one percent a minute, then two
+        for (seconds, state_of_charge) in [(0, 0.53), (60, 0.52), (120, 0.51), (180, 0.49)] {
+            battery.reading = Some(reading(State::Discharging, state_of_charge));
+            battery.record(at(seconds));
+        }
+        let seconds = |battery: &Battery| {
+            battery
+                .averaged_time_left()
+                .map(|time| time.as_secs_f32().round() as u64)
+        };
+        assert_eq!(seconds(&battery), Some(2_205));
+        assert_eq!(battery.line().to_string(), "BAT▼ 49% ■■■■■■■■■■");
+        battery.update(Action::ToggleBatteryDetails).unwrap();
+        assert!(battery.take_dirty());
+        assert_eq!(
+            battery.line().to_string(),
+            "BAT▼ 49% ■■■■■■■■■■ empty in 36m45s avg, 50m0s now"
+        );
+
+        // with a shorter window, the next reading forgets the older ones
+        battery.window = Duration::from_secs(60);
+        battery.reading = Some(reading(State::Discharging, 0.47));
+        battery.record(at(240));
+        assert_eq!(battery.history.len(), 2);
+        assert_eq!(seconds(&battery), Some(1_410));
+
+        // plugged in, the rate starts over
+        battery.reading = Some(reading(State::Charging, 0.5));
+        battery.record(at(250));
+        assert_eq!(battery.history.len(), 1);
+        assert_eq!(
+            battery.estimate(&battery.reading.unwrap()).unwrap(),
+            "full in ? avg, 50m0s now"
+        );
+        battery.reading = Some(PowerReading {
+            charge_limit: Some(80),
+            ..reading(State::Charging, 0.6)
         });
-        assert_eq!(true, true)
+        battery.record(at(310));
+        assert_eq!(seconds(&battery), Some(120));
+    }
+
+    #[test]
+    fn test_no_battery() {
+        let mut battery = battery(None);
+        let buffer = render(&mut battery);
+        assert_eq!(text(&buffer), "BAT○                ");
     }
 }
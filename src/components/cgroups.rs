@@ -0,0 +1,421 @@
+use std::time::Instant;
+
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::Component;
+use crate::config::{Config, Intervals};
+use crate::hints::Hint;
+use crate::model::{format_bytes, truncate};
+use crate::source::{system_source, ProcSource};
+use crate::staleness::Sample;
+use crate::symbols::symbols;
+use crate::tui::Frame;
+
+/// The top-level cgroups whose children are listed instead, as each of
+/// them is a container or a virtual machine.
+const CONTAINER_PARENTS: [&str; 2] = ["/docker", "/machine.slice"];
+/// The cgroup of the system services, whose container scopes are listed
+/// after it: with the systemd cgroup driver, the default on cgroup v2,
+/// docker and podman run each container in a `docker-<id>.scope` or
+/// `libpod-<id>.scope` in it.
+const SERVICES: &str = "/system.slice";
+const CONTAINER_SCOPES: [&str; 2] = ["docker-", "libpod-"];
+/// The width of the cgroup names.
+const NAME_WIDTH: usize = 40;
+
+/// A cgroup with the cpu and memory of the processes in it and below it.
+#[derive(Debug, Clone, PartialEq)]
+struct Group {
+    /// E.g. `/system.slice`.
+    path: String,
+    /// The cpu time in microseconds, `usage_usec` of `cpu.stat`.
+    usage: Option<u64>,
+    /// In percent of all cores, since the cgroups were read before.
+    cpu: Option<f64>,
+    /// In bytes, `memory.current`.
+    memory: Option<u64>,
+}
+
+/// Whether the cgroup is the scope of a docker or podman container.
+fn is_container_scope(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or_default();
+    name.ends_with(".scope")
+        && CONTAINER_SCOPES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+/// The `usage_usec` of a `cpu.stat`.
+fn parse_usage(stat: &str) -> Option<u64> {
+    stat.lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// The top-level cgroups, such as `system.slice`, `user.slice` and each
+/// docker container, for the cgroups tab. Enter lists the processes of the
+/// selected one.
+#[derive(Debug)]
+pub struct Cgroups {
+    source: Box<dyn ProcSource>,
+    groups: Vec<Group>,
+    /// When the cgroups were read.
+    read: Instant,
+    selected: usize,
+    /// The first cgroup in view.
+    offset: usize,
+    cadence: Cadence,
+    /// When a sample last succeeded.
+    sampled_at: Option<Instant>,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for Cgroups {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cgroups {
+    pub fn new() -> Self {
+        Self::with_source(system_source())
+    }
+
+    pub fn with_source(source: Box<dyn ProcSource>) -> Self {
+        let mut cgroups = Self {
+            source,
+            groups: vec![],
+            read: Instant::now(),
+            selected: 0,
+            offset: 0,
+            cadence: Cadence::from_millis(Intervals::default().cpu),
+            sampled_at: None,
+            dirty: true,
+        };
+        cgroups.refresh(Instant::now());
+        cgroups
+    }
+
+    /// The children of the root, with those of the container parents in
+    /// place of them and the container scopes of the services after those;
+    /// empty without cgroup v2.
+    fn paths(&self) -> Vec<String> {
+        let top = self.source.cgroup_children("/").unwrap_or_default();
+        top.into_iter()
+            .flat_map(|path| {
+                if CONTAINER_PARENTS.contains(&path.as_str()) {
+                    self.source.cgroup_children(&path).unwrap_or_default()
+                } else if path == SERVICES {
+                    let mut paths = self.source.cgroup_children(&path).unwrap_or_default();
+                    paths.retain(|path| is_container_scope(path));
+                    paths.insert(0, path);
+                    paths
+                } else {
+                    vec![path]
+                }
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn refresh(&mut self, now: Instant) {
+        self.dirty = true;
+        let seconds = now.duration_since(self.read).as_secs_f64();
+        let cores = self.source.num_cores().max(1) as f64;
+        let groups: Vec<Group> = self
+            .paths()
+            .into_iter()
+            .map(|path| {
+                let usage = self
+                    .source
+                    .cgroup_file(&path, "cpu.stat")
+                    .ok()
+                    .and_then(|stat| parse_usage(&stat));
+                let earlier = self
+                    .groups
+                    .iter()
+                    .find(|group| group.path == path)
+                    .and_then(|group| group.usage);
+                let cpu = match (usage, earlier) {
+                    (Some(usage), Some(earlier)) if seconds > 0.0 => {
+                        Some(usage.saturating_sub(earlier) as f64 / 10_000.0 / seconds / cores)
+                    }
+                    _ => None,
+                };
+                let memory = self
+                    .source
+                    .cgroup_file(&path, "memory.current")
+                    .ok()
+                    .and_then(|memory| memory.trim().parse().ok());
+                Group {
+                    path,
+                    usage,
+                    cpu,
+                    memory,
+                }
+            })
+            .collect();
+        if !groups.is_empty() {
+            self.sampled_at = Some(now);
+        }
+        self.groups = groups;
+        self.read = now;
+        self.selected = self.selected.min(self.groups.len().saturating_sub(1));
+    }
+
+    fn select(&mut self, step: isize) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(step)
+            .min(self.groups.len().saturating_sub(1));
+    }
+
+    /// E.g. `system.slice    12.5%   1.2GiB`, with dashes for what isn't
+    /// known (yet).
+    fn line(group: &Group) -> Line<'static> {
+        let name = truncate(group.path.trim_start_matches('/'), NAME_WIDTH);
+        let cpu = group
+            .cpu
+            .map_or("-".to_string(), |cpu| format!("{cpu:.1}%"));
+        let memory = group.memory.map_or("-".to_string(), format_bytes);
+        Line::from(format!("{name:<NAME_WIDTH$} {cpu:>7} {memory:>9}"))
+    }
+
+    /// Scrolls the window of `rows` rows so the selection is in it and, when
+    /// the list got shorter, no rows are left empty below the last cgroup.
+    fn scroll(&mut self, rows: usize) {
+        let rows = rows.max(1);
+        self.offset = self
+            .offset
+            .min(self.groups.len().saturating_sub(rows))
+            .clamp((self.selected + 1).saturating_sub(rows), self.selected);
+    }
+
+    /// The header and the `rows` cgroups in view.
+    fn lines(&self, rows: usize) -> Vec<Line<'static>> {
+        if self.groups.is_empty() {
+            return vec![Line::from("No cgroup v2 hierarchy found.").dim()];
+        }
+        let header = format!("{:<NAME_WIDTH$} {:>7} {:>9}", "cgroup", "cpu", "memory");
+        let mut lines = vec![Line::from(header).bold()];
+        let window = self.groups.iter().enumerate().skip(self.offset).take(rows);
+        lines.extend(window.map(|(i, group)| {
+            let line = Self::line(group);
+            if i == self.selected {
+                line.reversed()
+            } else {
+                line
+            }
+        }));
+        lines
+    }
+}
+
+impl Component for Cgroups {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.cadence = Cadence::from_millis(config.config.intervals.cpu);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick && self.cadence.due() {
+            self.refresh(Instant::now());
+        }
+        Ok(None)
+    }
+
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        match key.code {
+            KeyCode::Up => self.select(-1),
+            KeyCode::Down => self.select(1),
+            KeyCode::Enter => {
+                let group = self.groups.get(self.selected);
+                return Ok(group.map(|group| Action::FilterCgroup(group.path.clone())));
+            }
+            _ => return Ok(None),
+        }
+        self.dirty = true;
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn sampled(&self) -> Option<Sample> {
+        Sample::of("cgroups", self.sampled_at, &self.cadence)
+    }
+
+    fn hints(&self) -> Vec<Hint> {
+        let symbols = symbols();
+        vec![
+            Hint::new(format!("{}{}", symbols.up, symbols.down), "select"),
+            Hint::new("enter", "processes"),
+        ]
+    }
+
+    /// E.g. `system.slice: 12.5% cpu, 1.2GiB memory.` for each cgroup.
+    fn describe(&self) -> Vec<String> {
+        self.groups
+            .iter()
+            .map(|group| {
+                let mut usage = vec![];
+                usage.extend(group.cpu.map(|cpu| format!("{cpu:.1}% cpu")));
+                usage.extend(
+                    group
+                        .memory
+                        .map(|memory| format!("{} memory", format_bytes(memory))),
+                );
+                let name = group.path.trim_start_matches('/');
+                if usage.is_empty() {
+                    format!("{name}.")
+                } else {
+                    format!("{name}: {}.", usage.join(", "))
+                }
+            })
+            .collect()
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let block = Block::default()
+            .title(Title::from("cgroups").alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        // the borders and the header row are not part of the window
+        let rows = rect.height.saturating_sub(3) as usize;
+        self.scroll(rows);
+        f.render_widget(Paragraph::new(self.lines(rows)).block(block), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::source::fake::FakeProcSource;
+
+    fn source(usage: u64) -> FakeProcSource {
+        let mut source = FakeProcSource::default();
+        for (path, stat, memory) in [
+            ("/system.slice", usage, "104857600\n"),
+            ("/user.slice", 1_000_000, "2147483648\n"),
+            ("/docker/web", 0, "1048576\n"),
+            ("/docker/db", 0, "4194304\n"),
+        ] {
+            source.cgroup_files.insert(
+                format!("{path}/cpu.stat"),
+                format!("usage_usec {stat}\nuser_usec 0\nsystem_usec 0\n"),
+            );
+            source
+                .cgroup_files
+                .insert(format!("{path}/memory.current"), memory.to_string());
+        }
+        source
+    }
+
+    #[test]
+    fn test_parse_usage() {
+        assert_eq!(parse_usage("usage_usec 1500\nuser_usec 1000\n"), Some(1500));
+        assert_eq!(parse_usage("user_usec 1000\n"), None);
+    }
+
+    #[test]
+    fn test_cgroups() {
+        let mut cgroups = Cgroups::with_source(Box::new(source(1_000_000)));
+        let paths: Vec<&str> = cgroups.groups.iter().map(|g| g.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["/docker/db", "/docker/web", "/system.slice", "/user.slice"]
+        );
+        assert_eq!(cgroups.groups[2].cpu, None);
+
+        // a second of a core over two seconds
+        let num_cores = cgroups.source.num_cores() as f64;
+        cgroups.source = Box::new(source(2_000_000));
+        let read = cgroups.read;
+        cgroups.refresh(read + Duration::from_secs(2));
+        assert_eq!(cgroups.groups[2].cpu, Some(50.0 / num_cores));
+        assert_eq!(cgroups.groups[3].cpu, Some(0.0));
+        assert_eq!(
+            Cgroups::line(&cgroups.groups[3]).to_string(),
+            format!("{:<40}    0.0%      2GiB", "user.slice")
+        );
+
+        cgroups.handle_key_events(KeyCode::Down.into()).unwrap();
+        cgroups.handle_key_events(KeyCode::Down.into()).unwrap();
+        assert_eq!(
+            cgroups.handle_key_events(KeyCode::Enter.into()).unwrap(),
+            Some(Action::FilterCgroup("/system.slice".to_string()))
+        );
+        assert_eq!(cgroups.describe()[0], "docker/db: 0.0% cpu, 4MiB memory.");
+    }
+
+    #[test]
+    fn test_container_scopes() {
+        assert!(is_container_scope("/system.slice/docker-4f2a.scope"));
+        assert!(is_container_scope("/system.slice/libpod-9c1e.scope"));
+        assert!(!is_container_scope("/system.slice/docker.service"));
+        assert!(!is_container_scope("/system.slice/session-2.scope"));
+
+        let mut source = source(0);
+        for path in [
+            "/system.slice/docker-4f2a.scope",
+            "/system.slice/docker.service",
+            "/system.slice/libpod-9c1e.scope",
+        ] {
+            source
+                .cgroup_files
+                .insert(format!("{path}/memory.current"), "0\n".to_string());
+        }
+        let cgroups = Cgroups::with_source(Box::new(source));
+        let paths: Vec<&str> = cgroups.groups.iter().map(|g| g.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/docker/db",
+                "/docker/web",
+                "/system.slice",
+                "/system.slice/docker-4f2a.scope",
+                "/system.slice/libpod-9c1e.scope",
+                "/user.slice"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut cgroups = Cgroups::with_source(Box::new(source(0)));
+        let names = |cgroups: &Cgroups, rows| -> Vec<String> {
+            cgroups.lines(rows)[1..]
+                .iter()
+                .map(|line| line.to_string().split(' ').next().unwrap().to_string())
+                .collect()
+        };
+        for _ in 0..3 {
+            cgroups.handle_key_events(KeyCode::Down.into()).unwrap();
+        }
+        cgroups.scroll(2);
+        assert_eq!(names(&cgroups, 2), ["system.slice", "user.slice"]);
+        cgroups.handle_key_events(KeyCode::Up.into()).unwrap();
+        cgroups.handle_key_events(KeyCode::Up.into()).unwrap();
+        cgroups.scroll(2);
+        assert_eq!(names(&cgroups, 2), ["docker/web", "system.slice"]);
+        // a taller pane shows the whole list again
+        cgroups.scroll(10);
+        assert_eq!(cgroups.offset, 0);
+    }
+}
@@ -0,0 +1,91 @@
+use color_eyre::eyre::Result;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph};
+use tokio::sync::watch;
+
+use crate::action::Action;
+use crate::components::Component;
+use crate::tui::Frame;
+
+/// The latest metrics of the enabled collectors, a line each, as published
+/// by [`crate::collector::Collectors`].
+#[derive(Debug)]
+pub struct CollectorMetrics {
+    summaries: watch::Receiver<Vec<String>>,
+    dirty: bool,
+}
+
+impl CollectorMetrics {
+    pub fn new(summaries: watch::Receiver<Vec<String>>) -> Self {
+        Self {
+            summaries,
+            dirty: true,
+        }
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let summaries = self.summaries.borrow();
+        if summaries.is_empty() {
+            return vec![Line::from("No collectors enabled.").dim()];
+        }
+        summaries.iter().cloned().map(Line::from).collect()
+    }
+}
+
+impl Component for CollectorMetrics {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick && self.summaries.has_changed().unwrap_or(false) {
+            self.summaries.borrow_and_update();
+            self.dirty = true;
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn describe(&self) -> Vec<String> {
+        let summaries = self.summaries.borrow();
+        if summaries.is_empty() {
+            return vec!["No collectors enabled.".to_string()];
+        }
+        summaries
+            .iter()
+            .map(|summary| format!("{summary}."))
+            .collect()
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let block = Block::default()
+            .title(Title::from("collectors").alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        f.render_widget(Paragraph::new(self.lines()).block(block), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collector_metrics() {
+        let (tx, rx) = watch::channel(vec![]);
+        let mut metrics = CollectorMetrics::new(rx);
+        assert!(metrics.take_dirty());
+        assert_eq!(metrics.describe(), ["No collectors enabled."]);
+
+        metrics.update(Action::Tick).unwrap();
+        assert!(!metrics.take_dirty());
+        tx.send_replace(vec!["load 1m 0.52, 5m 0.40, 15m 0.31".to_string()]);
+        metrics.update(Action::Tick).unwrap();
+        assert!(metrics.take_dirty());
+        assert_eq!(metrics.describe(), ["load 1m 0.52, 5m 0.40, 15m 0.31."]);
+    }
+}
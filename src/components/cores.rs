@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use color_eyre::eyre::Result;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::{Position, Title};
+use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::cpufreq::sparkline;
+use crate::components::Component;
+use crate::config::{Config, Intervals};
+use crate::model::format_rate;
+use crate::source::{system_source, ProcSource};
+use crate::staleness::Sample;
+use crate::system::{ActivityCounters, CpuTicks};
+use crate::tui::Frame;
+use crate::widgets::bar::{bar, GREEN_TO_RED};
+use crate::widgets::heatmap::Heatmap;
+
+/// The number of usages in the history of a core.
+const HISTORY_LENGTH: usize = 30;
+/// The width of the bar of a core, e.g. ` 3 ■■■■■■■■■■  42% `.
+const BAR_WIDTH: u16 = 20;
+/// The width of a core, its bar and its history.
+const CORE_WIDTH: u16 = BAR_WIDTH + HISTORY_LENGTH as u16;
+/// The number of rates in the context switch and interrupt sparklines.
+const ACTIVITY_HISTORY: usize = 5;
+
+/// The usage of every core, with a history, for the performance tab. When
+/// there are too many cores for their bars to fit, a heatmap of their current
+/// usage is shown instead. Below them go the context switches and interrupts
+/// per second of the whole system.
+#[derive(Debug)]
+pub struct Cores {
+    source: Box<dyn ProcSource>,
+    ticks: Vec<CpuTicks>,
+    /// The recent usages of every core, in percent.
+    history: Vec<VecDeque<u64>>,
+    /// The counters at the previous refresh, to tell the rates.
+    activity: Option<(ActivityCounters, Instant)>,
+    /// The recent context switches and interrupts per second.
+    context_switches: VecDeque<u64>,
+    interrupts: VecDeque<u64>,
+    cadence: Cadence,
+    /// When a sample last succeeded.
+    sampled_at: Option<Instant>,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for Cores {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cores {
+    pub fn new() -> Self {
+        Self::with_source(system_source())
+    }
+
+    pub fn with_source(source: Box<dyn ProcSource>) -> Self {
+        let mut cores = Self {
+            source,
+            ticks: vec![],
+            history: vec![],
+            activity: None,
+            context_switches: VecDeque::new(),
+            interrupts: VecDeque::new(),
+            cadence: Cadence::from_millis(Intervals::default().cpu),
+            sampled_at: None,
+            dirty: true,
+        };
+        cores.refresh();
+        cores
+    }
+
+    /// Measures the usage of every core, the context switches and the
+    /// interrupts since the previous refresh, from a single read of
+    /// `/proc/stat`.
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn refresh(&mut self) {
+        self.dirty = true;
+        let Ok(stat) = self.source.system_stat() else {
+            return;
+        };
+        let now = Instant::now();
+        self.sampled_at = Some(now);
+        if let Some(counters) = ActivityCounters::of(&stat) {
+            if let Some((previous, measured)) = self.activity {
+                let seconds = now.duration_since(measured).as_secs_f64();
+                let (context_switches, interrupts) = counters.rates_since(&previous, seconds);
+                for (history, rate) in [
+                    (&mut self.context_switches, context_switches),
+                    (&mut self.interrupts, interrupts),
+                ] {
+                    history.push_back(rate.round() as u64);
+                    if history.len() > ACTIVITY_HISTORY {
+                        history.pop_front();
+                    }
+                }
+            }
+            self.activity = Some((counters, now));
+        }
+        let ticks = CpuTicks::cores(&stat.kernel);
+        if ticks.len() == self.ticks.len() {
+            self.history.resize_with(ticks.len(), VecDeque::new);
+            for ((now, earlier), history) in ticks.iter().zip(&self.ticks).zip(&mut self.history) {
+                history.push_back(now.usage_since(earlier).round() as u64);
+                if history.len() > HISTORY_LENGTH {
+                    history.pop_front();
+                }
+            }
+        }
+        self.ticks = ticks;
+    }
+
+    /// E.g. ` 3 ■■■■□□□□□□  42% ▁▂▅▇`.
+    fn line(core: usize, history: &VecDeque<u64>) -> Line<'static> {
+        let usage = history.back().copied().unwrap_or_default();
+        let mut spans = vec![Span::raw(format!("{core:>2} ")).dim()];
+        spans.extend(bar(usage as f32 / 100.0, &GREEN_TO_RED).spans);
+        spans.push(Span::raw(format!(" {usage:>3}% ")));
+        spans.push(Span::raw(sparkline(history, 0, 100)));
+        Line::from(spans)
+    }
+
+    /// E.g. ` ctx 12.3k ▁▂▅▇▃ int 1.2k ▁▁▂▁▁ `: the latest context switches
+    /// and interrupts per second with their recent history. Empty until
+    /// there are rates.
+    fn activity(&self) -> Line<'static> {
+        let mut spans = vec![Span::raw(" ")];
+        for (label, history) in [("ctx", &self.context_switches), ("int", &self.interrupts)] {
+            let Some(latest) = history.back() else {
+                return Line::default();
+            };
+            let max = history.iter().copied().max().unwrap_or_default();
+            spans.push(Span::raw(format!("{label} {:>5} ", format_rate(*latest))));
+            spans.push(Span::raw(sparkline(history, 0, max)).dim());
+            spans.push(Span::raw(" "));
+        }
+        Line::from(spans)
+    }
+}
+
+impl Component for Cores {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.cadence = Cadence::from_millis(config.config.intervals.cpu);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick && self.cadence.due() {
+            self.refresh();
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn sampled(&self) -> Option<Sample> {
+        Sample::of("cpu", self.sampled_at, &self.cadence)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let block = Block::default()
+            .title(Title::from("cores").alignment(Alignment::Center))
+            .title(Title::from(self.activity()).position(Position::Bottom))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        let inner = block.inner(rect);
+        f.render_widget(block, rect);
+        // as many columns of cores as fit, filled from top to bottom
+        let rows = inner.height.max(1) as usize;
+        let columns = self.history.len().div_ceil(rows).max(1);
+        if columns as u16 * BAR_WIDTH > inner.width {
+            let usages: Vec<u64> = self
+                .history
+                .iter()
+                .map(|history| history.back().copied().unwrap_or_default())
+                .collect();
+            f.render_widget(Heatmap::new(&usages), inner);
+            return Ok(());
+        }
+        let areas = Layout::new(
+            Direction::Horizontal,
+            vec![Constraint::Length(CORE_WIDTH); columns],
+        )
+        .split(inner);
+        for (area, start) in areas.iter().zip((0..self.history.len()).step_by(rows)) {
+            let lines: Vec<Line> = self.history[start..]
+                .iter()
+                .take(rows)
+                .enumerate()
+                .map(|(i, history)| Self::line(start + i, history))
+                .collect();
+            f.render_widget(Paragraph::new(lines), *area);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, prelude::*};
+
+    use super::*;
+    use crate::source::fake::FakeProcSource;
+
+    #[test]
+    fn test_cores() {
+        let mut source = FakeProcSource::default();
+        let mut cores = Cores::with_source(Box::new(source.clone()));
+        assert!(cores.history.is_empty());
+
+        // core 0 busy half of the 200 ticks, core 1 idle
+        source.kernel_stats = "cpu  1600 0 500 6100 0 0 0 0 0 0
+cpu0 850 0 250 3100 0 0 0 0 0 0
+cpu1 750 0 250 3200 0 0 0 0 0 0
+intr 1000 0 0
+ctxt 20000
+btime 1700000000
+processes 4000
+procs_running 2
+procs_blocked 0
+"
+        .to_string();
+        cores.source = Box::new(source.clone());
+        cores.refresh();
+        assert_eq!(
+            cores.history,
+            vec![VecDeque::from([50]), VecDeque::from([0])]
+        );
+
+        // a read that fails isn't a sample
+        let sampled = cores.sampled().unwrap().at;
+        source.kernel_stats = String::new();
+        cores.source = Box::new(source);
+        cores.refresh();
+        assert_eq!(cores.sampled().unwrap().at, sampled);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 4)).unwrap();
+        terminal.draw(|f| cores.draw(f, f.size()).unwrap()).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String { (0..40).map(|x| buffer.get(x, y).symbol()).collect() };
+        assert_eq!(row(1), "│  0 ■■■■■■■■■■  50% ▅                 │");
+        assert_eq!(row(2), "│  1 ■■■■■■■■■■   0% ▁                 │");
+
+        // too narrow for the bars
+        let mut terminal = Terminal::new(TestBackend::new(8, 3)).unwrap();
+        terminal.draw(|f| cores.draw(f, f.size()).unwrap()).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert_eq!(buffer.get(2, 1).symbol(), "■");
+        assert_eq!(buffer.get(2, 1).fg, GREEN_TO_RED[4]);
+        assert_eq!(buffer.get(4, 1).fg, GREEN_TO_RED[0]);
+    }
+
+    #[test]
+    fn test_activity() {
+        assert_eq!(format_rate(950), "950");
+        assert_eq!(format_rate(12_345), "12.3k");
+        assert_eq!(format_rate(1_250_000), "1.2M");
+
+        let mut cores = Cores::with_source(Box::<FakeProcSource>::default());
+        assert_eq!(cores.activity(), Line::default());
+        assert!(cores.activity.is_some());
+        cores.context_switches = VecDeque::from(vec![100, 12_345]);
+        cores.interrupts = VecDeque::from(vec![950, 950]);
+        assert_eq!(cores.activity().to_string(), " ctx 12.3k ▁█ int   950 ██ ");
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 4)).unwrap();
+        terminal.draw(|f| cores.draw(f, f.size()).unwrap()).unwrap();
+        let buffer = terminal.backend().buffer();
+        let bottom: String = (0..40).map(|x| buffer.get(x, 3).symbol()).collect();
+        assert!(
+            bottom.starts_with("╰ ctx 12.3k ▁█ int   950 ██ ─"),
+            "{bottom}"
+        );
+    }
+}
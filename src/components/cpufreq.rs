@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use color_eyre::eyre::Result;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::{Position, Title};
+use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::Component;
+use crate::config::{Config, Intervals};
+use crate::staleness::Sample;
+use crate::symbols::symbols;
+use crate::tui::Frame;
+
+const CPU_ROOT: &str = "/sys/devices/system/cpu";
+/// The number of readings in the history of a core.
+const HISTORY_LENGTH: usize = 10;
+
+/// The frequencies of a single core, in kHz, as reported by `cpufreq`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreFrequency {
+    pub cpu: usize,
+    pub current: u64,
+    pub min: u64,
+    pub max: u64,
+    pub governor: String,
+}
+
+/// Something that can tell the frequencies of all cores.
+pub trait FrequencySource: Debug + Send {
+    /// The cores ordered by number; empty when frequency scaling isn't available.
+    fn read(&self) -> Vec<CoreFrequency>;
+}
+
+/// Reads `/sys/devices/system/cpu/cpu*/cpufreq`.
+#[derive(Debug, Clone)]
+pub struct SysfsFrequencySource {
+    root: PathBuf,
+}
+
+impl Default for SysfsFrequencySource {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from(CPU_ROOT),
+        }
+    }
+}
+
+fn read_value(cpufreq: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(cpufreq.join(file))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+fn read_core(cpu: usize, cpufreq: &Path) -> Option<CoreFrequency> {
+    Some(CoreFrequency {
+        cpu,
+        current: read_value(cpufreq, "scaling_cur_freq")?.parse().ok()?,
+        min: read_value(cpufreq, "scaling_min_freq")?.parse().ok()?,
+        max: read_value(cpufreq, "scaling_max_freq")?.parse().ok()?,
+        governor: read_value(cpufreq, "scaling_governor").unwrap_or_default(),
+    })
+}
+
+impl FrequencySource for SysfsFrequencySource {
+    fn read(&self) -> Vec<CoreFrequency> {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return vec![];
+        };
+        let mut cores: Vec<CoreFrequency> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let name = entry.file_name();
+                let cpu = name.to_str()?.strip_prefix("cpu")?.parse().ok()?;
+                read_core(cpu, &entry.path().join("cpufreq"))
+            })
+            .collect();
+        cores.sort_by_key(|core| core.cpu);
+        cores
+    }
+}
+
+/// Formats a frequency in kHz as GHz, e.g. `2.40`.
+fn ghz(khz: u64, precision: usize) -> String {
+    format!("{:.*}", precision, khz as f64 / 1_000_000.0)
+}
+
+/// One spark per reading, scaled between the lowest and highest frequency.
+pub fn sparkline(history: &VecDeque<u64>, min: u64, max: u64) -> String {
+    let range = max.saturating_sub(min).max(1) as f64;
+    let sparks = symbols().sparks;
+    history
+        .iter()
+        .map(|khz| {
+            let fraction = khz.saturating_sub(min) as f64 / range;
+            let index = (fraction * (sparks.len() - 1) as f64).round() as usize;
+            sparks[index.min(sparks.len() - 1)]
+        })
+        .collect()
+}
+
+/// Current, minimum and maximum frequency and the governor of every core.
+#[derive(Debug)]
+pub struct CpuFrequency {
+    source: Box<dyn FrequencySource>,
+    cores: Vec<CoreFrequency>,
+    history: Vec<VecDeque<u64>>,
+    show_history: bool,
+    cadence: Cadence,
+    /// When a sample last succeeded.
+    sampled_at: Option<Instant>,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for CpuFrequency {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CpuFrequency {
+    pub fn new() -> Self {
+        Self::with_source(Box::new(SysfsFrequencySource::default()))
+    }
+
+    pub fn with_source(source: Box<dyn FrequencySource>) -> Self {
+        let mut frequency = Self {
+            source,
+            cores: vec![],
+            history: vec![],
+            show_history: true,
+            cadence: Cadence::from_millis(Intervals::default().cpu),
+            sampled_at: None,
+            dirty: true,
+        };
+        frequency.refresh();
+        frequency
+    }
+
+    /// Whether the system reports any frequencies at all.
+    pub fn is_available(&self) -> bool {
+        !self.cores.is_empty()
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn refresh(&mut self) {
+        self.dirty = true;
+        self.cores = self.source.read();
+        if !self.cores.is_empty() {
+            self.sampled_at = Some(Instant::now());
+        }
+        self.history.resize_with(self.cores.len(), VecDeque::new);
+        for (core, history) in self.cores.iter().zip(self.history.iter_mut()) {
+            history.push_back(core.current);
+            if history.len() > HISTORY_LENGTH {
+                history.pop_front();
+            }
+        }
+    }
+
+    /// The governor of all cores, or `mixed` when they differ.
+    fn governor(&self) -> &str {
+        match self.cores.first() {
+            Some(first) if self.cores.iter().all(|c| c.governor == first.governor) => {
+                &first.governor
+            }
+            Some(_) => "mixed",
+            None => "",
+        }
+    }
+
+    /// E.g. ` 0 2.40 0.8-3.6GHz ▁▂▃▅▇`.
+    fn line(&self, core: &CoreFrequency, history: &VecDeque<u64>) -> Line<'static> {
+        let mut spans = vec![
+            Span::raw(format!("{:>2} ", core.cpu)).dim(),
+            Span::raw(ghz(core.current, 2)).bold(),
+            Span::raw(format!(" {}-{}GHz", ghz(core.min, 1), ghz(core.max, 1))).dim(),
+        ];
+        if self.show_history {
+            spans.push(Span::raw(format!(
+                " {}",
+                sparkline(history, core.min, core.max)
+            )));
+        }
+        Line::from(spans)
+    }
+}
+
+impl Component for CpuFrequency {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.show_history = config.config.frequency_history;
+        self.cadence = Cadence::from_millis(config.config.intervals.cpu);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick && self.cadence.due() {
+            self.refresh();
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn sampled(&self) -> Option<Sample> {
+        Sample::of("freq", self.sampled_at, &self.cadence)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let lines: Vec<Line> = self
+            .cores
+            .iter()
+            .zip(self.history.iter())
+            .map(|(core, history)| self.line(core, history))
+            .collect();
+        let block = Block::default()
+            .title(Title::from("freq").alignment(Alignment::Center))
+            .title(
+                Title::from(self.governor().to_string())
+                    .position(Position::Bottom)
+                    .alignment(Alignment::Right),
+            )
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        f.render_widget(Paragraph::new(lines).block(block), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, prelude::*};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct FakeFrequencySource(Vec<CoreFrequency>);
+
+    impl FrequencySource for FakeFrequencySource {
+        fn read(&self) -> Vec<CoreFrequency> {
+            self.0.clone()
+        }
+    }
+
+    fn core(cpu: usize, current: u64, governor: &str) -> CoreFrequency {
+        CoreFrequency {
+            cpu,
+            current,
+            min: 800_000,
+            max: 3_600_000,
+            governor: governor.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let history = VecDeque::from(vec![800_000, 2_200_000, 3_600_000, 4_000_000]);
+        assert_eq!(sparkline(&history, 800_000, 3_600_000), "▁▅██");
+        assert_eq!(sparkline(&VecDeque::from(vec![5]), 5, 5), "▁");
+    }
+
+    #[test]
+    fn test_cpu_frequency() {
+        let source = FakeFrequencySource(vec![
+            core(0, 2_400_000, "powersave"),
+            core(1, 800_000, "powersave"),
+        ]);
+        let mut frequency = CpuFrequency::with_source(Box::new(source));
+        assert!(frequency.is_available());
+        assert_eq!(frequency.governor(), "powersave");
+        frequency.update(Action::Tick).unwrap();
+        assert_eq!(frequency.history[0].len(), 2);
+
+        let mut terminal = Terminal::new(TestBackend::new(30, 4)).unwrap();
+        terminal
+            .draw(|f| frequency.draw(f, f.size()).unwrap())
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String {
+            (0..30)
+                .map(|x| buffer.get(x, y).symbol().to_string())
+                .collect()
+        };
+        assert_eq!(row(1), "│  0 2.40 0.8-3.6GHz ▅▅      │");
+        assert_eq!(row(2), "│  1 0.80 0.8-3.6GHz ▁▁      │");
+        assert!(row(3).ends_with("powersave╯"));
+    }
+
+    #[test]
+    fn test_cpu_frequency_mixed_and_unavailable() {
+        let source = FakeFrequencySource(vec![
+            core(0, 2_400_000, "performance"),
+            core(1, 800_000, "powersave"),
+        ]);
+        assert_eq!(
+            CpuFrequency::with_source(Box::new(source)).governor(),
+            "mixed"
+        );
+        let source = SysfsFrequencySource {
+            root: PathBuf::from("/nonexistent"),
+        };
+        assert!(!CpuFrequency::with_source(Box::new(source)).is_available());
+    }
+}
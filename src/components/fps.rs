@@ -1,10 +1,15 @@
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
 use color_eyre::eyre::Result;
 use ratatui::{prelude::*, widgets::*};
 
 use super::Component;
-use crate::{action::Action, tui::Frame};
+use crate::{
+    action::{Action, UpdatePayload},
+    model::{format_bytes, get_memory},
+    source::{system_source, ProcSource},
+    tui::Frame,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Ticker {
@@ -12,7 +17,7 @@ pub enum Ticker {
     RenderTick,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug)]
 pub struct FpsCounter {
     app_start_time: Instant,
     app_frames: u32,
@@ -21,6 +26,80 @@ pub struct FpsCounter {
     render_start_time: Instant,
     render_frames: u32,
     render_fps: f64,
+
+    usage: SelfUsage,
+    update: Option<UpdatePayload>,
+}
+
+/// What brt itself costs: its memory, its cpu usage and the time it takes to
+/// handle a tick, measured once per second.
+#[derive(Debug)]
+pub struct SelfUsage {
+    source: Box<dyn ProcSource>,
+    pid: i32,
+    /// The cpu ticks used at the previous measurement.
+    ticks: Option<(u64, Instant)>,
+    cpu: f64,
+    resident_memory: u64,
+    /// The slowest tick since the previous measurement.
+    slowest: Duration,
+    latency: Duration,
+}
+
+impl SelfUsage {
+    pub fn new() -> Self {
+        Self::with_source(system_source(), std::process::id() as i32)
+    }
+
+    pub fn with_source(source: Box<dyn ProcSource>, pid: i32) -> Self {
+        Self {
+            source,
+            pid,
+            ticks: None,
+            cpu: 0.0,
+            resident_memory: 0,
+            slowest: Duration::ZERO,
+            latency: Duration::ZERO,
+        }
+    }
+
+    fn sampled(&mut self, duration: Duration) {
+        self.slowest = self.slowest.max(duration);
+    }
+
+    fn measure(&mut self, now: Instant) {
+        self.latency = std::mem::take(&mut self.slowest);
+        self.resident_memory = get_memory(self.source.as_ref(), self.pid);
+        let Ok(stat) = self.source.stat(self.pid) else {
+            return;
+        };
+        let ticks = stat.utime + stat.stime;
+        if let Some((previous, measured)) = self.ticks {
+            let seconds = now.duration_since(measured).as_secs_f64();
+            if seconds > 0.0 {
+                let used =
+                    ticks.saturating_sub(previous) as f64 / self.source.ticks_per_second() as f64;
+                self.cpu = used * 100.0 / seconds;
+            }
+        }
+        self.ticks = Some((ticks, now));
+    }
+
+    /// E.g. `brt 12.3MiB 0.8% tick 1.2ms`.
+    fn summary(&self) -> String {
+        format!(
+            "brt {} {:.1}% tick {:.1}ms",
+            format_bytes(self.resident_memory),
+            self.cpu,
+            self.latency.as_secs_f64() * 1_000.0
+        )
+    }
+}
+
+impl Default for SelfUsage {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Default for FpsCounter {
@@ -31,6 +110,10 @@ impl Default for FpsCounter {
 
 impl FpsCounter {
     pub fn new() -> Self {
+        Self::with_usage(SelfUsage::new())
+    }
+
+    pub fn with_usage(usage: SelfUsage) -> Self {
         Self {
             app_start_time: Instant::now(),
             app_frames: 0,
@@ -38,6 +121,8 @@ impl FpsCounter {
             render_start_time: Instant::now(),
             render_frames: 0,
             render_fps: 0.0,
+            usage,
+            update: None,
         }
     }
 
@@ -49,6 +134,7 @@ impl FpsCounter {
             self.app_fps = self.app_frames as f64 / elapsed;
             self.app_start_time = now;
             self.app_frames = 0;
+            self.usage.measure(now);
         }
         Ok(())
     }
@@ -66,6 +152,24 @@ impl FpsCounter {
     }
 }
 
+/// E.g. `data 1.2s old, read in 4.3ms, 312 +2 -1 | `, with the processes
+/// hidden by permissions when there are any.
+fn data_age(update: &UpdatePayload, now: SystemTime) -> String {
+    let age = now.duration_since(update.timestamp).unwrap_or_default();
+    let restricted = match update.restricted {
+        0 => String::new(),
+        count => format!(", {count} processes hidden by permissions"),
+    };
+    format!(
+        "data {:.1}s old, read in {:.1}ms, {} +{} -{}{restricted} | ",
+        age.as_secs_f64(),
+        update.duration.as_secs_f64() * 1_000.0,
+        update.processes,
+        update.started,
+        update.exited
+    )
+}
+
 impl Component for FpsCounter {
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         if let Action::Tick = action {
@@ -74,6 +178,12 @@ impl Component for FpsCounter {
         if let Action::Render = action {
             self.render_tick()?
         };
+        if let Action::Sampled(duration) = action {
+            self.usage.sampled(duration);
+        }
+        if let Action::Update(update) = action {
+            self.update = Some(update);
+        }
         Ok(None)
     }
 
@@ -89,8 +199,14 @@ impl Component for FpsCounter {
         let rect = rects[0];
 
         let s = format!(
-            "{:.2} fps (app) {:.2} fps (render)",
-            self.app_fps, self.render_fps
+            "{}{} | {:.2} fps (app) {:.2} fps (render)",
+            self.update
+                .as_ref()
+                .map(|u| data_age(u, SystemTime::now()))
+                .unwrap_or_default(),
+            self.usage.summary(),
+            self.app_fps,
+            self.render_fps
         );
         let block = Block::default().title(block::Title::from(s.dim()).alignment(Alignment::Right));
         f.render_widget(block, rect);
@@ -101,6 +217,8 @@ impl Component for FpsCounter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::source::fake::{FakeProcSource, FakeProcess, PAGE_SIZE};
+
     #[test]
     fn test_fps_app_tick() {
         let mut fps = FpsCounter::default();
@@ -130,4 +248,53 @@ mod tests {
         assert_eq!(fps.render_frames, 1);
         assert_eq!(fps.app_frames, 0);
     }
+
+    #[test]
+    fn test_self_usage() {
+        let source = FakeProcSource::default().with(7, FakeProcess::running(7, "brt", 100, 0));
+        let mut usage = SelfUsage::with_source(Box::new(source.clone()), 7);
+        let start = Instant::now();
+        usage.sampled(Duration::from_millis(3));
+        usage.sampled(Duration::from_millis(1));
+        usage.measure(start);
+        assert_eq!(usage.resident_memory, 512 * PAGE_SIZE);
+        assert_eq!(usage.latency, Duration::from_millis(3));
+        assert_eq!(usage.cpu, 0.0);
+
+        // 50 more ticks, half a second of cpu, over two seconds
+        usage.source = Box::new(source.with(7, FakeProcess::running(7, "brt", 150, 0)));
+        usage.measure(start + Duration::from_secs(2));
+        assert_eq!(usage.cpu, 25.0);
+        assert_eq!(usage.latency, Duration::ZERO);
+        assert_eq!(usage.summary(), "brt 2MiB 25.0% tick 0.0ms");
+    }
+
+    #[test]
+    fn test_data_age() {
+        let now = SystemTime::now();
+        let update = UpdatePayload {
+            timestamp: now - Duration::from_millis(1_200),
+            duration: Duration::from_micros(4_300),
+            processes: 312,
+            started: 2,
+            exited: 1,
+            restricted: 0,
+            top: Default::default(),
+        };
+        assert_eq!(
+            data_age(&update, now),
+            "data 1.2s old, read in 4.3ms, 312 +2 -1 | "
+        );
+        let hidden = UpdatePayload {
+            restricted: 3,
+            ..update.clone()
+        };
+        assert_eq!(
+            data_age(&hidden, now),
+            "data 1.2s old, read in 4.3ms, 312 +2 -1, 3 processes hidden by permissions | "
+        );
+        let mut fps = FpsCounter::default();
+        fps.update(Action::Update(update.clone())).unwrap();
+        assert_eq!(fps.update, Some(update));
+    }
 }
@@ -0,0 +1,474 @@
+use chrono::{DateTime, Local, TimeZone};
+use color_eyre::eyre::Result;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::battery::{Battery, PowerSource, SystemPowerSource};
+use crate::components::power::Power;
+use crate::components::wifi::Wifi;
+use crate::components::Component;
+use crate::config::{AppConfig, ClockConfig, Config, Intervals};
+use crate::i18n;
+use crate::source::{system_source, ProcSource};
+use crate::symbols::symbols;
+use crate::system::{CpuTicks, MemoryUsage};
+use crate::tui::Frame;
+use crate::widgets::bar::{gradient_bar, GREEN_TO_RED};
+
+const SUMMARY_BAR_LENGTH: usize = 5;
+/// The room left around the clock.
+const CLOCK_MARGIN: u16 = 2;
+
+/// The top line: version, clock, overall cpu and memory usage, the wifi
+/// signal, the power supply and profile, and the battery.
+#[derive(Debug)]
+pub struct Header {
+    source: Box<dyn ProcSource>,
+    battery: Battery,
+    wifi: Option<Wifi>,
+    power: Option<Power>,
+    cpu_ticks: CpuTicks,
+    cpu: f64,
+    memory: MemoryUsage,
+    /// Below this width the version, milliseconds and bars are left out.
+    compact_width: u16,
+    clock: DateTime<Local>,
+    clock_config: ClockConfig,
+    cpu_cadence: Cadence,
+    clock_cadence: Cadence,
+    /// The hostname, kernel release and boot time, read once.
+    system_info: Line<'static>,
+    show_system_info: bool,
+    hostname: Option<String>,
+    /// The title last given to the terminal window, when it is kept up to
+    /// date.
+    title: Option<String>,
+    show_title: bool,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Header {
+    pub fn new() -> Self {
+        Self::with_sources(system_source(), Box::new(SystemPowerSource::new()))
+            .with_wifi(Wifi::new())
+            .with_power(Power::new())
+    }
+
+    pub fn with_sources(source: Box<dyn ProcSource>, power: Box<dyn PowerSource>) -> Self {
+        Self {
+            source,
+            battery: Battery::with_source(power),
+            wifi: None,
+            power: None,
+            cpu_ticks: CpuTicks::default(),
+            cpu: 0.0,
+            memory: MemoryUsage::default(),
+            compact_width: AppConfig::default().compact_width,
+            clock: Local::now(),
+            clock_config: ClockConfig::default(),
+            cpu_cadence: Cadence::from_millis(Intervals::default().cpu),
+            clock_cadence: Cadence::from_millis(Intervals::default().clock),
+            system_info: Line::default(),
+            show_system_info: AppConfig::default().system_info,
+            hostname: None,
+            title: None,
+            show_title: AppConfig::default().terminal_title,
+            dirty: true,
+        }
+    }
+
+    /// Shows the wifi signal before the battery.
+    pub fn with_wifi(mut self, wifi: Wifi) -> Self {
+        self.wifi = Some(wifi);
+        self
+    }
+
+    /// Shows the power supply and profile before the battery.
+    pub fn with_power(mut self, power: Power) -> Self {
+        self.power = Some(power);
+        self
+    }
+
+    /// Measures the cpu usage since the previous refresh and the memory usage.
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn refresh(&mut self) {
+        self.dirty = true;
+        if let Ok(ticks) = CpuTicks::read(self.source.as_ref()) {
+            self.cpu = ticks.usage_since(&self.cpu_ticks);
+            self.cpu_ticks = ticks;
+        }
+        if let Ok(memory) = MemoryUsage::read(self.source.as_ref()) {
+            self.memory = memory;
+        }
+    }
+
+    /// E.g. ` myhost 6.8.0-45-generic booted 2024-05-01 08:12`; what the
+    /// system doesn't tell is left out.
+    fn read_system_info(&self) -> Line<'static> {
+        let source = self.source.as_ref();
+        let booted = source
+            .boot_time()
+            .ok()
+            .and_then(|seconds| Local.timestamp_opt(seconds as i64, 0).single())
+            .map(|time| format!("booted {}", time.format("%Y-%m-%d %H:%M")));
+        let parts: Vec<String> = [source.hostname().ok(), source.kernel_release().ok(), booted]
+            .into_iter()
+            .flatten()
+            .collect();
+        if parts.is_empty() {
+            return Line::default();
+        }
+        Line::from(format!(" {}", parts.join(" ")).dim())
+    }
+
+    /// E.g. `brt — myhost — cpu 42% mem 61%`.
+    fn title(&self) -> String {
+        let mut title = "brt — ".to_string();
+        if let Some(hostname) = &self.hostname {
+            title.push_str(&format!("{hostname} — "));
+        }
+        title.push_str(&format!(
+            "cpu {:.0}% mem {:.0}%",
+            self.cpu,
+            self.memory.percentage()
+        ));
+        title
+    }
+
+    /// The title of the terminal window when it changed.
+    fn update_title(&mut self) -> Option<Action> {
+        if !self.show_title {
+            return None;
+        }
+        let title = self.title();
+        if self.title.as_ref() == Some(&title) {
+            return None;
+        }
+        self.title = Some(title.clone());
+        Some(Action::SetTitle(title))
+    }
+
+    /// E.g. `cpu ■■□□□  42% mem ■■■□□  61%`, or `cpu  42% mem  61%` when
+    /// compact.
+    fn summary(&self, compact: bool) -> Line<'static> {
+        let symbols = symbols();
+        let bar = |fraction: f64| {
+            let glyphs = (symbols.bar, symbols.bar_empty);
+            gradient_bar(fraction as f32, SUMMARY_BAR_LENGTH, &GREEN_TO_RED, glyphs).spans
+        };
+        let mut spans = vec![Span::raw("cpu ")];
+        if !compact {
+            spans.extend(bar(self.cpu / 100.0));
+            spans.push(Span::raw(" "));
+        }
+        spans.push(Span::raw(format!("{:>3.0}% mem ", self.cpu)));
+        if !compact {
+            spans.extend(bar(self.memory.percentage() / 100.0));
+            spans.push(Span::raw(" "));
+        }
+        spans.push(Span::raw(format!("{:>3.0}% ", self.memory.percentage())));
+        Line::from(spans)
+    }
+}
+
+impl Component for Header {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.compact_width = config.config.compact_width;
+        self.clock_config = config.config.clock.clone().validated();
+        self.cpu_cadence = Cadence::from_millis(config.config.intervals.cpu);
+        self.clock_cadence = Cadence::from_millis(config.config.intervals.clock);
+        self.show_system_info = config.config.system_info;
+        self.show_title = config.config.terminal_title;
+        if let Some(wifi) = self.wifi.as_mut() {
+            wifi.register_config_handler(config.clone())?;
+        }
+        if let Some(power) = self.power.as_mut() {
+            power.register_config_handler(config.clone())?;
+        }
+        self.battery.register_config_handler(config)
+    }
+
+    fn init(&mut self) -> Result<()> {
+        self.refresh();
+        self.system_info = self.read_system_info();
+        self.hostname = self.source.hostname().ok();
+        if let Some(wifi) = self.wifi.as_mut() {
+            wifi.init()?;
+        }
+        if let Some(power) = self.power.as_mut() {
+            power.init()?;
+        }
+        self.battery.init()
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick {
+            if self.cpu_cadence.due() {
+                self.refresh();
+            }
+            if self.clock_cadence.due() {
+                self.clock = Local::now();
+                self.dirty = true;
+            }
+        }
+        if let Some(wifi) = self.wifi.as_mut() {
+            wifi.update(action.clone())?;
+        }
+        if let Some(power) = self.power.as_mut() {
+            if let Some(action) = power.update(action.clone())? {
+                return Ok(Some(action));
+            }
+        }
+        let tick = action == Action::Tick;
+        // a title left unsent goes with the next tick
+        match self.battery.update(action)? {
+            Some(action) => Ok(Some(action)),
+            None if tick => Ok(self.update_title()),
+            None => Ok(None),
+        }
+    }
+
+    fn describe(&self) -> Vec<String> {
+        let host = self
+            .hostname
+            .as_ref()
+            .map(|hostname| format!(" on {hostname}"))
+            .unwrap_or_default();
+        vec![format!(
+            "brt{host}: cpu {:.0}% busy, memory {:.0}% used.",
+            self.cpu,
+            self.memory.percentage()
+        )]
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        // all are taken, so none stays dirty after the next draw
+        let wifi = self.wifi.as_mut().is_some_and(Wifi::take_dirty);
+        let power = self.power.as_mut().is_some_and(Power::take_dirty);
+        std::mem::take(&mut self.dirty) | wifi | power | self.battery.take_dirty()
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let compact = rect.width < self.compact_width;
+        let version = if compact {
+            "brt".to_string()
+        } else {
+            format!("brt {}", env!("CARGO_PKG_VERSION"))
+        };
+        let mut version = Line::from(version.dim());
+        let locale = i18n::locale();
+        let format = self.clock_config.format(&locale, compact);
+        let clock =
+            Line::from(locale.format_time(&self.clock, &format)).alignment(Alignment::Center);
+        let summary = self.summary(compact);
+        let wifi = self.wifi.as_ref().map(Wifi::line).unwrap_or_default();
+        let power = self.power.as_ref().map(Power::line).unwrap_or_default();
+        let battery = self.battery.line();
+        let taken = |lines: &[&Line]| -> u16 {
+            lines.iter().map(|line| line.width() as u16).sum::<u16>()
+                + clock.width() as u16
+                + CLOCK_MARGIN
+        };
+        // only when everything else fits
+        let lines = [
+            &version,
+            &summary,
+            &wifi,
+            &power,
+            &battery,
+            &self.system_info,
+        ];
+        if self.show_system_info && !compact && taken(&lines) <= rect.width {
+            version.spans.extend(self.system_info.spans.clone());
+        }
+
+        let layout = Layout::new(
+            Direction::Horizontal,
+            [
+                Constraint::Length(version.width() as u16),
+                Constraint::Fill(1),
+                Constraint::Length(summary.width() as u16),
+                Constraint::Length(wifi.width() as u16),
+                Constraint::Length(power.width() as u16),
+                Constraint::Length(battery.width() as u16),
+            ],
+        )
+        .split(rect);
+
+        f.render_widget(version, layout[0]);
+        f.render_widget(clock, layout[1]);
+        f.render_widget(summary, layout[2]);
+        f.render_widget(wifi, layout[3]);
+        f.render_widget(power, layout[4]);
+        f.render_widget(battery, layout[5]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use battery::State;
+    use ratatui::{backend::TestBackend, prelude::*};
+
+    use super::*;
+    use crate::components::battery::PowerReading;
+    use crate::source::fake::FakeProcSource;
+
+    #[derive(Debug)]
+    struct FakePowerSource;
+
+    impl PowerSource for FakePowerSource {
+        fn read(&mut self) -> Option<PowerReading> {
+            Some(PowerReading {
+                state: State::Discharging,
+                state_of_charge: 0.8,
+                charge_limit: None,
+                time_left: None,
+            })
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        let mut header = Header::with_sources(
+            Box::new(FakeProcSource::default()),
+            Box::new(FakePowerSource),
+        );
+        header.init().unwrap();
+        assert_eq!(header.cpu, 25.0);
+        assert_eq!(header.memory.percentage(), 25.0);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 1)).unwrap();
+        terminal
+            .draw(|frame| header.draw(frame, frame.size()).unwrap())
+            .unwrap();
+        let line: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(line.starts_with(&format!("brt {}", env!("CARGO_PKG_VERSION"))));
+        assert!(line.ends_with("cpu ■■■■■  25% mem ■■■■■  25% BAT▼ 80% ■■■■■■■■■■"));
+
+        // a full bar ends in the reddest color
+        header.cpu = 100.0;
+        let summary = header.summary(false);
+        assert_eq!(summary.spans[5].style.fg, Some(GREEN_TO_RED[9]));
+    }
+
+    #[test]
+    fn test_header_cpu_since_previous_tick() {
+        let mut header = Header::with_sources(
+            Box::new(FakeProcSource::default()),
+            Box::new(FakePowerSource),
+        );
+        header.init().unwrap();
+        header.update(Action::Tick).unwrap();
+        // the fake counters didn't move
+        assert_eq!(header.cpu, 0.0);
+    }
+
+    #[test]
+    fn test_compact_header() {
+        let mut header = Header::with_sources(
+            Box::new(FakeProcSource::default()),
+            Box::new(FakePowerSource),
+        );
+        header.init().unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 1)).unwrap();
+        terminal
+            .draw(|frame| header.draw(frame, frame.size()).unwrap())
+            .unwrap();
+        let line: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(line.starts_with("brt "));
+        assert!(!line.contains(env!("CARGO_PKG_VERSION")));
+        assert!(line.ends_with("cpu  25% mem  25% BAT▼ 80% ■■■■■■■■■■"));
+    }
+
+    #[test]
+    fn test_system_info() {
+        let mut header = Header::with_sources(
+            Box::new(FakeProcSource::default()),
+            Box::new(FakePowerSource),
+        );
+        header.init().unwrap();
+        let info = header.system_info.to_string();
+        assert!(info.starts_with(" brt-host 6.8.0-brt booted 2023-11-1"));
+
+        let draw = |header: &mut Header| -> String {
+            let mut terminal = Terminal::new(TestBackend::new(140, 1)).unwrap();
+            terminal
+                .draw(|frame| header.draw(frame, frame.size()).unwrap())
+                .unwrap();
+            terminal
+                .backend()
+                .buffer()
+                .content()
+                .iter()
+                .map(|c| c.symbol())
+                .collect()
+        };
+        assert!(draw(&mut header).contains(&info));
+        header.show_system_info = false;
+        assert!(!draw(&mut header).contains("brt-host"));
+    }
+
+    #[test]
+    fn test_title() {
+        let mut header = Header::with_sources(
+            Box::new(FakeProcSource::default()),
+            Box::new(FakePowerSource),
+        );
+        header.init().unwrap();
+        assert_eq!(
+            header.update_title(),
+            Some(Action::SetTitle(
+                "brt — brt-host — cpu 25% mem 25%".to_string()
+            ))
+        );
+        // unchanged
+        assert_eq!(header.update_title(), None);
+        header.cpu = 42.4;
+        header.hostname = None;
+        assert_eq!(
+            header.update_title(),
+            Some(Action::SetTitle("brt — cpu 42% mem 25%".to_string()))
+        );
+
+        header.show_title = false;
+        header.cpu = 0.0;
+        assert_eq!(header.update_title(), None);
+    }
+
+    #[test]
+    fn test_describe() {
+        let mut header = Header::with_sources(
+            Box::new(FakeProcSource::default()),
+            Box::new(FakePowerSource),
+        );
+        header.init().unwrap();
+        assert_eq!(
+            header.describe(),
+            vec!["brt on brt-host: cpu 25% busy, memory 25% used."]
+        );
+    }
+}
@@ -0,0 +1,598 @@
+use std::collections::{HashMap, VecDeque};
+use std::ffi::CStr;
+use std::fmt::Debug;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use std::time::Instant;
+
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph, Sparkline};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::cpufreq::sparkline;
+use crate::components::Component;
+use crate::config::{Config, Intervals};
+use crate::hints::Hint;
+use crate::model::format_bytes;
+use crate::staleness::Sample;
+use crate::symbols::symbols;
+use crate::tui::Frame;
+
+/// The number of rates in the sparklines of the interfaces.
+const HISTORY_LENGTH: usize = 20;
+/// The number of rates kept for the graphs of the selected interface.
+const DETAIL_HISTORY_LENGTH: usize = 240;
+const SYS_CLASS_NET: &str = "/sys/class/net";
+/// The width of the interface names.
+const NAME_WIDTH: usize = 12;
+
+/// The bytes an interface received and sent since boot, and the packets it
+/// couldn't.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceCounters {
+    pub name: String,
+    pub received: u64,
+    pub sent: u64,
+    pub receive_errors: u64,
+    pub receive_drops: u64,
+    pub send_errors: u64,
+    pub send_drops: u64,
+}
+
+/// Parses `/proc/net/dev`, skipping its two header lines.
+pub fn parse_net_dev(text: &str) -> Vec<InterfaceCounters> {
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let counters: Vec<u64> = counters
+                .split_whitespace()
+                .filter_map(|counter| counter.parse().ok())
+                .collect();
+            Some(InterfaceCounters {
+                name: name.trim().to_string(),
+                received: *counters.first()?,
+                sent: *counters.get(8)?,
+                receive_errors: *counters.get(2)?,
+                receive_drops: *counters.get(3)?,
+                send_errors: *counters.get(10)?,
+                send_drops: *counters.get(11)?,
+            })
+        })
+        .collect()
+}
+
+/// What an interface is connected with; what the system doesn't tell is
+/// left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceLink {
+    /// The operational state, e.g. `up`, `down` or `unknown`.
+    pub state: String,
+    /// The negotiated speed in Mbit/s.
+    pub speed: Option<u64>,
+    pub mtu: Option<u64>,
+    /// The hardware address, e.g. `52:54:00:12:34:56`.
+    pub mac: Option<String>,
+    /// With their prefix length, e.g. `192.168.1.5/24`.
+    pub addresses: Vec<String>,
+}
+
+/// Something that can tell the counters of the network interfaces.
+pub trait InterfaceSource: Debug + Send {
+    fn read(&self) -> Vec<InterfaceCounters>;
+    /// Read when the details of the interface are shown.
+    fn link(&self, name: &str) -> InterfaceLink;
+}
+
+/// Reads `/proc/net/dev`, and the links from `/sys/class/net`.
+#[derive(Debug, Default, Clone)]
+pub struct ProcInterfaceSource;
+
+impl InterfaceSource for ProcInterfaceSource {
+    fn read(&self) -> Vec<InterfaceCounters> {
+        fs::read_to_string("/proc/net/dev")
+            .map(|text| parse_net_dev(&text))
+            .unwrap_or_default()
+    }
+
+    fn link(&self, name: &str) -> InterfaceLink {
+        let directory = Path::new(SYS_CLASS_NET).join(name);
+        let read = |file: &str| {
+            fs::read_to_string(directory.join(file))
+                .ok()
+                .map(|value| value.trim().to_string())
+        };
+        InterfaceLink {
+            state: read("operstate").unwrap_or_else(|| "unknown".to_string()),
+            // -1, or unreadable, while there is no link
+            speed: read("speed")
+                .and_then(|speed| speed.parse::<i64>().ok())
+                .and_then(|speed| u64::try_from(speed).ok())
+                .filter(|speed| *speed > 0),
+            mtu: read("mtu").and_then(|mtu| mtu.parse().ok()),
+            mac: read("address").filter(|mac| !mac.is_empty() && mac != "00:00:00:00:00:00"),
+            addresses: addresses(name),
+        }
+    }
+}
+
+/// The number of leading ones of a netmask.
+fn prefix_length(netmask: &[u8]) -> u32 {
+    netmask
+        .iter()
+        .map(|byte| byte.leading_ones())
+        .take_while(|ones| *ones > 0)
+        .sum()
+}
+
+/// The IPv4 and IPv6 addresses of an interface, from `getifaddrs`.
+fn addresses(name: &str) -> Vec<String> {
+    let mut first: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: the list is freed below, and only read before that.
+    if unsafe { libc::getifaddrs(&mut first) } != 0 {
+        return vec![];
+    }
+    let mut addresses = vec![];
+    let mut current = first;
+    // SAFETY: the entries, their names and their addresses of the family
+    // they tell are valid until the list is freed.
+    while let Some(entry) = unsafe { current.as_ref() } {
+        current = entry.ifa_next;
+        let Some(address) = (unsafe { entry.ifa_addr.as_ref() }) else {
+            continue;
+        };
+        if unsafe { CStr::from_ptr(entry.ifa_name) }.to_bytes() != name.as_bytes() {
+            continue;
+        }
+        let (address, netmask): (IpAddr, Vec<u8>) = match i32::from(address.sa_family) {
+            libc::AF_INET => {
+                let address = unsafe { &*entry.ifa_addr.cast::<libc::sockaddr_in>() };
+                let netmask = unsafe { entry.ifa_netmask.cast::<libc::sockaddr_in>().as_ref() };
+                (
+                    Ipv4Addr::from(u32::from_be(address.sin_addr.s_addr)).into(),
+                    netmask.map_or(vec![], |netmask| {
+                        netmask.sin_addr.s_addr.to_ne_bytes().to_vec()
+                    }),
+                )
+            }
+            libc::AF_INET6 => {
+                let address = unsafe { &*entry.ifa_addr.cast::<libc::sockaddr_in6>() };
+                let netmask = unsafe { entry.ifa_netmask.cast::<libc::sockaddr_in6>().as_ref() };
+                (
+                    Ipv6Addr::from(address.sin6_addr.s6_addr).into(),
+                    netmask.map_or(vec![], |netmask| netmask.sin6_addr.s6_addr.to_vec()),
+                )
+            }
+            _ => continue,
+        };
+        addresses.push(format!("{address}/{}", prefix_length(&netmask)));
+    }
+    // SAFETY: the list came from getifaddrs and nothing refers to it anymore.
+    unsafe { libc::freeifaddrs(first) };
+    addresses
+}
+
+/// The received and sent bytes per second of an interface, recent ones last.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InterfaceHistory {
+    pub received: VecDeque<u64>,
+    pub sent: VecDeque<u64>,
+}
+
+impl InterfaceHistory {
+    fn push(&mut self, received: u64, sent: u64) {
+        for (history, rate) in [(&mut self.received, received), (&mut self.sent, sent)] {
+            history.push_back(rate);
+            if history.len() > DETAIL_HISTORY_LENGTH {
+                history.pop_front();
+            }
+        }
+    }
+}
+
+/// The last `length` rates.
+fn recent(rates: &VecDeque<u64>, length: usize) -> VecDeque<u64> {
+    rates
+        .iter()
+        .skip(rates.len().saturating_sub(length))
+        .copied()
+        .collect()
+}
+
+/// The throughput of every network interface, for the network tab.
+#[derive(Debug)]
+pub struct Interfaces {
+    source: Box<dyn InterfaceSource>,
+    counters: Vec<InterfaceCounters>,
+    /// When the counters were read, to tell the rates.
+    read: Instant,
+    history: HashMap<String, InterfaceHistory>,
+    cadence: Cadence,
+    /// When a sample last succeeded.
+    sampled_at: Option<Instant>,
+    /// Changed since it was last drawn.
+    dirty: bool,
+    /// The index of the selected interface.
+    selected: usize,
+    /// The link of the selected interface, while its details are shown.
+    link: Option<InterfaceLink>,
+}
+
+impl Default for Interfaces {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interfaces {
+    pub fn new() -> Self {
+        Self::with_source(Box::new(ProcInterfaceSource))
+    }
+
+    pub fn with_source(source: Box<dyn InterfaceSource>) -> Self {
+        Self {
+            counters: source.read(),
+            source,
+            read: Instant::now(),
+            history: HashMap::new(),
+            cadence: Cadence::from_millis(Intervals::default().cpu),
+            sampled_at: None,
+            dirty: true,
+            selected: 0,
+            link: None,
+        }
+    }
+
+    fn selected(&self) -> Option<&InterfaceCounters> {
+        self.counters.get(self.selected)
+    }
+
+    /// Reads the link of the selected interface, to show its details.
+    fn read_link(&mut self) {
+        self.link = self
+            .selected()
+            .map(|interface| self.source.link(&interface.name));
+    }
+
+    /// Selects another interface, keeping its details shown when they are.
+    fn select(&mut self, step: isize) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(step)
+            .min(self.counters.len().saturating_sub(1));
+        if self.link.is_some() {
+            self.read_link();
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn refresh(&mut self, now: Instant) {
+        self.dirty = true;
+        let counters = self.source.read();
+        if !counters.is_empty() {
+            self.sampled_at = Some(now);
+        }
+        let seconds = now.duration_since(self.read).as_secs_f64();
+        if seconds > 0.0 {
+            let rate =
+                |now: u64, earlier: u64| (now.saturating_sub(earlier) as f64 / seconds) as u64;
+            for interface in &counters {
+                if let Some(earlier) = self.counters.iter().find(|c| c.name == interface.name) {
+                    self.history
+                        .entry(interface.name.clone())
+                        .or_default()
+                        .push(
+                            rate(interface.received, earlier.received),
+                            rate(interface.sent, earlier.sent),
+                        );
+                }
+            }
+        }
+        self.history
+            .retain(|name, _| counters.iter().any(|c| &c.name == name));
+        // the selection stays on the interface when others come or go
+        let selected = self.selected().map(|interface| interface.name.clone());
+        self.counters = counters;
+        self.selected = selected
+            .and_then(|name| self.counters.iter().position(|c| c.name == name))
+            .unwrap_or_default();
+        if self.link.is_some() {
+            self.read_link();
+        }
+        self.read = now;
+    }
+
+    /// E.g. `eth0  ↓ 1.2MiB/s ▁▃▅ ↑ 30KiB/s ▁▁▂`.
+    fn line(name: &str, history: &InterfaceHistory) -> Line<'static> {
+        let mut spans = vec![Span::raw(format!("{name:<NAME_WIDTH$.NAME_WIDTH$}")).bold()];
+        let symbols = symbols();
+        for (arrow, rates) in [
+            (symbols.down, &history.received),
+            (symbols.up, &history.sent),
+        ] {
+            let rates = recent(rates, HISTORY_LENGTH);
+            let rate = rates.back().copied().unwrap_or_default();
+            let max = rates.iter().copied().max().unwrap_or_default();
+            spans.push(Span::raw(format!(" {arrow}{:>9}/s ", format_bytes(rate))));
+            spans.push(Span::raw(sparkline(&rates, 0, max)).dim());
+        }
+        Line::from(spans)
+    }
+
+    /// The link, addresses and error counters of an interface, e.g.
+    /// `up 1000Mbit/s mtu 1500`.
+    fn detail_lines(interface: &InterfaceCounters, link: &InterfaceLink) -> Vec<Line<'static>> {
+        let mut state = vec![Span::raw(link.state.clone()).bold()];
+        if let Some(speed) = link.speed {
+            state.push(Span::raw(format!(" {speed}Mbit/s")));
+        }
+        if let Some(mtu) = link.mtu {
+            state.push(Span::raw(format!(" mtu {mtu}")));
+        }
+        let mut lines = vec![Line::from(state)];
+        if let Some(mac) = &link.mac {
+            lines.push(Line::from(vec!["mac ".dim(), Span::raw(mac.clone())]));
+        }
+        for address in &link.addresses {
+            lines.push(Line::from(vec!["addr ".dim(), Span::raw(address.clone())]));
+        }
+        let symbols = symbols();
+        for (arrow, errors, drops) in [
+            (
+                symbols.down,
+                interface.receive_errors,
+                interface.receive_drops,
+            ),
+            (symbols.up, interface.send_errors, interface.send_drops),
+        ] {
+            let counters = Span::raw(format!("{arrow} {errors} errors {drops} drops"));
+            lines.push(Line::from(if errors + drops > 0 {
+                counters.red()
+            } else {
+                counters
+            }));
+        }
+        lines
+    }
+
+    /// The details of the selected interface above graphs of its rates.
+    fn draw_details(&self, f: &mut Frame<'_>, rect: Rect, link: &InterfaceLink) {
+        let Some(interface) = self.selected() else {
+            return;
+        };
+        let block = Block::default()
+            .title(Title::from(interface.name.clone()).alignment(Alignment::Center))
+            .title(Title::from("esc".dim()).alignment(Alignment::Right))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        let inner = block.inner(rect);
+        f.render_widget(block, rect);
+        let lines = Self::detail_lines(interface, link);
+        let [text, received, sent] = *Layout::vertical([
+            Constraint::Length(lines.len() as u16),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ])
+        .split(inner) else {
+            return;
+        };
+        f.render_widget(Paragraph::new(lines), text);
+        let empty = InterfaceHistory::default();
+        let history = self.history.get(&interface.name).unwrap_or(&empty);
+        let symbols = symbols();
+        for (arrow, rates, area) in [
+            (symbols.down, &history.received, received),
+            (symbols.up, &history.sent, sent),
+        ] {
+            // the most recent rates that fit, at the right
+            let rates: Vec<u64> = recent(rates, area.width as usize).into();
+            let rate = rates.last().copied().unwrap_or_default();
+            let title = format!("{arrow} {}/s", format_bytes(rate));
+            let graph = Sparkline::default()
+                .block(Block::default().title(title.dim()))
+                .data(&rates)
+                .bar_set(symbols.levels.clone());
+            f.render_widget(graph, area);
+        }
+    }
+}
+
+impl Component for Interfaces {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.cadence = Cadence::from_millis(config.config.intervals.cpu);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick && self.cadence.due() {
+            self.refresh(Instant::now());
+        }
+        Ok(None)
+    }
+
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        match key.code {
+            KeyCode::Up => self.select(-1),
+            KeyCode::Down => self.select(1),
+            KeyCode::Enter if self.link.is_none() => self.read_link(),
+            KeyCode::Enter | KeyCode::Esc => self.link = None,
+            _ => return Ok(None),
+        }
+        self.dirty = true;
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn sampled(&self) -> Option<Sample> {
+        Sample::of("net", self.sampled_at, &self.cadence)
+    }
+
+    fn hints(&self) -> Vec<Hint> {
+        if self.link.is_some() {
+            return vec![Hint::new("esc", "close")];
+        }
+        let symbols = symbols();
+        vec![
+            Hint::new(format!("{}{}", symbols.up, symbols.down), "select"),
+            Hint::new("enter", "details"),
+        ]
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        if let Some(link) = &self.link {
+            self.draw_details(f, rect, link);
+            return Ok(());
+        }
+        let empty = InterfaceHistory::default();
+        let lines: Vec<Line> = self
+            .counters
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let line = Self::line(&c.name, self.history.get(&c.name).unwrap_or(&empty));
+                if i == self.selected {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect();
+        let block = Block::default()
+            .title(Title::from("network").alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        f.render_widget(Paragraph::new(lines).block(block), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    const NET_DEV: &str = "Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:    1000      10    0    0    0     0          0         0     1000      10    0    0    0     0       0          0
+  eth0: 2000000    1500    3    1    0     0          0         0    40000     300    0    2    0     0       0          0
+";
+
+    #[derive(Debug)]
+    struct FakeInterfaceSource(String);
+
+    impl InterfaceSource for FakeInterfaceSource {
+        fn read(&self) -> Vec<InterfaceCounters> {
+            parse_net_dev(&self.0)
+        }
+
+        fn link(&self, name: &str) -> InterfaceLink {
+            InterfaceLink {
+                state: "up".to_string(),
+                speed: (name == "eth0").then_some(1000),
+                mtu: Some(1500),
+                mac: Some("52:54:00:12:34:56".to_string()),
+                addresses: vec![format!("192.168.1.5/24 {name}")],
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_net_dev() {
+        assert_eq!(
+            parse_net_dev(NET_DEV),
+            vec![
+                InterfaceCounters {
+                    name: "lo".to_string(),
+                    received: 1000,
+                    sent: 1000,
+                    ..InterfaceCounters::default()
+                },
+                InterfaceCounters {
+                    name: "eth0".to_string(),
+                    received: 2_000_000,
+                    sent: 40_000,
+                    receive_errors: 3,
+                    receive_drops: 1,
+                    send_errors: 0,
+                    send_drops: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interfaces() {
+        let mut interfaces =
+            Interfaces::with_source(Box::new(FakeInterfaceSource(NET_DEV.to_string())));
+        let start = interfaces.read;
+        interfaces.source = Box::new(FakeInterfaceSource(
+            NET_DEV
+                .replace("2000000", "4097152")
+                .replace("40000     300", "60480     300"),
+        ));
+        interfaces.refresh(start + Duration::from_secs(2));
+        let eth0 = &interfaces.history["eth0"];
+        assert_eq!(eth0.received, VecDeque::from([1_048_576]));
+        assert_eq!(eth0.sent, VecDeque::from([10_240]));
+        assert_eq!(
+            Interfaces::line("eth0", eth0).to_string(),
+            "eth0         ↓     1MiB/s █ ↑    10KiB/s █"
+        );
+    }
+
+    #[test]
+    fn test_prefix_length() {
+        assert_eq!(prefix_length(&[255, 255, 255, 0]), 24);
+        assert_eq!(prefix_length(&[255, 255, 240, 0]), 20);
+        assert_eq!(prefix_length(&[0; 16]), 0);
+        assert_eq!(prefix_length(&[]), 0);
+    }
+
+    #[test]
+    fn test_details() {
+        let mut interfaces =
+            Interfaces::with_source(Box::new(FakeInterfaceSource(NET_DEV.to_string())));
+        let key = |interfaces: &mut Interfaces, code: KeyCode| {
+            interfaces.handle_key_events(KeyEvent::from(code)).unwrap()
+        };
+        key(&mut interfaces, KeyCode::Down);
+        key(&mut interfaces, KeyCode::Down);
+        assert_eq!(interfaces.selected().unwrap().name, "eth0");
+        key(&mut interfaces, KeyCode::Enter);
+        let link = interfaces.link.clone().unwrap();
+        assert_eq!(link.addresses, vec!["192.168.1.5/24 eth0"]);
+        let lines: Vec<String> = Interfaces::detail_lines(interfaces.selected().unwrap(), &link)
+            .iter()
+            .map(Line::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "up 1000Mbit/s mtu 1500",
+                "mac 52:54:00:12:34:56",
+                "addr 192.168.1.5/24 eth0",
+                "↓ 3 errors 1 drops",
+                "↑ 0 errors 2 drops",
+            ]
+        );
+
+        // moving on shows the details of the next interface
+        key(&mut interfaces, KeyCode::Up);
+        assert_eq!(interfaces.link.as_ref().unwrap().speed, None);
+        key(&mut interfaces, KeyCode::Esc);
+        assert_eq!(interfaces.link, None);
+    }
+}
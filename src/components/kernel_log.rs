@@ -0,0 +1,369 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use color_eyre::eyre::Result;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph};
+
+use crate::action::Action;
+use crate::components::Component;
+#[cfg(feature = "journald")]
+use crate::journal::{read_json, JsonEntry};
+use crate::tui::Frame;
+
+/// The number of messages kept.
+const MAX_MESSAGES: usize = 200;
+/// How often new messages are read.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// The size of the largest record `/dev/kmsg` hands out.
+const RECORD_SIZE: usize = 8192;
+
+/// A message of the kernel, e.g. of the OOM killer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelMessage {
+    /// From 0 for emergencies to 7 for debugging, as in syslog.
+    pub priority: u8,
+    /// Microseconds since boot.
+    pub timestamp: u64,
+    pub text: String,
+}
+
+impl KernelMessage {
+    /// E.g. `[  812.345678] Out of memory: Killed process 4242 (java)`, red
+    /// for errors, yellow for warnings and dimmed for debugging.
+    fn line(&self) -> Line<'static> {
+        let style = match self.priority {
+            0..=3 => Style::default().fg(Color::Red),
+            4 => Style::default().fg(Color::Yellow),
+            7 => Style::default().dim(),
+            _ => Style::default(),
+        };
+        Line::from(vec![
+            Span::raw(format!(
+                "[{:>5}.{:06}] ",
+                self.timestamp / 1_000_000,
+                self.timestamp % 1_000_000
+            ))
+            .dim(),
+            Span::styled(self.text.clone(), style),
+        ])
+    }
+}
+
+/// Parses a record of `/dev/kmsg`, e.g. `6,1234,5678901,-;usb 1-1: new
+/// device`, where the priority carries the facility in its higher bits.
+/// The lines after the first, which start with a space, describe the device
+/// and are left out.
+fn parse_kmsg(record: &str) -> Option<KernelMessage> {
+    let (prefix, text) = record.split_once(';')?;
+    let mut fields = prefix.split(',');
+    let priority = fields.next()?.parse::<u32>().ok()? & 7;
+    let timestamp = fields.nth(1)?.parse().ok()?;
+    Some(KernelMessage {
+        priority: priority as u8,
+        timestamp,
+        text: text.lines().next().unwrap_or_default().to_string(),
+    })
+}
+
+/// Something that tells the messages of the kernel.
+pub trait KernelLogSource: Debug + Send {
+    /// The messages logged since the previous read, the oldest first; the
+    /// first read returns the ones still in the ring buffer.
+    fn read(&mut self) -> io::Result<Vec<KernelMessage>>;
+}
+
+/// Reads the ring buffer through `/dev/kmsg`, which needs `CAP_SYSLOG` when
+/// `kernel.dmesg_restrict` is set.
+#[derive(Debug, Default)]
+pub struct KmsgSource {
+    file: Option<File>,
+}
+
+impl KernelLogSource for KmsgSource {
+    fn read(&mut self) -> io::Result<Vec<KernelMessage>> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(
+                File::options()
+                    .read(true)
+                    .custom_flags(libc::O_NONBLOCK)
+                    .open("/dev/kmsg")?,
+            ),
+        };
+        let mut messages = vec![];
+        let mut record = vec![0; RECORD_SIZE];
+        loop {
+            // every read returns a single record
+            match file.read(&mut record) {
+                Ok(0) => break,
+                Ok(length) => {
+                    messages.extend(parse_kmsg(&String::from_utf8_lossy(&record[..length])))
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                // records were overwritten before they were read
+                Err(e) if e.raw_os_error() == Some(libc::EPIPE) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(messages)
+    }
+}
+
+/// Reads the kernel messages of the current boot from the systemd journal,
+/// through `journalctl`.
+#[cfg(feature = "journald")]
+#[derive(Debug, Default)]
+pub struct JournalSource {
+    /// Where the previous read ended.
+    cursor: Option<String>,
+}
+
+/// A kernel message and the cursor after it, of an entry of the journal.
+#[cfg(feature = "journald")]
+fn from_journal(entry: JsonEntry) -> Option<(KernelMessage, String)> {
+    let message = KernelMessage {
+        priority: entry.priority,
+        timestamp: entry.monotonic?,
+        text: entry.message,
+    };
+    Some((message, entry.cursor?))
+}
+
+#[cfg(feature = "journald")]
+impl KernelLogSource for JournalSource {
+    fn read(&mut self) -> io::Result<Vec<KernelMessage>> {
+        let from = match &self.cursor {
+            Some(cursor) => format!("--after-cursor={cursor}"),
+            None => format!("--lines={MAX_MESSAGES}"),
+        };
+        let mut messages = vec![];
+        for entry in read_json(["--dmesg".to_string(), from])? {
+            if let Some((message, cursor)) = from_journal(entry) {
+                messages.push(message);
+                self.cursor = Some(cursor);
+            }
+        }
+        Ok(messages)
+    }
+}
+
+/// The latest messages of the kernel, so OOM kills and hardware errors show
+/// up next to the processes.
+#[derive(Debug)]
+pub struct KernelLog {
+    /// The reads of the source, which runs on a thread of its own as
+    /// `journalctl` can take a while.
+    reads: Receiver<io::Result<Vec<KernelMessage>>>,
+    messages: VecDeque<KernelMessage>,
+    /// Why the messages couldn't be read last time, e.g. without
+    /// `CAP_SYSLOG`.
+    error: Option<String>,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for KernelLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KernelLog {
+    /// Reads the journal when brt is built with it, and `/dev/kmsg` otherwise.
+    pub fn new() -> Self {
+        #[cfg(feature = "journald")]
+        return Self::with_source(Box::new(JournalSource::default()));
+        #[cfg(not(feature = "journald"))]
+        return Self::with_source(Box::new(KmsgSource::default()));
+    }
+
+    pub fn with_source(source: Box<dyn KernelLogSource>) -> Self {
+        Self::reading(source, REFRESH_INTERVAL)
+    }
+
+    /// Reads `source` every `interval` on a thread, failed reads included,
+    /// until the log is dropped.
+    fn reading(mut source: Box<dyn KernelLogSource>, interval: Duration) -> Self {
+        // a read waits for the previous one to be taken
+        let (tx, reads) = mpsc::sync_channel(1);
+        thread::spawn(move || {
+            while tx.send(source.read()).is_ok() {
+                thread::sleep(interval);
+            }
+        });
+        Self {
+            reads,
+            messages: VecDeque::new(),
+            error: None,
+            dirty: true,
+        }
+    }
+
+    /// Takes in the reads made since the last tick.
+    fn receive(&mut self) {
+        while let Ok(read) = self.reads.try_recv() {
+            self.apply(read);
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn apply(&mut self, read: io::Result<Vec<KernelMessage>>) {
+        match read {
+            Ok(messages) => {
+                if self.error.take().is_some() {
+                    self.dirty = true;
+                }
+                if messages.is_empty() {
+                    return;
+                }
+                self.dirty = true;
+                self.messages.extend(messages);
+                let excess = self.messages.len().saturating_sub(MAX_MESSAGES);
+                self.messages.drain(..excess);
+            }
+            Err(e) => {
+                self.dirty = true;
+                self.error = Some(format!("Unable to read the kernel messages: {e}"));
+            }
+        }
+    }
+
+    /// The last lines that fit in `height`.
+    fn lines(&self, height: usize) -> Vec<Line<'static>> {
+        if let Some(error) = &self.error {
+            return vec![Line::from(error.clone()).dim()];
+        }
+        let skip = self.messages.len().saturating_sub(height);
+        self.messages
+            .iter()
+            .skip(skip)
+            .map(KernelMessage::line)
+            .collect()
+    }
+}
+
+impl Component for KernelLog {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick {
+            self.receive();
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        if rect.height == 1 {
+            f.render_widget(Paragraph::new(self.lines(1)), rect);
+            return Ok(());
+        }
+        let block = Block::default()
+            .title(Title::from("dmesg").alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        let lines = self.lines(block.inner(rect).height as usize);
+        f.render_widget(Paragraph::new(lines).block(block), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct FakeKernelLogSource(VecDeque<io::Result<Vec<KernelMessage>>>);
+
+    impl KernelLogSource for FakeKernelLogSource {
+        fn read(&mut self) -> io::Result<Vec<KernelMessage>> {
+            self.0.pop_front().unwrap_or(Ok(vec![]))
+        }
+    }
+
+    fn message(priority: u8, text: &str) -> KernelMessage {
+        KernelMessage {
+            priority,
+            timestamp: 812_345_678,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_kmsg() {
+        assert_eq!(
+            parse_kmsg("3,1234,812345678,-;Out of memory: Killed process 4242 (java)\n"),
+            Some(message(3, "Out of memory: Killed process 4242 (java)"))
+        );
+        // the facility of user space messages is left out
+        let record = "12,1235,812345678,-;usb 1-1: reset\n SUBSYSTEM=usb\n DEVICE=c189:1\n";
+        assert_eq!(parse_kmsg(record), Some(message(4, "usb 1-1: reset")));
+        assert_eq!(parse_kmsg("no prefix"), None);
+    }
+
+    #[cfg(feature = "journald")]
+    #[test]
+    fn test_from_journal() {
+        let entry = r#"{"__CURSOR":"s=1;i=2","__MONOTONIC_TIMESTAMP":"812345678","PRIORITY":"3","MESSAGE":"Out of memory"}"#;
+        let entry = crate::journal::parse_json(entry).unwrap();
+        assert_eq!(
+            from_journal(entry),
+            Some((message(3, "Out of memory"), "s=1;i=2".to_string()))
+        );
+        assert_eq!(
+            from_journal(crate::journal::parse_json("{}").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_kernel_log() {
+        let source = FakeKernelLogSource(VecDeque::from([
+            Ok(vec![message(6, "booted"), message(3, "oom")]),
+            Ok(vec![]),
+            Ok((0..MAX_MESSAGES).map(|_| message(7, "debug")).collect()),
+            Err(io::Error::from(io::ErrorKind::PermissionDenied)),
+        ]));
+        let mut log = KernelLog::reading(Box::new(source), Duration::ZERO);
+        let next = |log: &mut KernelLog| {
+            let read = log.reads.recv().unwrap();
+            log.apply(read);
+        };
+        assert!(log.lines(1).is_empty());
+        next(&mut log);
+        let lines = log.lines(1);
+        assert_eq!(lines[0].to_string(), "[  812.345678] oom");
+        assert_eq!(lines[0].spans[1].style.fg, Some(Color::Red));
+        assert!(log.take_dirty());
+
+        next(&mut log);
+        assert!(!log.take_dirty());
+        next(&mut log);
+        assert_eq!(log.messages.len(), MAX_MESSAGES);
+        assert_eq!(log.lines(5).len(), 5);
+
+        next(&mut log);
+        assert_eq!(
+            log.lines(5)[0].to_string(),
+            "Unable to read the kernel messages: permission denied"
+        );
+
+        // it keeps reading after a failure
+        log.take_dirty();
+        next(&mut log);
+        assert!(log.take_dirty());
+        assert_eq!(log.lines(1)[0].to_string(), "[  812.345678] debug");
+    }
+}
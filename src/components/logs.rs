@@ -0,0 +1,172 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use color_eyre::eyre::Result;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::Component;
+use crate::session::Session;
+use crate::tui::Frame;
+use crate::utils::{get_data_dir, LOG_FILE};
+
+/// Only the end of the log file is read.
+const TAIL_BYTES: u64 = 64 * 1024;
+const MAX_LINES: usize = 200;
+/// How often the file is read again while it is shown.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The last lines of a file, at most `max_lines` of them.
+fn tail(path: &Path, max_lines: usize) -> io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let length = file.metadata()?.len();
+    let start = length.saturating_sub(TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let mut lines: Vec<&str> = text.lines().collect();
+    if start > 0 && !lines.is_empty() {
+        // the first line is most likely cut in half
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(max_lines);
+    Ok(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+/// The end of `brt.log`, shown over the bottom of its pane after
+/// [`Action::ToggleLogs`].
+#[derive(Debug)]
+pub struct Logs {
+    path: PathBuf,
+    visible: bool,
+    lines: Vec<String>,
+    cadence: Cadence,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for Logs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Logs {
+    pub fn new() -> Self {
+        Self::with_path(get_data_dir().join(LOG_FILE.clone()))
+    }
+
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            path,
+            visible: false,
+            lines: vec![],
+            cadence: Cadence::new(REFRESH_INTERVAL),
+            dirty: true,
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.dirty = true;
+        self.lines = match tail(&self.path, MAX_LINES) {
+            Ok(lines) => lines,
+            Err(e) => vec![format!("Unable to read {}: {e}", self.path.display())],
+        };
+    }
+}
+
+impl Component for Logs {
+    fn save_session(&self, session: &mut Session) {
+        session.show_logs = self.visible;
+    }
+
+    fn restore_session(&mut self, session: &Session) {
+        self.visible = session.show_logs;
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::ToggleLogs => {
+                self.visible = !self.visible;
+                self.dirty = true;
+                if self.visible {
+                    self.refresh();
+                }
+            }
+            Action::Tick if self.visible && self.cadence.due() => self.refresh(),
+            _ => (),
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+        let layout = Layout::new(
+            Direction::Vertical,
+            [Constraint::Fill(2), Constraint::Fill(1)],
+        )
+        .split(rect);
+        let area = layout[1];
+        let height = area.height.saturating_sub(2) as usize;
+        let skip = self.lines.len().saturating_sub(height);
+        let lines: Vec<Line> = self.lines[skip..]
+            .iter()
+            .map(|l| Line::from(l.as_str()))
+            .collect();
+        let block = Block::default()
+            .title(Title::from(LOG_FILE.as_str()).alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).block(block), area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("brt-{}-{name}", std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_tail() {
+        let path = log_file("tail.log", "one\ntwo\nthree\n");
+        assert_eq!(tail(&path, 2).unwrap(), vec!["two", "three"]);
+        assert_eq!(tail(&path, 5).unwrap().len(), 3);
+
+        let long = "x".repeat(100) + "\n";
+        let path = log_file("long.log", &long.repeat(1000));
+        let lines = tail(&path, usize::MAX).unwrap();
+        assert_eq!(lines.len(), (TAIL_BYTES / 101) as usize);
+        assert!(lines.iter().all(|l| l.len() == 100));
+    }
+
+    #[test]
+    fn test_logs_toggle() {
+        let mut logs = Logs::with_path(log_file("toggle.log", "started\n"));
+        logs.update(Action::Tick).unwrap();
+        assert!(logs.lines.is_empty());
+        logs.update(Action::ToggleLogs).unwrap();
+        assert_eq!(logs.lines, vec!["started"]);
+        logs.update(Action::ToggleLogs).unwrap();
+        assert!(!logs.visible);
+    }
+}
@@ -0,0 +1,250 @@
+use std::time::Instant;
+
+use color_eyre::eyre::Result;
+use procfs_core::{ProcError, ProcResult};
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::Component;
+use crate::config::{Config, Intervals};
+use crate::model::{format_bytes, truncate};
+use crate::source::{system_source, ProcSource};
+use crate::staleness::Sample;
+use crate::symbols::symbols;
+use crate::system::{KernelMemory, MemoryUsage, SlabCache};
+use crate::tui::Frame;
+use crate::widgets::bar::{gradient_bar, GREEN_TO_RED};
+
+const BAR_LENGTH: usize = 5;
+/// The number of slab caches in the details.
+const SLAB_CACHES: usize = 5;
+/// The width of the name of a slab cache.
+const SLAB_NAME_WIDTH: usize = 16;
+
+/// The memory and swap usage, for the performance tab. The huge pages and the
+/// largest slab caches are shown after [`Action::ToggleMemoryDetails`], for
+/// debugging the memory the kernel itself uses.
+#[derive(Debug)]
+pub struct Memory {
+    source: Box<dyn ProcSource>,
+    memory: MemoryUsage,
+    /// The total and free swap, in bytes.
+    swap: (u64, u64),
+    /// Only read while the details are shown.
+    details: Option<KernelMemory>,
+    /// The largest slab caches, or why they can't be read.
+    slab_caches: ProcResult<Vec<SlabCache>>,
+    cadence: Cadence,
+    /// When a sample last succeeded.
+    sampled_at: Option<Instant>,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::with_source(system_source())
+    }
+
+    pub fn with_source(source: Box<dyn ProcSource>) -> Self {
+        let mut memory = Self {
+            source,
+            memory: MemoryUsage::default(),
+            swap: (0, 0),
+            details: None,
+            slab_caches: Ok(vec![]),
+            cadence: Cadence::from_millis(Intervals::default().cpu),
+            sampled_at: None,
+            dirty: true,
+        };
+        memory.refresh();
+        memory
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn refresh(&mut self) {
+        self.dirty = true;
+        let source = self.source.as_ref();
+        if let Ok(memory) = MemoryUsage::read(source) {
+            self.memory = memory;
+            self.sampled_at = Some(Instant::now());
+        }
+        if let Ok(meminfo) = source.meminfo() {
+            self.swap = (meminfo.swap_total, meminfo.swap_free);
+        }
+        if self.details.is_some() {
+            self.details = Some(KernelMemory::read(source).unwrap_or_default());
+            self.slab_caches = SlabCache::read_largest(source, SLAB_CACHES);
+        }
+    }
+
+    fn toggle_details(&mut self) {
+        self.details = match self.details {
+            Some(_) => None,
+            None => Some(KernelMemory::default()),
+        };
+        self.refresh();
+    }
+
+    /// E.g. `mem  ■■■□□ 4GiB/16GiB`.
+    fn usage_line(label: &str, used: u64, total: u64) -> Line<'static> {
+        let fraction = used as f32 / total.max(1) as f32;
+        let mut spans = vec![Span::raw(format!("{label:<5}")).dim()];
+        let symbols = symbols();
+        let glyphs = (symbols.bar, symbols.bar_empty);
+        spans.extend(gradient_bar(fraction, BAR_LENGTH, &GREEN_TO_RED, glyphs).spans);
+        spans.push(Span::raw(format!(
+            " {}/{}",
+            format_bytes(used),
+            format_bytes(total)
+        )));
+        Line::from(spans)
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let (swap_total, swap_free) = self.swap;
+        let mut lines = vec![
+            Self::usage_line("mem", self.memory.used(), self.memory.total),
+            Self::usage_line("swap", swap_total.saturating_sub(swap_free), swap_total),
+        ];
+        let Some(kernel) = &self.details else {
+            return lines;
+        };
+        let label = |text: &str| Span::raw(format!("{text:<5}")).dim();
+        let huge = if kernel.hugepages_total == 0 {
+            "none".to_string()
+        } else {
+            format!(
+                "{}/{} {} {}, {} rsvd",
+                kernel.hugepages_used(),
+                kernel.hugepages_total,
+                symbols().times,
+                format_bytes(kernel.hugepage_size),
+                kernel.hugepages_reserved
+            )
+        };
+        lines.push(Line::from(vec![label("huge"), Span::raw(huge)]));
+        lines.push(Line::from(vec![
+            label("thp"),
+            Span::raw(format_bytes(kernel.anon_hugepages)),
+        ]));
+        lines.push(Line::from(vec![
+            label("slab"),
+            Span::raw(format!(
+                "{}, {} reclaimable",
+                format_bytes(kernel.slab),
+                format_bytes(kernel.slab_reclaimable)
+            )),
+        ]));
+        match &self.slab_caches {
+            Ok(caches) => lines.extend(caches.iter().map(|cache| {
+                Line::from(format!(
+                    "  {:<SLAB_NAME_WIDTH$} {:>8}",
+                    truncate(&cache.name, SLAB_NAME_WIDTH),
+                    format_bytes(cache.bytes)
+                ))
+            })),
+            Err(ProcError::PermissionDenied(_)) => {
+                lines.push(Line::from("  slabinfo needs root".dim()));
+            }
+            Err(_) => (),
+        }
+        lines
+    }
+}
+
+impl Component for Memory {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.cadence = Cadence::from_millis(config.config.intervals.cpu);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::ToggleMemoryDetails => self.toggle_details(),
+            Action::Tick if self.cadence.due() => self.refresh(),
+            _ => (),
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn sampled(&self) -> Option<Sample> {
+        Sample::of("mem", self.sampled_at, &self.cadence)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let block = Block::default()
+            .title(Title::from("memory").alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        f.render_widget(Paragraph::new(self.lines()).block(block), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProcSource;
+
+    #[test]
+    fn test_memory() {
+        let mut source = FakeProcSource::default();
+        source.meminfo += "HugePages_Total:     512
+HugePages_Free:      510
+HugePages_Rsvd:        4
+Hugepagesize:       2048 kB
+";
+        let mut memory = Memory::with_source(Box::new(source.clone()));
+        let text = |memory: &Memory| -> Vec<String> {
+            memory.lines().iter().map(|line| line.to_string()).collect()
+        };
+        assert_eq!(
+            text(&memory),
+            vec!["mem  ■■■■■ 4GiB/16GiB", "swap ■■■■■ 0B/2GiB"]
+        );
+        // a full bar ends in the reddest color
+        let full = Memory::usage_line("mem", 1, 1);
+        assert_eq!(full.spans[BAR_LENGTH].style.fg, Some(GREEN_TO_RED[9]));
+
+        memory.update(Action::ToggleMemoryDetails).unwrap();
+        assert_eq!(
+            text(&memory)[2..],
+            [
+                "huge 2/512 × 2MiB, 4 rsvd",
+                "thp  0B",
+                "slab 256MiB, 0B reclaimable",
+                "  slabinfo needs root",
+            ]
+        );
+
+        source.slabinfo = Some(
+            "slabinfo - version: 2.1
+a_rather_long_cache_name 100 100 1024 4 1 : tunables 0 0 0 : slabdata 25 25 0
+"
+            .to_string(),
+        );
+        memory.source = Box::new(source);
+        memory.refresh();
+        assert_eq!(text(&memory)[5], "  a_rather_long_c…   100KiB");
+
+        memory.update(Action::ToggleMemoryDetails).unwrap();
+        assert_eq!(text(&memory).len(), 2);
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use color_eyre::eyre::Result;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::Component;
+use crate::config::{Config, Intervals};
+use crate::model::format_bytes;
+use crate::staleness::Sample;
+use crate::symbols::symbols;
+use crate::tui::Frame;
+use crate::widgets::bar::{gradient_bar, GREEN_TO_RED};
+
+const NODE_ROOT: &str = "/sys/devices/system/node";
+const BAR_LENGTH: usize = 5;
+
+/// The memory of a single NUMA node, in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeMemory {
+    pub node: usize,
+    pub total: u64,
+    pub free: u64,
+}
+
+impl NodeMemory {
+    fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+}
+
+/// Something that can tell the memory of all NUMA nodes.
+pub trait NodeSource: Debug + Send {
+    /// The nodes ordered by number; empty when the system has no NUMA
+    /// information.
+    fn read(&self) -> Vec<NodeMemory>;
+}
+
+/// Reads `/sys/devices/system/node/node*/meminfo`.
+#[derive(Debug, Clone)]
+pub struct SysfsNodeSource {
+    root: PathBuf,
+}
+
+impl Default for SysfsNodeSource {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from(NODE_ROOT),
+        }
+    }
+}
+
+/// Parses a node's meminfo, where lines look like
+/// `Node 0 MemTotal:        6147400 kB`.
+fn parse_meminfo(node: usize, meminfo: &str) -> Option<NodeMemory> {
+    let mut total = None;
+    let mut free = None;
+    for line in meminfo.lines() {
+        let mut fields = line.split_whitespace().skip(2);
+        let value = match (fields.next(), fields.next()) {
+            (Some(key), Some(value)) => (key, value.parse::<u64>().ok()? * 1024),
+            _ => continue,
+        };
+        match value {
+            ("MemTotal:", bytes) => total = Some(bytes),
+            ("MemFree:", bytes) => free = Some(bytes),
+            _ => (),
+        }
+    }
+    Some(NodeMemory {
+        node,
+        total: total?,
+        free: free?,
+    })
+}
+
+impl NodeSource for SysfsNodeSource {
+    fn read(&self) -> Vec<NodeMemory> {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return vec![];
+        };
+        let mut nodes: Vec<NodeMemory> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let name = entry.file_name();
+                let node = name.to_str()?.strip_prefix("node")?.parse().ok()?;
+                let meminfo = fs::read_to_string(entry.path().join("meminfo")).ok()?;
+                parse_meminfo(node, &meminfo)
+            })
+            .collect();
+        nodes.sort_by_key(|node| node.node);
+        nodes
+    }
+}
+
+/// The bytes a process has on each node, from its `/proc/<pid>/numa_maps`,
+/// where mappings look like
+/// `55c1f33b4000 default file=/usr/bin/head mapped=2 N0=2 kernelpagesize_kB=4`.
+pub fn numa_maps_summary(numa_maps: &str) -> BTreeMap<usize, u64> {
+    let mut nodes = BTreeMap::new();
+    for line in numa_maps.lines() {
+        let page_size = line
+            .split_whitespace()
+            .find_map(|field| field.strip_prefix("kernelpagesize_kB="))
+            .and_then(|kb| kb.parse::<u64>().ok())
+            .unwrap_or(4)
+            * 1024;
+        for field in line.split_whitespace() {
+            let Some((node, pages)) = field.strip_prefix('N').and_then(|f| f.split_once('='))
+            else {
+                continue;
+            };
+            if let (Ok(node), Ok(pages)) = (node.parse(), pages.parse::<u64>()) {
+                *nodes.entry(node).or_default() += pages * page_size;
+            }
+        }
+    }
+    nodes
+}
+
+/// The memory usage of every NUMA node, for multi-socket machines.
+#[derive(Debug)]
+pub struct Numa {
+    source: Box<dyn NodeSource>,
+    nodes: Vec<NodeMemory>,
+    cadence: Cadence,
+    /// When a sample last succeeded.
+    sampled_at: Option<Instant>,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for Numa {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Numa {
+    pub fn new() -> Self {
+        Self::with_source(Box::new(SysfsNodeSource::default()))
+    }
+
+    pub fn with_source(source: Box<dyn NodeSource>) -> Self {
+        let mut numa = Self {
+            source,
+            nodes: vec![],
+            cadence: Cadence::from_millis(Intervals::default().cpu),
+            sampled_at: None,
+            dirty: true,
+        };
+        numa.refresh();
+        numa
+    }
+
+    /// Whether there is more than one node, so the pane tells something.
+    pub fn is_available(&self) -> bool {
+        self.nodes.len() > 1
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn refresh(&mut self) {
+        self.dirty = true;
+        self.nodes = self.source.read();
+        if !self.nodes.is_empty() {
+            self.sampled_at = Some(Instant::now());
+        }
+    }
+
+    /// E.g. `N0 ■■■□□ 4.2GiB/8GiB`.
+    fn line(node: &NodeMemory) -> Line<'static> {
+        let fraction = node.used() as f32 / node.total.max(1) as f32;
+        let mut spans = vec![Span::raw(format!("N{:<2}", node.node)).dim()];
+        let symbols = symbols();
+        let glyphs = (symbols.bar, symbols.bar_empty);
+        spans.extend(gradient_bar(fraction, BAR_LENGTH, &GREEN_TO_RED, glyphs).spans);
+        spans.push(Span::raw(format!(
+            " {}/{}",
+            format_bytes(node.used()),
+            format_bytes(node.total)
+        )));
+        Line::from(spans)
+    }
+}
+
+impl Component for Numa {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.cadence = Cadence::from_millis(config.config.intervals.cpu);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick && self.cadence.due() {
+            self.refresh();
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn sampled(&self) -> Option<Sample> {
+        Sample::of("numa", self.sampled_at, &self.cadence)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let lines: Vec<Line> = self.nodes.iter().map(Self::line).collect();
+        let block = Block::default()
+            .title(Title::from("numa").alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        f.render_widget(Paragraph::new(lines).block(block), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, prelude::*};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct FakeNodeSource(Vec<NodeMemory>);
+
+    impl NodeSource for FakeNodeSource {
+        fn read(&self) -> Vec<NodeMemory> {
+            self.0.clone()
+        }
+    }
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn test_parse_meminfo() {
+        let meminfo = "Node 1 MemTotal:        6147400 kB
+Node 1 MemFree:          467492 kB
+Node 1 MemUsed:         5679908 kB
+Node 1 HugePages_Total:     0
+";
+        assert_eq!(
+            parse_meminfo(1, meminfo),
+            Some(NodeMemory {
+                node: 1,
+                total: 6_147_400 * 1024,
+                free: 467_492 * 1024,
+            })
+        );
+        assert_eq!(parse_meminfo(0, "Node 0 MemUsed: 1 kB\n"), None);
+    }
+
+    #[test]
+    fn test_numa_maps_summary() {
+        let numa_maps = "\
+55c1f33b4000 default file=/usr/bin/head mapped=2 N0=2 kernelpagesize_kB=4
+7f0000000000 interleave:0-1 anon=1024 dirty=1024 N0=512 N1=512 kernelpagesize_kB=4
+7f2000000000 bind:1 huge anon=2 N1=2 kernelpagesize_kB=2048
+7f4000000000 default
+";
+        let summary = numa_maps_summary(numa_maps);
+        assert_eq!(
+            summary,
+            BTreeMap::from([(0, 514 * 4096), (1, 512 * 4096 + 2 * 2048 * 1024)])
+        );
+    }
+
+    #[test]
+    fn test_numa() {
+        let source = FakeNodeSource(vec![
+            NodeMemory {
+                node: 0,
+                total: 8 * GIB,
+                free: 2 * GIB,
+            },
+            NodeMemory {
+                node: 1,
+                total: 8 * GIB,
+                free: 8 * GIB,
+            },
+        ]);
+        let mut numa = Numa::with_source(Box::new(source));
+        assert!(numa.is_available());
+
+        let mut terminal = Terminal::new(TestBackend::new(30, 4)).unwrap();
+        terminal.draw(|f| numa.draw(f, f.size()).unwrap()).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String {
+            (0..30)
+                .map(|x| buffer.get(x, y).symbol().to_string())
+                .collect()
+        };
+        assert_eq!(row(1), "│ N0 ■■■■■ 6GiB/8GiB         │");
+        assert_eq!(row(2), "│ N1 ■■■■■ 0B/8GiB           │");
+        // the fourth of five glyphs
+        assert_eq!(buffer.get(8, 1).fg, GREEN_TO_RED[7]);
+        assert_eq!(buffer.get(9, 1).fg, crate::widgets::bar::EMPTY_COLOR);
+    }
+
+    #[test]
+    fn test_numa_single_node_and_unavailable() {
+        let source = FakeNodeSource(vec![NodeMemory {
+            node: 0,
+            total: GIB,
+            free: GIB,
+        }]);
+        assert!(!Numa::with_source(Box::new(source)).is_available());
+        let source = SysfsNodeSource {
+            root: PathBuf::from("/nonexistent"),
+        };
+        assert!(!Numa::with_source(Box::new(source)).is_available());
+    }
+}
@@ -0,0 +1,478 @@
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use color_eyre::eyre::Result;
+use ratatui::layout::Rect;
+use ratatui::text::Line;
+use tracing::warn;
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::battery::POWER_SUPPLY_ROOT;
+use crate::components::Component;
+use crate::config::{Config, Intervals};
+use crate::i18n::{tr, Text};
+use crate::symbols::symbols;
+use crate::tui::Frame;
+
+/// Where the firmware keeps its power profile and the ones to choose from.
+const PLATFORM_PROFILE_ROOT: &str = "/sys/firmware/acpi";
+
+/// Whether the system runs on mains power, and how it spends it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PowerState {
+    /// Unknown without an AC adapter, e.g. on a desktop.
+    pub ac: Option<bool>,
+    /// E.g. `balanced`, when the platform has profiles.
+    pub profile: Option<String>,
+    /// The profiles that can be switched between, in their order.
+    pub profiles: Vec<String>,
+}
+
+/// Whether any of the mains supplies under `root`, e.g. `AC` or `ADP1`, is
+/// online.
+fn read_ac(root: &Path) -> Option<bool> {
+    let mut online = None;
+    for entry in fs::read_dir(root).ok()?.flatten() {
+        let supply = entry.path();
+        if !fs::read_to_string(supply.join("type")).is_ok_and(|kind| kind.trim() == "Mains") {
+            continue;
+        }
+        if let Ok(value) = fs::read_to_string(supply.join("online")) {
+            online = Some(online.unwrap_or(false) || value.trim() == "1");
+        }
+    }
+    online
+}
+
+/// The platform profile under `root` and its choices.
+fn read_platform_profile(root: &Path) -> (Option<String>, Vec<String>) {
+    let profile = fs::read_to_string(root.join("platform_profile"))
+        .ok()
+        .map(|profile| profile.trim().to_string());
+    let profiles = fs::read_to_string(root.join("platform_profile_choices"))
+        .map(|choices| choices.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+    (profile, profiles)
+}
+
+/// Something that can tell the power state, and switch the profile; it is
+/// used on a thread of its own, as D-Bus calls can hang.
+pub trait PowerProfileSource: Debug + Send {
+    fn read(&mut self) -> PowerState;
+    /// Which may well need more privileges than brt has.
+    fn set_profile(&mut self, profile: &str) -> io::Result<()>;
+}
+
+/// power-profiles-daemon, through which the desktop switches profiles too.
+#[cfg(feature = "power-profiles")]
+mod daemon {
+    use std::collections::HashMap;
+    use std::io;
+
+    use zbus::blocking::{Connection, Proxy};
+    use zbus::zvariant::OwnedValue;
+
+    const NAME: &str = "net.hadess.PowerProfiles";
+    const PATH: &str = "/net/hadess/PowerProfiles";
+
+    #[derive(Debug)]
+    pub struct Daemon {
+        proxy: Proxy<'static>,
+        /// Which don't change while the daemon runs.
+        profiles: Option<Vec<String>>,
+    }
+
+    impl Daemon {
+        pub fn connect() -> zbus::Result<Self> {
+            let connection = Connection::system()?;
+            Ok(Self {
+                proxy: Proxy::new(&connection, NAME, PATH, NAME)?,
+                profiles: None,
+            })
+        }
+
+        pub fn profile(&self) -> zbus::Result<String> {
+            self.proxy.get_property("ActiveProfile")
+        }
+
+        pub fn profiles(&mut self) -> zbus::Result<Vec<String>> {
+            if let Some(profiles) = &self.profiles {
+                return Ok(profiles.clone());
+            }
+            let profiles: Vec<HashMap<String, OwnedValue>> = self.proxy.get_property("Profiles")?;
+            let profiles: Vec<String> = profiles
+                .iter()
+                .filter_map(|profile| <&str>::try_from(&**profile.get("Profile")?).ok())
+                .map(str::to_string)
+                .collect();
+            Ok(self.profiles.insert(profiles).clone())
+        }
+
+        pub fn set_profile(&self, profile: &str) -> io::Result<()> {
+            self.proxy
+                .set_property("ActiveProfile", profile)
+                .map_err(io::Error::other)
+        }
+    }
+}
+
+/// Reads whether the AC adapter is online, and the profile from
+/// power-profiles-daemon when built with it and running, or else from the
+/// firmware.
+#[derive(Debug, Default)]
+pub struct SystemPowerProfileSource {
+    /// Connected to on the first read, `None` inside when it isn't running.
+    #[cfg(feature = "power-profiles")]
+    daemon: Option<Option<daemon::Daemon>>,
+}
+
+impl SystemPowerProfileSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(feature = "power-profiles")]
+    fn daemon(&mut self) -> Option<&mut daemon::Daemon> {
+        self.daemon
+            .get_or_insert_with(|| match daemon::Daemon::connect() {
+                Ok(daemon) => Some(daemon),
+                Err(e) => {
+                    tracing::debug!("Not using power-profiles-daemon: {e}");
+                    None
+                }
+            })
+            .as_mut()
+    }
+}
+
+impl PowerProfileSource for SystemPowerProfileSource {
+    fn read(&mut self) -> PowerState {
+        let ac = read_ac(Path::new(POWER_SUPPLY_ROOT));
+        #[cfg(feature = "power-profiles")]
+        if let Some(daemon) = self.daemon() {
+            if let (Ok(profile), Ok(profiles)) = (daemon.profile(), daemon.profiles()) {
+                return PowerState {
+                    ac,
+                    profile: Some(profile),
+                    profiles,
+                };
+            }
+        }
+        let (profile, profiles) = read_platform_profile(Path::new(PLATFORM_PROFILE_ROOT));
+        PowerState {
+            ac,
+            profile,
+            profiles,
+        }
+    }
+
+    fn set_profile(&mut self, profile: &str) -> io::Result<()> {
+        #[cfg(feature = "power-profiles")]
+        if let Some(daemon) = self.daemon() {
+            return daemon.set_profile(profile);
+        }
+        fs::write(
+            Path::new(PLATFORM_PROFILE_ROOT).join("platform_profile"),
+            profile,
+        )
+    }
+}
+
+/// What the source is asked for on its thread.
+#[derive(Debug)]
+enum Request {
+    Read,
+    Switch(String),
+}
+
+/// What the source answered.
+#[derive(Debug)]
+enum Reply {
+    Read(PowerState),
+    /// The profile switched to, how that went and the state after.
+    Switched(String, io::Result<()>, PowerState),
+}
+
+/// The header segment of the power supply and profile; empty when neither
+/// is known.
+#[derive(Debug)]
+pub struct Power {
+    requests: Sender<Request>,
+    replies: Receiver<Reply>,
+    /// A read was asked for and not answered yet.
+    reading: bool,
+    state: PowerState,
+    cadence: Cadence,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for Power {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Power {
+    pub fn new() -> Self {
+        Self::with_source(Box::new(SystemPowerProfileSource::new()))
+    }
+
+    /// Hands `source` to a thread that answers the requests until the
+    /// component is dropped.
+    pub fn with_source(mut source: Box<dyn PowerProfileSource>) -> Self {
+        let (requests, requested) = mpsc::channel();
+        let (answer, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for request in requested {
+                let reply = match request {
+                    Request::Read => Reply::Read(source.read()),
+                    Request::Switch(profile) => {
+                        let result = source.set_profile(&profile);
+                        Reply::Switched(profile, result, source.read())
+                    }
+                };
+                if answer.send(reply).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            requests,
+            replies,
+            reading: false,
+            state: PowerState::default(),
+            cadence: Cadence::from_millis(Intervals::default().battery),
+            dirty: true,
+        }
+    }
+
+    /// Asks for a read, unless one is on its way.
+    fn read(&mut self) {
+        if !self.reading {
+            self.reading = self.requests.send(Request::Read).is_ok();
+        }
+    }
+
+    /// Asks to switch to the profile after the current one, or tells there
+    /// is none.
+    fn cycle(&mut self) -> Option<Action> {
+        let profiles = &self.state.profiles;
+        let current = self
+            .state
+            .profile
+            .as_ref()
+            .and_then(|profile| profiles.iter().position(|p| p == profile));
+        let Some(next) = profiles.get(current.map_or(0, |i| (i + 1) % profiles.len())) else {
+            return Some(Action::Toast(
+                "There are no power profiles to switch between".to_string(),
+            ));
+        };
+        let _ = self.requests.send(Request::Switch(next.clone()));
+        None
+    }
+
+    /// Takes in the replies so far, telling how the last switch went.
+    fn receive(&mut self) -> Option<Action> {
+        let mut toast = None;
+        while let Ok(reply) = self.replies.try_recv() {
+            toast = self.apply(reply).or(toast);
+        }
+        toast
+    }
+
+    fn apply(&mut self, reply: Reply) -> Option<Action> {
+        let (state, toast) = match reply {
+            Reply::Read(state) => {
+                self.reading = false;
+                (state, None)
+            }
+            Reply::Switched(profile, Ok(()), state) => {
+                (state, Some(format!("Power profile: {profile}")))
+            }
+            Reply::Switched(profile, Err(e), state) => {
+                warn!("Unable to switch to the {profile} power profile: {e}");
+                let message = format!("Unable to switch to the {profile} power profile: {e}");
+                (state, Some(message))
+            }
+        };
+        self.dirty |= state != self.state;
+        self.state = state;
+        toast.map(Action::Toast)
+    }
+
+    /// E.g. `AC ⚡ performance ` or `BAT balanced `.
+    pub fn line(&self) -> Line<'static> {
+        let mut parts = match self.state.ac {
+            Some(true) => vec![format!("AC {}", symbols().plugged)],
+            Some(false) => vec![tr(Text::Battery).to_string()],
+            None => vec![],
+        };
+        parts.extend(self.state.profile.clone());
+        if parts.is_empty() {
+            return Line::default();
+        }
+        Line::from(format!("{} ", parts.join(" ")))
+    }
+}
+
+impl Component for Power {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.cadence = Cadence::from_millis(config.config.intervals.battery);
+        Ok(())
+    }
+
+    fn init(&mut self) -> Result<()> {
+        self.read();
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Tick => {
+                let toast = self.receive();
+                if self.cadence.due() {
+                    self.read();
+                }
+                Ok(toast)
+            }
+            Action::CyclePowerProfile => Ok(self.cycle()),
+            _ => Ok(None),
+        }
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        f.render_widget(self.line(), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct FakePowerProfileSource {
+        state: PowerState,
+        permitted: bool,
+    }
+
+    impl PowerProfileSource for FakePowerProfileSource {
+        fn read(&mut self) -> PowerState {
+            self.state.clone()
+        }
+
+        fn set_profile(&mut self, profile: &str) -> io::Result<()> {
+            if !self.permitted {
+                return Err(io::ErrorKind::PermissionDenied.into());
+            }
+            self.state.profile = Some(profile.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_read_sysfs() {
+        let root = std::env::temp_dir().join(format!("brt-power-{}", std::process::id()));
+        let supplies = root.join("power_supply");
+        for (supply, kind, online) in [("AC", "Mains", "0\n"), ("ADP1", "Mains", "1\n")] {
+            let path = supplies.join(supply);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("type"), format!("{kind}\n")).unwrap();
+            fs::write(path.join("online"), online).unwrap();
+        }
+        fs::write(root.join("platform_profile"), "balanced\n").unwrap();
+        fs::write(
+            root.join("platform_profile_choices"),
+            "low-power balanced performance\n",
+        )
+        .unwrap();
+        let ac = read_ac(&supplies);
+        let profile = read_platform_profile(&root);
+        fs::remove_file(supplies.join("ADP1/online")).unwrap();
+        let unplugged = read_ac(&supplies);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(ac, Some(true));
+        assert_eq!(unplugged, Some(false));
+        assert_eq!(
+            profile,
+            (
+                Some("balanced".to_string()),
+                vec![
+                    "low-power".to_string(),
+                    "balanced".to_string(),
+                    "performance".to_string()
+                ]
+            )
+        );
+        assert_eq!(read_ac(&supplies), None);
+        assert_eq!(read_platform_profile(&root), (None, vec![]));
+    }
+
+    /// Waits for the next reply of the source.
+    fn answer(power: &mut Power) -> Option<Action> {
+        let reply = power.replies.recv().unwrap();
+        power.apply(reply)
+    }
+
+    #[test]
+    fn test_power() {
+        let mut power = Power::with_source(Box::<FakePowerProfileSource>::default());
+        power.init().unwrap();
+        assert_eq!(answer(&mut power), None);
+        assert_eq!(power.line(), Line::default());
+        assert_eq!(
+            power.update(Action::CyclePowerProfile).unwrap(),
+            Some(Action::Toast(
+                "There are no power profiles to switch between".to_string()
+            ))
+        );
+
+        let state = PowerState {
+            ac: Some(true),
+            profile: Some("balanced".to_string()),
+            profiles: vec!["power-saver".to_string(), "balanced".to_string()],
+        };
+        let mut power = Power::with_source(Box::new(FakePowerProfileSource {
+            state: state.clone(),
+            permitted: false,
+        }));
+        power.init().unwrap();
+        answer(&mut power);
+        assert_eq!(power.line().to_string(), "AC ⚡ balanced ");
+        assert_eq!(power.update(Action::CyclePowerProfile).unwrap(), None);
+        assert_eq!(
+            answer(&mut power),
+            Some(Action::Toast(
+                "Unable to switch to the power-saver power profile: permission denied".to_string()
+            ))
+        );
+
+        let mut power = Power::with_source(Box::new(FakePowerProfileSource {
+            state: PowerState {
+                ac: Some(false),
+                ..state
+            },
+            permitted: true,
+        }));
+        power.init().unwrap();
+        answer(&mut power);
+        power.take_dirty();
+        power.update(Action::CyclePowerProfile).unwrap();
+        assert_eq!(
+            answer(&mut power),
+            Some(Action::Toast("Power profile: power-saver".to_string()))
+        );
+        assert!(power.take_dirty());
+        assert_eq!(power.line().to_string(), "BAT power-saver ");
+    }
+}
@@ -1,50 +1,176 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
 use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 
 use color_eyre::eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent};
-use log::{debug, info, warn};
-use procfs::process::all_processes;
-use ratatui::layout::Constraint::{Fill, Length, Percentage};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::Constraint::Percentage;
 use ratatui::widgets::block::{Position, Title};
 use ratatui::widgets::TableState;
 use ratatui::{prelude::*, widgets::*};
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+use tui_input::backend::crossterm::EventHandler;
 use tui_input::Input;
 
 use super::{Component, Frame};
-use crate::action::Action;
-use crate::components::process::Order::{Command, Cpu, Name, NumberOfThreads, Pid};
-use crate::model::{create_rows, to_brt_process, BrtProcess};
+use crate::action::{Action, UpdatePayload};
+use crate::cadence::Cadence;
+use crate::capabilities;
+use crate::clipboard::{Clipboard, SystemClipboard};
+use crate::components::numa::numa_maps_summary;
+use crate::components::process::Order::{
+    Command, Cpu, CpuRecent, Elapsed, Memory, Name, Nice, NumberOfThreads, Pid, Priority, Received,
+    Sent, Started, State, User,
+};
+use crate::components::top::top_processes;
+use crate::config::{AppConfig, AttachCommands, Config, Intervals};
+use crate::control::{
+    can_signal_group, io_priority, is_critical, split_command_line, system_service, Batch,
+    BatchAction, CommandTemplate, IoClass, IoPriority, Scope, Signal, Tracer,
+};
+use crate::filter::Filter;
+use crate::hints::Hint;
+use crate::journal::{Entry, Journalctl, Unit, UnitJournal};
+use crate::listing::{Job, Listing};
+use crate::model::{
+    format_bytes, format_start_time, get_processes, rows, sanitize, to_brt_process, BrtProcess,
+    Column, Lifecycle, RowTint, Usage, DEFAULT_COLUMNS,
+};
+use crate::network::NetworkSampler;
+use crate::proc_events::{ProcEvent, ProcEvents};
+use crate::recording::{Recorder, Replay};
+use crate::session::Session;
+use crate::source::{system_source, ProcSource};
+use crate::staleness::Sample;
+use crate::symbols::symbols;
+use crate::system::{CgroupMemory, MemoryUsage, ProcessMemory};
+use crate::widgets::popup::Popup;
+use crate::widgets::sparkline::MAX_DOTS;
 
-#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+/// The niceness added or removed by a single renice.
+const RENICE_STEP: i32 = 5;
+/// The number of targets listed in the confirmation popup.
+const MAX_LISTED_TARGETS: usize = 10;
+/// The number of refreshes started and exited processes stay highlighted.
+const HIGHLIGHT_REFRESHES: u32 = 2;
+/// From this percentage of used memory on, the processes with the highest
+/// OOM scores are highlighted.
+const OOM_RISK_USED: f64 = 90.0;
+/// The number of processes highlighted as the likely victims of the OOM
+/// killer.
+const OOM_RISK_PROCESSES: usize = 3;
+/// The most started processes read on a tick; the others are listed by the
+/// next refresh.
+const MAX_EVENT_READS: usize = 64;
+
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Order {
     #[default]
     Pid,
     Name,
     Command,
     NumberOfThreads,
+    User,
+    Memory,
     Cpu,
+    /// The average of the latest cpu samples, which jumps less than the
+    /// latest one.
+    CpuRecent,
+    Started,
+    Elapsed,
+    Nice,
+    Priority,
+    State,
+    Received,
+    Sent,
+    Capabilities,
+    OomScore,
+    OomScoreAdj,
+    ContextSwitches,
 }
 
 impl Order {
+    /// Every order, in the order `<` and `>` cycle through them.
+    fn all() -> impl Iterator<Item = Order> {
+        std::iter::successors(Some(Order::default()), |order| {
+            Some(order.next()).filter(|next| *next != Order::default())
+        })
+    }
+
     fn next(&self) -> Self {
         use Order::*;
         match *self {
             Pid => Name,
             Name => Command,
             Command => NumberOfThreads,
-            NumberOfThreads => Cpu,
-            Cpu => Pid,
+            NumberOfThreads => User,
+            User => Memory,
+            Memory => Cpu,
+            Cpu => CpuRecent,
+            CpuRecent => Started,
+            Started => Elapsed,
+            Elapsed => Nice,
+            Nice => Priority,
+            Priority => State,
+            State => Received,
+            Received => Sent,
+            Sent => Capabilities,
+            Capabilities => OomScore,
+            OomScore => OomScoreAdj,
+            OomScoreAdj => ContextSwitches,
+            ContextSwitches => Pid,
+        }
+    }
+
+    /// Whether one of `columns` shows the values sorted by; the recent cpu
+    /// is that of the cpu columns.
+    fn is_shown(self, columns: &[Column]) -> bool {
+        let order = if self == CpuRecent { Cpu } else { self };
+        columns.iter().any(|column| Order::from(*column) == order)
+    }
+
+    /// The order after this one, or before it when not `forward`, that one
+    /// of `columns` shows; this one when there is none.
+    fn step_shown(self, forward: bool, columns: &[Column]) -> Self {
+        let mut order = self;
+        for _ in Order::all() {
+            order = if forward {
+                order.next()
+            } else {
+                order.previous()
+            };
+            if order.is_shown(columns) {
+                return order;
+            }
         }
+        self
     }
 
     fn previous(&self) -> Self {
         use Order::*;
         match *self {
-            Pid => Cpu,
-            Cpu => NumberOfThreads,
+            Pid => ContextSwitches,
+            ContextSwitches => OomScoreAdj,
+            OomScoreAdj => OomScore,
+            OomScore => Capabilities,
+            Capabilities => Sent,
+            Sent => Received,
+            Received => State,
+            State => Priority,
+            Priority => Nice,
+            Nice => Elapsed,
+            Elapsed => Started,
+            Started => CpuRecent,
+            CpuRecent => Cpu,
+            Cpu => Memory,
+            Memory => User,
+            User => NumberOfThreads,
             NumberOfThreads => Command,
             Command => Name,
             Name => Pid,
@@ -52,6 +178,32 @@ impl Order {
     }
 }
 
+impl From<Column> for Order {
+    /// The order of the values in a column; the graph is sorted by cpu.
+    fn from(column: Column) -> Self {
+        match column {
+            Column::Pid => Pid,
+            Column::Program => Name,
+            Column::Command => Command,
+            Column::Threads => NumberOfThreads,
+            Column::User => User,
+            Column::Memory => Memory,
+            Column::CpuGraph | Column::Cpu => Cpu,
+            Column::Started => Started,
+            Column::Elapsed => Elapsed,
+            Column::Nice => Nice,
+            Column::Priority => Priority,
+            Column::State => State,
+            Column::Received => Received,
+            Column::Sent => Sent,
+            Column::Capabilities => Order::Capabilities,
+            Column::OomScore => Order::OomScore,
+            Column::OomScoreAdj => Order::OomScoreAdj,
+            Column::ContextSwitches => Order::ContextSwitches,
+        }
+    }
+}
+
 impl fmt::Display for Order {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -59,290 +211,2678 @@ impl fmt::Display for Order {
             Name => write!(f, "name"),
             Command => write!(f, "command"),
             NumberOfThreads => write!(f, "threads"),
+            User => write!(f, "user"),
+            Memory => write!(f, "memory"),
             Cpu => write!(f, "cpu"),
+            CpuRecent => write!(f, "recent cpu"),
+            Started => write!(f, "started"),
+            Elapsed => write!(f, "elapsed"),
+            Nice => write!(f, "nice"),
+            Priority => write!(f, "priority"),
+            State => write!(f, "state"),
+            Received => write!(f, "received"),
+            Sent => write!(f, "sent"),
+            Order::Capabilities => write!(f, "capabilities"),
+            Order::OomScore => write!(f, "oom score"),
+            Order::OomScoreAdj => write!(f, "oom adjustment"),
+            Order::ContextSwitches => write!(f, "context switches"),
+        }
+    }
+}
+
+/// An order and its direction, to break the ties in the sorted column.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct SortKey {
+    pub order: Order,
+    /// From the highest to the lowest value.
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// The popup of `o`, with every order by priority: the processes are sorted
+/// by the first, the ties in it by the second, and so on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sorter {
+    pub keys: Vec<SortKey>,
+    pub selected: usize,
+}
+
+impl Sorter {
+    /// The orders not sorted by yet go last, from the lowest value; only
+    /// those of `columns` are listed after the sorted one.
+    fn new(primary: SortKey, tie_breakers: &[SortKey], columns: &[Column]) -> Self {
+        let mut keys = vec![primary];
+        let defaults = Order::all().map(|order| SortKey {
+            order,
+            descending: false,
+        });
+        for key in tie_breakers.iter().copied().chain(defaults) {
+            if key.order.is_shown(columns) && !keys.iter().any(|k| k.order == key.order) {
+                keys.push(key);
+            }
+        }
+        Self { keys, selected: 0 }
+    }
+
+    fn select(&mut self, step: isize) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(step)
+            .min(self.keys.len() - 1);
+    }
+
+    /// Moves the selected order up or down by `step` priorities.
+    fn move_selected(&mut self, step: isize) {
+        let to = self
+            .selected
+            .saturating_add_signed(step)
+            .min(self.keys.len() - 1);
+        self.keys.swap(self.selected, to);
+        self.selected = to;
+    }
+
+    fn toggle_direction(&mut self) {
+        let key = &mut self.keys[self.selected];
+        key.descending = !key.descending;
+    }
+}
+
+/// The query of `/`, matched against the programs, users and command lines.
+#[derive(Debug, Default)]
+pub struct Search {
+    pub query: Input,
+    /// Still being typed, so the keys go to the query.
+    pub editing: bool,
+}
+
+impl Search {
+    fn matches(&self, process: &BrtProcess) -> bool {
+        let query = self.query.value().to_lowercase();
+        [&process.program, &process.username(), &process.command]
+            .iter()
+            .any(|text| text.to_lowercase().contains(&query))
+    }
+}
+
+/// The filter expression of `f`; see [`crate::filter`].
+#[derive(Debug, Default)]
+pub struct FilterInput {
+    pub expression: Input,
+    /// The expression that was applied last, and what it parsed into.
+    pub applied: String,
+    pub filter: Filter,
+    /// Still being typed, so the keys go to the expression.
+    pub editing: bool,
+    /// Why the typed expression can't be applied.
+    pub error: Option<String>,
+}
+
+impl FilterInput {
+    /// Applies the typed expression, or keeps why it can't be.
+    fn apply(&mut self) -> bool {
+        match Filter::parse(self.expression.value()) {
+            Ok(filter) => {
+                self.filter = filter;
+                self.applied = self.expression.value().trim().to_string();
+                self.editing = false;
+                self.error = None;
+                true
+            }
+            Err(e) => {
+                self.error = Some(e);
+                false
+            }
+        }
+    }
+
+    /// Stops editing and goes back to the applied expression.
+    fn discard(&mut self) {
+        self.expression = Input::new(self.applied.clone());
+        self.editing = false;
+        self.error = None;
+    }
+
+    /// E.g. `filter: user:root cpu>5`, with a cursor while it is typed.
+    fn title(&self) -> Option<Line<'static>> {
+        if self.editing {
+            let mut spans = vec![format!("filter: {}_", self.expression.value()).yellow()];
+            if let Some(error) = &self.error {
+                spans.push(format!(" {error}").red());
+            }
+            return Some(Line::from(spans));
         }
+        (!self.applied.is_empty()).then(|| Line::from(format!("filter: {}", self.applied).yellow()))
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct Process {
     pub show_help: bool,
     pub app_ticker: usize,
     pub render_ticker: usize,
     pub input: Input,
     pub process_map: HashMap<i32, BrtProcess>,
-    pub processes: Vec<BrtProcess>,
+    /// The listed processes, as the listing thread last filtered and ordered
+    /// them.
+    pub processes: Arc<Vec<BrtProcess>>,
+    pub listing: Listing,
     pub order: Order,
+    /// Order the processes with equal values in the sorted column, in turn.
+    pub tie_breakers: Vec<SortKey>,
+    pub columns: Vec<Column>,
     pub scrollbar_state: ScrollbarState,
     pub state: TableState,
     pub action_tx: Option<UnboundedSender<Action>>,
+    pub recorder: Option<Recorder>,
+    pub replay: Option<Replay>,
+    pub source: Box<dyn ProcSource>,
+    pub marked: HashSet<i32>,
+    pub batch: Option<Batch>,
+    /// Below this width the optional columns are left out.
+    pub compact_width: u16,
+    pub cadence: Cadence,
+    /// When a sample last succeeded.
+    pub sampled_at: Option<Instant>,
+    /// The processes that started or exited recently, with the number of
+    /// refreshes they stay highlighted.
+    pub lifecycles: HashMap<i32, (Lifecycle, u32)>,
+    /// Recently exited processes, still shown for a little while.
+    pub exited: HashMap<i32, BrtProcess>,
+    /// Sort from the highest to the lowest value.
+    pub descending: bool,
+    /// The width of the table when it was last drawn.
+    pub width: u16,
+    pub network: NetworkSampler,
+    /// The number of cpu samples averaged for [`Order::CpuRecent`].
+    pub recent_cpu_samples: usize,
+    /// From which cpu usage the columns of the cpu graph get more dots.
+    pub graph_thresholds: [f64; MAX_DOTS],
+    /// Styles the rows by their cpu or memory usage.
+    pub row_tint: Option<RowTint>,
+    /// The memory of the system, for tinting the rows by memory usage.
+    pub memory_total: u64,
+    /// The details of a process, read when they were opened.
+    pub details: Option<Popup<'static>>,
+    /// The latest entries of the unit of the process whose details are
+    /// shown, over the details.
+    pub unit_log: Option<Popup<'static>>,
+    /// The unit whose journal is being read, while the popup tells so.
+    pub reading_journal: Option<Unit>,
+    pub journal: Arc<dyn UnitJournal>,
+    /// Where pids, command lines and shell commands are copied to.
+    pub clipboard: Box<dyn Clipboard>,
+    /// The number of entries shown of a unit.
+    pub journal_lines: usize,
+    /// The io priority being picked for the selected or marked processes.
+    pub io_picker: Option<IoPriority>,
+    /// The orders being rearranged.
+    pub sorter: Option<Sorter>,
+    /// The command lines of the tracers, e.g. `strace -p {pid}`.
+    pub attach: AttachCommands,
+    /// Changed since it was last drawn.
+    pub dirty: bool,
+    /// Whether processes of system services are treated as critical.
+    pub guard_system_services: bool,
+    /// The ancestry of the process jumped to, shown below the table until the
+    /// selection moves on.
+    pub breadcrumb: Option<String>,
+    /// Where the sorted processes go, for the control socket.
+    pub publisher: Option<watch::Sender<Arc<Vec<BrtProcess>>>>,
+    pub search: Option<Search>,
+    /// Only the processes matching it are listed.
+    pub filter: FilterInput,
+    /// Whether the totals of the filtered processes are shown.
+    pub show_totals: bool,
+    /// Processes that started and exited since the last tick, when the
+    /// kernel tells.
+    pub events: Option<ProcEvents>,
+    /// The pids forked since the last refresh, to tell the ones that also
+    /// exited.
+    pub forked: HashSet<i32>,
+    /// The processes that came and went since the last refresh, which it
+    /// would never have listed.
+    pub short_lived: usize,
+    /// The short-lived processes between the last two refreshes, which the
+    /// title tells.
+    pub last_short_lived: usize,
+    /// The processes whose details brt isn't allowed to read, as of the last
+    /// refresh.
+    pub restricted: usize,
+    /// When the context switches were last read, for their rate.
+    pub context_switches_read: Option<Instant>,
+    /// The number of columns scrolled out of view on the left; the frozen
+    /// ones never are.
+    pub column_offset: usize,
+}
+
+impl Default for Process {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Process {
     pub fn new() -> Process {
-        let mut process = Process::default();
+        Process::with_source(system_source())
+    }
+
+    pub fn with_source(source: Box<dyn ProcSource>) -> Process {
+        let mut process = Process {
+            show_help: false,
+            app_ticker: 0,
+            render_ticker: 0,
+            input: Input::default(),
+            process_map: HashMap::new(),
+            processes: Arc::default(),
+            listing: Listing::spawn(),
+            order: Order::default(),
+            tie_breakers: vec![],
+            columns: DEFAULT_COLUMNS.to_vec(),
+            scrollbar_state: ScrollbarState::default(),
+            state: TableState::new().with_selected(Some(0)),
+            action_tx: None,
+            recorder: None,
+            replay: None,
+            source,
+            marked: HashSet::new(),
+            batch: None,
+            compact_width: AppConfig::default().compact_width,
+            cadence: Cadence::from_millis(Intervals::default().processes),
+            sampled_at: None,
+            lifecycles: HashMap::new(),
+            exited: HashMap::new(),
+            descending: false,
+            width: u16::MAX,
+            network: NetworkSampler::new(),
+            recent_cpu_samples: AppConfig::default().recent_cpu_samples,
+            graph_thresholds: AppConfig::default().graph_thresholds,
+            row_tint: None,
+            memory_total: 0,
+            details: None,
+            unit_log: None,
+            reading_journal: None,
+            journal: Arc::new(Journalctl),
+            clipboard: Box::new(SystemClipboard),
+            journal_lines: AppConfig::default().journal_lines,
+            io_picker: None,
+            sorter: None,
+            attach: AttachCommands::default(),
+            dirty: true,
+            guard_system_services: AppConfig::default().guard_system_services,
+            breadcrumb: None,
+            publisher: None,
+            search: None,
+            filter: FilterInput::default(),
+            show_totals: AppConfig::default().totals,
+            events: None,
+            forked: HashSet::new(),
+            short_lived: 0,
+            last_short_lived: 0,
+            restricted: 0,
+            context_switches_read: None,
+            column_offset: 0,
+        };
         process.process_map = process.get_processes();
-        process.processes = process.process_map.clone().into_values().collect();
-        process.state = TableState::new().with_selected(Some(0));
+        process.processes = Arc::new(process.process_map.clone().into_values().collect());
         process
     }
 
-    pub fn refresh(&mut self) {
-        let length = self.process_map.len();
-        let new_processes = self.get_processes();
-        let mut updated_processes = HashMap::new();
-        for (pid, process) in new_processes {
-            let old_process_option = self.process_map.get(&pid);
-            if old_process_option.is_some() {
-                let mut old_process = old_process_option.unwrap().clone();
-                old_process.cpus.push_back(process.cpu);
-                old_process.cpus.pop_front();
-                old_process.cpu_graph = crate::model::get_cpu_graph(&old_process.cpus);
-                updated_processes.insert(pid, old_process);
-            };
-        }
-        self.process_map = updated_processes;
-        self.processes = self.process_map.clone().into_values().collect();
-        self.scrollbar_state = self.scrollbar_state.content_length(length);
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
     }
 
-    pub fn order_string(&mut self) -> String {
-        format!("{} {} {}", "<".red(), self.order, ">".red())
+    /// Publishes the processes every time they are sorted.
+    pub fn with_publisher(mut self, publisher: watch::Sender<Arc<Vec<BrtProcess>>>) -> Self {
+        self.publisher = Some(publisher);
+        self
     }
 
-    pub fn tick(&mut self) {
-        self.app_ticker = self.app_ticker.saturating_add(1);
-        // if self.app_ticker % 5 == 0 {
-        // self.processes = self.get_all_processes();
-        self.refresh();
+    pub fn with_events(mut self, events: ProcEvents) -> Self {
+        self.events = Some(events);
+        self
+    }
 
-        self.order_by_enum();
-        info!("Refreshed process list.");
-        // }
+    pub fn with_replay(mut self, replay: Replay) -> Self {
+        self.process_map = replay.current_processes();
+        self.processes = Arc::new(self.process_map.clone().into_values().collect());
+        self.replay = Some(replay);
+        self
     }
 
-    fn get_processes(&mut self) -> HashMap<i32, BrtProcess> {
-        let processes: HashMap<i32, BrtProcess> = all_processes()
-            .expect("Can't read /proc")
-            .filter_map(|p| match p {
-                Ok(p) => {
-                    let brt_process = to_brt_process(&p);
-                    if brt_process.is_some() {
-                        Some((p.pid, brt_process?))
-                    } else {
-                        None
-                    }
+    #[tracing::instrument(level = "debug", skip_all, fields(replay = self.replay.is_some()))]
+    pub fn refresh(&mut self) -> UpdatePayload {
+        let timestamp = SystemTime::now();
+        let started = Instant::now();
+        let current = if let Some(replay) = self.replay.as_mut() {
+            replay.advance();
+            replay.current_processes()
+        } else {
+            let mut current = self.get_processes();
+            for (pid, process) in current.iter_mut() {
+                if let Some(old_process) = self.process_map.get(pid) {
+                    let mut cpus = old_process.cpus.clone();
+                    cpus.push_back(process.cpu);
+                    cpus.pop_front();
+                    process.cpus = cpus;
+                };
+            }
+            if self.columns.contains(&Column::Capabilities) {
+                for (pid, process) in current.iter_mut() {
+                    process.capabilities = self.source.capabilities(*pid).unwrap_or_default();
                 }
-                Err(e) => match e {
-                    procfs::ProcError::NotFound(_) => None,
-                    procfs::ProcError::Io(_e, _path) => None,
-                    x => {
-                        warn!("Can't read process due to error {x:?}");
-                        None
-                    }
-                },
-            })
-            .collect();
-        processes
+            }
+            if self.columns.iter().any(Column::is_oom) {
+                self.read_oom_scores(&mut current);
+            }
+            if self.columns.contains(&Column::ContextSwitches) {
+                self.read_context_switches(&mut current, started);
+            }
+            if self.columns.iter().any(Column::is_network) {
+                let throughput = self.network.sample();
+                for (pid, process) in current.iter_mut() {
+                    let rate = throughput.get(pid).copied().unwrap_or_default();
+                    (process.received, process.sent) = (rate.received, rate.sent);
+                }
+            }
+            if self
+                .row_tint
+                .as_ref()
+                .is_some_and(|tint| tint.by == Usage::Memory)
+            {
+                let memory = MemoryUsage::read(self.source.as_ref()).unwrap_or_default();
+                self.memory_total = memory.total;
+            }
+            current
+        };
+        // there is brt itself at least, unless /proc couldn't be read
+        if !current.is_empty() {
+            self.sampled_at = Some(started);
+        }
+        let (started_processes, exited_processes) = self.track_lifecycles(current);
+        self.marked.retain(|pid| self.process_map.contains_key(pid));
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record(&self.process_map) {
+                warn!("Unable to record processes: {e}");
+            }
+        }
+        self.list_processes();
+        self.restricted = self.process_map.values().filter(|p| p.restricted).count();
+        UpdatePayload {
+            timestamp,
+            duration: started.elapsed(),
+            processes: self.process_map.len(),
+            started: started_processes,
+            exited: exited_processes,
+            restricted: self.restricted,
+            top: top_processes(self.process_map.values()),
+        }
+    }
+
+    /// Has the current and recently exited processes that pass the filter
+    /// listed again in order, on the listing thread; the list is shown once
+    /// it is received on a tick or render.
+    pub fn list_processes(&mut self) {
+        self.read_cgroups();
+        let mut keys = vec![SortKey {
+            order: self.order,
+            descending: self.descending,
+        }];
+        keys.extend(self.tie_breakers());
+        self.listing.request(Job {
+            processes: self
+                .process_map
+                .values()
+                .chain(self.exited.values())
+                .cloned()
+                .collect(),
+            filter: self.filter.filter.clone(),
+            keys,
+            recent_cpu_samples: self.recent_cpu_samples,
+        });
     }
 
-    pub fn order_by_enum(&mut self) {
-        let order = self.order;
-        match order {
-            Pid => self.order_by_pid(),
-            Name => self.order_by_program(),
-            Command => self.order_by_command(),
-            NumberOfThreads => self.order_by_number_of_threads(),
-            Cpu => self.order_by_cpu(),
+    /// Shows the latest list the listing thread built, returning whether
+    /// there was one.
+    fn receive_list(&mut self) -> bool {
+        let Some(processes) = self.listing.latest() else {
+            return false;
+        };
+        self.show_list(processes);
+        true
+    }
+
+    /// Waits for the list asked for last and shows it, for a frame that has
+    /// to show it right away.
+    pub fn wait_for_list(&mut self) {
+        if let Some(processes) = self.listing.wait() {
+            self.show_list(processes);
         }
     }
 
-    pub fn order_by_pid(&mut self) {
-        self.processes.sort_by(|a, b| a.pid.cmp(&b.pid))
+    fn show_list(&mut self, processes: Arc<Vec<BrtProcess>>) {
+        if let Some(selected) = self.state.selected() {
+            self.state
+                .select(Some(selected.min(processes.len().saturating_sub(1))));
+        }
+        if let Some(publisher) = &self.publisher {
+            publisher.send_replace(Arc::clone(&processes));
+        }
+        self.processes = processes;
     }
 
-    pub fn order_by_program(&mut self) {
-        self.processes.sort_by(|a, b| a.program.cmp(&b.program))
+    /// Reads the cgroups of the processes that don't have theirs yet, while
+    /// the filter needs them.
+    fn read_cgroups(&mut self) {
+        if !self.filter.filter.needs_cgroup() {
+            return;
+        }
+        for (pid, process) in self.process_map.iter_mut() {
+            if process.cgroup.is_none() {
+                process.cgroup = self.source.cgroup(*pid).ok();
+            }
+        }
     }
 
-    pub fn order_by_command(&mut self) {
-        self.processes.sort_by(|a, b| a.command.cmp(&b.command))
+    /// Filters the processes by `cgroup`, replacing the expression.
+    fn filter_cgroup(&mut self, cgroup: &str) {
+        self.filter.expression = Input::new(format!("cgroup:{cgroup}"));
+        if self.filter.apply() {
+            self.list_processes();
+        }
     }
 
-    pub fn order_by_number_of_threads(&mut self) {
-        self.processes.sort_by(|a, b| {
-            a.number_of_threads
-                .partial_cmp(&b.number_of_threads)
-                .unwrap()
-        })
+    /// The summed cpu and resident memory of the listed processes, e.g.
+    /// `total 12.5% cpu 1.2GiB mem`, while they are filtered. Exited
+    /// processes no longer use either.
+    fn totals_title(&self) -> Option<String> {
+        if !self.show_totals || self.filter.filter.is_empty() {
+            return None;
+        }
+        let (cpu, memory) = self.totals();
+        Some(format!("total {cpu:.1}% cpu {} mem", format_bytes(memory)))
     }
 
-    pub fn order_by_cpu(&mut self) {
+    fn totals(&self) -> (f64, u64) {
         self.processes
-            .sort_by(|a, b| a.cpu.partial_cmp(&b.cpu).unwrap())
+            .iter()
+            .filter(|process| !self.exited.contains_key(&process.pid))
+            .fold((0.0, 0), |(cpu, memory), process| {
+                (cpu + process.cpu, memory + process.resident_memory)
+            })
     }
 
-    pub fn render_tick(&mut self) {
-        info!("Render Tick");
-        self.render_ticker = self.render_ticker.saturating_add(1);
+    /// Reads the context switches, and their rate since the previous refresh
+    /// at `now`.
+    fn read_context_switches(&mut self, processes: &mut HashMap<i32, BrtProcess>, now: Instant) {
+        let seconds = self
+            .context_switches_read
+            .map(|read| now.saturating_duration_since(read).as_secs_f64());
+        for (pid, process) in processes.iter_mut() {
+            let Ok((voluntary, involuntary)) = self.source.context_switches(*pid) else {
+                continue;
+            };
+            process.context_switches = voluntary + involuntary;
+            // without a previous count, e.g. when the column was just added,
+            // there is no rate yet
+            let previous = self
+                .process_map
+                .get(pid)
+                .map(|old| old.context_switches)
+                .filter(|count| *count > 0);
+            if let (Some(previous), Some(seconds)) = (previous, seconds.filter(|s| *s > 0.0)) {
+                let switches = process.context_switches.saturating_sub(previous);
+                process.context_switch_rate = (switches as f64 / seconds).round() as u64;
+            }
+        }
+        self.context_switches_read = Some(now);
     }
 
-    pub fn jump(&mut self, steps: i64) {
-        let location = self.state.selected().unwrap_or(0) as i64;
-        let length = self.process_map.len() as i64;
-        debug!(
-            "Move {} steps in [{}..{}] when current location is {}.",
-            steps, 0, length, location
-        );
-        let mut index = location + steps;
-        while index < 0 {
-            index += length;
+    /// Reads the OOM scores and, when memory runs low, marks the processes
+    /// the OOM killer would pick first.
+    fn read_oom_scores(&self, processes: &mut HashMap<i32, BrtProcess>) {
+        for (pid, process) in processes.iter_mut() {
+            (process.oom_score, process.oom_score_adj) =
+                self.source.oom_score(*pid).unwrap_or_default();
+        }
+        let memory = MemoryUsage::read(self.source.as_ref()).unwrap_or_default();
+        if memory.total == 0 || memory.percentage() < OOM_RISK_USED {
+            return;
+        }
+        let mut scores: Vec<&mut BrtProcess> = processes
+            .values_mut()
+            .filter(|process| process.oom_score > 0)
+            .collect();
+        scores.sort_by_key(|process| std::cmp::Reverse(process.oom_score));
+        for process in scores.into_iter().take(OOM_RISK_PROCESSES) {
+            process.oom_risk = true;
         }
-        let new_location = (index % length) as usize;
-        debug!("New location is {}.", new_location);
-        self.state.select(Some(new_location));
-        self.scrollbar_state = self.scrollbar_state.position(new_location);
     }
-}
 
-impl Component for Process {
-    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
-        self.action_tx = Some(tx);
-        Ok(())
+    /// Replaces the process map with `current`, remembering which processes
+    /// started and which exited since the previous refresh. Exited processes
+    /// are kept around, and both are highlighted, for a few refreshes.
+    /// Returns the number of started and exited processes.
+    fn track_lifecycles(&mut self, current: HashMap<i32, BrtProcess>) -> (usize, usize) {
+        let mut started = 0;
+        let mut exited = 0;
+        self.lifecycles.retain(|_, (_, refreshes)| {
+            *refreshes -= 1;
+            *refreshes > 0
+        });
+        let lifecycles = &self.lifecycles;
+        self.exited
+            .retain(|pid, _| lifecycles.contains_key(pid) && !current.contains_key(pid));
+        for pid in current.keys() {
+            if !self.process_map.contains_key(pid) {
+                self.lifecycles
+                    .insert(*pid, (Lifecycle::Started, HIGHLIGHT_REFRESHES));
+                started += 1;
+            }
+        }
+        for (pid, process) in self.process_map.drain() {
+            if !current.contains_key(&pid) {
+                self.lifecycles
+                    .insert(pid, (Lifecycle::Exited, HIGHLIGHT_REFRESHES));
+                self.exited.insert(pid, process);
+                exited += 1;
+            }
+        }
+        self.process_map = current;
+        (started, exited)
     }
 
-    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
-        debug!("Handling {:?}.", key);
-        let action = match key.code {
-            KeyCode::Up => Action::Up,
-            KeyCode::Down => Action::Down,
-            KeyCode::PageUp => Action::PageUp,
-            KeyCode::PageDown => Action::PageDown,
-            KeyCode::Left => Action::Left,
-            KeyCode::Right => Action::Right,
-            KeyCode::Esc => Action::Quit,
-            _ => Action::Update,
+    /// Applies a replay control action and shows the sample at the new position.
+    fn control_replay(&mut self, action: Action) {
+        let Some(replay) = self.replay.as_mut() else {
+            return;
         };
-        Ok(Some(action))
+        match action {
+            Action::TogglePause => replay.toggle_pause(),
+            Action::SeekForward => replay.seek_forward(),
+            Action::SeekBackward => replay.seek_backward(),
+            Action::SpeedUp => replay.speed_up(),
+            Action::SpeedDown => replay.speed_down(),
+            _ => return,
+        }
+        self.refresh();
     }
 
-    fn update(&mut self, action: Action) -> Result<Option<Action>> {
-        match action {
-            Action::Tick => self.tick(),
-            Action::Render => self.render_tick(),
-            Action::Up => self.jump(-1),
-            Action::Down => self.jump(1),
-            Action::PageUp => self.jump(-20),
-            Action::PageDown => self.jump(20),
-            Action::Left => {
-                self.order = self.order.previous();
-                self.order_by_enum();
+    /// The configured columns, without the optional ones in narrow panes
+    /// and the ones scrolled out of view.
+    fn visible_columns(&self, width: u16) -> Vec<Column> {
+        let mut skipped = 0;
+        self.columns
+            .iter()
+            .filter(|column| width >= self.compact_width || !column.is_optional())
+            .filter(|column| {
+                if column.is_frozen() || skipped == self.column_offset {
+                    return true;
+                }
+                skipped += 1;
+                false
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Scrolls the columns sideways by `step`, leaving at least one besides
+    /// the frozen ones.
+    fn scroll_columns(&mut self, step: isize) {
+        let scrollable = self
+            .columns
+            .iter()
+            .filter(|column| self.width >= self.compact_width || !column.is_optional())
+            .filter(|column| !column.is_frozen())
+            .count();
+        self.column_offset = self
+            .column_offset
+            .saturating_add_signed(step)
+            .min(scrollable.saturating_sub(1));
+        self.dirty = true;
+    }
+
+    fn selected_process(&self) -> Option<&BrtProcess> {
+        self.processes.get(self.state.selected()?)
+    }
+
+    /// Selects the listed process with this pid, if there is one.
+    fn select_pid(&mut self, pid: i32) -> bool {
+        match self.processes.iter().position(|process| process.pid == pid) {
+            Some(index) => {
+                self.state.select(Some(index));
+                true
             }
-            Action::Right => {
-                self.order = self.order.next();
-                self.order_by_enum();
+            None => false,
+        }
+    }
+
+    /// The positions of the processes matching the search, in the current
+    /// order.
+    fn search_matches(&self) -> Vec<usize> {
+        let Some(search) = self.search.as_ref().filter(|s| !s.query.value().is_empty()) else {
+            return vec![];
+        };
+        self.processes
+            .iter()
+            .enumerate()
+            .filter(|(_, process)| search.matches(process))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Selects the next match after the selected process, or the previous
+    /// one before it, wrapping around; the selected process itself counts
+    /// when `inclusive`, so typing keeps a match that still matches.
+    fn jump_to_match(&mut self, forward: bool, inclusive: bool) {
+        let matches = self.search_matches();
+        let selected = self.state.selected().unwrap_or(0);
+        let target = if forward {
+            matches
+                .iter()
+                .find(|&&index| index > selected || (inclusive && index == selected))
+                .or(matches.first())
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|&&index| index < selected || (inclusive && index == selected))
+                .or(matches.last())
+        };
+        if let Some(&index) = target {
+            self.state.select(Some(index));
+        }
+    }
+
+    /// E.g. `/ssh 3 matches`, with a cursor while the query is typed.
+    fn search_title(&self) -> Option<String> {
+        let search = self.search.as_ref()?;
+        let cursor = if search.editing { "_" } else { "" };
+        let count = self.search_matches().len();
+        let matches = if count == 1 { "match" } else { "matches" };
+        Some(format!(
+            "/{}{cursor} {count} {matches}",
+            search.query.value()
+        ))
+    }
+
+    /// E.g. `systemd 1 › sshd 812 › bash 900`, from init down to `pid`.
+    fn ancestry(&self, pid: i32) -> String {
+        let mut ancestors = Vec::new();
+        let mut current = self.process_map.get(&pid);
+        // the pids only loop when a parent exited and its pid was reused
+        while let Some(process) = current.filter(|_| ancestors.len() <= self.process_map.len()) {
+            ancestors.push(format!("{} {}", process.program, process.pid));
+            current = self
+                .process_map
+                .get(&process.ppid)
+                .filter(|parent| parent.pid != process.pid);
+        }
+        ancestors.reverse();
+        ancestors.join(&format!(" {} ", symbols().separator))
+    }
+
+    /// Selects the parent, or the first listed child, of the selected process
+    /// and shows where it is in the tree.
+    fn jump_in_tree(&mut self, to_parent: bool) -> Option<Action> {
+        let selected = self.selected_process()?;
+        let (pid, ppid) = (selected.pid, selected.ppid);
+        let target = if to_parent {
+            (ppid != pid).then_some(ppid)
+        } else {
+            self.processes
+                .iter()
+                .find(|process| process.ppid == pid && process.pid != pid)
+                .map(|process| process.pid)
+        };
+        match target {
+            Some(target) if self.select_pid(target) => {
+                self.breadcrumb = Some(self.ancestry(target));
+                None
             }
-            _ => (),
+            _ if to_parent => Some(Action::Toast(format!("The parent of {pid} isn't listed"))),
+            _ => Some(Action::Toast(format!("{pid} has no listed children"))),
         }
-        Ok(None)
     }
 
-    fn draw(&mut self, f: &mut Frame<'_>, _rect: Rect) -> Result<()> {
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Percentage(100)])
-            .split(f.size());
+    /// Marks or unmarks the selected process and moves to the next one.
+    pub fn toggle_mark(&mut self) {
+        let Some(pid) = self.selected_process().map(|p| p.pid) else {
+            return;
+        };
+        if !self.marked.remove(&pid) {
+            self.marked.insert(pid);
+        }
+        self.jump(1);
+    }
 
-        let rows = create_rows(&self.processes);
+    /// Asks for confirmation to apply an action to the marked processes, or
+    /// to the selected one when none are marked. Those that exited are left
+    /// out, as their pids may already be another process's, which is told
+    /// when none are left.
+    pub fn prepare_batch(&mut self, action: BatchAction) -> Option<Action> {
+        if self.replay.is_some() {
+            warn!("Ignoring '{action}' on replayed processes.");
+            return None;
+        }
+        let chosen: Vec<&BrtProcess> = if self.marked.is_empty() {
+            self.selected_process().into_iter().collect()
+        } else {
+            self.processes
+                .iter()
+                .filter(|p| self.marked.contains(&p.pid))
+                .collect()
+        };
+        let roots: Vec<i32> = chosen
+            .iter()
+            .map(|p| p.pid)
+            .filter(|pid| !self.has_exited(*pid))
+            .collect();
+        if roots.is_empty() {
+            return chosen
+                .first()
+                .map(|p| Action::Toast(format!("{} already exited", p.program)));
+        }
+        self.input.reset();
+        self.batch = Some(self.scoped_batch(action, roots, Scope::Process));
+        None
+    }
 
-        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .begin_symbol(Some("↑"))
-            .end_symbol(Some("↓"))
-            .track_symbol(Some(" "))
-            .style(Color::White);
+    /// The descendants of `roots` in the ppid map, the deepest first, then
+    /// the roots, each once.
+    fn descendants(&self, roots: &[i32]) -> Vec<i32> {
+        let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+        for process in self.process_map.values() {
+            if process.ppid != process.pid {
+                children.entry(process.ppid).or_default().push(process.pid);
+            }
+        }
+        let mut seen: HashSet<i32> = roots.iter().copied().collect();
+        let mut levels = vec![roots.to_vec()];
+        while let Some(level) = levels.last().filter(|level| !level.is_empty()) {
+            let mut next: Vec<i32> = level
+                .iter()
+                .flat_map(|pid| children.get(pid).into_iter().flatten())
+                .copied()
+                .filter(|pid| seen.insert(*pid))
+                .collect();
+            next.sort_unstable();
+            levels.push(next);
+        }
+        levels.into_iter().rev().flatten().collect()
+    }
 
-        let selected_style = Style::default()
-            .bg(Color::Rgb(0xd4, 0x54, 0x54))
-            .fg(Color::White)
-            .add_modifier(Modifier::BOLD);
+    /// The process groups of `roots` that can be signalled as a whole, and
+    /// the pids of their members.
+    fn group_members(&self, roots: &[i32]) -> (Vec<i32>, Vec<i32>) {
+        let group = |pid: &i32| self.source.stat(*pid).ok().map(|stat| stat.pgrp);
+        let mut groups: Vec<i32> = roots
+            .iter()
+            .filter_map(group)
+            .filter(|group| can_signal_group(*group))
+            .collect();
+        groups.sort_unstable();
+        groups.dedup();
+        let mut members: Vec<i32> = self
+            .process_map
+            .keys()
+            .filter(|pid| group(pid).is_some_and(|g| groups.contains(&g)))
+            .copied()
+            .collect();
+        members.sort_unstable();
+        (groups, members)
+    }
 
-        let header = [
-            Cell::new(Line::from("Pid:").alignment(Alignment::Right)),
-            Cell::new("Program:"),
-            Cell::new("Command:"),
-            Cell::new(Line::from("Threads:").alignment(Alignment::Right)),
-            Cell::new("User:"),
-            Cell::new("MemB"),
-            Cell::new(""),
-            Cell::new("Cpu%"),
-        ]
-        .iter()
-        .cloned()
-        .map(Cell::from)
-        .collect::<Row>()
-        .height(1)
-        .style(Style::default().bold());
+    /// A batch applying `action` to `roots` and to the processes `scope`
+    /// adds, which has to be confirmed by typing a pid if any is critical.
+    fn scoped_batch(&self, action: BatchAction, roots: Vec<i32>, scope: Scope) -> Batch {
+        let (groups, pids) = match scope {
+            Scope::Process => (vec![], roots.clone()),
+            Scope::Descendants => (vec![], self.descendants(&roots)),
+            Scope::Group => self.group_members(&roots),
+        };
+        let processes: Vec<&BrtProcess> = pids
+            .iter()
+            .filter_map(|pid| self.process_map.get(pid))
+            .collect();
+        let critical = processes
+            .iter()
+            .find(|p| is_critical(p.pid, p.ppid))
+            .map(|p| (p.pid, None));
+        let (critical, service) = critical
+            .or_else(|| {
+                if !self.guard_system_services {
+                    return None;
+                }
+                processes.iter().find_map(|p| {
+                    let cgroup = self.source.cgroup(p.pid).ok()?;
+                    Some((p.pid, Some(system_service(&cgroup)?.to_string())))
+                })
+            })
+            .unzip();
+        let targets: Vec<(i32, String)> = processes
+            .iter()
+            .map(|p| (p.pid, p.program.clone()))
+            .collect();
+        Batch {
+            action,
+            roots,
+            targets,
+            scope,
+            groups,
+            critical,
+            service: service.flatten(),
+        }
+    }
 
-        let processes = self.processes.len();
-        let process = format!("{}/{}", self.state.selected().unwrap() + 1, processes);
+    /// Switches the pending signal to the next or the previous scope, past
+    /// the group one when none of the groups can be signalled; the other
+    /// actions apply to the chosen processes only.
+    fn rescope_batch(&mut self, forward: bool) {
+        if !self
+            .batch
+            .as_ref()
+            .is_some_and(|batch| matches!(batch.action, BatchAction::Signal(_)))
+        {
+            return;
+        }
+        let Some(batch) = self.batch.take() else {
+            return;
+        };
+        let step = |scope: Scope| {
+            if forward {
+                scope.next()
+            } else {
+                scope.previous()
+            }
+        };
+        let mut scoped = self.scoped_batch(batch.action, batch.roots, step(batch.scope));
+        if scoped.scope == Scope::Group && scoped.groups.is_empty() {
+            scoped = self.scoped_batch(scoped.action, scoped.roots, step(scoped.scope));
+        }
+        self.input.reset();
+        self.batch = Some(scoped);
+    }
 
-        let block = Block::default()
-            .title(Title::from("brt").alignment(Alignment::Center))
-            .title(Title::from(self.order_string()).alignment(Alignment::Right))
-            .title(
-                Title::from(process)
-                    .position(Position::Bottom)
-                    .alignment(Alignment::Right),
-            )
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::White))
-            .border_type(BorderType::Rounded);
-
-        let widths = [
-            Percentage(5),
-            Percentage(15),
-            Fill(1),
-            Percentage(5),
-            Percentage(5),
-            Length(5),
-            Length(5),
-            Length(5),
-        ];
+    /// Applies the pending batch, unless the pid of its critical target
+    /// wasn't typed, which is told instead.
+    pub fn confirm_batch(&mut self) -> Option<Action> {
+        let batch = self.batch.take()?;
+        if !batch.is_confirmed_by(self.input.value()) {
+            let pid = batch.critical.unwrap_or_default();
+            self.batch = Some(batch);
+            return Some(Action::Toast(format!("Type {pid} to confirm")));
+        }
+        batch.execute();
+        self.marked.clear();
+        self.input.reset();
+        None
+    }
 
-        let table = Table::new(rows, widths)
-            .block(block)
-            .header(header)
-            .highlight_style(selected_style);
+    /// Copies the pid or the command line of the selected process and tells
+    /// how that went.
+    fn copy_selected(&self, command: bool) -> Option<Action> {
+        let process = self.selected_process()?;
+        let (what, text) = if command {
+            ("command line", process.command.trim_end().to_string())
+        } else {
+            ("pid", process.pid.to_string())
+        };
+        let message = match self.clipboard.copy(&text) {
+            Ok(()) => format!("Copied {what} of {}", process.pid),
+            Err(e) => {
+                warn!("Unable to copy {what}: {e}");
+                format!("Unable to copy: {e}")
+            }
+        };
+        Some(Action::Toast(message))
+    }
 
-        f.render_stateful_widget(table, layout[0], &mut self.state);
-        f.render_stateful_widget(
-            scrollbar,
-            layout[0].inner(&Margin {
-                vertical: 1,
-                horizontal: 1,
-            }),
-            &mut self.scrollbar_state,
-        );
-        Ok(())
+    /// Drops the pending batch for the command line that would apply it,
+    /// which is printed on exit when it can't be copied.
+    fn copy_shell_command(&mut self) -> Option<Action> {
+        let batch = self.batch.take()?;
+        self.input.reset();
+        let command = batch.shell_command();
+        match self.clipboard.copy(&command) {
+            Ok(()) => Some(Action::Toast(format!("Copied `{command}`"))),
+            Err(e) => {
+                warn!("Unable to copy the command line: {e}");
+                Some(Action::PrintOnExit(command))
+            }
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::VecDeque;
+    /// Runs the tracer attached to the selected process, in the terminal.
+    fn attach(&self, tracer: Tracer) -> Option<Action> {
+        if self.replay.is_some() {
+            warn!("Ignoring {tracer} on replayed processes.");
+            return None;
+        }
+        let process = self.selected_process()?;
+        match self.attach.command(tracer, process.pid) {
+            Some(command) => Some(Action::RunInTerminal(command)),
+            None => Some(Action::Toast(format!("No {tracer} command configured"))),
+        }
+    }
 
-    #[test]
-    fn test_brt_process_new() {
-        let process = BrtProcess::new();
-        assert_eq!(process.pid, 0);
-        assert_eq!(process.cpus, VecDeque::from(vec![0_f64; 10]));
+    /// Runs a command line bound to a key for the selected process, on its
+    /// own or, when it needs it, in the terminal. The placeholders are
+    /// replaced after the line is split, so a program with a space in its
+    /// name stays one argument.
+    fn run_command(&self, template: &CommandTemplate) -> Option<Action> {
+        if self.replay.is_some() {
+            warn!("Ignoring '{}' on replayed processes.", template.command);
+            return None;
+        }
+        let process = self.selected_process()?;
+        let args = match split_command_line(&template.command) {
+            Ok(args) => args,
+            Err(e) => {
+                return Some(Action::Toast(format!(
+                    "Unable to run '{}': {e}",
+                    template.command
+                )))
+            }
+        };
+        let pid = process.pid.to_string();
+        let user = process.username();
+        let command: Vec<String> = args
+            .iter()
+            .map(|arg| {
+                arg.replace("{pid}", &pid)
+                    .replace("{comm}", &process.program)
+                    .replace("{user}", &user)
+            })
+            .collect();
+        if command.is_empty() {
+            None
+        } else if template.terminal {
+            Some(Action::RunInTerminal(command))
+        } else {
+            Some(Action::RunDetached(command))
+        }
     }
 
-    #[test]
-    fn test_process_jump() {
-        let mut process = Process::new();
-        process.process_map = process.get_processes();
+    /// Opens the io priority picker at the priority of the selected process.
+    fn pick_io_priority(&mut self) -> Option<Action> {
+        if self.replay.is_some() {
+            warn!("Ignoring ionice on replayed processes.");
+            return None;
+        }
+        let process = self.selected_process()?;
+        let priority = match io_priority(process.pid) {
+            // without a class the level follows the niceness
+            Ok(priority) if priority.class == IoClass::None => IoPriority {
+                class: IoClass::BestEffort,
+                level: ((process.nice + 20) / 5).clamp(0, 7) as u8,
+            },
+            Ok(priority) => priority,
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                return Some(Action::Toast(format!("ionice: {e}")));
+            }
+            Err(e) => {
+                warn!("Unable to read the io priority of {}: {e}", process.pid);
+                IoPriority::default()
+            }
+        };
+        self.io_picker = Some(priority);
+        None
+    }
+
+    fn io_picker_popup(priority: &IoPriority) -> Popup<'static> {
+        let symbols = symbols();
+        Popup::new(
+            "io priority",
+            vec![
+                Line::from(vec!["< ".red(), priority.to_string().bold(), " >".red()]),
+                Line::default(),
+                Line::from(
+                    format!(
+                        "{}/{} class, {}/{} level",
+                        symbols.left, symbols.right, symbols.up, symbols.down
+                    )
+                    .dim(),
+                ),
+                Line::from("enter to continue, esc to cancel".dim()),
+            ],
+        )
+    }
+
+    /// The orders by priority, dimmed after the pid as they never matter.
+    fn sorter_popup(sorter: &Sorter) -> Popup<'static> {
+        let symbols = symbols();
+        let last = sorter.keys.iter().position(|key| key.order == Pid);
+        let mut lines: Vec<Line> = sorter
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let arrow = if key.descending {
+                    symbols.falling
+                } else {
+                    symbols.rising
+                };
+                let mut line = Line::from(format!("{:>2} {arrow} {}", i + 1, key.order));
+                if last.is_some_and(|last| i > last) {
+                    line = line.dim();
+                }
+                if i == sorter.selected {
+                    line = line.reversed();
+                }
+                line
+            })
+            .collect();
+        lines.push(Line::default());
+        lines.push(Line::from(
+            format!(
+                "{}/{} select, j/k move, space flips",
+                symbols.up, symbols.down
+            )
+            .dim(),
+        ));
+        lines.push(Line::from("enter to apply, esc to cancel".dim()));
+        Popup::new("sort by", lines)
+    }
+
+    /// Shows the details of the selected process, or hides them.
+    fn toggle_details(&mut self) {
+        self.unit_log = None;
+        self.reading_journal = None;
+        if self.details.take().is_none() {
+            self.details = self
+                .selected_process()
+                .map(|process| details_popup(self.source.as_ref(), process));
+        }
+    }
+
+    /// Shows the latest journal entries of the unit of the selected process
+    /// over its details, or hides them; a toast when it runs in none. The
+    /// entries are read on a thread, and shown once they are sent back.
+    fn toggle_unit_log(&mut self) -> Option<Action> {
+        self.reading_journal = None;
+        if self.unit_log.take().is_some() {
+            return None;
+        }
+        let process = self.selected_process()?;
+        let unit = self
+            .source
+            .cgroup(process.pid)
+            .ok()
+            .and_then(|cgroup| Unit::from_cgroup(&cgroup));
+        let Some(unit) = unit else {
+            return Some(Action::Toast(format!(
+                "{} doesn't run in a systemd unit",
+                process.program
+            )));
+        };
+        let tx = self.action_tx.clone()?;
+        let (journal, lines, read) = (Arc::clone(&self.journal), self.journal_lines, unit.clone());
+        std::thread::spawn(move || {
+            let entries = journal.tail(&read, lines).map_err(|e| e.to_string());
+            // the app may have quit meanwhile
+            let _ = tx.send(Action::UnitLogRead(read, entries));
+        });
+        self.unit_log = Some(unit_log_popup(&unit, None));
+        self.reading_journal = Some(unit);
+        None
+    }
+
+    /// Shows the entries read of `unit`, unless its popup was closed since.
+    fn show_unit_log(&mut self, unit: Unit, entries: Result<Vec<Entry>, String>) {
+        if self.unit_log.is_some() && self.reading_journal.as_ref() == Some(&unit) {
+            self.unit_log = Some(unit_log_popup(&unit, Some(entries)));
+            self.reading_journal = None;
+        }
+    }
+
+    fn batch_popup(batch: &Batch, typed: &str) -> Popup<'static> {
+        let mut lines = vec![Line::from(batch.question()), Line::default()];
+        if let BatchAction::Signal(_) = batch.action {
+            let symbols = symbols();
+            lines.push(Line::from(vec![
+                "scope: ".into(),
+                format!("{} {} {}", symbols.left, batch.scope, symbols.right).bold(),
+            ]));
+            lines.push(Line::default());
+        }
+        for (pid, program) in batch.targets.iter().take(MAX_LISTED_TARGETS) {
+            lines.push(Line::from(format!("{pid:>7} {program}")));
+        }
+        if batch.targets.len() > MAX_LISTED_TARGETS {
+            lines.push(Line::from(
+                format!(
+                    "    ... and {} more",
+                    batch.targets.len() - MAX_LISTED_TARGETS
+                )
+                .dim(),
+            ));
+        }
+        lines.push(Line::default());
+        if let Some(pid) = batch.critical {
+            let warning = match &batch.service {
+                Some(service) => format!("{pid} runs the system service {service}."),
+                None => format!("{pid} is critical to the system or to brt."),
+            };
+            lines.push(Line::from(warning.red()));
+            lines.push(Line::from(vec![
+                "Type ".into(),
+                pid.to_string().bold(),
+                " and enter to confirm, esc to cancel, ".into(),
+                "c".bold(),
+                " to copy: ".into(),
+                typed.to_string().bold(),
+            ]));
+            return Popup::new("confirm", lines).style(Style::default().fg(Color::Red));
+        }
+        lines.push(Line::from(vec![
+            "y".bold(),
+            "es / ".into(),
+            "n".bold(),
+            "o / ".into(),
+            "c".bold(),
+            "opy as a command".into(),
+        ]));
+        Popup::new("confirm", lines)
+    }
+
+    pub fn order_string(&mut self) -> String {
+        format!("{} {} {}", "<".red(), self.order, ">".red())
+    }
+
+    /// Lists the processes that started, and highlights those that exited,
+    /// since the last tick rather than waiting for the next refresh. Tells
+    /// whether there were any.
+    fn apply_events(&mut self) -> bool {
+        let Some(events) = &self.events else {
+            return false;
+        };
+        let events = events.drain();
+        if events.is_empty() {
+            return false;
+        }
+        self.count_short_lived(&events);
+        for pid in started_pids(&events) {
+            let Some(mut process) = to_brt_process(self.source.as_ref(), pid) else {
+                continue;
+            };
+            match self.process_map.get(&pid) {
+                Some(old_process) => process.cpus = old_process.cpus.clone(),
+                None => {
+                    self.lifecycles
+                        .insert(pid, (Lifecycle::Started, HIGHLIGHT_REFRESHES));
+                }
+            }
+            self.process_map.insert(pid, process);
+        }
+        // it stays listed until the refresh that no longer finds it, as it
+        // may still be a zombie
+        for event in events {
+            if let ProcEvent::Exit(pid) = event {
+                if self.process_map.contains_key(&pid) {
+                    self.lifecycles
+                        .insert(pid, (Lifecycle::Exited, HIGHLIGHT_REFRESHES));
+                }
+            }
+        }
+        self.list_processes();
+        true
+    }
+
+    /// Counts the processes that exited after they were forked since the
+    /// last refresh.
+    fn count_short_lived(&mut self, events: &[ProcEvent]) {
+        for event in events {
+            match event {
+                ProcEvent::Fork(pid) => {
+                    self.forked.insert(*pid);
+                }
+                ProcEvent::Exit(pid) if self.forked.remove(pid) => self.short_lived += 1,
+                _ => (),
+            }
+        }
+    }
+
+    /// E.g. `12 short-lived procs in the last interval`, when there were
+    /// any between the last two refreshes.
+    fn short_lived_title(&self) -> Option<String> {
+        (self.last_short_lived > 0).then(|| {
+            format!(
+                "{} short-lived procs in the last interval",
+                self.last_short_lived
+            )
+        })
+    }
+
+    /// E.g. `3 hidden by permissions`, when brt can't read some processes.
+    fn restricted_title(&self) -> Option<String> {
+        (self.restricted > 0).then(|| format!("{} hidden by permissions", self.restricted))
+    }
+
+    pub fn tick(&mut self) -> UpdatePayload {
+        self.app_ticker = self.app_ticker.saturating_add(1);
+        // the refresh reads whatever the pending events are about, and
+        // starts counting the short-lived processes anew once they are
+        // counted
+        if let Some(events) = &self.events {
+            let events = events.drain();
+            self.count_short_lived(&events);
+        }
+        self.last_short_lived = self.short_lived;
+        self.forked.clear();
+        self.short_lived = 0;
+        let update = self.refresh();
+        info!(
+            processes = update.processes,
+            started = update.started,
+            exited = update.exited,
+            "Refreshed process list."
+        );
+        update
+    }
+
+    fn get_processes(&mut self) -> HashMap<i32, BrtProcess> {
+        get_processes(self.source.as_ref())
+    }
+
+    /// The tie breakers that can change the order: not the sorted column, and
+    /// none after the pid, which no two processes share.
+    fn tie_breakers(&self) -> Vec<SortKey> {
+        let mut keys = vec![];
+        for key in &self.tie_breakers {
+            if key.order != self.order {
+                keys.push(*key);
+            }
+            if key.order == Pid {
+                break;
+            }
+        }
+        keys
+    }
+
+    /// Sorts by the visible column at `position`, counting from one, or
+    /// flips the direction when the table is already sorted by it.
+    pub fn sort_by_column(&mut self, position: usize) {
+        let columns = self.visible_columns(self.width);
+        let Some(column) = position.checked_sub(1).and_then(|i| columns.get(i)) else {
+            return;
+        };
+        let order = Order::from(*column);
+        if order == self.order {
+            self.descending = !self.descending;
+        } else {
+            self.order = order;
+            self.descending = false;
+        }
+        self.list_processes();
+    }
+
+    /// The header of the columns, with the direction on the sorted one.
+    fn header(&self, columns: &[Column]) -> Row<'static> {
+        // the recent cpu usage has no column of its own
+        let order = if self.order == CpuRecent {
+            Cpu
+        } else {
+            self.order
+        };
+        let sorted = columns
+            .iter()
+            .position(|column| *column != Column::CpuGraph && Order::from(*column) == order);
+        let symbols = symbols();
+        let indicator = if self.descending {
+            symbols.falling
+        } else {
+            symbols.rising
+        };
+        let cells = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| column.header((Some(i) == sorted).then_some(indicator)));
+        std::iter::once(Cell::new("")).chain(cells).collect()
+    }
+
+    /// Scrolls the window of `rows` rows so the selection is in it and, when
+    /// the list got shorter, no rows are left empty below the last process.
+    /// The scrollbar follows the window rather than the selection.
+    fn scroll(&mut self, rows: usize) {
+        // a pane too low for any row keeps the selection at the top
+        let rows = rows.max(1);
+        let length = self.processes.len();
+        let selected = self
+            .state
+            .selected()
+            .unwrap_or_default()
+            .min(length.saturating_sub(1));
+        let offset = self
+            .state
+            .offset()
+            .min(length.saturating_sub(rows))
+            .clamp((selected + 1).saturating_sub(rows), selected);
+        self.state.select(Some(selected));
+        *self.state.offset_mut() = offset;
+        self.scrollbar_state = ScrollbarState::new(length.saturating_sub(rows) + 1)
+            .viewport_content_length(rows)
+            .position(offset);
+    }
+
+    pub fn render_tick(&mut self) {
+        info!("Render Tick");
+        self.render_ticker = self.render_ticker.saturating_add(1);
+    }
+
+    /// Whether the process exited and is only still shown as such.
+    fn has_exited(&self, pid: i32) -> bool {
+        matches!(self.lifecycles.get(&pid), Some((Lifecycle::Exited, _)))
+    }
+
+    /// Moves the selection `steps` processes down, or up when negative,
+    /// wrapping around at either end. The processes that exited are passed
+    /// over, as nothing can be done to them.
+    pub fn jump(&mut self, steps: i64) {
+        let location = self.state.selected().unwrap_or(0) as i64;
+        let length = self.processes.len() as i64;
+        if length == 0 {
+            return;
+        }
+        debug!(
+            "Move {} steps in [{}..{}] when current location is {}.",
+            steps, 0, length, location
+        );
+        let step = if steps < 0 { -1 } else { 1 };
+        let mut index = (location + steps).rem_euclid(length);
+        for _ in 0..length {
+            if !self.has_exited(self.processes[index as usize].pid) {
+                debug!("New location is {}.", index);
+                self.state.select(Some(index as usize));
+                return;
+            }
+            index = (index + step).rem_euclid(length);
+        }
+    }
+}
+
+impl Component for Process {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.action_tx = Some(tx);
+        Ok(())
+    }
+
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.compact_width = config.config.compact_width;
+        self.recent_cpu_samples = config.config.recent_cpu_samples;
+        self.graph_thresholds = config.config.graph_thresholds;
+        self.row_tint = config.config.row_colors;
+        self.attach = config.config.attach;
+        self.guard_system_services = config.config.guard_system_services;
+        self.show_totals = config.config.totals;
+        self.journal_lines = config.config.journal_lines;
+        self.tie_breakers = config.config.tie_breakers;
+        self.cadence = Cadence::from_millis(config.config.intervals.processes);
+        if !config.config.columns.is_empty() {
+            self.columns = config.config.columns;
+        }
+        Ok(())
+    }
+
+    fn save_session(&self, session: &mut Session) {
+        session.order = self.order;
+        session.descending = self.descending;
+        session.tie_breakers = self.tie_breakers.clone();
+        session.filter = self.filter.applied.clone();
+    }
+
+    fn restore_session(&mut self, session: &Session) {
+        self.order = session.order;
+        self.descending = session.descending;
+        if !session.tie_breakers.is_empty() {
+            self.tie_breakers = session.tie_breakers.clone();
+        }
+        if !session.filter.is_empty() {
+            self.filter.expression = Input::new(session.filter.clone());
+            if !self.filter.apply() {
+                warn!("Ignoring the saved filter '{}'.", session.filter);
+                self.filter.discard();
+            }
+        }
+        self.list_processes();
+    }
+
+    fn hints(&self) -> Vec<Hint> {
+        let symbols = symbols();
+        let hints: &[(&str, &'static str)] = if let Some(batch) = &self.batch {
+            let keys: &[(&str, &str)] = if batch.critical.is_some() {
+                &[
+                    ("0-9", "type pid"),
+                    ("enter", "confirm"),
+                    ("esc", "cancel"),
+                    ("c", "copy command"),
+                ]
+            } else {
+                &[("y", "confirm"), ("n", "cancel"), ("c", "copy command")]
+            };
+            let mut hints: Vec<Hint> = keys
+                .iter()
+                .map(|(key, label)| Hint::new(*key, label))
+                .collect();
+            if let BatchAction::Signal(_) = batch.action {
+                hints.push(Hint::new(
+                    format!("{}{}", symbols.left, symbols.right),
+                    "scope",
+                ));
+            }
+            return hints;
+        } else if self.filter.editing || self.search.as_ref().is_some_and(|s| s.editing) {
+            &[("enter", "apply"), ("esc", "cancel")]
+        } else if self.io_picker.is_some() {
+            let class = format!("{}{}", symbols.left, symbols.right);
+            let level = format!("{}{}", symbols.up, symbols.down);
+            return vec![
+                Hint::new(class, "class"),
+                Hint::new(level, "level"),
+                Hint::new("enter", "set"),
+                Hint::new("esc", "cancel"),
+            ];
+        } else if self.sorter.is_some() {
+            let select = format!("{}{}", symbols.up, symbols.down);
+            return vec![
+                Hint::new(select, "select"),
+                Hint::new("j/k", "move"),
+                Hint::new("space", "direction"),
+                Hint::new("enter", "apply"),
+                Hint::new("esc", "cancel"),
+            ];
+        } else if self.unit_log.is_some() {
+            &[("j", "details"), ("esc", "back")]
+        } else if self.details.is_some() {
+            &[("j", "journal"), ("esc", "close")]
+        } else if self.search.is_some() {
+            &[("n", "next"), ("N", "previous"), ("esc", "clear")]
+        } else {
+            &[
+                ("enter", "details"),
+                ("/", "search"),
+                ("f", "filter"),
+                ("space", "mark"),
+                ("k", "term"),
+                ("K", "kill"),
+                ("n", "nice"),
+                ("i", "io"),
+                ("1-9", "sort"),
+                ("o", "order"),
+            ]
+        };
+        hints
+            .iter()
+            .map(|(key, label)| Hint::new(*key, label))
+            .collect()
+    }
+
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        debug!("Handling {:?}.", key);
+        if let Some(critical) = self.batch.as_ref().map(|batch| batch.critical.is_some()) {
+            let action = match key.code {
+                KeyCode::Enter => Some(Action::Confirm),
+                KeyCode::Esc => Some(Action::Cancel),
+                KeyCode::Char('y') if !critical => Some(Action::Confirm),
+                KeyCode::Char('n') if !critical => Some(Action::Cancel),
+                KeyCode::Char('c') => Some(Action::CopyShellCommand),
+                KeyCode::Left | KeyCode::Right => {
+                    self.rescope_batch(key.code == KeyCode::Right);
+                    self.dirty = true;
+                    None
+                }
+                KeyCode::Char('0'..='9') | KeyCode::Backspace if critical => {
+                    self.input.handle_event(&crossterm::event::Event::Key(key));
+                    None
+                }
+                _ => None,
+            };
+            return Ok(action);
+        }
+        if let Some(search) = self.search.as_mut().filter(|search| search.editing) {
+            let action = match key.code {
+                KeyCode::Enter => {
+                    search.editing = false;
+                    Some(Action::EnterNormal)
+                }
+                KeyCode::Esc => {
+                    self.search = None;
+                    Some(Action::EnterNormal)
+                }
+                _ => {
+                    search
+                        .query
+                        .handle_event(&crossterm::event::Event::Key(key));
+                    self.jump_to_match(true, true);
+                    None
+                }
+            };
+            self.dirty = true;
+            return Ok(action);
+        }
+        if self.filter.editing {
+            let action = match key.code {
+                KeyCode::Enter => self.filter.apply().then(|| {
+                    self.list_processes();
+                    Action::EnterNormal
+                }),
+                KeyCode::Esc => {
+                    self.filter.discard();
+                    Some(Action::EnterNormal)
+                }
+                _ => {
+                    let filter = &mut self.filter;
+                    filter
+                        .expression
+                        .handle_event(&crossterm::event::Event::Key(key));
+                    filter.error = None;
+                    None
+                }
+            };
+            self.dirty = true;
+            return Ok(action);
+        }
+        if let Some(priority) = self.io_picker {
+            let action = match key.code {
+                KeyCode::Enter => Some(Action::SetIoPriority(priority)),
+                KeyCode::Esc => Some(Action::Cancel),
+                KeyCode::Left => {
+                    self.io_picker = Some(priority.with_class_step(-1));
+                    None
+                }
+                KeyCode::Right => {
+                    self.io_picker = Some(priority.with_class_step(1));
+                    None
+                }
+                KeyCode::Up => {
+                    self.io_picker = Some(priority.with_level_step(-1));
+                    None
+                }
+                KeyCode::Down => {
+                    self.io_picker = Some(priority.with_level_step(1));
+                    None
+                }
+                _ => None,
+            };
+            return Ok(action);
+        }
+        if let Some(sorter) = self.sorter.as_mut() {
+            let action = match key.code {
+                KeyCode::Enter => Some(Action::SetSortKeys(sorter.keys.clone())),
+                KeyCode::Esc => Some(Action::Cancel),
+                KeyCode::Up => {
+                    sorter.select(-1);
+                    None
+                }
+                KeyCode::Down => {
+                    sorter.select(1);
+                    None
+                }
+                KeyCode::Char('k') => {
+                    sorter.move_selected(-1);
+                    None
+                }
+                KeyCode::Char('j') => {
+                    sorter.move_selected(1);
+                    None
+                }
+                KeyCode::Char(' ') => {
+                    sorter.toggle_direction();
+                    None
+                }
+                _ => None,
+            };
+            return Ok(action);
+        }
+        // those are bound in the config, e.g. alt and a digit selects a tab
+        if key
+            .modifiers
+            .intersects(KeyModifiers::ALT | KeyModifiers::CONTROL)
+        {
+            return Ok(None);
+        }
+        if self.unit_log.is_some() && matches!(key.code, KeyCode::Esc | KeyCode::Char('j')) {
+            return Ok(Some(Action::ToggleUnitLog));
+        }
+        if self.details.is_some() {
+            match key.code {
+                KeyCode::Esc | KeyCode::Enter => return Ok(Some(Action::ToggleDetails)),
+                KeyCode::Char('j') => return Ok(Some(Action::ToggleUnitLog)),
+                _ => (),
+            }
+        }
+        if self.search.is_some() {
+            match key.code {
+                KeyCode::Char('n') => return Ok(Some(Action::NextMatch)),
+                KeyCode::Char('N') => return Ok(Some(Action::PreviousMatch)),
+                KeyCode::Esc => return Ok(Some(Action::Cancel)),
+                _ => (),
+            }
+        }
+        let action = match key.code {
+            KeyCode::Enter => Action::ToggleDetails,
+            KeyCode::Up => Action::Up,
+            KeyCode::Down => Action::Down,
+            KeyCode::PageUp => Action::PageUp,
+            KeyCode::PageDown => Action::PageDown,
+            KeyCode::Left if key.modifiers.contains(KeyModifiers::SHIFT) => Action::ScrollLeft,
+            KeyCode::Right if key.modifiers.contains(KeyModifiers::SHIFT) => Action::ScrollRight,
+            KeyCode::Left => Action::Left,
+            KeyCode::Right => Action::Right,
+            KeyCode::Esc => Action::Quit,
+            KeyCode::Char(' ') => Action::ToggleMark,
+            KeyCode::Char('k') => Action::Terminate,
+            KeyCode::Char('K') => Action::Kill,
+            KeyCode::Char('n') => Action::Renice(RENICE_STEP),
+            KeyCode::Char('N') => Action::Renice(-RENICE_STEP),
+            KeyCode::Char('i') => Action::PickIoPriority,
+            KeyCode::Char('o') => Action::PickOrder,
+            KeyCode::Char('s') => Action::Attach(Tracer::Strace),
+            KeyCode::Char('g') => Action::Attach(Tracer::Gdb),
+            KeyCode::Char('p') => Action::TogglePause,
+            KeyCode::Char('y') => Action::CopyPid,
+            KeyCode::Char('Y') => Action::CopyCommand,
+            KeyCode::Char('u') => Action::JumpToParent,
+            KeyCode::Char('c') => Action::JumpToChild,
+            KeyCode::Char('/') => Action::Search,
+            KeyCode::Char('f') => Action::EditFilter,
+            KeyCode::Char(']') => Action::SeekForward,
+            KeyCode::Char('[') => Action::SeekBackward,
+            KeyCode::Char('+') => Action::SpeedUp,
+            KeyCode::Char('-') => Action::SpeedDown,
+            KeyCode::Char(digit @ '1'..='9') => Action::SortBy(digit as usize - '0' as usize),
+            _ => return Ok(None),
+        };
+        Ok(Some(action))
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        // anything but the passing of time changes what is shown
+        if !matches!(action, Action::Tick | Action::Render | Action::Sampled(_)) {
+            self.dirty = true;
+        }
+        // the ancestry is of the process jumped to
+        let moved = matches!(
+            action,
+            Action::Up | Action::Down | Action::PageUp | Action::PageDown
+        );
+        if matches!(action, Action::Tick | Action::Render) && self.receive_list() {
+            self.dirty = true;
+        }
+        match action {
+            Action::Tick if self.cadence.due() => {
+                self.dirty = true;
+                return Ok(Some(Action::Update(self.tick())));
+            }
+            Action::Tick => self.dirty |= self.apply_events(),
+            Action::Render => self.render_tick(),
+            Action::Up => self.jump(-1),
+            Action::Down => self.jump(1),
+            Action::PageUp => self.jump(-20),
+            Action::PageDown => self.jump(20),
+            Action::Left => {
+                self.order = self.order.step_shown(false, &self.columns);
+                self.list_processes();
+            }
+            Action::Right => {
+                self.order = self.order.step_shown(true, &self.columns);
+                self.list_processes();
+            }
+            Action::ScrollLeft => self.scroll_columns(-1),
+            Action::ScrollRight => self.scroll_columns(1),
+            Action::TogglePause
+            | Action::SeekForward
+            | Action::SeekBackward
+            | Action::SpeedUp
+            | Action::SpeedDown => self.control_replay(action),
+            Action::SortBy(position) => self.sort_by_column(position),
+            Action::OrderBy(order, descending) => {
+                (self.order, self.descending) = (order, descending);
+                self.list_processes();
+            }
+            Action::ToggleMark => self.toggle_mark(),
+            Action::ToggleDetails => self.toggle_details(),
+            Action::ToggleUnitLog => return Ok(self.toggle_unit_log()),
+            Action::UnitLogRead(unit, entries) => self.show_unit_log(unit, entries),
+            Action::Terminate => return Ok(self.prepare_batch(BatchAction::Signal(Signal::Term))),
+            Action::Kill => return Ok(self.prepare_batch(BatchAction::Signal(Signal::Kill))),
+            Action::Renice(delta) => return Ok(self.prepare_batch(BatchAction::Renice(delta))),
+            Action::PickIoPriority => return Ok(self.pick_io_priority()),
+            Action::SetIoPriority(priority) => {
+                self.io_picker = None;
+                return Ok(self.prepare_batch(BatchAction::IoPriority(priority)));
+            }
+            Action::PickOrder => {
+                let primary = SortKey {
+                    order: self.order,
+                    descending: self.descending,
+                };
+                self.sorter = Some(Sorter::new(primary, &self.tie_breakers, &self.columns));
+            }
+            Action::SetSortKeys(keys) => {
+                self.sorter = None;
+                if let Some((primary, tie_breakers)) = keys.split_first() {
+                    (self.order, self.descending) = (primary.order, primary.descending);
+                    self.tie_breakers = tie_breakers.to_vec();
+                    self.list_processes();
+                }
+            }
+            Action::Confirm => return Ok(self.confirm_batch()),
+            Action::CopyShellCommand => return Ok(self.copy_shell_command()),
+            Action::Cancel => {
+                self.batch = None;
+                self.io_picker = None;
+                self.sorter = None;
+                self.search = None;
+                self.input.reset();
+            }
+            Action::Search => {
+                self.search = Some(Search {
+                    editing: true,
+                    ..Search::default()
+                });
+                return Ok(Some(Action::EnterInsert));
+            }
+            Action::EditFilter => {
+                self.filter.editing = true;
+                return Ok(Some(Action::EnterInsert));
+            }
+            Action::FilterCgroup(cgroup) => self.filter_cgroup(&cgroup),
+            Action::NextMatch => self.jump_to_match(true, false),
+            Action::PreviousMatch => self.jump_to_match(false, false),
+            Action::Attach(tracer) => return Ok(self.attach(tracer)),
+            Action::RunCommand(ref template) => return Ok(self.run_command(template)),
+            Action::CopyPid => return Ok(self.copy_selected(false)),
+            Action::CopyCommand => return Ok(self.copy_selected(true)),
+            Action::JumpToParent => return Ok(self.jump_in_tree(true)),
+            Action::JumpToChild => return Ok(self.jump_in_tree(false)),
+            _ => (),
+        }
+        if moved {
+            self.breadcrumb = None;
+        }
+        Ok(None)
+    }
+
+    fn describe(&self) -> Vec<String> {
+        let direction = |descending| {
+            if descending {
+                "highest first"
+            } else {
+                "lowest first"
+            }
+        };
+        let mut order = format!("{}, {}", self.order, direction(self.descending));
+        if let Some(key) = self.tie_breakers().first() {
+            order += &format!(", then by {}, {}", key.order, direction(key.descending));
+        }
+        let mut lines = vec![format!(
+            "{} processes, sorted by {order}.",
+            self.processes.len()
+        )];
+        if !self.filter.applied.is_empty() {
+            let (cpu, memory) = self.totals();
+            lines.push(format!(
+                "Filtered by {}; together they use {cpu:.1}% cpu and {} memory.",
+                self.filter.applied,
+                format_bytes(memory)
+            ));
+        }
+        if self.column_offset > 0 {
+            lines.push(format!(
+                "{} columns are scrolled out of view.",
+                self.column_offset
+            ));
+        }
+        if self.last_short_lived > 0 {
+            lines.push(format!(
+                "{} processes started and exited between the last two refreshes.",
+                self.last_short_lived
+            ));
+        }
+        if self.restricted > 0 {
+            lines.push(format!(
+                "{} processes can't be read without more permissions.",
+                self.restricted
+            ));
+        }
+        if let Some(process) = self.selected_process() {
+            lines.push(format!(
+                "Selected process {}, {}, of user {}.",
+                process.pid,
+                process.program,
+                process.username()
+            ));
+            lines.push(format!(
+                "Cpu {:.1}%, memory {}, threads {}, state {}, nice {}, started {}.",
+                process.cpu,
+                format_bytes(process.resident_memory),
+                process.number_of_threads,
+                process.state,
+                process.nice,
+                format_start_time(process.start_time)
+            ));
+            let command = process.command.trim_end();
+            if !command.is_empty() {
+                // without a full stop, which could be taken for part of it
+                lines.push(format!("Command line: {command}"));
+            }
+        }
+        lines
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn sampled(&self) -> Option<Sample> {
+        Sample::of("proc", self.sampled_at, &self.cadence)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Percentage(100)])
+            .split(rect);
+
+        self.width = rect.width;
+        let columns = self.visible_columns(rect.width);
+        let lifecycles = self
+            .lifecycles
+            .iter()
+            .map(|(pid, (lifecycle, _))| (*pid, *lifecycle))
+            .collect();
+        let widths = std::iter::once(Constraint::Length(1))
+            .chain(columns.iter().map(Column::width))
+            .collect::<Vec<_>>();
+        // lays the columns out like the table does, inside its borders and
+        // right padding, so the text can be cut to fit
+        let table_width = rect.width.saturating_sub(3);
+        let column_widths: Vec<u16> = Layout::horizontal(widths.clone())
+            .spacing(1)
+            .split(Rect::new(0, 0, table_width, 1))
+            .iter()
+            .skip(1)
+            .map(|area| area.width)
+            .collect();
+        // the borders and the header row are not part of the window
+        let window = layout[0].height.saturating_sub(3) as usize;
+        self.scroll(window);
+        // only the rows in the window are built
+        let offset = self.state.offset();
+        let shown = &self.processes[offset..(offset + window).min(self.processes.len())];
+        let (row_tint, memory_total) = (&self.row_tint, self.memory_total);
+        let rows = rows(
+            shown,
+            &columns,
+            &column_widths,
+            &self.graph_thresholds,
+            &self.marked,
+            &lifecycles,
+            |process| row_tint.as_ref()?.style(process, memory_total),
+        );
+
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(Some(symbols().up))
+            .end_symbol(Some(symbols().down))
+            .track_symbol(Some(" "))
+            .style(Color::White);
+
+        let selected_style = Style::default()
+            .bg(Color::Rgb(0xd4, 0x54, 0x54))
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD);
+
+        let header = self
+            .header(&columns)
+            .height(1)
+            .style(Style::default().bold());
+
+        let processes = self.processes.len();
+        let process = format!("{}/{}", self.state.selected().unwrap() + 1, processes);
+
+        let mut block = Block::default()
+            .title(Title::from("brt").alignment(Alignment::Center))
+            .title(Title::from(self.order_string()).alignment(Alignment::Right))
+            .title(
+                Title::from(process)
+                    .position(Position::Bottom)
+                    .alignment(Alignment::Right),
+            )
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .border_type(BorderType::Rounded)
+            // keeps the last column clear of the scrollbar
+            .padding(Padding::new(0, 1, 0, 0));
+        if let Some(replay) = &self.replay {
+            block = block.title(Title::from(replay.status().yellow()).alignment(Alignment::Left));
+        }
+        if self.column_offset > 0 {
+            let scrolled = format!("{} {} columns", symbols().left, self.column_offset);
+            block = block.title(Title::from(scrolled.dim()).alignment(Alignment::Left));
+        }
+        if let Some(filter) = self.filter.title() {
+            block = block.title(Title::from(filter).alignment(Alignment::Left));
+        }
+        if let Some(search) = self.search_title() {
+            block = block.title(Title::from(search.yellow()).alignment(Alignment::Left));
+        }
+        if !self.marked.is_empty() {
+            block = block.title(
+                Title::from(format!("{} marked", self.marked.len()).yellow())
+                    .position(Position::Bottom)
+                    .alignment(Alignment::Left),
+            );
+        }
+
+        if let Some(short_lived) = self.short_lived_title() {
+            block = block.title(
+                Title::from(short_lived.magenta())
+                    .position(Position::Bottom)
+                    .alignment(Alignment::Left),
+            );
+        }
+
+        if let Some(restricted) = self.restricted_title() {
+            block = block.title(
+                Title::from(restricted.dim())
+                    .position(Position::Bottom)
+                    .alignment(Alignment::Left),
+            );
+        }
+
+        if let Some(totals) = self.totals_title() {
+            block = block.title(
+                Title::from(totals.yellow())
+                    .position(Position::Bottom)
+                    .alignment(Alignment::Center),
+            );
+        }
+
+        if let Some(breadcrumb) = &self.breadcrumb {
+            block = block.title(
+                Title::from(breadcrumb.clone().dim())
+                    .position(Position::Bottom)
+                    .alignment(Alignment::Left),
+            );
+        }
+
+        let table = Table::new(rows, widths)
+            .block(block)
+            .header(header)
+            .highlight_style(selected_style);
+
+        let mut state = TableState::default()
+            .with_selected(self.state.selected().map(|selected| selected - offset));
+        f.render_stateful_widget(table, layout[0], &mut state);
+        if self.processes.len() > window {
+            f.render_stateful_widget(
+                scrollbar,
+                layout[0].inner(&Margin {
+                    vertical: 1,
+                    horizontal: 1,
+                }),
+                &mut self.scrollbar_state,
+            );
+        }
+        if let Some(details) = &self.details {
+            f.render_widget(details.clone(), rect);
+        }
+        if let Some(unit_log) = &self.unit_log {
+            f.render_widget(unit_log.clone(), rect);
+        }
+        if let Some(priority) = &self.io_picker {
+            f.render_widget(Self::io_picker_popup(priority), rect);
+        }
+        if let Some(sorter) = &self.sorter {
+            f.render_widget(Self::sorter_popup(sorter), rect);
+        }
+        if let Some(batch) = &self.batch {
+            f.render_widget(Self::batch_popup(batch, self.input.value()), rect);
+        }
+        Ok(())
+    }
+}
+
+/// The processes a drain of events started or replaced the program of, to
+/// be read: each once, in the order they came, without those that exited
+/// within the same drain and at most [`MAX_EVENT_READS`].
+fn started_pids(events: &[ProcEvent]) -> Vec<i32> {
+    let exited: HashSet<i32> = events
+        .iter()
+        .filter_map(|event| match event {
+            ProcEvent::Exit(pid) => Some(*pid),
+            _ => None,
+        })
+        .collect();
+    let mut seen = HashSet::new();
+    events
+        .iter()
+        .filter_map(|event| match event {
+            ProcEvent::Fork(pid) | ProcEvent::Exec(pid) => Some(*pid),
+            ProcEvent::Exit(_) => None,
+        })
+        .filter(|pid| !exited.contains(pid) && seen.insert(*pid))
+        .take(MAX_EVENT_READS)
+        .collect()
+}
+
+/// The latest entries of `unit`, or why they can't be read, or that they are
+/// being read.
+fn unit_log_popup(unit: &Unit, entries: Option<Result<Vec<Entry>, String>>) -> Popup<'static> {
+    let mut lines: Vec<Line> = match entries {
+        None => vec![Line::from("Reading the journal...".dim())],
+        Some(Ok(entries)) if entries.is_empty() => vec![Line::from("No entries".dim())],
+        Some(Ok(entries)) => entries.iter().map(Entry::line).collect(),
+        Some(Err(e)) => vec![Line::from(format!("Unable to read the journal: {e}").red())],
+    };
+    lines.push(Line::default());
+    lines.push(Line::from("j or esc to go back".dim()));
+    let kind = if unit.user.is_some() {
+        "user unit"
+    } else {
+        "unit"
+    };
+    Popup::new(format!("journal of {kind} {}", unit.name), lines)
+}
+
+/// The width the capability names are wrapped at in the details.
+const DETAILS_WIDTH: usize = 60;
+
+/// The identity, capabilities and security label of a process.
+fn details_popup(source: &dyn ProcSource, process: &BrtProcess) -> Popup<'static> {
+    let mut lines = vec![
+        Line::from(vec!["Pid:     ".bold(), process.pid.to_string().into()]),
+        Line::from(vec!["Program: ".bold(), process.program.clone().into()]),
+        Line::from(vec![
+            "Command: ".bold(),
+            process.command.trim_end().to_string().into(),
+        ]),
+        Line::from(vec!["User:    ".bold(), process.username().into()]),
+    ];
+    let label = match source.security_label(process.pid) {
+        Ok(label) if !label.is_empty() => sanitize(&label),
+        Ok(_) => "none".to_string(),
+        Err(e) => format!("unknown ({e})"),
+    };
+    lines.push(Line::from(vec!["Label:   ".bold(), label.into()]));
+    let io = match io_priority(process.pid) {
+        Ok(priority) => Some(priority.to_string()),
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => None,
+        Err(e) => Some(format!("unknown ({e})")),
+    };
+    if let Some(io) = io {
+        lines.push(Line::from(vec!["IO:      ".bold(), io.into()]));
+    }
+    match source.capabilities(process.pid) {
+        Ok(0) => lines.push(Line::from(vec!["Caps:    ".bold(), "none".into()])),
+        Ok(mask) => {
+            let indicator = capabilities::indicator(mask);
+            lines.push(Line::from(vec![
+                "Caps:    ".bold(),
+                format!("{mask:#018x} {indicator}").into(),
+            ]));
+            let mut line = String::new();
+            for name in capabilities::names(mask) {
+                if !line.is_empty() && line.len() + name.len() + 1 > DETAILS_WIDTH {
+                    lines.push(Line::from(format!("  {line}")));
+                    line.clear();
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(&name);
+            }
+            lines.push(Line::from(format!("  {line}")));
+        }
+        Err(e) => lines.push(Line::from(vec![
+            "Caps:    ".bold(),
+            format!("unknown ({e})").into(),
+        ])),
+    }
+    if let Ok(memory) = CgroupMemory::read(source, process.pid) {
+        lines.push(Line::from(vec![
+            "Cgroup:  ".bold(),
+            sanitize(&memory.cgroup).into(),
+        ]));
+        let limit = memory.max.map_or("max".to_string(), format_bytes);
+        let mut usage = format!("{}/{limit}", format_bytes(memory.current));
+        if let Some(percentage) = memory.percentage() {
+            usage.push_str(&format!(" ({percentage:.0}%)"));
+        }
+        let style = if memory.is_near_limit() {
+            Style::default().fg(Color::Yellow).bold()
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            "Memory:  ".bold(),
+            Span::styled(usage, style),
+        ]));
+    }
+    // the resident memory counts shared pages in full for every process
+    if let Ok(memory) = ProcessMemory::read(source, process.pid) {
+        let (rss, shared) = (format_bytes(memory.rss), format_bytes(memory.shared));
+        match (memory.pss, memory.private, memory.swap) {
+            (Some(pss), private, swap) => {
+                let optional = |bytes: Option<u64>| bytes.map_or("?".to_string(), format_bytes);
+                lines.push(Line::from(vec![
+                    "Maps:    ".bold(),
+                    format!(
+                        "rss {rss} pss {} swap {}",
+                        format_bytes(pss),
+                        optional(swap)
+                    )
+                    .into(),
+                ]));
+                lines.push(Line::from(format!(
+                    "         shared {shared} private {}",
+                    optional(private)
+                )));
+            }
+            _ => lines.push(Line::from(vec![
+                "Maps:    ".bold(),
+                format!("rss {rss} shared {shared} (statm)").into(),
+            ])),
+        }
+    }
+    // how often a process wakes up tells more about what it costs the
+    // battery than its cpu usage
+    if let Ok((voluntary, involuntary)) = source.context_switches(process.pid) {
+        lines.push(Line::from(vec![
+            "Ctx sw:  ".bold(),
+            format!("{voluntary} voluntary, {involuntary} involuntary").into(),
+        ]));
+    }
+    if let Ok(wakeups) = source.wakeups(process.pid) {
+        let rate = wakeups as f64 / process.elapsed().max(1) as f64;
+        lines.push(Line::from(vec![
+            "Wakeups: ".bold(),
+            format!("{wakeups} ({rate:.1}/s)").into(),
+        ]));
+    }
+    if let Ok(numa_maps) = source.numa_maps(process.pid) {
+        let nodes = numa_maps_summary(&numa_maps);
+        if !nodes.is_empty() {
+            let summary = nodes
+                .iter()
+                .map(|(node, bytes)| format!("N{node} {}", format_bytes(*bytes)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            lines.push(Line::from(vec!["NUMA:    ".bold(), summary.into()]));
+        }
+    }
+    Popup::new(format!("process {}", process.pid), lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::{FakeProcSource, FakeProcess};
+    use color_eyre::eyre::eyre;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[test]
+    fn test_brt_process_new() {
+        let process = BrtProcess::new();
+        assert_eq!(process.pid, 0);
+        assert_eq!(process.cpus, VecDeque::from(vec![0_f64; 10]));
+    }
+
+    /// Lists the processes again and waits for the list.
+    fn relist(process: &mut Process) {
+        process.list_processes();
+        process.wait_for_list();
+    }
+
+    fn fake_source() -> FakeProcSource {
+        let mut source = FakeProcSource::default()
+            .with(2, FakeProcess::zombie(2, "defunct"))
+            .with(3, FakeProcess::forbidden());
+        for pid in 10..40 {
+            source = source.with(
+                pid,
+                FakeProcess::running(pid, "worker", pid as u64 * 100, 0),
+            );
+        }
+        source
+    }
+
+    #[test]
+    fn test_process_fake_source() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        // the zombie and the forbidden process are kept
+        assert_eq!(process.process_map.len(), 32);
+        assert!(process.process_map.contains_key(&2));
+        assert!(process.process_map[&3].restricted);
+        assert_eq!(process.refresh().restricted, 1);
+        assert_eq!(
+            process.restricted_title().unwrap(),
+            "1 hidden by permissions"
+        );
+
+        // the zombie and the forbidden process use nothing
+        let idle = |process: &Process| -> HashSet<i32> {
+            process.processes[..2].iter().map(|p| p.pid).collect()
+        };
+        process.order = Order::Cpu;
+        relist(&mut process);
+        assert_eq!(idle(&process), HashSet::from([2, 3]));
+        assert_eq!(process.processes.last().unwrap().pid, 39);
+
+        process.order = Order::Name;
+        relist(&mut process);
+        assert_eq!(process.processes[0].program, "?");
+        assert_eq!(process.processes[1].program, "defunct");
+
+        process.order = Order::Memory;
+        relist(&mut process);
+        assert_eq!(idle(&process), HashSet::from([2, 3]));
+        assert_eq!(
+            process.processes.last().unwrap().resident_memory,
+            512 * 4096
+        );
+    }
+
+    #[test]
+    fn test_tie_breakers() {
+        let mut process = Process::with_source(Box::new(FakeProcSource::default()));
+        let worker = |pid, program: &str, resident_memory| BrtProcess {
+            pid,
+            program: program.to_string(),
+            resident_memory,
+            ..BrtProcess::default()
+        };
+        process.process_map = [
+            worker(1, "nginx", 300),
+            worker(2, "bash", 500),
+            worker(3, "nginx", 100),
+            worker(4, "nginx", 200),
+        ]
+        .into_iter()
+        .map(|worker| (worker.pid, worker))
+        .collect();
+        let pids =
+            |process: &Process| -> Vec<i32> { process.processes.iter().map(|p| p.pid).collect() };
+        let key = |order, descending| SortKey { order, descending };
+        process.order = Order::Name;
+        process.tie_breakers = vec![key(Order::Memory, false)];
+        relist(&mut process);
+        assert_eq!(pids(&process), vec![2, 3, 4, 1]);
+
+        // each has its own direction
+        process.descending = true;
+        relist(&mut process);
+        assert_eq!(pids(&process), vec![3, 4, 1, 2]);
+        process.tie_breakers = vec![key(Order::Memory, true)];
+        relist(&mut process);
+        assert_eq!(pids(&process), vec![1, 4, 3, 2]);
+        process.state.select(None);
+        assert_eq!(
+            process.describe(),
+            vec!["4 processes, sorted by name, highest first, then by memory, highest first."]
+        );
+
+        // the same order as the sorted column is left out
+        process.order = Order::Memory;
+        process.descending = false;
+        relist(&mut process);
+        assert_eq!(pids(&process), vec![3, 4, 1, 2]);
+
+        // and the ones after the pid never matter
+        process.order = Order::Name;
+        process.tie_breakers = vec![key(Order::Pid, false), key(Order::Memory, false)];
+        assert_eq!(process.tie_breakers(), vec![key(Order::Pid, false)]);
+        relist(&mut process);
+        assert_eq!(pids(&process), vec![2, 1, 3, 4]);
+    }
+
+    #[test]
+    fn test_sorter() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        process.order = Order::Name;
+        process.tie_breakers = vec![SortKey {
+            order: Order::Memory,
+            descending: true,
+        }];
+        let key = |code| KeyEvent::from(code);
+        assert_eq!(
+            process.handle_key_events(key(KeyCode::Char('o'))).unwrap(),
+            Some(Action::PickOrder)
+        );
+        process.update(Action::PickOrder).unwrap();
+        let sorter = process.sorter.as_ref().unwrap();
+        // every order of a column is listed once, the sorted one first
+        let shown = Order::all()
+            .filter(|order| order.is_shown(&process.columns))
+            .count();
+        assert!(shown < Order::all().count());
+        assert_eq!(sorter.keys.len(), shown);
+        let orders: Vec<Order> = sorter.keys[..3].iter().map(|key| key.order).collect();
+        assert_eq!(orders, vec![Order::Name, Order::Memory, Order::Pid]);
+
+        // memory goes first, from the lowest value
+        for code in [
+            KeyCode::Up,
+            KeyCode::Down,
+            KeyCode::Char('k'),
+            KeyCode::Char('k'),
+            KeyCode::Char(' '),
+        ] {
+            assert_eq!(process.handle_key_events(key(code)).unwrap(), None);
+        }
+        let sorter = process.sorter.clone().unwrap();
+        assert_eq!(sorter.selected, 0);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 30));
+        Process::sorter_popup(&sorter).render(buf.area, &mut buf);
+        let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+        let symbols = symbols();
+        assert!(text.contains(&format!(" 1 {} memory", symbols.rising)));
+        assert!(text.contains(&format!(" 2 {} name", symbols.rising)));
+
+        assert_eq!(
+            process.handle_key_events(key(KeyCode::Enter)).unwrap(),
+            Some(Action::SetSortKeys(sorter.keys.clone()))
+        );
+        process.update(Action::SetSortKeys(sorter.keys)).unwrap();
+        assert!(process.sorter.is_none());
+        assert_eq!((process.order, process.descending), (Order::Memory, false));
+        assert_eq!(process.tie_breakers[0].order, Order::Name);
+
+        let mut session = Session::default();
+        process.save_session(&mut session);
+        assert_eq!(session.tie_breakers, process.tie_breakers);
+    }
+
+    #[test]
+    fn test_order_shown() {
+        let columns = [Column::Pid, Column::Program, Column::Cpu];
+        assert!(Order::CpuRecent.is_shown(&columns));
+        assert!(!Order::Memory.is_shown(&columns));
+        let mut order = Order::Name;
+        let mut seen = vec![];
+        for _ in 0..4 {
+            order = order.step_shown(true, &columns);
+            seen.push(order);
+        }
+        assert_eq!(
+            seen,
+            [Order::Cpu, Order::CpuRecent, Order::Pid, Order::Name]
+        );
+        assert_eq!(Order::Pid.step_shown(false, &columns), Order::CpuRecent);
+        assert_eq!(Order::Memory.step_shown(true, &[]), Order::Memory);
+    }
+
+    #[test]
+    fn test_order_cycle() {
+        let mut order = Order::default();
+        let mut seen = vec![];
+        loop {
+            seen.push(order.to_string());
+            assert_eq!(order.next().previous(), order);
+            order = order.next();
+            if order == Order::default() {
+                break;
+            }
+        }
+        assert_eq!(
+            seen,
+            vec![
+                "pid",
+                "name",
+                "command",
+                "threads",
+                "user",
+                "memory",
+                "cpu",
+                "recent cpu",
+                "started",
+                "elapsed",
+                "nice",
+                "priority",
+                "state",
+                "received",
+                "sent",
+                "capabilities",
+                "oom score",
+                "oom adjustment",
+                "context switches"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_marks() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        relist(&mut process);
+        process.toggle_mark();
+        process.toggle_mark();
+        assert_eq!(process.state.selected(), Some(2));
+        assert_eq!(process.marked, HashSet::from([2, 3]));
+        process.jump(-1);
+        process.toggle_mark();
+        assert_eq!(process.marked, HashSet::from([2]));
+
+        process.prepare_batch(BatchAction::Signal(Signal::Term));
+        let batch = process.batch.clone().unwrap();
+        assert_eq!(batch.targets, vec![(2, "defunct".to_string())]);
+        process.update(Action::Cancel).unwrap();
+        assert!(process.batch.is_none());
+        assert_eq!(process.marked.len(), 1);
+    }
+
+    #[test]
+    fn test_process_batch_without_marks() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        relist(&mut process);
+        process.jump(3);
+        process.update(Action::Renice(5)).unwrap();
+        let batch = process.batch.as_ref().unwrap();
+        assert_eq!(batch.targets, vec![(11, "worker".to_string())]);
+        assert_eq!(batch.question(), "Renice 1 process by +5?");
+
+        let key = KeyEvent::from(KeyCode::Char('q'));
+        assert_eq!(process.handle_key_events(key).unwrap(), None);
+        let key = KeyEvent::from(KeyCode::Esc);
+        assert_eq!(
+            process.handle_key_events(key).unwrap(),
+            Some(Action::Cancel)
+        );
+    }
+
+    #[test]
+    fn test_hints() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        relist(&mut process);
+        let keys = |process: &Process| -> Vec<String> {
+            process
+                .hints()
+                .into_iter()
+                .map(|hint| format!("{} {}", hint.key, hint.label))
+                .collect()
+        };
+        assert_eq!(
+            keys(&process)[..3],
+            ["enter details", "/ search", "f filter"]
+        );
+
+        process.update(Action::EditFilter).unwrap();
+        assert_eq!(keys(&process), ["enter apply", "esc cancel"]);
+        process
+            .handle_key_events(KeyEvent::from(KeyCode::Esc))
+            .unwrap();
+
+        process.jump(3);
+        process.update(Action::Terminate).unwrap();
+        assert_eq!(
+            keys(&process),
+            ["y confirm", "n cancel", "c copy command", "←→ scope"]
+        );
+        process.update(Action::Cancel).unwrap();
+
+        process.update(Action::PickIoPriority).unwrap();
+        assert_eq!(keys(&process)[0], "←→ class");
+    }
+
+    /// A clipboard keeping what is copied, or failing without somewhere to
+    /// keep it.
+    #[derive(Debug)]
+    struct FakeClipboard(Option<Arc<Mutex<Vec<String>>>>);
+
+    impl Clipboard for FakeClipboard {
+        fn copy(&self, text: &str) -> Result<()> {
+            let copied = self.0.as_ref().ok_or_else(|| eyre!("No clipboard"))?;
+            copied.lock().unwrap().push(text.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_copy_shell_command() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        relist(&mut process);
+        assert!(process.select_pid(11));
+        process.update(Action::Renice(5)).unwrap();
+        assert_eq!(
+            process
+                .handle_key_events(KeyEvent::from(KeyCode::Char('c')))
+                .unwrap(),
+            Some(Action::CopyShellCommand)
+        );
+        let copied = Arc::new(Mutex::new(vec![]));
+        process.clipboard = Box::new(FakeClipboard(Some(copied.clone())));
+        let command = "renice --relative 5 -p 11".to_string();
+        assert_eq!(
+            process.update(Action::CopyShellCommand).unwrap(),
+            Some(Action::Toast(format!("Copied `{command}`")))
+        );
+        assert_eq!(*copied.lock().unwrap(), vec![command.clone()]);
+        assert!(process.batch.is_none());
+        assert_eq!(process.update(Action::CopyShellCommand).unwrap(), None);
+
+        // without a clipboard to copy to, it is printed on exit
+        process.clipboard = Box::new(FakeClipboard(None));
+        process.update(Action::Renice(5)).unwrap();
+        assert_eq!(
+            process.update(Action::CopyShellCommand).unwrap(),
+            Some(Action::PrintOnExit(command))
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let source = fake_source().with(70, FakeProcess::running(70, "sshd", 0, 0));
+        let mut process = Process::with_source(Box::new(source));
+        relist(&mut process);
+        process.state.select(None);
+        assert_eq!(
+            process.describe(),
+            vec!["33 processes, sorted by pid, lowest first."]
+        );
+
+        process.filter.expression = Input::new("pid>=60".to_string());
+        process.filter.apply();
+        relist(&mut process);
+        process.select_pid(70);
+        Arc::make_mut(&mut process.processes)[0].cpu = 12.5;
+        let lines = process.describe();
+        assert_eq!(
+            lines[1],
+            "Filtered by pid>=60; together they use 12.5% cpu and 2MiB memory."
+        );
+        // the user and start time depend on the system running the test
+        assert!(lines[2].starts_with("Selected process 70, "));
+        assert!(
+            lines[3].starts_with("Cpu 12.5%, memory 2MiB, threads 1, state S, nice 0, started ")
+        );
+        assert_eq!(lines[4], "Command line: /usr/bin/sshd --flag");
+    }
+
+    #[test]
+    fn test_proc_events() {
+        let source = fake_source();
+        let mut process = Process::with_source(Box::new(source.clone()));
+        relist(&mut process);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        process = process.with_events(ProcEvents::from_receiver(receiver));
+        // the first tick refreshes, and the next ones until the cadence is
+        // due only apply the events
+        process.update(Action::Tick).unwrap();
+        process.source = Box::new(
+            source
+                .with(50, FakeProcess::running(50, "make", 0, 0))
+                .with(51, FakeProcess::zombie(51, "cc")),
+        );
+        for event in [
+            ProcEvent::Fork(50),
+            ProcEvent::Exit(12),
+            ProcEvent::Fork(51),
+            ProcEvent::Exit(51),
+            // gone before it could be read
+            ProcEvent::Fork(52),
+        ] {
+            sender.send(event).unwrap();
+        }
+        process.dirty = false;
+        process.update(Action::Tick).unwrap();
+        assert!(process.dirty);
+        process.wait_for_list();
+        // one that exited within the same tick isn't read
+        assert_eq!(process.processes.len(), 33);
+        assert_eq!(
+            process.lifecycles[&50],
+            (Lifecycle::Started, HIGHLIGHT_REFRESHES)
+        );
+        assert!(!process.lifecycles.contains_key(&51));
+        assert_eq!(
+            process.lifecycles[&12],
+            (Lifecycle::Exited, HIGHLIGHT_REFRESHES)
+        );
+        assert!(!process.process_map.contains_key(&52));
+        assert_eq!(process.short_lived, 1);
+
+        process.dirty = false;
+        process.update(Action::Tick).unwrap();
+        assert!(!process.dirty);
+
+        // the ones never listed count as well, until the next refresh
+        sender.send(ProcEvent::Exit(52)).unwrap();
+        process.update(Action::Tick).unwrap();
+        assert_eq!(process.short_lived, 2);
+        assert_eq!(process.short_lived_title(), None);
+
+        // the events the refresh drains count in the interval it ends
+        sender.send(ProcEvent::Fork(53)).unwrap();
+        sender.send(ProcEvent::Exit(53)).unwrap();
+        process.tick();
+        assert_eq!(process.short_lived, 0);
+        assert_eq!(
+            process.short_lived_title().unwrap(),
+            "3 short-lived procs in the last interval"
+        );
+        process.tick();
+        assert_eq!(process.short_lived_title(), None);
+    }
+
+    #[test]
+    fn test_started_pids() {
+        let events = [
+            ProcEvent::Fork(50),
+            ProcEvent::Exec(50),
+            ProcEvent::Fork(51),
+            ProcEvent::Exec(52),
+            ProcEvent::Exit(51),
+        ];
+        assert_eq!(started_pids(&events), [50, 52]);
+        let events: Vec<ProcEvent> = (0..100).map(ProcEvent::Fork).collect();
+        assert_eq!(started_pids(&events).len(), MAX_EVENT_READS);
+    }
+
+    #[test]
+    fn test_process_jump() {
+        let mut process = Process::with_source(Box::new(fake_source()));
         assert_eq!(process.state.selected(), Some(0));
         process.jump(5);
         assert_eq!(process.state.selected(), Some(5));
@@ -363,4 +2903,951 @@ mod tests {
         process.jump(1);
         assert_eq!(process.state.selected(), Some(1));
     }
+
+    #[test]
+    fn test_search() {
+        let source = fake_source()
+            .with(50, FakeProcess::running(50, "sshd", 0, 0))
+            .with(60, FakeProcess::running(60, "nginx", 0, 0))
+            .with(70, FakeProcess::running(70, "sshd", 0, 0));
+        let mut process = Process::with_source(Box::new(source));
+        relist(&mut process);
+        let selected = |process: &Process| process.selected_process().unwrap().pid;
+        let press = |process: &mut Process, code: KeyCode| -> Option<Action> {
+            let action = process.handle_key_events(KeyEvent::from(code)).unwrap()?;
+            process.update(action).unwrap()
+        };
+
+        assert_eq!(
+            press(&mut process, KeyCode::Char('/')),
+            Some(Action::EnterInsert)
+        );
+        for c in "SSH".chars() {
+            assert_eq!(press(&mut process, KeyCode::Char(c)), None);
+        }
+        assert_eq!(selected(&process), 50);
+        assert_eq!(process.search_title().unwrap(), "/SSH_ 2 matches");
+        assert_eq!(
+            process
+                .handle_key_events(KeyEvent::from(KeyCode::Enter))
+                .unwrap(),
+            Some(Action::EnterNormal)
+        );
+        assert_eq!(process.search_title().unwrap(), "/SSH 2 matches");
+
+        // n and N move between the matches instead of renicing
+        press(&mut process, KeyCode::Char('n'));
+        assert_eq!(selected(&process), 70);
+        press(&mut process, KeyCode::Char('n'));
+        assert_eq!(selected(&process), 50);
+        press(&mut process, KeyCode::Char('N'));
+        assert_eq!(selected(&process), 70);
+        // the order is kept
+        assert_eq!(process.processes[0].pid, 2);
+
+        press(&mut process, KeyCode::Esc);
+        assert!(process.search.is_none());
+        assert_eq!(
+            process
+                .handle_key_events(KeyEvent::from(KeyCode::Char('n')))
+                .unwrap(),
+            Some(Action::Renice(RENICE_STEP))
+        );
+    }
+
+    #[test]
+    fn test_filter() {
+        let source = fake_source()
+            .with(50, FakeProcess::running(50, "sshd", 0, 0))
+            .with(70, FakeProcess::running(70, "sshd", 0, 0));
+        let mut process = Process::with_source(Box::new(source));
+        let type_in = |process: &mut Process, text: &str| {
+            for c in text.chars() {
+                assert_eq!(
+                    process
+                        .handle_key_events(KeyEvent::from(KeyCode::Char(c)))
+                        .unwrap(),
+                    None
+                );
+            }
+            process
+                .handle_key_events(KeyEvent::from(KeyCode::Enter))
+                .unwrap()
+        };
+        let key = KeyEvent::from(KeyCode::Char('f'));
+        let action = process.handle_key_events(key).unwrap().unwrap();
+        assert_eq!(process.update(action).unwrap(), Some(Action::EnterInsert));
+
+        assert_eq!(type_in(&mut process, "name:ssh colour:red"), None);
+        assert_eq!(
+            process.filter.title().unwrap().to_string(),
+            "filter: name:ssh colour:red_ colour:red: unknown field colour"
+        );
+        for _ in 0..11 {
+            process
+                .handle_key_events(KeyEvent::from(KeyCode::Backspace))
+                .unwrap();
+        }
+        assert_eq!(type_in(&mut process, " pid>=60"), Some(Action::EnterNormal));
+        process.wait_for_list();
+        let pids =
+            |process: &Process| -> Vec<i32> { process.processes.iter().map(|p| p.pid).collect() };
+        assert_eq!(pids(&process), vec![70]);
+        assert_eq!(
+            process.filter.title().unwrap().to_string(),
+            "filter: name:ssh pid>=60"
+        );
+        Arc::make_mut(&mut process.processes)[0].cpu = 12.5;
+        assert_eq!(process.totals_title().unwrap(), "total 12.5% cpu 2MiB mem");
+        process.show_totals = false;
+        assert_eq!(process.totals_title(), None);
+        process.show_totals = true;
+
+        // the filter is applied to every refresh
+        process.refresh();
+        process.wait_for_list();
+        assert_eq!(pids(&process), vec![70]);
+
+        process.update(Action::EditFilter).unwrap();
+        process
+            .handle_key_events(KeyEvent::from(KeyCode::Char('x')))
+            .unwrap();
+        process
+            .handle_key_events(KeyEvent::from(KeyCode::Esc))
+            .unwrap();
+        assert_eq!(process.filter.expression.value(), "name:ssh pid>=60");
+    }
+
+    #[test]
+    fn test_filter_cgroup() {
+        let mut nginx = FakeProcess::running(50, "nginx", 0, 0);
+        nginx.cgroup = Some("/system.slice/nginx.service".to_string());
+        let mut bash = FakeProcess::running(70, "bash", 0, 0);
+        bash.cgroup = Some("/user.slice/user-1000.slice/session-2.scope".to_string());
+        let source = fake_source().with(50, nginx).with(70, bash);
+        let mut process = Process::with_source(Box::new(source));
+        assert!(process.processes.iter().all(|p| p.cgroup.is_none()));
+
+        process
+            .update(Action::FilterCgroup("/system.slice".to_string()))
+            .unwrap();
+        process.wait_for_list();
+        let pids: Vec<i32> = process.processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![50]);
+        assert_eq!(
+            process.filter.title().unwrap().to_string(),
+            "filter: cgroup:/system.slice"
+        );
+        process.refresh();
+        process.wait_for_list();
+        assert_eq!(process.processes.len(), 1);
+    }
+
+    #[test]
+    fn test_draw_without_rows() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        relist(&mut process);
+        process.jump(5);
+        // the borders and the header leave no room for rows
+        for height in [0, 3] {
+            let mut terminal =
+                Terminal::new(ratatui::backend::TestBackend::new(60, height)).unwrap();
+            terminal
+                .draw(|f| process.draw(f, f.size()).unwrap())
+                .unwrap();
+        }
+        process.processes = Arc::default();
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(60, 3)).unwrap();
+        terminal
+            .draw(|f| process.draw(f, f.size()).unwrap())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_signal_scope() {
+        // make 10 runs cc 11 and ld 12, and cc runs as 13, in a pipeline
+        // with sort 14
+        let source = FakeProcSource::default()
+            .with(1, FakeProcess::running(1, "init", 100, 0))
+            .with(10, FakeProcess::running(10, "make", 200, 0))
+            .with(11, FakeProcess::running(11, "cc", 300, 0).with_ppid(10))
+            .with(12, FakeProcess::running(12, "ld", 300, 0).with_ppid(10))
+            .with(13, FakeProcess::running(13, "as", 300, 0).with_ppid(11))
+            .with(14, FakeProcess::running(14, "sort", 300, 0).with_pgrp(10))
+            .with(15, FakeProcess::running(15, "getty", 300, 0).with_pgrp(1));
+        let mut process = Process::with_source(Box::new(source));
+        relist(&mut process);
+        assert!(process.select_pid(10));
+        process.update(Action::Terminate).unwrap();
+        let targets = |process: &Process| -> Vec<i32> {
+            let batch = process.batch.as_ref().unwrap();
+            batch.targets.iter().map(|(pid, _)| *pid).collect()
+        };
+        assert_eq!(targets(&process), [10]);
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        process.handle_key_events(key(KeyCode::Right)).unwrap();
+        // bottom-up, so make can't start another cc
+        assert_eq!(targets(&process), [13, 11, 12, 10]);
+        assert_eq!(
+            process.batch.as_ref().unwrap().shell_command(),
+            "kill -TERM 13 11 12 10"
+        );
+
+        process.handle_key_events(key(KeyCode::Right)).unwrap();
+        let batch = process.batch.as_ref().unwrap();
+        assert_eq!(batch.scope, Scope::Group);
+        assert_eq!(batch.groups, [10]);
+        assert_eq!(targets(&process), [10, 14]);
+        assert_eq!(batch.shell_command(), "kill -TERM -- -10");
+
+        process.handle_key_events(key(KeyCode::Left)).unwrap();
+        process.handle_key_events(key(KeyCode::Left)).unwrap();
+        assert_eq!(process.batch.as_ref().unwrap().scope, Scope::Process);
+        process.update(Action::Cancel).unwrap();
+
+        // signalling the group of init would signal every process
+        assert!(process.select_pid(15));
+        process.update(Action::Terminate).unwrap();
+        process.handle_key_events(key(KeyCode::Left)).unwrap();
+        assert_eq!(process.batch.as_ref().unwrap().scope, Scope::Descendants);
+        process.handle_key_events(key(KeyCode::Right)).unwrap();
+        assert_eq!(process.batch.as_ref().unwrap().scope, Scope::Process);
+        process.update(Action::Cancel).unwrap();
+
+        // other actions keep to the chosen processes
+        process.update(Action::Renice(5)).unwrap();
+        process.handle_key_events(key(KeyCode::Right)).unwrap();
+        assert_eq!(process.batch.as_ref().unwrap().scope, Scope::Process);
+    }
+
+    #[test]
+    fn test_process_tree_jump() {
+        let source = FakeProcSource::default()
+            .with(1, FakeProcess::running(1, "init", 100, 0))
+            .with(10, FakeProcess::running(10, "sshd", 200, 0))
+            .with(11, FakeProcess::running(11, "bash", 300, 0).with_ppid(10))
+            .with(12, FakeProcess::running(12, "vim", 400, 0).with_ppid(11));
+        let mut process = Process::with_source(Box::new(source));
+        relist(&mut process);
+        assert!(process.select_pid(11));
+
+        assert_eq!(process.update(Action::JumpToParent).unwrap(), None);
+        assert_eq!(process.selected_process().unwrap().pid, 10);
+        assert_eq!(process.breadcrumb.as_deref(), Some("init 1 › sshd 10"));
+        process.update(Action::JumpToChild).unwrap();
+        assert_eq!(process.selected_process().unwrap().pid, 11);
+        assert_eq!(
+            process.breadcrumb.as_deref(),
+            Some("init 1 › sshd 10 › bash 11")
+        );
+
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(60, 8)).unwrap();
+        terminal
+            .draw(|f| process.draw(f, f.size()).unwrap())
+            .unwrap();
+        let bottom: String = (0..60)
+            .map(|x| terminal.backend().buffer().get(x, 7).symbol().to_string())
+            .collect();
+        assert!(
+            bottom.starts_with("╰init 1 › sshd 10 › bash 11"),
+            "{bottom}"
+        );
+
+        // moving on hides the ancestry
+        process.update(Action::Down).unwrap();
+        assert_eq!(process.breadcrumb, None);
+
+        assert!(process.select_pid(12));
+        assert_eq!(
+            process.update(Action::JumpToChild).unwrap(),
+            Some(Action::Toast("12 has no listed children".to_string()))
+        );
+        assert!(process.select_pid(1));
+        assert_eq!(
+            process.update(Action::JumpToParent).unwrap(),
+            Some(Action::Toast("The parent of 1 isn't listed".to_string()))
+        );
+        assert_eq!(process.selected_process().unwrap().pid, 1);
+    }
+
+    #[test]
+    fn test_visible_columns() {
+        let process = Process::with_source(Box::new(fake_source()));
+        assert_eq!(process.visible_columns(120), DEFAULT_COLUMNS.to_vec());
+        assert_eq!(
+            process.visible_columns(80),
+            vec![
+                Column::Pid,
+                Column::Program,
+                Column::Command,
+                Column::Memory,
+                Column::Cpu
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scroll_columns() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        process.width = 120;
+        let key = KeyEvent::new(KeyCode::Right, KeyModifiers::SHIFT);
+        assert_eq!(
+            process.handle_key_events(key).unwrap(),
+            Some(Action::ScrollRight)
+        );
+        process.update(Action::ScrollRight).unwrap();
+        process.update(Action::ScrollRight).unwrap();
+        assert_eq!(
+            process.visible_columns(120),
+            vec![
+                Column::Pid,
+                Column::Program,
+                Column::User,
+                Column::Memory,
+                Column::CpuGraph,
+                Column::Cpu
+            ]
+        );
+        assert!(process
+            .describe()
+            .contains(&"2 columns are scrolled out of view.".to_string()));
+
+        // the last column stays, and the frozen ones wherever they are
+        for _ in 0..10 {
+            process.update(Action::ScrollRight).unwrap();
+        }
+        assert_eq!(process.column_offset, 5);
+        process.columns = vec![Column::Cpu, Column::Program, Column::Memory];
+        process.column_offset = 1;
+        assert_eq!(
+            process.visible_columns(120),
+            vec![Column::Program, Column::Memory]
+        );
+        process.update(Action::ScrollLeft).unwrap();
+        process.update(Action::ScrollLeft).unwrap();
+        assert_eq!(process.visible_columns(120), process.columns);
+    }
+
+    #[test]
+    fn test_process_lifecycles() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        process.source = Box::new(
+            fake_source()
+                .with(50, FakeProcess::running(50, "new", 0, 0))
+                .without(10),
+        );
+        let update = process.refresh();
+        process.wait_for_list();
+        assert_eq!(
+            (update.processes, update.started, update.exited),
+            (32, 1, 1)
+        );
+        assert!(process.process_map.contains_key(&50));
+        assert!(!process.process_map.contains_key(&10));
+        assert_eq!(process.processes.len(), 33);
+        assert_eq!(process.lifecycles[&50], (Lifecycle::Started, 2));
+        assert_eq!(process.lifecycles[&10], (Lifecycle::Exited, 2));
+        assert_eq!(process.exited[&10].program, "worker");
+        let exited = process.processes.iter().position(|p| p.pid == 10).unwrap();
+        process.state.select(Some(exited - 1));
+        process.jump(1);
+        assert_eq!(process.state.selected(), Some(exited + 1));
+        process.jump(-1);
+        assert_eq!(process.state.selected(), Some(exited - 1));
+        // its pid may already be another process's
+        assert!(process.select_pid(10));
+        assert_eq!(
+            process.update(Action::Kill).unwrap(),
+            Some(Action::Toast("worker already exited".to_string()))
+        );
+        assert!(process.batch.is_none());
+
+        process.refresh();
+        assert_eq!(process.lifecycles[&50], (Lifecycle::Started, 1));
+        assert!(process.exited.contains_key(&10));
+
+        process.refresh();
+        process.wait_for_list();
+        assert!(process.lifecycles.is_empty());
+        assert!(process.exited.is_empty());
+        assert_eq!(process.processes.len(), 32);
+    }
+
+    #[test]
+    fn test_sort_by_column() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        let key = KeyEvent::from(KeyCode::Char('8'));
+        let action = process.handle_key_events(key).unwrap().unwrap();
+        assert_eq!(action, Action::SortBy(8));
+        process.update(action).unwrap();
+        process.wait_for_list();
+        assert_eq!(process.order, Order::Cpu);
+        assert_eq!(process.processes.last().unwrap().pid, 39);
+
+        process.update(Action::SortBy(8)).unwrap();
+        process.wait_for_list();
+        assert!(process.descending);
+        assert_eq!(process.processes.first().unwrap().pid, 39);
+
+        process.update(Action::SortBy(1)).unwrap();
+        assert_eq!((process.order, process.descending), (Order::Pid, false));
+        process.update(Action::SortBy(9)).unwrap();
+        assert_eq!(process.order, Order::Pid);
+
+        // the optional columns are not counted in a narrow table
+        process.width = 80;
+        process.update(Action::SortBy(4)).unwrap();
+        assert_eq!(process.order, Order::Memory);
+    }
+
+    #[test]
+    fn test_sort_indicator() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        process.order = Order::Cpu;
+        process.descending = true;
+        let header = process.header(&DEFAULT_COLUMNS);
+        let expected: Row = std::iter::once(Cell::new(""))
+            .chain(
+                DEFAULT_COLUMNS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| column.header((i == 7).then_some("▼"))),
+            )
+            .collect();
+        assert_eq!(header, expected);
+    }
+
+    #[test]
+    fn test_oom_scores() {
+        let mut source = fake_source();
+        for (pid, score, adj) in [(50, 900, 500), (51, 600, 0), (52, 300, 0), (53, 200, -900)] {
+            let mut process = FakeProcess::running(pid, "hungry", 0, 0);
+            process.oom_score = Some((score, adj));
+            source = source.with(pid, process);
+        }
+        let mut process = Process::with_source(Box::new(source.clone()));
+        process.columns.push(Column::OomScore);
+        process.refresh();
+        process.order = Order::OomScore;
+        process.descending = true;
+        relist(&mut process);
+        let scores: Vec<(i32, u32, i32)> = process.processes[..4]
+            .iter()
+            .map(|p| (p.pid, p.oom_score, p.oom_score_adj))
+            .collect();
+        assert_eq!(
+            scores,
+            vec![(50, 900, 500), (51, 600, 0), (52, 300, 0), (53, 200, -900)]
+        );
+        // a quarter of the memory is used
+        assert!(process.processes.iter().all(|p| !p.oom_risk));
+
+        source.meminfo = source
+            .meminfo
+            .replace("MemAvailable:   12582912 kB", "MemAvailable:   1048576 kB");
+        process.source = Box::new(source);
+        process.refresh();
+        let mut at_risk: Vec<i32> = process
+            .process_map
+            .values()
+            .filter(|p| p.oom_risk)
+            .map(|p| p.pid)
+            .collect();
+        at_risk.sort();
+        assert_eq!(at_risk, vec![50, 51, 52]);
+    }
+
+    #[test]
+    fn test_context_switches() {
+        let busy = |voluntary| {
+            let mut process = FakeProcess::running(50, "chatty", 0, 0);
+            process.context_switches = Some((voluntary, 10));
+            process
+        };
+        let mut process = Process::with_source(Box::new(fake_source().with(50, busy(100))));
+        process.columns.push(Column::ContextSwitches);
+        process.refresh();
+        assert_eq!(process.process_map[&50].context_switches, 110);
+        assert_eq!(process.process_map[&50].context_switch_rate, 0);
+
+        // two seconds later
+        process.context_switches_read = process
+            .context_switches_read
+            .map(|read| read - Duration::from_secs(2));
+        process.source = Box::new(fake_source().with(50, busy(300)));
+        process.refresh();
+        assert_eq!(process.process_map[&50].context_switch_rate, 100);
+        process.order = Order::ContextSwitches;
+        process.descending = true;
+        relist(&mut process);
+        assert_eq!(process.processes[0].pid, 50);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let mut admin = FakeProcess::running(50, "admin", 0, 0);
+        admin.capabilities = Some(capabilities::CAP_SYS_ADMIN | 1);
+        admin.security_label = Some("system_u:system_r:init_t:s0".to_string());
+        let mut server = FakeProcess::running(51, "server", 0, 0);
+        server.capabilities = Some(1 << 10);
+        let source = fake_source().with(50, admin).with(51, server);
+        let mut process = Process::with_source(Box::new(source));
+        process.columns.push(Column::Capabilities);
+        process.refresh();
+        process.order = Order::Capabilities;
+        relist(&mut process);
+        let pids: Vec<i32> = process
+            .processes
+            .iter()
+            .rev()
+            .take(2)
+            .map(|p| p.pid)
+            .collect();
+        assert_eq!(pids, vec![50, 51]);
+
+        process.state.select(Some(process.processes.len() - 1));
+        process.update(Action::ToggleDetails).unwrap();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 80, 16));
+        process.details.clone().unwrap().render(buf.area, &mut buf);
+        let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("Label:   system_u:system_r:init_t:s0 "));
+        assert!(text.contains("0x0000000000200001 ⚷"));
+        assert!(text.contains("CAP_CHOWN CAP_SYS_ADMIN"));
+        assert!(text.contains("NUMA:    N0 2MiB"));
+        // in the root cgroup
+        assert!(!text.contains("Cgroup:"));
+
+        let key = KeyEvent::from(KeyCode::Esc);
+        assert_eq!(
+            process.handle_key_events(key).unwrap(),
+            Some(Action::ToggleDetails)
+        );
+        process.update(Action::ToggleDetails).unwrap();
+        assert!(process.details.is_none());
+    }
+
+    #[test]
+    fn test_attach() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        relist(&mut process);
+        let pid = process.selected_process().unwrap().pid;
+        let key = KeyEvent::from(KeyCode::Char('s'));
+        let action = process.handle_key_events(key).unwrap().unwrap();
+        assert_eq!(action, Action::Attach(Tracer::Strace));
+        assert_eq!(
+            process.update(action).unwrap(),
+            Some(Action::RunInTerminal(vec![
+                "strace".into(),
+                "-f".into(),
+                "-p".into(),
+                pid.to_string()
+            ]))
+        );
+        process.attach.gdb.clear();
+        assert_eq!(
+            process.update(Action::Attach(Tracer::Gdb)).unwrap(),
+            Some(Action::Toast("No gdb command configured".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_run_command() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        relist(&mut process);
+        let selected = process.selected_process().unwrap();
+        let (pid, program, user) = (selected.pid, selected.program.clone(), selected.username());
+        let template = |command: &str, terminal| {
+            Action::RunCommand(CommandTemplate {
+                command: command.to_string(),
+                terminal,
+            })
+        };
+        assert_eq!(
+            process
+                .update(template(
+                    "kitty -- htop -p {pid} --user={user} '{comm} x'",
+                    false
+                ))
+                .unwrap(),
+            Some(Action::RunDetached(vec![
+                "kitty".into(),
+                "--".into(),
+                "htop".into(),
+                "-p".into(),
+                pid.to_string(),
+                format!("--user={user}"),
+                format!("{program} x"),
+            ]))
+        );
+        assert_eq!(
+            process.update(template("htop -p {pid}", true)).unwrap(),
+            Some(Action::RunInTerminal(vec![
+                "htop".into(),
+                "-p".into(),
+                pid.to_string()
+            ]))
+        );
+        assert_eq!(
+            process.update(template("htop 'x", false)).unwrap(),
+            Some(Action::Toast(
+                "Unable to run 'htop 'x': missing closing '".to_string()
+            ))
+        );
+        assert_eq!(process.update(template(" ", false)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_wakeup_details() {
+        let mut source = fake_source();
+        let process = crate::model::to_brt_process(&source, 10).unwrap();
+        let text = |source: &FakeProcSource| -> String {
+            let details = details_popup(source, &process);
+            let mut buf = Buffer::empty(Rect::new(0, 0, 80, 16));
+            details.render(buf.area, &mut buf);
+            buf.content().iter().map(|c| c.symbol()).collect()
+        };
+        let details = text(&source);
+        assert!(details.contains("Ctx sw:  120 voluntary, 4 involuntary"));
+        assert!(details.contains("Wakeups: 250 ("));
+
+        // kernels without scheduler statistics
+        source.processes.get_mut(&10).unwrap().sched = Some(String::new());
+        assert!(!text(&source).contains("Wakeups:"));
+    }
+
+    #[test]
+    fn test_memory_maps_details() {
+        let mut source = fake_source();
+        let process = crate::model::to_brt_process(&source, 10).unwrap();
+        let text = |source: &FakeProcSource| -> String {
+            let details = details_popup(source, &process);
+            let mut buf = Buffer::empty(Rect::new(0, 0, 80, 20));
+            details.render(buf.area, &mut buf);
+            buf.content().iter().map(|c| c.symbol()).collect()
+        };
+        let details = text(&source);
+        assert!(details.contains("Maps:    rss 2MiB pss 1.8MiB swap 128KiB"));
+        assert!(details.contains("         shared 512KiB private 1.5MiB"));
+
+        // smaps_rollup can't be read, statm can
+        source.processes.get_mut(&10).unwrap().smaps_rollup = None;
+        assert!(text(&source).contains("Maps:    rss 2MiB shared 512KiB (statm)"));
+    }
+
+    #[test]
+    fn test_cgroup_memory_details() {
+        let mut web = FakeProcess::running(60, "web", 0, 0);
+        web.cgroup = Some("/docker/web".to_string());
+        let mut source = fake_source().with(60, web);
+        for (name, value) in [
+            ("memory.current", "996147200"),
+            ("memory.max", "1073741824"),
+        ] {
+            source
+                .cgroup_files
+                .insert(format!("/docker/web/{name}"), value.to_string());
+        }
+        let process = crate::model::to_brt_process(&source, 60).unwrap();
+        let details = details_popup(&source, &process);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 80, 14));
+        details.render(buf.area, &mut buf);
+        let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("Cgroup:  /docker/web "));
+        assert!(text.contains("Memory:  950MiB/1GiB (93%)"));
+        // near the limit
+        let start = text[..text.find("950MiB").unwrap()].chars().count() as u16;
+        let (x, y) = (start % buf.area.width, start / buf.area.width);
+        assert_eq!(buf.get(x, y).fg, Color::Yellow);
+    }
+
+    #[test]
+    fn test_session() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        process.sort_by_column(6);
+        process.sort_by_column(6);
+        process.filter.expression = Input::new("pid<20".to_string());
+        process.filter.apply();
+        let mut session = Session::default();
+        process.save_session(&mut session);
+        assert_eq!(session.order, Order::Memory);
+        assert!(session.descending);
+        assert_eq!(session.filter, "pid<20");
+
+        let mut restored = Process::with_source(Box::new(fake_source()));
+        restored.restore_session(&session);
+        restored.wait_for_list();
+        assert_eq!(restored.order, Order::Memory);
+        assert_eq!(restored.filter.applied, "pid<20");
+        assert!(restored.processes.iter().all(|process| process.pid < 20));
+        // the zombie or the forbidden process
+        assert_eq!(restored.processes.last().unwrap().resident_memory, 0);
+
+        // an expression this version can't parse is left out
+        session.filter = "colour:red".to_string();
+        let mut restored = Process::with_source(Box::new(fake_source()));
+        restored.restore_session(&session);
+        assert_eq!(restored.filter.applied, "");
+        assert!(restored.filter.error.is_none());
+    }
+
+    #[test]
+    fn test_scroll_window() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        relist(&mut process);
+        process.jump(25);
+        process.scroll(10);
+        assert_eq!(process.state.offset(), 16);
+        assert_eq!(
+            process.scrollbar_state,
+            ScrollbarState::new(23)
+                .viewport_content_length(10)
+                .position(16)
+        );
+
+        // moving up within the window keeps it where it is
+        process.jump(-5);
+        process.scroll(10);
+        assert_eq!(process.state.offset(), 16);
+
+        // when the list shrinks the window moves up, so no rows are left empty
+        Arc::make_mut(&mut process.processes).truncate(20);
+        process.scroll(10);
+        assert_eq!(process.state.selected(), Some(19));
+        assert_eq!(process.state.offset(), 10);
+
+        // a list shorter than the window isn't scrolled at all
+        Arc::make_mut(&mut process.processes).truncate(4);
+        process.scroll(10);
+        assert_eq!(process.state.selected(), Some(3));
+        assert_eq!(process.state.offset(), 0);
+    }
+
+    #[test]
+    fn test_order_by_recent_cpu() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        let steady = process.process_map.get_mut(&10).unwrap();
+        steady.cpus = VecDeque::from(vec![0.0, 0.0, 0.0, 0.0, 0.0, 50.0, 50.0, 50.0, 50.0, 50.0]);
+        steady.cpu = 50.0;
+        let spike = process.process_map.get_mut(&11).unwrap();
+        spike.cpus = VecDeque::from(vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 90.0]);
+        spike.cpu = 90.0;
+        assert!((spike.recent_cpu(5) - 18.0).abs() < 1e-9);
+        assert_eq!(spike.recent_cpu(0), 90.0);
+        assert!((spike.recent_cpu(50) - 9.0).abs() < 1e-9);
+
+        process.order = Order::Cpu;
+        process.descending = true;
+        relist(&mut process);
+        assert_eq!(process.processes[0].pid, 11);
+
+        process.order = Order::CpuRecent;
+        relist(&mut process);
+        assert_eq!(process.processes[0].pid, 10);
+        let header = process.header(&[Column::Pid, Column::Cpu]);
+        let expected: Row = [
+            Cell::new(""),
+            Column::Pid.header(None),
+            Column::Cpu.header(Some("▼")),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(header, expected);
+    }
+
+    #[test]
+    fn test_critical_batch() {
+        let source = fake_source().with(1, FakeProcess::running(1, "init", 0, 0));
+        let mut process = Process::with_source(Box::new(source));
+        relist(&mut process);
+        process.update(Action::Kill).unwrap();
+        assert_eq!(process.batch.as_ref().unwrap().critical, Some(1));
+
+        // 'y' no longer confirms, the pid has to be typed
+        let key = |code| KeyEvent::from(code);
+        assert_eq!(
+            process.handle_key_events(key(KeyCode::Char('y'))).unwrap(),
+            None
+        );
+        assert_eq!(
+            process.handle_key_events(key(KeyCode::Enter)).unwrap(),
+            Some(Action::Confirm)
+        );
+        assert_eq!(
+            process.update(Action::Confirm).unwrap(),
+            Some(Action::Toast("Type 1 to confirm".to_string()))
+        );
+        assert!(process.batch.is_some());
+
+        for code in [
+            KeyCode::Char('1'),
+            KeyCode::Char('x'),
+            KeyCode::Char('2'),
+            KeyCode::Backspace,
+        ] {
+            process.handle_key_events(key(code)).unwrap();
+        }
+        assert_eq!(process.input.value(), "1");
+        let mut buf = Buffer::empty(Rect::new(0, 0, 80, 10));
+        Process::batch_popup(process.batch.as_ref().unwrap(), process.input.value())
+            .render(buf.area, &mut buf);
+        let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("1 is critical to the system or to brt."));
+        assert!(text.contains("Type 1 and enter to confirm, esc to cancel, c to copy: 1"));
+        let corner = buf.content().iter().find(|c| c.symbol() == "╭").unwrap();
+        assert_eq!(corner.fg, Color::Red);
+
+        assert_eq!(
+            process.handle_key_events(key(KeyCode::Esc)).unwrap(),
+            Some(Action::Cancel)
+        );
+        process.update(Action::Cancel).unwrap();
+        assert!(process.batch.is_none());
+        assert_eq!(process.input.value(), "");
+    }
+
+    /// A journal with an entry per unit, telling the number of entries asked
+    /// for.
+    #[derive(Debug)]
+    struct FakeUnitJournal;
+
+    impl UnitJournal for FakeUnitJournal {
+        fn tail(&self, unit: &Unit, lines: usize) -> io::Result<Vec<Entry>> {
+            Ok(vec![Entry {
+                priority: 3,
+                timestamp: 1_700_000_000_000_000,
+                text: format!("{} failed, {lines} lines", unit.name),
+            }])
+        }
+    }
+
+    #[test]
+    fn test_unit_log() {
+        let mut nginx = FakeProcess::running(50, "nginx", 0, 0);
+        nginx.cgroup = Some("/system.slice/nginx.service".to_string());
+        let source = fake_source().with(50, nginx);
+        let mut process = Process::with_source(Box::new(source));
+        process.journal = Arc::new(FakeUnitJournal);
+        process.journal_lines = 5;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        process.register_action_handler(tx).unwrap();
+        relist(&mut process);
+        assert!(process.select_pid(50));
+        process.update(Action::ToggleDetails).unwrap();
+        assert_eq!(
+            process
+                .handle_key_events(KeyCode::Char('j').into())
+                .unwrap(),
+            Some(Action::ToggleUnitLog)
+        );
+        assert_eq!(process.update(Action::ToggleUnitLog).unwrap(), None);
+        let text = |process: &Process| -> String {
+            let mut buf = Buffer::empty(Rect::new(0, 0, 80, 10));
+            process.unit_log.clone().unwrap().render(buf.area, &mut buf);
+            buf.content().iter().map(|c| c.symbol()).collect()
+        };
+        assert!(text(&process).contains("journal of unit nginx.service"));
+        assert!(text(&process).contains("Reading the journal..."));
+        let read = rx.blocking_recv().unwrap();
+        assert!(matches!(read, Action::UnitLogRead(ref unit, _) if unit.name == "nginx.service"));
+        process.update(read.clone()).unwrap();
+        assert!(text(&process).contains("nginx.service failed, 5 lines"));
+        assert!(process.reading_journal.is_none());
+
+        // back to the details, then closed with them
+        assert_eq!(
+            process.handle_key_events(KeyCode::Esc.into()).unwrap(),
+            Some(Action::ToggleUnitLog)
+        );
+        process.update(Action::ToggleUnitLog).unwrap();
+        assert!(process.unit_log.is_none() && process.details.is_some());
+        // entries read after it was closed aren't shown
+        process.update(read).unwrap();
+        assert!(process.unit_log.is_none());
+        process.update(Action::ToggleUnitLog).unwrap();
+        process.update(Action::ToggleDetails).unwrap();
+        assert!(process.unit_log.is_none() && process.details.is_none());
+
+        // a process outside of any unit
+        assert!(process.select_pid(10));
+        assert_eq!(
+            process.update(Action::ToggleUnitLog).unwrap(),
+            Some(Action::Toast(
+                "worker doesn't run in a systemd unit".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_system_service_batch() {
+        let mut nginx = FakeProcess::running(50, "nginx", 0, 0);
+        nginx.cgroup = Some("/system.slice/nginx.service".to_string());
+        let source = fake_source().with(50, nginx);
+        let mut process = Process::with_source(Box::new(source));
+        relist(&mut process);
+        assert!(process.select_pid(50));
+        process.update(Action::Renice(5)).unwrap();
+        let batch = process.batch.take().unwrap();
+        assert_eq!(batch.critical, Some(50));
+        assert_eq!(batch.service.as_deref(), Some("nginx.service"));
+        let mut buf = Buffer::empty(Rect::new(0, 0, 80, 10));
+        Process::batch_popup(&batch, "").render(buf.area, &mut buf);
+        let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("50 runs the system service nginx.service."));
+
+        // the guard can be switched off
+        process.guard_system_services = false;
+        process.update(Action::Renice(5)).unwrap();
+        let batch = process.batch.take().unwrap();
+        assert_eq!((batch.critical, batch.service), (None, None));
+
+        // other processes are left alone
+        process.guard_system_services = true;
+        assert!(process.select_pid(10));
+        process.update(Action::Kill).unwrap();
+        assert_eq!(process.batch.unwrap().critical, None);
+    }
+
+    #[test]
+    fn test_io_picker() {
+        let mut process = Process::with_source(Box::new(fake_source()));
+        relist(&mut process);
+        let key = |code| KeyEvent::from(code);
+        assert_eq!(
+            process.handle_key_events(key(KeyCode::Char('i'))).unwrap(),
+            Some(Action::PickIoPriority)
+        );
+        process.update(Action::PickIoPriority).unwrap();
+        assert!(process.io_picker.is_some());
+
+        // whatever the priority was, this ends at the lowest best effort one
+        for code in [KeyCode::Left, KeyCode::Left, KeyCode::Right]
+            .into_iter()
+            .chain([KeyCode::Down; 8])
+        {
+            assert_eq!(process.handle_key_events(key(code)).unwrap(), None);
+        }
+        let lowest = IoPriority {
+            class: IoClass::BestEffort,
+            level: 7,
+        };
+        assert_eq!(process.io_picker, Some(lowest));
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 8));
+        Process::io_picker_popup(&lowest).render(buf.area, &mut buf);
+        let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("< best-effort/7 >"));
+
+        assert_eq!(
+            process.handle_key_events(key(KeyCode::Enter)).unwrap(),
+            Some(Action::SetIoPriority(lowest))
+        );
+        process.update(Action::SetIoPriority(lowest)).unwrap();
+        assert!(process.io_picker.is_none());
+        let batch = process.batch.as_ref().unwrap();
+        assert_eq!(batch.action, BatchAction::IoPriority(lowest));
+        assert_eq!(
+            batch.question(),
+            "Set the io priority of 1 process to best-effort/7?"
+        );
+    }
 }
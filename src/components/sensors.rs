@@ -0,0 +1,275 @@
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use color_eyre::eyre::Result;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::Component;
+use crate::config::{Config, Intervals};
+use crate::staleness::Sample;
+use crate::tui::Frame;
+
+const HWMON_ROOT: &str = "/sys/class/hwmon";
+/// Used for sensors that don't tell when they are too hot.
+const DEFAULT_HIGH: f64 = 80.0;
+const DEFAULT_CRITICAL: f64 = 95.0;
+/// The width of the sensor labels.
+const LABEL_WIDTH: usize = 16;
+
+/// A temperature sensor, in degrees Celsius, as reported by `hwmon`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Temperature {
+    /// The driver, e.g. `coretemp` or `nvme`.
+    pub chip: String,
+    pub label: String,
+    pub current: f64,
+    pub high: Option<f64>,
+    pub critical: Option<f64>,
+}
+
+impl Temperature {
+    fn style(&self) -> Style {
+        if self.current >= self.critical.unwrap_or(DEFAULT_CRITICAL) {
+            Style::default().fg(Color::Red).bold()
+        } else if self.current >= self.high.unwrap_or(DEFAULT_HIGH) {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::Green)
+        }
+    }
+}
+
+/// Something that can tell the temperatures of the system.
+pub trait SensorSource: Debug + Send {
+    /// The sensors ordered by chip and label; empty without any.
+    fn read(&self) -> Vec<Temperature>;
+}
+
+/// Reads `/sys/class/hwmon/hwmon*/temp*`.
+#[derive(Debug, Clone)]
+pub struct SysfsSensorSource {
+    root: PathBuf,
+}
+
+impl Default for SysfsSensorSource {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from(HWMON_ROOT),
+        }
+    }
+}
+
+fn read_value(hwmon: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(hwmon.join(file))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Reads a temperature in millidegrees as degrees.
+fn read_degrees(hwmon: &Path, file: &str) -> Option<f64> {
+    Some(read_value(hwmon, file)?.parse::<f64>().ok()? / 1_000.0)
+}
+
+fn read_chip(hwmon: &Path) -> Vec<Temperature> {
+    let chip = read_value(hwmon, "name").unwrap_or_default();
+    let Ok(entries) = fs::read_dir(hwmon) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            let sensor = name.to_str()?.strip_suffix("_input")?;
+            sensor.strip_prefix("temp")?;
+            Some(Temperature {
+                chip: chip.clone(),
+                label: read_value(hwmon, &format!("{sensor}_label"))
+                    .unwrap_or_else(|| sensor.to_string()),
+                current: read_degrees(hwmon, &format!("{sensor}_input"))?,
+                high: read_degrees(hwmon, &format!("{sensor}_max")),
+                critical: read_degrees(hwmon, &format!("{sensor}_crit")),
+            })
+        })
+        .collect()
+}
+
+impl SensorSource for SysfsSensorSource {
+    fn read(&self) -> Vec<Temperature> {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return vec![];
+        };
+        let mut temperatures: Vec<Temperature> = entries
+            .filter_map(|entry| Some(read_chip(&entry.ok()?.path())))
+            .flatten()
+            .collect();
+        temperatures.sort_by(|a, b| (&a.chip, &a.label).cmp(&(&b.chip, &b.label)));
+        temperatures
+    }
+}
+
+/// The temperatures of the system, for the sensors tab.
+#[derive(Debug)]
+pub struct Sensors {
+    source: Box<dyn SensorSource>,
+    temperatures: Vec<Temperature>,
+    cadence: Cadence,
+    /// When a sample last succeeded.
+    sampled_at: Option<Instant>,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for Sensors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sensors {
+    pub fn new() -> Self {
+        Self::with_source(Box::new(SysfsSensorSource::default()))
+    }
+
+    pub fn with_source(source: Box<dyn SensorSource>) -> Self {
+        let mut sensors = Self {
+            source,
+            temperatures: vec![],
+            cadence: Cadence::from_millis(Intervals::default().cpu),
+            sampled_at: None,
+            dirty: true,
+        };
+        sensors.refresh();
+        sensors
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn refresh(&mut self) {
+        self.dirty = true;
+        self.temperatures = self.source.read();
+        if !self.temperatures.is_empty() {
+            self.sampled_at = Some(Instant::now());
+        }
+    }
+
+    /// The chips with their sensors below them, e.g. `  Core 0   45.0°C`.
+    fn lines(&self) -> Vec<Line<'static>> {
+        if self.temperatures.is_empty() {
+            return vec![Line::from("No temperature sensors found.").dim()];
+        }
+        let mut lines = vec![];
+        let mut chip = None;
+        for temperature in &self.temperatures {
+            if chip != Some(&temperature.chip) {
+                chip = Some(&temperature.chip);
+                lines.push(Line::from(temperature.chip.clone()).bold());
+            }
+            lines.push(Line::from(vec![
+                Span::raw(format!(
+                    "  {:<LABEL_WIDTH$.LABEL_WIDTH$} ",
+                    temperature.label
+                )),
+                Span::styled(
+                    format!("{:>6.1}°C", temperature.current),
+                    temperature.style(),
+                ),
+            ]));
+        }
+        lines
+    }
+}
+
+impl Component for Sensors {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.cadence = Cadence::from_millis(config.config.intervals.cpu);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick && self.cadence.due() {
+            self.refresh();
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn sampled(&self) -> Option<Sample> {
+        Sample::of("sensors", self.sampled_at, &self.cadence)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let block = Block::default()
+            .title(Title::from("sensors").alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        f.render_widget(Paragraph::new(self.lines()).block(block), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sysfs_sensor_source() {
+        let root = std::env::temp_dir().join(format!("brt-hwmon-{}", std::process::id()));
+        let hwmon = root.join("hwmon0");
+        fs::create_dir_all(&hwmon).unwrap();
+        for (file, value) in [
+            ("name", "coretemp\n"),
+            ("temp2_input", "45000\n"),
+            ("temp2_label", "Core 0\n"),
+            ("temp2_max", "84000\n"),
+            ("temp1_input", "97500\n"),
+            ("temp1_crit", "95000\n"),
+            ("fan1_input", "1200\n"),
+        ] {
+            fs::write(hwmon.join(file), value).unwrap();
+        }
+        let temperatures = SysfsSensorSource { root: root.clone() }.read();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            temperatures,
+            vec![
+                Temperature {
+                    chip: "coretemp".to_string(),
+                    label: "Core 0".to_string(),
+                    current: 45.0,
+                    high: Some(84.0),
+                    critical: None,
+                },
+                Temperature {
+                    chip: "coretemp".to_string(),
+                    label: "temp1".to_string(),
+                    current: 97.5,
+                    high: None,
+                    critical: Some(95.0),
+                },
+            ]
+        );
+
+        let sensors = Sensors {
+            source: Box::new(SysfsSensorSource::default()),
+            temperatures,
+            cadence: Cadence::from_millis(1_000),
+            sampled_at: None,
+            dirty: true,
+        };
+        let lines = sensors.lines();
+        assert_eq!(lines[0].to_string(), "coretemp");
+        assert_eq!(lines[1].to_string(), "  Core 0             45.0°C");
+        assert_eq!(lines[1].spans[1].style.fg, Some(Color::Green));
+        assert_eq!(lines[2].spans[1].style.fg, Some(Color::Red));
+    }
+}
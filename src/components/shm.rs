@@ -0,0 +1,515 @@
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::fmt::Debug;
+use std::fs;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use color_eyre::eyre::Result;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::Component;
+use crate::config::{Config, Intervals};
+use crate::model::{format_bytes, truncate};
+use crate::staleness::Sample;
+use crate::symbols::symbols;
+use crate::tui::Frame;
+use crate::widgets::bar::{gradient_bar, GREEN_TO_RED};
+
+const MOUNTS: &str = "/proc/self/mounts";
+const SYSV_SHM: &str = "/proc/sysvipc/shm";
+/// Where POSIX shared memory objects are created.
+const POSIX_SHM: &str = "/dev/shm";
+const BAR_LENGTH: usize = 5;
+/// The width of the mount points and the segment names.
+const NAME_WIDTH: usize = 12;
+
+/// A mounted tmpfs; its files are memory, or swap, that no process owns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmpfsMount {
+    pub path: String,
+    pub used: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    /// Made with `shmget`, listed in `/proc/sysvipc/shm`.
+    SysV,
+    /// Made with `shm_open`, a file in `/dev/shm`.
+    Posix,
+}
+
+/// A shared memory segment and the processes it is known to belong to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    /// The id of a System V segment, the file name of a POSIX one.
+    pub name: String,
+    pub bytes: u64,
+    /// The programs of the creator of a System V segment, or of the
+    /// processes that map a POSIX one.
+    pub owners: Vec<String>,
+}
+
+/// Something that can tell the tmpfs mounts and the shared memory.
+pub trait ShmSource: Debug + Send + Sync {
+    fn tmpfs(&self) -> Vec<TmpfsMount>;
+    fn segments(&self) -> Vec<Segment>;
+}
+
+/// The mount points of the tmpfs file systems of a `/proc/self/mounts`,
+/// where spaces in paths are written as `\040`.
+pub fn parse_tmpfs_mounts(mounts: &str) -> Vec<String> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (_, path, kind) = (fields.next()?, fields.next()?, fields.next()?);
+            (kind == "tmpfs").then(|| unescape(path))
+        })
+        .collect()
+}
+
+/// Replaces the octal escapes of the kernel, e.g. `\040` for a space.
+fn unescape(path: &str) -> String {
+    let mut unescaped = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(index) = rest.find('\\') {
+        unescaped.push_str(&rest[..index]);
+        let escape = rest.get(index + 1..index + 4);
+        match escape.and_then(|octal| u8::from_str_radix(octal, 8).ok()) {
+            Some(byte) => {
+                unescaped.push(char::from(byte));
+                rest = &rest[index + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// The id, size in bytes and creator pid of each segment of a
+/// `/proc/sysvipc/shm`, which starts with a header.
+pub fn parse_sysv_shm(shm: &str) -> Vec<(String, u64, i32)> {
+    shm.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (id, bytes, cpid) = (fields.get(1)?, fields.get(3)?, fields.get(4)?);
+            Some((id.to_string(), bytes.parse().ok()?, cpid.parse().ok()?))
+        })
+        .collect()
+}
+
+/// The POSIX shared memory objects a `/proc/<pid>/maps` maps.
+pub fn parse_shm_maps(maps: &str) -> Vec<String> {
+    let prefix = format!("{POSIX_SHM}/");
+    let mut names: Vec<String> = maps
+        .lines()
+        .filter_map(|line| {
+            let path = line.split_whitespace().nth(5)?;
+            Some(path.strip_prefix(&prefix)?.to_string())
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// The used and total bytes of the file system mounted at `path`.
+fn statvfs(path: &str) -> Option<(u64, u64)> {
+    let path = CString::new(path).ok()?;
+    // SAFETY: an all zero statvfs is valid.
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: path is NUL terminated and stats is a valid statvfs to write to.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    let block = stats.f_frsize as u64;
+    let size = stats.f_blocks as u64 * block;
+    let free = stats.f_bfree as u64 * block;
+    Some((size.saturating_sub(free), size))
+}
+
+fn program(pid: i32) -> Option<String> {
+    let comm = fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    Some(comm.trim_end().to_string())
+}
+
+/// Reads the mounts, `/proc/sysvipc/shm`, `/dev/shm` and the maps of every
+/// process that may be read.
+#[derive(Debug, Default)]
+pub struct SystemShmSource;
+
+impl SystemShmSource {
+    /// The programs mapping each POSIX object; only those of processes
+    /// whose maps can be read are known.
+    fn posix_owners() -> BTreeMap<String, Vec<String>> {
+        let mut owners: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let Ok(entries) = fs::read_dir("/proc") else {
+            return owners;
+        };
+        for entry in entries.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+                continue;
+            };
+            let Ok(maps) = fs::read_to_string(format!("/proc/{pid}/maps")) else {
+                continue;
+            };
+            for name in parse_shm_maps(&maps) {
+                if let Some(program) = program(pid) {
+                    owners.entry(name).or_default().push(program);
+                }
+            }
+        }
+        owners
+    }
+}
+
+impl ShmSource for SystemShmSource {
+    fn tmpfs(&self) -> Vec<TmpfsMount> {
+        let mounts = fs::read_to_string(MOUNTS).unwrap_or_default();
+        parse_tmpfs_mounts(&mounts)
+            .into_iter()
+            .filter_map(|path| {
+                let (used, size) = statvfs(&path)?;
+                Some(TmpfsMount { path, used, size })
+            })
+            .collect()
+    }
+
+    fn segments(&self) -> Vec<Segment> {
+        let shm = fs::read_to_string(SYSV_SHM).unwrap_or_default();
+        let mut segments: Vec<Segment> = parse_sysv_shm(&shm)
+            .into_iter()
+            .map(|(id, bytes, cpid)| Segment {
+                kind: SegmentKind::SysV,
+                name: id,
+                bytes,
+                owners: program(cpid).into_iter().collect(),
+            })
+            .collect();
+        let Ok(entries) = fs::read_dir(POSIX_SHM) else {
+            return segments;
+        };
+        let objects: Vec<(String, u64)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                metadata.is_file().then(|| {
+                    (
+                        entry.file_name().to_string_lossy().into_owned(),
+                        metadata.len(),
+                    )
+                })
+            })
+            .collect();
+        // the maps of all processes are only read when there is something
+        // to find in them
+        if !objects.is_empty() {
+            let mut owners = Self::posix_owners();
+            segments.extend(objects.into_iter().map(|(name, bytes)| Segment {
+                kind: SegmentKind::Posix,
+                owners: owners.remove(&name).unwrap_or_default(),
+                name,
+                bytes,
+            }));
+        }
+        segments
+    }
+}
+
+/// What a read of the source found.
+type Read = (Vec<TmpfsMount>, Vec<Segment>);
+
+/// The tmpfs mounts and shared memory segments, where memory that no
+/// process seems to use often hides.
+#[derive(Debug)]
+pub struct SharedMemory {
+    source: Arc<dyn ShmSource>,
+    mounts: Vec<TmpfsMount>,
+    /// The largest first.
+    segments: Vec<Segment>,
+    /// The read in progress, on a thread as it goes through the maps of
+    /// every process.
+    reading: Option<Receiver<Read>>,
+    /// Shown on the current tab; it isn't read while hidden.
+    visible: bool,
+    cadence: Cadence,
+    /// When a sample last succeeded.
+    sampled_at: Option<Instant>,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for SharedMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedMemory {
+    pub fn new() -> Self {
+        Self::with_source(Box::new(SystemShmSource))
+    }
+
+    pub fn with_source(source: Box<dyn ShmSource>) -> Self {
+        Self {
+            source: source.into(),
+            mounts: vec![],
+            segments: vec![],
+            reading: None,
+            visible: true,
+            cadence: Cadence::from_millis(Intervals::default().processes),
+            sampled_at: None,
+            dirty: true,
+        }
+    }
+
+    /// Starts a read, unless one is in progress.
+    fn refresh(&mut self) {
+        if self.reading.is_some() {
+            return;
+        }
+        let source = self.source.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || tx.send((source.tmpfs(), source.segments())));
+        self.reading = Some(rx);
+    }
+
+    /// Takes in the read in progress once it is done.
+    fn receive(&mut self) {
+        let Some(reading) = &self.reading else {
+            return;
+        };
+        match reading.try_recv() {
+            Ok(read) => {
+                self.reading = None;
+                self.apply(read);
+            }
+            Err(TryRecvError::Empty) => (),
+            Err(TryRecvError::Disconnected) => self.reading = None,
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn apply(&mut self, (mounts, mut segments): Read) {
+        segments.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.name.cmp(&b.name)));
+        if !mounts.is_empty() {
+            self.sampled_at = Some(Instant::now());
+        }
+        self.dirty |= mounts != self.mounts || segments != self.segments;
+        (self.mounts, self.segments) = (mounts, segments);
+    }
+
+    /// E.g. `/dev/shm     ■□□□□ 12MiB/7.8GiB`, then e.g.
+    /// `sysv 32786   4MiB Xorg` for each segment.
+    fn lines(&self) -> Vec<Line<'static>> {
+        let mut lines: Vec<Line> = self
+            .mounts
+            .iter()
+            .map(|mount| {
+                let fraction = mount.used as f32 / mount.size.max(1) as f32;
+                let mut spans = vec![Span::raw(format!(
+                    "{:<NAME_WIDTH$} ",
+                    truncate(&mount.path, NAME_WIDTH)
+                ))
+                .dim()];
+                let symbols = symbols();
+                let glyphs = (symbols.bar, symbols.bar_empty);
+                spans.extend(gradient_bar(fraction, BAR_LENGTH, &GREEN_TO_RED, glyphs).spans);
+                spans.push(Span::raw(format!(
+                    " {}/{}",
+                    format_bytes(mount.used),
+                    format_bytes(mount.size)
+                )));
+                Line::from(spans)
+            })
+            .collect();
+        for segment in &self.segments {
+            let kind = match segment.kind {
+                SegmentKind::SysV => "sysv ",
+                SegmentKind::Posix => "posix",
+            };
+            let owners = match &segment.owners[..] {
+                [] => "?".to_string(),
+                [owner] => owner.clone(),
+                [owner, others @ ..] => format!("{owner} +{}", others.len()),
+            };
+            lines.push(Line::from(vec![
+                Span::raw(kind).dim(),
+                Span::raw(format!(
+                    " {:<NAME_WIDTH$} {:>7} {owners}",
+                    truncate(&segment.name, NAME_WIDTH),
+                    format_bytes(segment.bytes)
+                )),
+            ]));
+        }
+        lines
+    }
+}
+
+impl Component for SharedMemory {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.cadence = Cadence::from_millis(config.config.intervals.processes);
+        Ok(())
+    }
+
+    fn init(&mut self) -> Result<()> {
+        self.refresh();
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick {
+            self.receive();
+            if self.visible && self.reading.is_none() && self.cadence.due() {
+                self.refresh();
+            }
+        }
+        Ok(None)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn sampled(&self) -> Option<Sample> {
+        Sample::of("shm", self.sampled_at, &self.cadence)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let block = Block::default()
+            .title(Title::from("shared memory").alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        f.render_widget(Paragraph::new(self.lines()).block(block), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct FakeShmSource(Vec<TmpfsMount>, Vec<Segment>);
+
+    impl ShmSource for FakeShmSource {
+        fn tmpfs(&self) -> Vec<TmpfsMount> {
+            self.0.clone()
+        }
+
+        fn segments(&self) -> Vec<Segment> {
+            self.1.clone()
+        }
+    }
+
+    const MIB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_parse_tmpfs_mounts() {
+        let mounts = "\
+sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
+tmpfs /run tmpfs rw,nosuid,nodev,noexec,relatime,size=1620728k,mode=755 0 0
+/dev/nvme0n1p2 / ext4 rw,relatime 0 0
+tmpfs /dev/shm tmpfs rw,nosuid,nodev 0 0
+tmpfs /mnt/my\\040ram tmpfs rw 0 0
+";
+        assert_eq!(
+            parse_tmpfs_mounts(mounts),
+            vec!["/run", "/dev/shm", "/mnt/my ram"]
+        );
+        assert_eq!(unescape("a\\b\\0401"), "a\\b 1");
+    }
+
+    #[test]
+    fn test_parse_sysv_shm() {
+        let shm = "       key      shmid perms                  size  cpid  lpid nattch   uid   gid  cuid  cgid      atime      dtime      ctime                   rss                  swap
+         0      32786  1600                524288  1801  2299      2  1000  1000  1000  1000 1718000000 1718000000 1718000000                524288                     0
+";
+        assert_eq!(
+            parse_sysv_shm(shm),
+            vec![("32786".to_string(), 524288, 1801)]
+        );
+    }
+
+    #[test]
+    fn test_parse_shm_maps() {
+        let maps = "\
+7f5e2c000000-7f5e30000000 rw-s 00000000 00:1a 1042 /dev/shm/pulse-shm-2714
+7f5e30000000-7f5e30021000 rw-p 00000000 00:00 0
+7f5e31000000-7f5e32000000 rw-s 00000000 00:1a 1042 /dev/shm/pulse-shm-2714
+7f5e33000000-7f5e34000000 r--p 00000000 08:02 77 /usr/lib/libc.so.6
+";
+        assert_eq!(parse_shm_maps(maps), vec!["pulse-shm-2714"]);
+    }
+
+    #[test]
+    fn test_lines() {
+        let mounts = vec![TmpfsMount {
+            path: "/dev/shm".to_string(),
+            used: 64 * MIB,
+            size: 256 * MIB,
+        }];
+        let segments = vec![
+            Segment {
+                kind: SegmentKind::SysV,
+                name: "32786".to_string(),
+                bytes: MIB / 2,
+                owners: vec![],
+            },
+            Segment {
+                kind: SegmentKind::Posix,
+                name: "pulse-shm-2714".to_string(),
+                bytes: 64 * MIB,
+                owners: vec!["pulseaudio".to_string(), "firefox".to_string()],
+            },
+        ];
+        let mut shm = SharedMemory::with_source(Box::new(FakeShmSource(mounts, segments)));
+        shm.init().unwrap();
+        let read = shm.reading.take().unwrap().recv().unwrap();
+        shm.apply(read);
+        let text: Vec<String> = shm.lines().iter().map(Line::to_string).collect();
+        assert_eq!(
+            text,
+            vec![
+                "/dev/shm     ■■■■■ 64MiB/256MiB",
+                "posix pulse-shm-2…   64MiB pulseaudio +1",
+                "sysv  32786         512KiB ?",
+            ]
+        );
+        assert!(shm.take_dirty());
+        shm.update(Action::Tick).unwrap();
+        assert!(!shm.take_dirty());
+    }
+
+    #[test]
+    fn test_hidden() {
+        let mut shm = SharedMemory::with_source(Box::new(FakeShmSource(vec![], vec![])));
+        shm.set_visible(false);
+        shm.update(Action::Tick).unwrap();
+        assert!(shm.reading.is_none());
+        shm.set_visible(true);
+        shm.update(Action::Tick).unwrap();
+        assert!(shm.reading.is_some());
+    }
+}
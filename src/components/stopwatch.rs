@@ -0,0 +1,154 @@
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::Result;
+use ratatui::prelude::*;
+
+use super::Component;
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::model::format_elapsed;
+use crate::tui::Frame;
+
+/// A stopwatch started and stopped with a key, e.g. to time a deployment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Watch {
+    name: String,
+    started: Instant,
+    /// The time it ran, once stopped.
+    stopped: Option<Duration>,
+}
+
+impl Watch {
+    fn elapsed(&self, now: Instant) -> Duration {
+        self.stopped
+            .unwrap_or_else(|| now.saturating_duration_since(self.started))
+    }
+}
+
+/// How long brt has been running, followed by the named stopwatches.
+#[derive(Debug)]
+pub struct Stopwatch {
+    session: Instant,
+    /// In the order they were first started; starting a stopped one again
+    /// starts it from zero.
+    watches: Vec<Watch>,
+    /// The line shows whole seconds.
+    cadence: Cadence,
+    dirty: bool,
+}
+
+impl Default for Stopwatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Self::started_at(Instant::now())
+    }
+
+    fn started_at(session: Instant) -> Self {
+        Self {
+            session,
+            watches: vec![],
+            cadence: Cadence::from_millis(1_000),
+            dirty: true,
+        }
+    }
+
+    /// Starts the stopwatch called `name`, or stops it when it runs.
+    fn toggle(&mut self, name: &str, now: Instant) {
+        self.dirty = true;
+        let Some(watch) = self.watches.iter_mut().find(|watch| watch.name == name) else {
+            self.watches.push(Watch {
+                name: name.to_string(),
+                started: now,
+                stopped: None,
+            });
+            return;
+        };
+        match watch.stopped {
+            Some(_) => {
+                watch.started = now;
+                watch.stopped = None;
+            }
+            None => watch.stopped = Some(now.saturating_duration_since(watch.started)),
+        }
+    }
+
+    /// E.g. `session 1h2m deploy 3m12s`, with the running stopwatches in
+    /// bold and the stopped ones dimmed.
+    fn line(&self, now: Instant) -> Line<'static> {
+        let session = now.saturating_duration_since(self.session).as_secs();
+        let mut spans = vec![Span::raw(format!("session {}", format_elapsed(session)))];
+        for watch in &self.watches {
+            let text = format!(
+                " {} {}",
+                watch.name,
+                format_elapsed(watch.elapsed(now).as_secs())
+            );
+            spans.push(match watch.stopped {
+                Some(_) => text.dim(),
+                None => text.bold(),
+            });
+        }
+        Line::from(spans)
+    }
+}
+
+impl Component for Stopwatch {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Tick if self.cadence.due() => self.dirty = true,
+            Action::ToggleStopwatch(name) => self.toggle(&name, Instant::now()),
+            _ => (),
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn describe(&self) -> Vec<String> {
+        vec![format!("{}.", self.line(Instant::now()))]
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        f.render_widget(self.line(Instant::now()), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stopwatch() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let mut stopwatch = Stopwatch::started_at(start);
+        assert_eq!(stopwatch.line(at(5)).to_string(), "session 5s");
+
+        stopwatch.toggle("deploy", at(60));
+        stopwatch.toggle("build", at(90));
+        let line = stopwatch.line(at(252));
+        assert_eq!(line.to_string(), "session 4m12s deploy 3m12s build 2m42s");
+        assert!(line.spans[1].style.add_modifier.contains(Modifier::BOLD));
+
+        // stopped, it keeps its time
+        stopwatch.toggle("deploy", at(300));
+        let line = stopwatch.line(at(3_600));
+        assert_eq!(line.to_string(), "session 1h0m deploy 4m0s build 58m30s");
+        assert!(line.spans[1].style.add_modifier.contains(Modifier::DIM));
+
+        // and starts from zero again
+        stopwatch.toggle("deploy", at(3_600));
+        assert_eq!(
+            stopwatch.line(at(3_610)).to_string(),
+            "session 1h0m deploy 10s build 58m40s"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use color_eyre::eyre::Result;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::widgets::Tabs;
+use strum::IntoEnumIterator;
+
+use crate::action::Action;
+use crate::app::Tab;
+use crate::components::Component;
+use crate::session::Session;
+use crate::symbols::symbols;
+use crate::tui::Frame;
+
+/// The names of the tabs, with the selected one highlighted.
+#[derive(Debug, Default)]
+pub struct TabBar {
+    selected: Tab,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl TabBar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Component for TabBar {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if let Action::SelectTab(number) = action {
+            if let Some(tab) = Tab::from_number(number) {
+                self.selected = tab;
+                self.dirty = true;
+            }
+        }
+        Ok(None)
+    }
+
+    fn restore_session(&mut self, session: &Session) {
+        self.selected = session.tab;
+        self.dirty = true;
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let titles = Tab::iter().map(|tab| format!("{} {tab}", tab.number()));
+        let tabs = Tabs::new(titles)
+            .select(self.selected as usize)
+            .style(Style::default().dim())
+            .highlight_style(Style::default().fg(Color::White).bold().not_dim())
+            .divider(symbols().divider);
+        f.render_widget(tabs, rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, prelude::*};
+
+    use super::*;
+
+    #[test]
+    fn test_tab_bar() {
+        let mut bar = TabBar::new();
+        bar.update(Action::SelectTab(3)).unwrap();
+        assert_eq!(bar.selected, Tab::Sensors);
+        bar.update(Action::SelectTab(9)).unwrap();
+        assert_eq!(bar.selected, Tab::Sensors);
+
+        let mut terminal = Terminal::new(TestBackend::new(72, 1)).unwrap();
+        terminal.draw(|f| bar.draw(f, f.size()).unwrap()).unwrap();
+        let buffer = terminal.backend().buffer();
+        let line: String = buffer.content().iter().map(|c| c.symbol()).collect();
+        assert_eq!(
+            line.trim_end(),
+            " 1 Processes │ 2 Performance │ 3 Sensors │ 4 Network │ 5 Cgroups"
+        );
+        let selected = line.find("3 Sensors").unwrap();
+        let selected = line[..selected].chars().count() as u16;
+        assert!(buffer.get(selected, 0).modifier.contains(Modifier::BOLD));
+        assert!(!buffer.get(1, 0).modifier.contains(Modifier::BOLD));
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::Result;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+
+use crate::action::Action;
+use crate::components::Component;
+use crate::tui::Frame;
+
+/// How long a message stays on screen.
+const DURATION: Duration = Duration::from_secs(2);
+
+/// A short message in the bottom right corner of its pane, shown after an
+/// [`Action::Toast`] and hidden again after a few seconds.
+#[derive(Debug, Default)]
+pub struct Toast {
+    message: Option<(String, Instant)>,
+    /// Whether a message was drawn last time, so it is drawn away once.
+    shown: bool,
+}
+
+impl Toast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn visible_message(&self) -> Option<&str> {
+        self.message
+            .as_ref()
+            .filter(|(_, shown)| shown.elapsed() < DURATION)
+            .map(|(message, _)| message.as_str())
+    }
+
+    /// The bottom right of `rect`, just above its bottom border.
+    fn area(message: &str, rect: Rect) -> Rect {
+        let width = (message.chars().count() as u16 + 4).min(rect.width);
+        let height = 3.min(rect.height);
+        Rect {
+            x: rect.right().saturating_sub(width + 1).max(rect.x),
+            y: rect.bottom().saturating_sub(height + 1).max(rect.y),
+            width,
+            height,
+        }
+    }
+}
+
+impl Component for Toast {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if let Action::Toast(message) = action {
+            self.message = Some((message, Instant::now()));
+        }
+        Ok(None)
+    }
+
+    /// Dirty while a message is shown, and once more to hide it.
+    fn take_dirty(&mut self) -> bool {
+        self.visible_message().is_some() || self.shown
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        self.shown = self.visible_message().is_some();
+        let Some(message) = self.visible_message() else {
+            return Ok(());
+        };
+        let area = Self::area(message, rect);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Yellow));
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(format!(" {message}")).block(block), area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toast() {
+        let mut toast = Toast::new();
+        assert_eq!(toast.visible_message(), None);
+        toast
+            .update(Action::Toast("Copied 42".to_string()))
+            .unwrap();
+        assert_eq!(toast.visible_message(), Some("Copied 42"));
+        toast.message = Some(("old".to_string(), Instant::now() - DURATION));
+        assert_eq!(toast.visible_message(), None);
+    }
+
+    #[test]
+    fn test_toast_dirty() {
+        let mut toast = Toast::new();
+        assert!(!toast.take_dirty());
+        toast
+            .update(Action::Toast("Copied 42".to_string()))
+            .unwrap();
+        toast.shown = true;
+        assert!(toast.take_dirty());
+        // drawn away once after it expired
+        toast.message = Some(("old".to_string(), Instant::now() - DURATION));
+        assert!(toast.take_dirty());
+        toast.shown = false;
+        assert!(!toast.take_dirty());
+    }
+
+    #[test]
+    fn test_toast_area() {
+        let rect = Rect::new(0, 1, 40, 20);
+        assert_eq!(Toast::area("Copied 42", rect), Rect::new(26, 17, 13, 3));
+        assert_eq!(Toast::area("Copied 42", Rect::new(0, 0, 5, 2)).width, 5);
+    }
+}
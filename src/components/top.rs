@@ -0,0 +1,225 @@
+use std::cmp::Reverse;
+
+use color_eyre::eyre::Result;
+use ratatui::{prelude::*, widgets::block::Title, widgets::*};
+
+use super::Component;
+use crate::{
+    action::{Action, TopProcess, TopProcesses},
+    model::{format_bytes, BrtProcess},
+    symbols::symbols,
+    tui::Frame,
+};
+
+/// The number of processes in each list.
+pub const TOP_COUNT: usize = 5;
+/// The width of the memory or cpu usage after a program name.
+const VALUE_WIDTH: usize = 8;
+/// Wider than this the two lists go side by side.
+const SIDE_BY_SIDE_WIDTH: u16 = 40;
+
+/// Writes the memory or cpu usage of a process.
+type Format = fn(&TopProcess) -> String;
+
+/// The processes with the most resident memory and the highest cpu usage.
+pub fn top_processes<'a>(processes: impl IntoIterator<Item = &'a BrtProcess>) -> TopProcesses {
+    let mut processes: Vec<&BrtProcess> = processes.into_iter().collect();
+    let top = |processes: &[&BrtProcess]| {
+        processes
+            .iter()
+            .take(TOP_COUNT)
+            .map(|process| TopProcess {
+                pid: process.pid,
+                program: process.program.clone(),
+                resident_memory: process.resident_memory,
+                cpu: process.cpu,
+            })
+            .collect()
+    };
+    processes.sort_by_key(|process| Reverse(process.resident_memory));
+    let by_memory = top(&processes);
+    processes.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+    let by_cpu = top(&processes);
+    TopProcesses { by_memory, by_cpu }
+}
+
+fn format_memory(process: &TopProcess) -> String {
+    format_bytes(process.resident_memory)
+}
+
+fn format_cpu(process: &TopProcess) -> String {
+    format!("{:.1}%", process.cpu)
+}
+
+/// The heaviest processes, on a single line in the header or footer, or as
+/// two lists in the side pane.
+#[derive(Debug, Default)]
+pub struct Top {
+    top: TopProcesses,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Top {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// E.g. `mem firefox 1.2GiB code 812MiB │ cpu rustc 98.0% code 12.5%`.
+    fn line(&self) -> Line<'static> {
+        let mut spans = vec![Span::raw("mem").dim()];
+        for process in &self.top.by_memory {
+            spans.push(Span::raw(format!(" {}", process.program)));
+            spans.push(Span::raw(format!(" {}", format_memory(process))).dim());
+        }
+        spans.push(Span::raw(format!(" {} cpu", symbols().divider)).dim());
+        for process in &self.top.by_cpu {
+            spans.push(Span::raw(format!(" {}", process.program)));
+            spans.push(Span::raw(format!(" {}", format_cpu(process))).dim());
+        }
+        Line::from(spans)
+    }
+
+    /// The program names, cut to leave room for the values in `width`.
+    fn list(processes: &[TopProcess], width: u16, value: Format) -> Vec<Line<'static>> {
+        let name_width = (width as usize).saturating_sub(VALUE_WIDTH + 1);
+        processes
+            .iter()
+            .map(|process| {
+                Line::from(vec![
+                    Span::raw(format!("{:<name_width$.name_width$} ", process.program)),
+                    Span::raw(format!("{:>VALUE_WIDTH$}", value(process))).dim(),
+                ])
+            })
+            .collect()
+    }
+
+    fn draw_lists(&self, f: &mut Frame<'_>, rect: Rect) {
+        let block = Block::default()
+            .title(Title::from("top").alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+        let inner = block.inner(rect);
+        f.render_widget(block, rect);
+        let halves = if inner.width >= SIDE_BY_SIDE_WIDTH {
+            Layout::new(
+                Direction::Horizontal,
+                [Constraint::Fill(1), Constraint::Fill(1)],
+            )
+        } else {
+            // the title, the processes and an empty line
+            let height = TOP_COUNT as u16 + 2;
+            Layout::new(
+                Direction::Vertical,
+                [Constraint::Length(height), Constraint::Fill(1)],
+            )
+        }
+        .split(inner);
+        let lists: [(&str, &[TopProcess], Format); 2] = [
+            ("mem", &self.top.by_memory, format_memory),
+            ("cpu", &self.top.by_cpu, format_cpu),
+        ];
+        for (area, (title, processes, value)) in halves.iter().zip(lists) {
+            let mut lines = vec![Line::from(title).dim()];
+            lines.extend(Self::list(processes, area.width, value));
+            f.render_widget(Paragraph::new(lines), *area);
+        }
+    }
+}
+
+impl Component for Top {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if let Action::Update(update) = action {
+            self.top = update.top;
+            self.dirty = true;
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        if rect.height == 1 {
+            f.render_widget(self.line(), rect);
+        } else {
+            self.draw_lists(f, rect);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    use super::*;
+
+    fn process(pid: i32, program: &str, resident_memory: u64, cpu: f64) -> BrtProcess {
+        BrtProcess {
+            pid,
+            program: program.to_string(),
+            resident_memory,
+            cpu,
+            ..BrtProcess::default()
+        }
+    }
+
+    fn top() -> Top {
+        let mut processes: Vec<BrtProcess> = (1..=6)
+            .map(|pid| process(pid, &format!("p{pid}"), pid as u64 * 1_024, 0.0))
+            .collect();
+        processes.push(process(7, "rustc", 0, 98.5));
+        processes.push(process(8, "code", 512, 12.25));
+        let mut top = Top::new();
+        let update = Action::Update(crate::action::UpdatePayload {
+            timestamp: std::time::SystemTime::UNIX_EPOCH,
+            duration: std::time::Duration::ZERO,
+            processes: processes.len(),
+            started: 0,
+            exited: 0,
+            restricted: 0,
+            top: top_processes(&processes),
+        });
+        top.update(update).unwrap();
+        top
+    }
+
+    #[test]
+    fn test_top_processes() {
+        let top = top().top;
+        let programs = |processes: &[TopProcess]| -> Vec<String> {
+            processes.iter().map(|p| p.program.clone()).collect()
+        };
+        assert_eq!(programs(&top.by_memory), ["p6", "p5", "p4", "p3", "p2"]);
+        assert_eq!(programs(&top.by_cpu)[..2], ["rustc", "code"]);
+        assert_eq!(top.by_cpu[1].cpu, 12.25);
+        assert_eq!(format_cpu(&top.by_cpu[0]), "98.5%");
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut top = top();
+        let mut terminal = Terminal::new(TestBackend::new(60, 1)).unwrap();
+        terminal.draw(|f| top.draw(f, f.size()).unwrap()).unwrap();
+        let line: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(line.starts_with("mem p6 6KiB p5 5KiB p4 4KiB"));
+
+        let mut terminal = Terminal::new(TestBackend::new(30, 14)).unwrap();
+        terminal.draw(|f| top.draw(f, f.size()).unwrap()).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String { (0..30).map(|x| buffer.get(x, y).symbol()).collect() };
+        assert_eq!(row(2), "│ p6                    6KiB │");
+        assert_eq!(row(8), "│ cpu                        │");
+        assert_eq!(row(9), "│ rustc                98.5% │");
+    }
+}
@@ -0,0 +1,287 @@
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+use color_eyre::eyre::Result;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+use crate::action::Action;
+use crate::cadence::Cadence;
+use crate::components::Component;
+use crate::config::{Config, Intervals};
+use crate::tui::Frame;
+use crate::widgets::bar::{bar, RED_TO_GREEN};
+
+const WIRELESS: &str = "/proc/net/wireless";
+/// The link quality cfg80211 drivers report a perfect signal with.
+const MAX_QUALITY: f32 = 70.0;
+/// From a weak to a strong signal.
+const SIGNAL_GRADIENT: [Color; 5] = [
+    RED_TO_GREEN[0],
+    RED_TO_GREEN[3],
+    RED_TO_GREEN[5],
+    RED_TO_GREEN[7],
+    RED_TO_GREEN[9],
+];
+
+/// The signal of the first wireless interface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WifiReading {
+    pub interface: String,
+    /// The network, when the driver tells.
+    pub ssid: Option<String>,
+    /// From 0.0 to 1.0.
+    pub quality: f32,
+    /// The signal level in dBm.
+    pub level: f32,
+}
+
+/// Parses `/proc/net/wireless`, skipping its two header lines, into the
+/// first interface.
+pub fn parse_wireless(text: &str) -> Option<WifiReading> {
+    let line = text.lines().nth(2)?;
+    let (interface, values) = line.split_once(':')?;
+    let values: Vec<f32> = values
+        .split_whitespace()
+        .skip(1)
+        .take(2)
+        .filter_map(|value| value.trim_end_matches('.').parse().ok())
+        .collect();
+    let [link, level] = values[..] else {
+        return None;
+    };
+    Some(WifiReading {
+        interface: interface.trim().to_string(),
+        ssid: None,
+        quality: (link / MAX_QUALITY).clamp(0.0, 1.0),
+        level,
+    })
+}
+
+/// `SIOCGIWESSID` of the wireless extensions, which cfg80211 still answers.
+#[cfg(target_os = "linux")]
+const SIOCGIWESSID: u64 = 0x8B1B;
+#[cfg(target_os = "linux")]
+const IW_ESSID_MAX_SIZE: usize = 32;
+
+/// `struct iw_point` of `linux/wireless.h`.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct IwPoint {
+    pointer: *mut libc::c_void,
+    length: u16,
+    flags: u16,
+}
+
+/// `struct iwreq`, padded to the size of its largest member.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct IwRequest {
+    name: [libc::c_char; libc::IFNAMSIZ],
+    essid: IwPoint,
+    padding: [u8; 16],
+}
+
+/// The network the interface is connected with.
+#[cfg(target_os = "linux")]
+fn read_ssid(interface: &str) -> io::Result<String> {
+    // SAFETY: socket has no memory safety requirements.
+    let socket = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if socket < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the descriptor was just created and is owned by nobody else.
+    let socket = unsafe { OwnedFd::from_raw_fd(socket) };
+    let mut ssid = [0u8; IW_ESSID_MAX_SIZE + 1];
+    let mut request = IwRequest {
+        name: [0; libc::IFNAMSIZ],
+        essid: IwPoint {
+            pointer: ssid.as_mut_ptr().cast(),
+            length: ssid.len() as u16,
+            flags: 0,
+        },
+        padding: [0; 16],
+    };
+    // leaves the name terminated
+    for (c, byte) in request
+        .name
+        .iter_mut()
+        .zip(interface.bytes().take(libc::IFNAMSIZ - 1))
+    {
+        *c = byte as libc::c_char;
+    }
+    // SAFETY: the request and the buffer it points to outlive the call.
+    if unsafe { libc::ioctl(socket.as_raw_fd(), SIOCGIWESSID as _, &mut request) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let length = usize::from(request.essid.length).min(IW_ESSID_MAX_SIZE);
+    Ok(String::from_utf8_lossy(&ssid[..length]).into_owned())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_ssid(_interface: &str) -> io::Result<String> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "wireless extensions are linux only",
+    ))
+}
+
+/// Something that can tell the wifi signal, if there is a wireless
+/// interface.
+pub trait WifiSource: Debug {
+    fn read(&mut self) -> Option<WifiReading>;
+}
+
+/// Reads `/proc/net/wireless`, and the network from the interface.
+#[derive(Debug, Default)]
+pub struct SystemWifiSource;
+
+impl WifiSource for SystemWifiSource {
+    fn read(&mut self) -> Option<WifiReading> {
+        let mut reading = parse_wireless(&fs::read_to_string(WIRELESS).ok()?)?;
+        reading.ssid = read_ssid(&reading.interface)
+            .ok()
+            .filter(|ssid| !ssid.is_empty());
+        Some(reading)
+    }
+}
+
+/// The header segment of the wifi signal; empty without a wireless
+/// interface.
+#[derive(Debug)]
+pub struct Wifi {
+    source: Box<dyn WifiSource>,
+    reading: Option<WifiReading>,
+    cadence: Cadence,
+    /// Changed since it was last drawn.
+    dirty: bool,
+}
+
+impl Default for Wifi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Wifi {
+    pub fn new() -> Self {
+        Self::with_source(Box::new(SystemWifiSource))
+    }
+
+    pub fn with_source(source: Box<dyn WifiSource>) -> Self {
+        Self {
+            source,
+            reading: None,
+            cadence: Cadence::from_millis(Intervals::default().cpu),
+            dirty: true,
+        }
+    }
+
+    /// E.g. `home -56dBm ■■■■□ `, with the interface when the network is
+    /// unknown.
+    pub fn line(&self) -> Line<'static> {
+        let Some(reading) = &self.reading else {
+            return Line::default();
+        };
+        let index = (reading.quality * (SIGNAL_GRADIENT.len() - 1) as f32).round() as usize;
+        let color = SIGNAL_GRADIENT[index.min(SIGNAL_GRADIENT.len() - 1)];
+        let name = reading.ssid.as_ref().unwrap_or(&reading.interface);
+        let mut spans = vec![Span::styled(
+            format!("{name} {:.0}dBm ", reading.level),
+            Style::default().fg(color),
+        )];
+        spans.extend(bar(reading.quality, &SIGNAL_GRADIENT).spans);
+        spans.push(Span::raw(" "));
+        Line::from(spans)
+    }
+}
+
+impl Component for Wifi {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        self.cadence = Cadence::from_millis(config.config.intervals.cpu);
+        Ok(())
+    }
+
+    fn init(&mut self) -> Result<()> {
+        self.reading = self.source.read();
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::Tick && self.cadence.due() {
+            let reading = self.source.read();
+            self.dirty |= reading != self.reading;
+            self.reading = reading;
+        }
+        Ok(None)
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        f.render_widget(self.line(), rect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIRELESS: &str =
+        "Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+ face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+wlp2s0: 0000   56.  -54.  -256        0      0      0      0      0        0
+";
+
+    #[derive(Debug)]
+    struct FakeWifiSource(Option<WifiReading>);
+
+    impl WifiSource for FakeWifiSource {
+        fn read(&mut self) -> Option<WifiReading> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_parse_wireless() {
+        assert_eq!(
+            parse_wireless(WIRELESS),
+            Some(WifiReading {
+                interface: "wlp2s0".to_string(),
+                ssid: None,
+                quality: 0.8,
+                level: -54.0,
+            })
+        );
+        // without a wireless interface there are only the headers
+        let headers: String = WIRELESS.lines().take(2).collect::<Vec<_>>().join("\n");
+        assert_eq!(parse_wireless(&headers), None);
+    }
+
+    #[test]
+    fn test_line() {
+        let mut wifi = Wifi::with_source(Box::new(FakeWifiSource(None)));
+        wifi.init().unwrap();
+        assert_eq!(wifi.line(), Line::default());
+
+        let mut reading = parse_wireless(WIRELESS).unwrap();
+        wifi.source = Box::new(FakeWifiSource(Some(reading.clone())));
+        wifi.init().unwrap();
+        assert_eq!(wifi.line().to_string(), "wlp2s0 -54dBm ■■■■■ ");
+        assert_eq!(wifi.line().spans[0].style.fg, Some(RED_TO_GREEN[7]));
+
+        reading.ssid = Some("home".to_string());
+        reading.quality = 0.1;
+        wifi.source = Box::new(FakeWifiSource(Some(reading)));
+        wifi.init().unwrap();
+        assert_eq!(wifi.line().to_string(), "home -54dBm ■■■■■ ");
+        assert_eq!(wifi.line().spans[0].style.fg, Some(RED_TO_GREEN[0]));
+    }
+}
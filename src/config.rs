@@ -1,21 +1,455 @@
 use std::{collections::HashMap, path::PathBuf};
 
+use chrono::format::{Item, StrftimeItems};
 use color_eyre::eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use derive_deref::{Deref, DerefMut};
 use ratatui::style::{Color, Modifier, Style};
 use serde::{de::Deserializer, Deserialize};
 
-use crate::{action::Action, app::Mode};
+use crate::{
+    action::Action,
+    app::{Mode, Pane},
+    components::process::{Order, SortKey},
+    control::Tracer,
+    i18n::Locale,
+    layout::Node,
+    model::{Column, RowTint, Usage, DEFAULT_COLUMNS, GRAPH_THRESHOLDS},
+    widgets::{bar::RED_TO_GREEN, sparkline::MAX_DOTS},
+};
 
 const CONFIG: &str = include_str!("../.config/config.json5");
 
-#[derive(Clone, Debug, Deserialize, Default)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct AppConfig {
     #[serde(default)]
     pub _data_dir: PathBuf,
     #[serde(default)]
     pub _config_dir: PathBuf,
+    /// The columns of the process table, from left to right.
+    #[serde(default = "default_columns")]
+    pub columns: Vec<Column>,
+    /// Show a short history of the frequency of each core.
+    #[serde(default = "default_true")]
+    pub frequency_history: bool,
+    /// Warn when a discharging battery drops to this percentage.
+    #[serde(default = "default_battery_low")]
+    pub battery_low: u32,
+    /// Warn again, more urgently, at this percentage.
+    #[serde(default = "default_battery_critical")]
+    pub battery_critical: u32,
+    /// The seconds over which the rate of the battery is averaged for the
+    /// smoothed estimate of the time left.
+    #[serde(default = "default_battery_window")]
+    pub battery_window: u64,
+    #[serde(default)]
+    pub battery_bar: BarConfig,
+    /// Panes narrower than this drop the less important columns and details.
+    #[serde(default = "default_compact_width")]
+    pub compact_width: u16,
+    /// In terminals narrower than this the side pane goes below the main one.
+    #[serde(default = "default_stack_width")]
+    pub stack_width: u16,
+    /// Where the panes go instead, e.g. `rows(header, columns(main, side 30%), footer)`;
+    /// see [`crate::layout`].
+    #[serde(default, deserialize_with = "deserialize_layout")]
+    pub layout: Option<Node>,
+    #[serde(default)]
+    pub intervals: Intervals,
+    /// The number of cpu samples averaged when sorting by recent cpu usage.
+    #[serde(default = "default_recent_cpu_samples")]
+    pub recent_cpu_samples: usize,
+    /// The cpu usage in percent from which a column of the cpu graph of a
+    /// process has one, two, three and four dots.
+    #[serde(default = "default_graph_thresholds")]
+    pub graph_thresholds: [f64; MAX_DOTS],
+    /// How to order the processes with equal values in the sorted column,
+    /// in turn, e.g. `[{ order: 'Memory', descending: true }]` to break ties
+    /// in cpu usage. The `secondary_order: 'Memory'` of older configs is
+    /// read as the only one.
+    #[serde(
+        default,
+        alias = "secondary_order",
+        deserialize_with = "deserialize_tie_breakers"
+    )]
+    pub tie_breakers: Vec<SortKey>,
+    /// Tint the rows of the process table by cpu or memory usage, e.g.
+    /// `{ by: 'Memory', steps: [[50, 'rgb520'], [90, 'white on red']] }`
+    /// with the usage in percent and a style as in `styles`.
+    #[serde(default, deserialize_with = "deserialize_row_colors")]
+    pub row_colors: Option<RowTint>,
+    /// The language and time format, e.g. `nl_NL`; by default taken from
+    /// `LC_ALL`, `LC_MESSAGES` or `LANG`.
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub clock: ClockConfig,
+    /// Where to show the processes using the most memory and cpu, if at all.
+    #[serde(default)]
+    pub top_processes: Option<Pane>,
+    /// Where to show the latest kernel messages, if at all.
+    #[serde(default)]
+    pub kernel_log: Option<Pane>,
+    /// Where to show how long brt runs and the stopwatches, if at all.
+    #[serde(default = "default_stopwatch")]
+    pub stopwatch: Option<Pane>,
+    #[serde(default)]
+    pub attach: AttachCommands,
+    /// Have the pid of a process in the system slice typed before it is
+    /// signalled or reniced.
+    #[serde(default = "default_true")]
+    pub guard_system_services: bool,
+    /// Show the hostname, kernel release and boot time in the header.
+    #[serde(default = "default_true")]
+    pub system_info: bool,
+    /// Show the summed cpu and resident memory of the filtered processes
+    /// below the process table.
+    #[serde(default = "default_true")]
+    pub totals: bool,
+    /// Draw with ASCII instead of braille, block and arrow glyphs.
+    #[serde(default)]
+    pub ascii: bool,
+    /// Show the keys that act in the current state at the bottom.
+    #[serde(default = "default_true")]
+    pub hints: bool,
+    /// Show how long ago the panes on the tab and the collectors sampled
+    /// their data above the keys, in yellow when late and red when stalled.
+    #[serde(default)]
+    pub staleness: bool,
+    /// The frames per second while the terminal doesn't have the focus, in
+    /// terminals that tell.
+    #[serde(default = "default_unfocused_frame_rate")]
+    pub unfocused_frame_rate: f64,
+    /// Keep the cpu and memory usage in the title of the terminal window.
+    #[serde(default = "default_true")]
+    pub terminal_title: bool,
+    #[serde(default)]
+    pub text_dump: TextDumpConfig,
+    /// The number of journal entries shown of the systemd unit of a process.
+    #[serde(default = "default_journal_lines")]
+    pub journal_lines: usize,
+    /// The collectors that start turned off, e.g. `['pressure']`; see
+    /// [`crate::collector`].
+    #[serde(default)]
+    pub disabled_collectors: Vec<String>,
+}
+
+/// The command lines that attach a tracer to the selected process, in which
+/// `{pid}` is replaced by its pid.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct AttachCommands {
+    pub strace: Vec<String>,
+    pub gdb: Vec<String>,
+}
+
+impl Default for AttachCommands {
+    fn default() -> Self {
+        let command = |line: &str| line.split(' ').map(String::from).collect();
+        Self {
+            strace: command("strace -f -p {pid}"),
+            gdb: command("gdb -p {pid}"),
+        }
+    }
+}
+
+impl AttachCommands {
+    /// The command line attaching `tracer` to `pid`; `None` when it was
+    /// configured empty.
+    pub fn command(&self, tracer: Tracer, pid: i32) -> Option<Vec<String>> {
+        let line = match tracer {
+            Tracer::Strace => &self.strace,
+            Tracer::Gdb => &self.gdb,
+        };
+        let pid = pid.to_string();
+        (!line.is_empty()).then(|| line.iter().map(|arg| arg.replace("{pid}", &pid)).collect())
+    }
+}
+
+/// Where the plain text description of the view goes, e.g. for a screen
+/// reader.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct TextDumpConfig {
+    /// A command line reading the description from its standard input, e.g.
+    /// `["espeak-ng"]`; without one it is written to `path`.
+    pub command: Vec<String>,
+    /// By default `view.txt` in the data directory.
+    pub path: Option<PathBuf>,
+}
+
+/// How the bar of the battery charge is drawn, e.g.
+/// `{ length: 5, glyph: '▮', gradient: ['red', 'yellow', 'green'] }`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct BarConfig {
+    /// The number of glyphs.
+    pub length: usize,
+    /// The glyphs of the filled and the empty part; by default those of the
+    /// symbols, which depend on `ascii`.
+    pub glyph: Option<String>,
+    pub empty_glyph: Option<String>,
+    /// The colors from empty to full, as in `styles`, spread over the
+    /// length.
+    #[serde(deserialize_with = "deserialize_gradient")]
+    pub gradient: Vec<Color>,
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        Self {
+            length: RED_TO_GREEN.len(),
+            glyph: None,
+            empty_glyph: None,
+            gradient: RED_TO_GREEN.to_vec(),
+        }
+    }
+}
+
+fn deserialize_gradient<'de, D>(deserializer: D) -> Result<Vec<Color>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let colors = Vec::<String>::deserialize(deserializer)?;
+    if colors.is_empty() {
+        return Err(serde::de::Error::custom(
+            "A gradient needs a color at least",
+        ));
+    }
+    colors
+        .iter()
+        .map(|color| {
+            parse_style(color)
+                .fg
+                .ok_or_else(|| serde::de::Error::custom(format!("Unknown color `{color}`")))
+        })
+        .collect()
+}
+
+/// How the header clock is written.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ClockConfig {
+    /// A strftime format like `%H:%M`, used instead of the settings below.
+    pub format: Option<String>,
+    /// Hours up to 12 followed by AM or PM; by default as usual in the locale.
+    pub twelve_hour: Option<bool>,
+    /// Also show the day and the date, e.g. `Fri 17 May 14:05:09.250`.
+    pub date: bool,
+}
+
+impl ClockConfig {
+    /// The strftime format of the clock; without milliseconds when `compact`.
+    pub fn format(&self, locale: &Locale, compact: bool) -> String {
+        if let Some(format) = &self.format {
+            return format.clone();
+        }
+        let date = match (self.date, compact) {
+            (false, _) => "",
+            (true, false) => "%a %-d %b ",
+            (true, true) => "%a %-d ",
+        };
+        let fraction = if compact { "" } else { "%.3f" };
+        if self
+            .twelve_hour
+            .unwrap_or_else(|| locale.uses_12_hour_clock())
+        {
+            format!("{date}%I:%M:%S{fraction} %p")
+        } else {
+            format!("{date}%H:%M:%S{fraction}")
+        }
+    }
+
+    /// Drops a custom format chrono can't write, which would otherwise panic
+    /// on every frame.
+    pub fn validated(mut self) -> Self {
+        if let Some(format) = &self.format {
+            if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                tracing::warn!("Ignoring the invalid clock format {format:?}");
+                self.format = None;
+            }
+        }
+        self
+    }
+}
+
+/// How often each collector samples, in milliseconds.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Intervals {
+    pub processes: u64,
+    /// The cpu and memory usage and the cpu frequencies.
+    pub cpu: u64,
+    pub battery: u64,
+    pub clock: u64,
+}
+
+impl Default for Intervals {
+    fn default() -> Self {
+        Self {
+            processes: 2_000,
+            cpu: 500,
+            battery: 30_000,
+            clock: 100,
+        }
+    }
+}
+
+impl Intervals {
+    /// The ticks per second needed to serve the most frequent collector.
+    pub fn tick_rate(&self) -> f64 {
+        let shortest = [self.processes, self.cpu, self.battery, self.clock]
+            .into_iter()
+            .min()
+            .unwrap_or_default()
+            .max(10);
+        1_000.0 / shortest as f64
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            _data_dir: PathBuf::default(),
+            _config_dir: PathBuf::default(),
+            columns: default_columns(),
+            frequency_history: true,
+            battery_low: default_battery_low(),
+            battery_critical: default_battery_critical(),
+            battery_window: default_battery_window(),
+            battery_bar: BarConfig::default(),
+            compact_width: default_compact_width(),
+            stack_width: default_stack_width(),
+            layout: None,
+            intervals: Intervals::default(),
+            recent_cpu_samples: default_recent_cpu_samples(),
+            graph_thresholds: GRAPH_THRESHOLDS,
+            tie_breakers: vec![],
+            row_colors: None,
+            locale: None,
+            clock: ClockConfig::default(),
+            top_processes: None,
+            kernel_log: None,
+            stopwatch: default_stopwatch(),
+            attach: AttachCommands::default(),
+            guard_system_services: true,
+            system_info: true,
+            totals: true,
+            ascii: false,
+            hints: true,
+            staleness: false,
+            unfocused_frame_rate: default_unfocused_frame_rate(),
+            terminal_title: true,
+            text_dump: TextDumpConfig::default(),
+            journal_lines: default_journal_lines(),
+            disabled_collectors: vec![],
+        }
+    }
+}
+
+fn default_columns() -> Vec<Column> {
+    DEFAULT_COLUMNS.to_vec()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_battery_low() -> u32 {
+    20
+}
+
+fn default_battery_critical() -> u32 {
+    10
+}
+
+fn default_battery_window() -> u64 {
+    600
+}
+
+fn default_journal_lines() -> usize {
+    20
+}
+
+fn default_compact_width() -> u16 {
+    100
+}
+
+fn default_stack_width() -> u16 {
+    120
+}
+
+fn default_unfocused_frame_rate() -> f64 {
+    1.0
+}
+
+fn default_stopwatch() -> Option<Pane> {
+    Some(Pane::Footer)
+}
+
+fn default_recent_cpu_samples() -> usize {
+    5
+}
+
+fn default_graph_thresholds() -> [f64; MAX_DOTS] {
+    GRAPH_THRESHOLDS
+}
+
+fn deserialize_tie_breakers<'de, D>(deserializer: D) -> Result<Vec<SortKey>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum TieBreakers {
+        Keys(Vec<SortKey>),
+        Secondary(Option<Order>),
+    }
+
+    Ok(match TieBreakers::deserialize(deserializer)? {
+        TieBreakers::Keys(keys) => keys,
+        TieBreakers::Secondary(order) => order
+            .map(|order| SortKey {
+                order,
+                descending: false,
+            })
+            .into_iter()
+            .collect(),
+    })
+}
+
+fn deserialize_layout<'de, D>(deserializer: D) -> Result<Option<Node>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let layout = String::deserialize(deserializer)?;
+    layout.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+/// Orange from 50% and red from 90%.
+fn default_row_steps() -> Vec<(f64, String)> {
+    vec![(50.0, "rgb520".to_string()), (90.0, "red".to_string())]
+}
+
+fn deserialize_row_colors<'de, D>(deserializer: D) -> Result<Option<RowTint>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct RowColors {
+        #[serde(default)]
+        by: Usage,
+        #[serde(default = "default_row_steps")]
+        steps: Vec<(f64, String)>,
+    }
+
+    let RowColors { by, mut steps } = RowColors::deserialize(deserializer)?;
+    steps.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    let steps = steps
+        .into_iter()
+        .map(|(from, style)| (from, parse_style(&style)))
+        .collect();
+    Ok(Some(RowTint { by, steps }))
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -56,7 +490,7 @@ impl Config {
             }
         }
         if !found_config {
-            log::error!("No configuration file found. Application may not behave as expected");
+            tracing::error!("No configuration file found. Application may not behave as expected");
         }
 
         let mut cfg: Self = builder.build()?.try_deserialize()?;
@@ -412,6 +846,7 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::control::CommandTemplate;
 
     #[test]
     fn test_parse_style_default() {
@@ -471,9 +906,203 @@ mod tests {
                 .unwrap(),
             &Action::Quit
         );
+        assert_eq!(
+            c.keybindings
+                .get(&Mode::Process)
+                .unwrap()
+                .get(&parse_key_sequence("<Ctrl-l>").unwrap_or_default())
+                .unwrap(),
+            &Action::ClearScreen
+        );
+        assert_eq!(
+            c.keybindings
+                .get(&Mode::Process)
+                .unwrap()
+                .get(&parse_key_sequence("<Alt-2>").unwrap_or_default())
+                .unwrap(),
+            &Action::SelectTab(2)
+        );
         Ok(())
     }
 
+    #[test]
+    fn test_intervals() {
+        let config: AppConfig = json5::from_str("{ intervals: { cpu: 250 } }").unwrap();
+        assert_eq!(config.intervals.cpu, 250);
+        assert_eq!(config.intervals.processes, 2_000);
+        assert_eq!(config.intervals.tick_rate(), 10.0);
+        let intervals = Intervals {
+            clock: 1_000,
+            ..config.intervals
+        };
+        assert_eq!(intervals.tick_rate(), 4.0);
+    }
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(AppConfig::default().layout, None);
+        let config: AppConfig = json5::from_str("{ layout: 'rows(header, main)' }").unwrap();
+        assert_eq!(config.layout, Some("rows(header, main)".parse().unwrap()));
+        let error = json5::from_str::<AppConfig>("{ layout: 'rows(header, top)' }").unwrap_err();
+        assert!(error.to_string().contains("Unknown pane `top`"));
+    }
+
+    #[test]
+    fn test_tie_breakers() {
+        let memory = |descending| SortKey {
+            order: Order::Memory,
+            descending,
+        };
+        let config: AppConfig =
+            json5::from_str("{ tie_breakers: [{ order: 'Memory', descending: true }] }").unwrap();
+        assert_eq!(config.tie_breakers, vec![memory(true)]);
+        let config: AppConfig = json5::from_str("{ secondary_order: 'Memory' }").unwrap();
+        assert_eq!(config.tie_breakers, vec![memory(false)]);
+        let config: AppConfig = json5::from_str("{ secondary_order: null }").unwrap();
+        assert_eq!(config.tie_breakers, vec![]);
+        assert!(json5::from_str::<AppConfig>("{ secondary_order: 'Size' }").is_err());
+    }
+
+    #[test]
+    fn test_row_colors() {
+        assert_eq!(AppConfig::default().row_colors, None);
+        let config: AppConfig = json5::from_str("{ row_colors: {} }").unwrap();
+        let tint = config.row_colors.unwrap();
+        assert_eq!(tint.by, Usage::Cpu);
+        assert_eq!(
+            tint.steps,
+            vec![
+                (50.0, Style::default().fg(Color::Indexed(208))),
+                (90.0, Style::default().fg(Color::Indexed(1))),
+            ]
+        );
+
+        let config: AppConfig = json5::from_str(
+            "{ row_colors: { by: 'Memory', steps: [[90, 'on red'], [25, 'yellow']] } }",
+        )
+        .unwrap();
+        let tint = config.row_colors.unwrap();
+        assert_eq!(tint.by, Usage::Memory);
+        assert_eq!(
+            tint.steps,
+            vec![
+                (25.0, Style::default().fg(Color::Indexed(3))),
+                (90.0, Style::default().bg(Color::Indexed(1))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_battery_bar() {
+        assert_eq!(AppConfig::default().battery_bar.length, 10);
+        let config: AppConfig = json5::from_str(
+            "{ battery_bar: { length: 5, glyph: '▮', gradient: ['red', 'green'] } }",
+        )
+        .unwrap();
+        assert_eq!(
+            config.battery_bar,
+            BarConfig {
+                length: 5,
+                glyph: Some("▮".to_string()),
+                empty_glyph: None,
+                gradient: vec![Color::Indexed(1), Color::Indexed(2)],
+            }
+        );
+        let error = json5::from_str::<AppConfig>("{ battery_bar: { gradient: ['red', 'teal'] } }")
+            .unwrap_err();
+        assert!(error.to_string().contains("Unknown color `teal`"));
+        assert!(json5::from_str::<AppConfig>("{ battery_bar: { gradient: [] } }").is_err());
+    }
+
+    #[test]
+    fn test_top_processes() {
+        let config: AppConfig = json5::from_str("{ top_processes: 'Footer' }").unwrap();
+        assert_eq!(config.top_processes, Some(Pane::Footer));
+        assert_eq!(AppConfig::default().top_processes, None);
+
+        let config: AppConfig = json5::from_str("{ kernel_log: 'Side' }").unwrap();
+        assert_eq!(config.kernel_log, Some(Pane::Side));
+        assert_eq!(AppConfig::default().kernel_log, None);
+
+        let config: AppConfig = json5::from_str("{ stopwatch: null }").unwrap();
+        assert_eq!(config.stopwatch, None);
+        assert_eq!(AppConfig::default().stopwatch, Some(Pane::Footer));
+    }
+
+    #[test]
+    fn test_clock() {
+        let config: AppConfig = json5::from_str("{ clock: { date: true } }").unwrap();
+        let english = Locale::default();
+        assert_eq!(
+            config.clock.format(&english, false),
+            "%a %-d %b %H:%M:%S%.3f"
+        );
+        assert_eq!(config.clock.format(&english, true), "%a %-d %H:%M:%S");
+        let twelve = ClockConfig {
+            twelve_hour: Some(true),
+            ..ClockConfig::default()
+        };
+        assert_eq!(twelve.format(&english, true), "%I:%M:%S %p");
+        let american = Locale::parse("en_US");
+        assert_eq!(
+            ClockConfig::default().format(&american, false),
+            "%I:%M:%S%.3f %p"
+        );
+        let custom = ClockConfig {
+            format: Some("%H:%M".into()),
+            ..twelve
+        };
+        assert_eq!(custom.clone().validated().format(&english, false), "%H:%M");
+        let invalid = ClockConfig {
+            format: Some("%H:%Q".into()),
+            ..custom
+        };
+        assert_eq!(invalid.validated().format(&english, true), "%I:%M:%S %p");
+    }
+
+    #[test]
+    fn test_attach_commands() {
+        let defaults = AttachCommands::default();
+        assert_eq!(
+            defaults.command(Tracer::Strace, 42).unwrap(),
+            vec!["strace", "-f", "-p", "42"]
+        );
+        let config: AppConfig =
+            json5::from_str("{ attach: { gdb: ['rust-gdb', '--pid={pid}'], strace: [] } }")
+                .unwrap();
+        assert_eq!(
+            config.attach.command(Tracer::Gdb, 7).unwrap(),
+            vec!["rust-gdb", "--pid=7"]
+        );
+        assert_eq!(config.attach.command(Tracer::Strace, 7), None);
+    }
+
+    #[test]
+    fn test_run_command_binding() {
+        let keybindings: KeyBindings = json5::from_str(
+            r#"{ "Process": {
+                "<F3>": { "RunCommand": "kitty -- htop -p {pid}" },
+                "<F4>": { "RunCommand": { "command": "htop -p {pid}", "terminal": true } },
+            } }"#,
+        )
+        .unwrap();
+        let binding = |key| &keybindings[&Mode::Process][&parse_key_sequence(key).unwrap()];
+        assert_eq!(
+            binding("<F3>"),
+            &Action::RunCommand(CommandTemplate {
+                command: "kitty -- htop -p {pid}".to_string(),
+                terminal: false
+            })
+        );
+        assert_eq!(
+            binding("<F4>"),
+            &Action::RunCommand(CommandTemplate {
+                command: "htop -p {pid}".to_string(),
+                terminal: true
+            })
+        );
+    }
+
     #[test]
     fn test_simple_keys() {
         assert_eq!(
@@ -0,0 +1,823 @@
+use std::fmt;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::source::system_source;
+
+/// The lowest and highest niceness a process can have.
+const NICE_RANGE: (i64, i64) = (-20, 19);
+/// The pid of kthreadd, the parent of all kernel threads.
+const KTHREADD: i32 = 2;
+/// Tells `ioprio_get` and `ioprio_set` the id is a process.
+#[cfg(target_os = "linux")]
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+/// The io priority is the class shifted by this, plus the level.
+const IOPRIO_CLASS_SHIFT: i32 = 13;
+/// The lowest level, which is the highest priority, is 0.
+pub const IO_LEVELS: u8 = 8;
+
+/// Whether signalling or renicing a process can take the system or brt
+/// itself down: init, the kernel threads and brt.
+pub fn is_critical(pid: i32, ppid: i32) -> bool {
+    pid == 1 || pid == KTHREADD || ppid == KTHREADD || pid == std::process::id() as i32
+}
+
+/// The unit of a process that runs in the system slice, e.g. `nginx.service`
+/// for the cgroup `/system.slice/nginx.service`.
+pub fn system_service(cgroup: &str) -> Option<&str> {
+    cgroup
+        .strip_prefix("/system.slice/")?
+        .split('/')
+        .next()
+        .filter(|unit| !unit.is_empty())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Term,
+    Kill,
+}
+
+impl Signal {
+    fn number(&self) -> libc::c_int {
+        match self {
+            Signal::Term => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+        }
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Signal::Term => write!(f, "SIGTERM"),
+            Signal::Kill => write!(f, "SIGKILL"),
+        }
+    }
+}
+
+pub fn send_signal(pid: i32, signal: Signal) -> io::Result<()> {
+    // SAFETY: kill has no memory safety requirements.
+    if unsafe { libc::kill(pid, signal.number()) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Whether the process group `group` can be signalled as a whole: a group of
+/// 1 or less would signal every process brt may signal, and brt's own group
+/// brt itself.
+pub fn can_signal_group(group: i32) -> bool {
+    // SAFETY: getpgrp has no memory safety requirements and can't fail.
+    group > 1 && group != unsafe { libc::getpgrp() }
+}
+
+pub fn send_group_signal(group: i32, signal: Signal) -> io::Result<()> {
+    if !can_signal_group(group) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("process group {group} can't be signalled as a whole"),
+        ));
+    }
+    // SAFETY: killpg has no memory safety requirements.
+    if unsafe { libc::killpg(group, signal.number()) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Adds `delta` to the niceness of a process, within the allowed range.
+pub fn renice(pid: i32, delta: i32) -> io::Result<()> {
+    let nice = system_source().stat(pid).map_err(io::Error::other)?.nice;
+    let nice = (nice + delta as i64).clamp(NICE_RANGE.0, NICE_RANGE.1);
+    // SAFETY: setpriority has no memory safety requirements.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice as libc::c_int) } == 0
+    {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// The io scheduling classes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IoClass {
+    /// Not set: best effort, at a level that follows the niceness.
+    None,
+    Realtime,
+    BestEffort,
+    /// Only when no other process needs the disk.
+    Idle,
+}
+
+/// The io scheduling class and level of a process, as `ionice` shows it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IoPriority {
+    pub class: IoClass,
+    /// From 0, the highest priority, to 7; the idle class has no levels.
+    pub level: u8,
+}
+
+impl Default for IoPriority {
+    fn default() -> Self {
+        Self {
+            class: IoClass::BestEffort,
+            level: 4,
+        }
+    }
+}
+
+impl IoPriority {
+    fn from_raw(raw: i32) -> Self {
+        let class = match raw >> IOPRIO_CLASS_SHIFT {
+            1 => IoClass::Realtime,
+            2 => IoClass::BestEffort,
+            3 => IoClass::Idle,
+            _ => IoClass::None,
+        };
+        let level = (raw & ((1 << IOPRIO_CLASS_SHIFT) - 1)) as u8;
+        Self { class, level }
+    }
+
+    fn raw(&self) -> i32 {
+        let class = match self.class {
+            IoClass::None => 0,
+            IoClass::Realtime => 1,
+            IoClass::BestEffort => 2,
+            IoClass::Idle => 3,
+        };
+        let level = if self.class == IoClass::Idle {
+            0
+        } else {
+            self.level
+        };
+        (class << IOPRIO_CLASS_SHIFT) | level as i32
+    }
+
+    /// The next class in the picker, from idle to realtime, or back with
+    /// `steps` below zero.
+    pub fn with_class_step(self, steps: i32) -> Self {
+        const CLASSES: [IoClass; 3] = [IoClass::Idle, IoClass::BestEffort, IoClass::Realtime];
+        let current = CLASSES
+            .iter()
+            .position(|class| *class == self.class)
+            .unwrap_or(1) as i32;
+        let index = (current + steps).clamp(0, CLASSES.len() as i32 - 1);
+        Self {
+            class: CLASSES[index as usize],
+            ..self
+        }
+    }
+
+    /// A higher level, which is a lower priority, or a lower one with
+    /// `steps` below zero.
+    pub fn with_level_step(self, steps: i32) -> Self {
+        let level = (self.level as i32 + steps).clamp(0, IO_LEVELS as i32 - 1);
+        Self {
+            level: level as u8,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.class {
+            IoClass::None => write!(f, "none"),
+            IoClass::Realtime => write!(f, "realtime/{}", self.level),
+            IoClass::BestEffort => write!(f, "best-effort/{}", self.level),
+            IoClass::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn io_priority(pid: i32) -> io::Result<IoPriority> {
+    // SAFETY: ioprio_get has no memory safety requirements.
+    let raw = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, pid) };
+    if raw < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(IoPriority::from_raw(raw as i32))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn io_priority(_pid: i32) -> io::Result<IoPriority> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "io priorities are linux only",
+    ))
+}
+
+#[cfg(target_os = "linux")]
+pub fn set_io_priority(pid: i32, priority: IoPriority) -> io::Result<()> {
+    // SAFETY: ioprio_set has no memory safety requirements.
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            pid,
+            priority.raw(),
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_io_priority(_pid: i32, _priority: IoPriority) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "io priorities are linux only",
+    ))
+}
+
+/// A tool that attaches to a running process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tracer {
+    Strace,
+    Gdb,
+}
+
+impl fmt::Display for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Tracer::Strace => write!(f, "strace"),
+            Tracer::Gdb => write!(f, "gdb"),
+        }
+    }
+}
+
+/// A command line bound to a key, run for the selected process: a string
+/// such as `"kitty -- htop -p {pid}"`, which is started on its own, or
+/// `{ "command": "htop -p {pid}", "terminal": true }` for one that needs the
+/// terminal while it runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "CommandTemplateConfig")]
+pub struct CommandTemplate {
+    pub command: String,
+    pub terminal: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CommandTemplateConfig {
+    Line(String),
+    Full {
+        command: String,
+        #[serde(default)]
+        terminal: bool,
+    },
+}
+
+impl From<CommandTemplateConfig> for CommandTemplate {
+    fn from(config: CommandTemplateConfig) -> Self {
+        match config {
+            CommandTemplateConfig::Line(command) => CommandTemplate {
+                command,
+                terminal: false,
+            },
+            CommandTemplateConfig::Full { command, terminal } => {
+                CommandTemplate { command, terminal }
+            }
+        }
+    }
+}
+
+/// Splits a command line into its arguments the way a shell does, without
+/// expanding anything: single quotes keep everything up to the next one,
+/// double quotes everything but a backslash before `"` or `\`, and a
+/// backslash elsewhere keeps the character after it.
+pub fn split_command_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = vec![];
+    let mut arg: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => args.extend(arg.take()),
+            '\'' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => return Err("missing closing '".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => arg.push(c),
+                            Some(c) => arg.extend(['\\', c]),
+                            None => return Err("missing closing \"".to_string()),
+                        },
+                        Some(c) => arg.push(c),
+                        None => return Err("missing closing \"".to_string()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => arg.get_or_insert_with(String::new).push(c),
+                None => return Err("nothing to escape after \\".to_string()),
+            },
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
+/// Starts a command line on its own, without the terminal: in a process
+/// group of its own, so Ctrl-C in brt doesn't reach it, and with its output
+/// discarded, so it can't write over the TUI. A thread waits for it to exit,
+/// so it doesn't linger as a zombie.
+pub fn spawn_detached(command: &[String]) -> io::Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Runs a command line in the terminal and waits for it to exit. brt ignores
+/// interrupts meanwhile, so Ctrl-C stops the command but not brt.
+pub fn run_in_terminal(command: &[String]) -> io::Result<ExitStatus> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+    let mut child = Command::new(program);
+    child.args(args);
+    // SAFETY: signal is async-signal-safe, so it may be called between fork
+    // and exec.
+    unsafe {
+        child.pre_exec(|| {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+            Ok(())
+        });
+    }
+    // SAFETY: ignoring a signal has no memory safety requirements.
+    let previous = unsafe { libc::signal(libc::SIGINT, libc::SIG_IGN) };
+    let status = child.status();
+    // SAFETY: restores the disposition returned above.
+    unsafe { libc::signal(libc::SIGINT, previous) };
+    status
+}
+
+/// Something to do to one or more processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchAction {
+    Signal(Signal),
+    Renice(i32),
+    IoPriority(IoPriority),
+}
+
+impl BatchAction {
+    fn apply(&self, pid: i32) -> io::Result<()> {
+        match *self {
+            BatchAction::Signal(signal) => send_signal(pid, signal),
+            BatchAction::Renice(delta) => renice(pid, delta),
+            BatchAction::IoPriority(priority) => set_io_priority(pid, priority),
+        }
+    }
+}
+
+impl fmt::Display for BatchAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BatchAction::Signal(signal) => write!(f, "{signal}"),
+            BatchAction::Renice(delta) => write!(f, "renice {delta:+}"),
+            BatchAction::IoPriority(priority) => write!(f, "ionice {priority}"),
+        }
+    }
+}
+
+/// Which processes a signal goes to, besides the chosen ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    #[default]
+    Process,
+    /// Also their descendants, which are signalled first, from the deepest
+    /// up, so a parent like a build tool doesn't spawn new children.
+    Descendants,
+    /// Their whole process groups, e.g. a shell pipeline.
+    Group,
+}
+
+impl Scope {
+    /// The scope after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            Scope::Process => Scope::Descendants,
+            Scope::Descendants => Scope::Group,
+            Scope::Group => Scope::Process,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        self.next().next()
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scope::Process => write!(f, "process only"),
+            Scope::Descendants => write!(f, "process + descendants"),
+            Scope::Group => write!(f, "process group"),
+        }
+    }
+}
+
+/// An action waiting for confirmation, with the pids and programs it applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batch {
+    pub action: BatchAction,
+    /// The marked or the selected processes.
+    pub roots: Vec<i32>,
+    /// In the order they are acted on.
+    pub targets: Vec<(i32, String)>,
+    pub scope: Scope,
+    /// The process groups signalled as a whole in the group scope, in which
+    /// the targets are their members.
+    pub groups: Vec<i32>,
+    /// A critical target, whose pid has to be typed to confirm.
+    pub critical: Option<i32>,
+    /// The system service the critical target runs, when that is what makes
+    /// it critical.
+    pub service: Option<String>,
+}
+
+impl Batch {
+    /// E.g. `Send SIGTERM to 3 processes?`.
+    pub fn question(&self) -> String {
+        let count = self.targets.len();
+        let noun = if count == 1 { "process" } else { "processes" };
+        match self.action {
+            BatchAction::Signal(signal) if self.scope == Scope::Group => {
+                let groups: Vec<String> = self.groups.iter().map(i32::to_string).collect();
+                let plural = if groups.len() == 1 { "" } else { "s" };
+                format!(
+                    "Send {signal} to process group{plural} {} of {count} {noun}?",
+                    groups.join(", ")
+                )
+            }
+            BatchAction::Signal(signal) if self.scope == Scope::Descendants => {
+                format!("Send {signal} to {count} {noun}, the deepest first?")
+            }
+            BatchAction::Signal(signal) => format!("Send {signal} to {count} {noun}?"),
+            BatchAction::Renice(delta) => format!("Renice {count} {noun} by {delta:+}?"),
+            BatchAction::IoPriority(priority) => {
+                format!("Set the io priority of {count} {noun} to {priority}?")
+            }
+        }
+    }
+
+    /// Whether `typed` confirms the batch: anything does, unless there is a
+    /// critical target, whose pid it has to be.
+    pub fn is_confirmed_by(&self, typed: &str) -> bool {
+        self.critical
+            .map_or(true, |pid| typed.trim() == pid.to_string())
+    }
+
+    /// The command line doing the same from a shell, e.g. `kill -TERM 12 34`,
+    /// `kill -TERM -- -12` for a process group or
+    /// `renice --relative 5 -p 12`, which adds to the niceness as brt does.
+    pub fn shell_command(&self) -> String {
+        if let (BatchAction::Signal(signal), Scope::Group) = (self.action, self.scope) {
+            let groups: Vec<String> = self
+                .groups
+                .iter()
+                .map(|group| format!("-{group}"))
+                .collect();
+            let name = signal.to_string();
+            let name = name.trim_start_matches("SIG");
+            return format!("kill -{name} -- {}", groups.join(" "));
+        }
+        let pids: Vec<String> = self
+            .targets
+            .iter()
+            .map(|(pid, _)| pid.to_string())
+            .collect();
+        let pids = pids.join(" ");
+        match self.action {
+            BatchAction::Signal(Signal::Term) => format!("kill -TERM {pids}"),
+            BatchAction::Signal(Signal::Kill) => format!("kill -KILL {pids}"),
+            BatchAction::Renice(delta) => format!("renice --relative {delta} -p {pids}"),
+            BatchAction::IoPriority(priority) => match priority.class {
+                IoClass::None => format!("ionice -c 0 -p {pids}"),
+                IoClass::Idle => format!("ionice -c 3 -p {pids}"),
+                class => format!(
+                    "ionice -c {} -n {} -p {pids}",
+                    if class == IoClass::Realtime { 1 } else { 2 },
+                    priority.level
+                ),
+            },
+        }
+    }
+
+    /// Applies the action to all targets and returns the pids it failed for,
+    /// or the negated ids of the process groups.
+    pub fn execute(&self) -> Vec<i32> {
+        let mut failed = Vec::new();
+        if let (BatchAction::Signal(signal), Scope::Group) = (self.action, self.scope) {
+            for group in &self.groups {
+                if let Err(e) = send_group_signal(*group, signal) {
+                    warn!(
+                        "Unable to apply '{}' to process group {group}: {e}",
+                        self.action
+                    );
+                    failed.push(-group);
+                }
+            }
+            info!(
+                "Applied '{}' to {} process groups, {} failed.",
+                self.action,
+                self.groups.len(),
+                failed.len()
+            );
+            return failed;
+        }
+        for (pid, program) in &self.targets {
+            if let Err(e) = self.action.apply(*pid) {
+                warn!(
+                    "Unable to apply '{}' to {pid} ({program}): {e}",
+                    self.action
+                );
+                failed.push(*pid);
+            }
+        }
+        info!(
+            "Applied '{}' to {} processes, {} failed.",
+            self.action,
+            self.targets.len(),
+            failed.len()
+        );
+        failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_command_line() {
+        let split = |line| split_command_line(line).unwrap();
+        assert_eq!(
+            split("kitty --  htop -p {pid}"),
+            ["kitty", "--", "htop", "-p", "{pid}"]
+        );
+        assert_eq!(
+            split(r#"sh -c 'echo "$0" {comm}' "a \"b\" \c" d\ e ''"#),
+            ["sh", "-c", "echo \"$0\" {comm}", "a \"b\" \\c", "d e", ""]
+        );
+        assert!(split(" ").is_empty());
+        assert!(split_command_line("sh -c 'echo").is_err());
+        assert!(split_command_line("echo \"a").is_err());
+        assert!(split_command_line("echo \\").is_err());
+    }
+
+    #[test]
+    fn test_command_template() {
+        let template: CommandTemplate = json5::from_str(r#""kitty -- htop""#).unwrap();
+        assert!(!template.terminal);
+        let template: CommandTemplate =
+            json5::from_str(r#"{ command: "htop -p {pid}", terminal: true }"#).unwrap();
+        assert_eq!(
+            template,
+            CommandTemplate {
+                command: "htop -p {pid}".to_string(),
+                terminal: true
+            }
+        );
+        // as an action is recorded and replayed
+        let json = serde_json::to_string(&template).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CommandTemplate>(&json).unwrap(),
+            template
+        );
+    }
+
+    #[test]
+    fn test_batch_question() {
+        let batch = Batch {
+            action: BatchAction::Signal(Signal::Term),
+            roots: vec![1, 2],
+            targets: vec![(1, "init".to_string()), (2, "kthreadd".to_string())],
+            scope: Scope::Process,
+            groups: vec![],
+            critical: Some(1),
+            service: None,
+        };
+        assert_eq!(batch.question(), "Send SIGTERM to 2 processes?");
+        let batch = Batch {
+            action: BatchAction::Renice(5),
+            roots: vec![1],
+            targets: vec![(1, "init".to_string())],
+            scope: Scope::Process,
+            groups: vec![],
+            critical: Some(1),
+            service: None,
+        };
+        assert_eq!(batch.question(), "Renice 1 process by +5?");
+    }
+
+    #[test]
+    fn test_scope() {
+        let batch = Batch {
+            action: BatchAction::Signal(Signal::Term),
+            roots: vec![12],
+            targets: vec![(34, "cc".to_string()), (12, "make".to_string())],
+            scope: Scope::Descendants,
+            groups: vec![],
+            critical: None,
+            service: None,
+        };
+        assert_eq!(
+            batch.question(),
+            "Send SIGTERM to 2 processes, the deepest first?"
+        );
+        let batch = Batch {
+            scope: Scope::Group,
+            groups: vec![12],
+            ..batch
+        };
+        assert_eq!(
+            batch.question(),
+            "Send SIGTERM to process group 12 of 2 processes?"
+        );
+        assert_eq!(Scope::Process.next(), Scope::Descendants);
+        assert_eq!(Scope::Process.previous(), Scope::Group);
+        assert_eq!(Scope::Group.next().to_string(), "process only");
+    }
+
+    #[test]
+    fn test_shell_command() {
+        let batch = |action| Batch {
+            action,
+            roots: vec![12, 34],
+            targets: vec![(12, "sleep".to_string()), (34, "cat".to_string())],
+            scope: Scope::Process,
+            groups: vec![],
+            critical: None,
+            service: None,
+        };
+        let command = |action| batch(action).shell_command();
+        assert_eq!(
+            command(BatchAction::Signal(Signal::Term)),
+            "kill -TERM 12 34"
+        );
+        assert_eq!(
+            command(BatchAction::Signal(Signal::Kill)),
+            "kill -KILL 12 34"
+        );
+        assert_eq!(
+            command(BatchAction::Renice(-3)),
+            "renice --relative -3 -p 12 34"
+        );
+        let priority = IoPriority::default();
+        assert_eq!(
+            command(BatchAction::IoPriority(priority)),
+            "ionice -c 2 -n 4 -p 12 34"
+        );
+        assert_eq!(
+            command(BatchAction::IoPriority(priority.with_class_step(1))),
+            "ionice -c 1 -n 4 -p 12 34"
+        );
+        assert_eq!(
+            command(BatchAction::IoPriority(priority.with_class_step(-1))),
+            "ionice -c 3 -p 12 34"
+        );
+        let group = Batch {
+            scope: Scope::Group,
+            groups: vec![12],
+            ..batch(BatchAction::Signal(Signal::Kill))
+        };
+        assert_eq!(group.shell_command(), "kill -KILL -- -12");
+    }
+
+    #[test]
+    fn test_batch_execute_reports_failures() {
+        let batch = Batch {
+            action: BatchAction::Renice(0),
+            roots: vec![std::process::id() as i32, i32::MAX],
+            targets: vec![
+                (std::process::id() as i32, "brt".to_string()),
+                (i32::MAX, "gone".to_string()),
+            ],
+            scope: Scope::Process,
+            groups: vec![],
+            critical: None,
+            service: None,
+        };
+        assert_eq!(batch.execute(), vec![i32::MAX]);
+    }
+
+    #[test]
+    fn test_io_priority() {
+        let priority = IoPriority::default();
+        assert_eq!(IoPriority::from_raw(priority.raw()), priority);
+        assert_eq!(priority.to_string(), "best-effort/4");
+        assert_eq!(IoPriority::from_raw(0).to_string(), "none");
+        let idle = priority.with_class_step(-1);
+        assert_eq!(idle.class, IoClass::Idle);
+        assert_eq!(idle.raw(), 3 << 13);
+        assert_eq!(idle.with_class_step(-1), idle);
+        let realtime = priority.with_class_step(5).with_level_step(-6);
+        assert_eq!(realtime.to_string(), "realtime/0");
+        assert_eq!(priority.with_level_step(10).level, 7);
+
+        let own = std::process::id() as i32;
+        let batch = Batch {
+            action: BatchAction::IoPriority(idle),
+            roots: vec![own],
+            targets: vec![(own, "brt".to_string())],
+            scope: Scope::Process,
+            groups: vec![],
+            critical: None,
+            service: None,
+        };
+        assert_eq!(
+            batch.question(),
+            "Set the io priority of 1 process to idle?"
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_own_io_priority() {
+        let own = std::process::id() as i32;
+        let current = io_priority(own).unwrap();
+        set_io_priority(own, current).unwrap();
+        assert_eq!(io_priority(own).unwrap(), current);
+        assert!(io_priority(i32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_group_signal() {
+        // SAFETY: getpgrp can't fail.
+        let own = unsafe { libc::getpgrp() };
+        for group in [-1, 0, 1, own] {
+            assert!(!can_signal_group(group));
+            let error = send_group_signal(group, Signal::Term).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+        assert!(can_signal_group(i32::MAX));
+        assert!(send_group_signal(i32::MAX, Signal::Term).is_err());
+    }
+
+    #[test]
+    fn test_critical_batch() {
+        assert!(is_critical(1, 0));
+        assert!(is_critical(2, 0));
+        assert!(is_critical(57, 2));
+        assert!(is_critical(std::process::id() as i32, 1));
+        assert!(!is_critical(4242, 1));
+        assert_eq!(
+            system_service("/system.slice/nginx.service"),
+            Some("nginx.service")
+        );
+        assert_eq!(
+            system_service("/system.slice/docker-1.scope/init"),
+            Some("docker-1.scope")
+        );
+        assert_eq!(system_service("/user.slice/user-1000.slice"), None);
+        assert_eq!(system_service("/system.slice/"), None);
+
+        let mut batch = Batch {
+            action: BatchAction::Signal(Signal::Kill),
+            roots: vec![1],
+            targets: vec![(1, "init".to_string())],
+            scope: Scope::Process,
+            groups: vec![],
+            critical: Some(1),
+            service: None,
+        };
+        assert!(!batch.is_confirmed_by(""));
+        assert!(!batch.is_confirmed_by("12"));
+        assert!(batch.is_confirmed_by("1"));
+        batch.critical = None;
+        assert!(batch.is_confirmed_by(""));
+    }
+}
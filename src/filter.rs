@@ -0,0 +1,274 @@
+//! Filter expressions for the process table, e.g. `user:root cpu>5 mem>100M`.
+//! Every term has to match: `field:text` compares text, `field>number` and
+//! the other comparisons compare numbers, and a term without a field looks
+//! for the text in the program and command line. `cgroup:/system.slice`
+//! keeps the processes in that cgroup and the ones below it.
+
+use crate::model::BrtProcess;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds(&self, value: f64, limit: f64) -> bool {
+        match self {
+            Comparison::Less => value < limit,
+            Comparison::LessOrEqual => value <= limit,
+            Comparison::Equal => value == limit,
+            Comparison::GreaterOrEqual => value >= limit,
+            Comparison::Greater => value > limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Number {
+    Pid,
+    /// In percent.
+    Cpu,
+    /// The resident memory in bytes; the limit may have a binary suffix like
+    /// `100M`.
+    Memory,
+    Threads,
+    Nice,
+}
+
+impl Number {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "pid" => Some(Number::Pid),
+            "cpu" => Some(Number::Cpu),
+            "mem" | "memory" => Some(Number::Memory),
+            "threads" => Some(Number::Threads),
+            "nice" => Some(Number::Nice),
+            _ => None,
+        }
+    }
+
+    fn value(&self, process: &BrtProcess) -> f64 {
+        match self {
+            Number::Pid => process.pid as f64,
+            Number::Cpu => process.cpu,
+            Number::Memory => process.resident_memory as f64,
+            Number::Threads => process.number_of_threads as f64,
+            Number::Nice => process.nice as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Predicate {
+    /// Part of the program or the command line.
+    Contains(String),
+    User(String),
+    Program(String),
+    Command(String),
+    /// The state letter, e.g. `R`.
+    State(char),
+    /// A cgroup path with a leading slash and without a trailing one, e.g.
+    /// `/system.slice`.
+    Cgroup(String),
+    Compare(Number, Comparison, f64),
+}
+
+impl Predicate {
+    fn parse(term: &str) -> Result<Self, String> {
+        let Some(start) = term.find([':', '<', '>', '=']) else {
+            return Ok(Predicate::Contains(term.to_lowercase()));
+        };
+        let (field, rest) = term.split_at(start);
+        let field = field.to_lowercase();
+        if let Some(text) = rest.strip_prefix(':') {
+            let text = text.to_lowercase();
+            return match field.as_str() {
+                "user" => Ok(Predicate::User(text)),
+                "name" | "program" => Ok(Predicate::Program(text)),
+                "cmd" | "command" => Ok(Predicate::Command(text)),
+                "state" => match text.chars().next() {
+                    Some(state) => Ok(Predicate::State(state.to_ascii_uppercase())),
+                    None => Err(format!("{term}: no state")),
+                },
+                "cgroup" => Ok(Predicate::Cgroup(format!("/{}", text.trim_matches('/')))),
+                _ => Err(format!("{term}: unknown field {field}")),
+            };
+        }
+        let number = Number::parse(&field).ok_or(format!("{term}: unknown field {field}"))?;
+        let (comparison, limit) = [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+            ("=", Comparison::Equal),
+        ]
+        .into_iter()
+        .find_map(|(operator, comparison)| Some((comparison, rest.strip_prefix(operator)?)))
+        .ok_or(format!("{term}: no comparison"))?;
+        let limit = match number {
+            Number::Memory => parse_size(limit),
+            _ => limit.parse().ok(),
+        }
+        .ok_or(format!("{term}: {limit} is not a number"))?;
+        Ok(Predicate::Compare(number, comparison, limit))
+    }
+
+    fn matches(&self, process: &BrtProcess) -> bool {
+        match self {
+            Predicate::Contains(text) => {
+                process.program.to_lowercase().contains(text)
+                    || process.command.to_lowercase().contains(text)
+            }
+            Predicate::User(user) => process.username().to_lowercase() == *user,
+            Predicate::Program(text) => process.program.to_lowercase().contains(text),
+            Predicate::Command(text) => process.command.to_lowercase().contains(text),
+            Predicate::State(state) => process.state == *state,
+            Predicate::Cgroup(path) => process.cgroup.as_ref().is_some_and(|cgroup| {
+                let cgroup = cgroup.to_lowercase();
+                path == "/"
+                    || cgroup
+                        .strip_prefix(path.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            }),
+            Predicate::Compare(number, comparison, limit) => {
+                comparison.holds(number.value(process), *limit)
+            }
+        }
+    }
+}
+
+/// E.g. `100M`, `1.5GiB` or `4096`, in bytes.
+fn parse_size(size: &str) -> Option<f64> {
+    let size = size.to_uppercase();
+    let size = size.trim_end_matches("IB").trim_end_matches('B');
+    let (number, unit) = match size.char_indices().last()? {
+        (i, 'K') => (&size[..i], 1u64 << 10),
+        (i, 'M') => (&size[..i], 1 << 20),
+        (i, 'G') => (&size[..i], 1 << 30),
+        (i, 'T') => (&size[..i], 1 << 40),
+        _ => (size, 1),
+    };
+    Some(number.parse::<f64>().ok()? * unit as f64)
+}
+
+/// A parsed filter expression; the empty one matches every process.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    predicates: Vec<Predicate>,
+}
+
+impl Filter {
+    /// Parses an expression, or tells which term is wrong.
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let predicates = expression
+            .split_whitespace()
+            .map(Predicate::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { predicates })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.predicates.is_empty()
+    }
+
+    pub fn matches(&self, process: &BrtProcess) -> bool {
+        self.predicates
+            .iter()
+            .all(|predicate| predicate.matches(process))
+    }
+
+    /// Whether it looks at the cgroups, which are only read then.
+    pub fn needs_cgroup(&self) -> bool {
+        self.predicates
+            .iter()
+            .any(|predicate| matches!(predicate, Predicate::Cgroup(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(program: &str, cpu: f64, resident_memory: u64) -> BrtProcess {
+        BrtProcess {
+            pid: 42,
+            program: program.to_string(),
+            command: format!("/usr/bin/{program} --verbose"),
+            uid: Some(0),
+            cpu,
+            resident_memory,
+            state: 'S',
+            ..BrtProcess::new()
+        }
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096"), Some(4096.0));
+        assert_eq!(parse_size("100M"), Some(100.0 * 1024.0 * 1024.0));
+        assert_eq!(parse_size("1.5GiB"), Some(1.5 * 1024.0 * 1024.0 * 1024.0));
+        assert_eq!(parse_size("2kb"), Some(2048.0));
+        assert_eq!(parse_size("lots"), None);
+    }
+
+    #[test]
+    fn test_filter() {
+        let java = process("java", 12.5, 2 << 30);
+        let bash = process("bash", 0.1, 4 << 20);
+        let filter = Filter::parse("user:root cpu>5 mem>100M").unwrap();
+        assert!(filter.matches(&java));
+        assert!(!filter.matches(&bash));
+
+        let filter = Filter::parse("VERBOSE state:s pid=42 threads<=0 nice>=0").unwrap();
+        assert!(filter.matches(&java) && filter.matches(&bash));
+        assert!(!Filter::parse("name:bash").unwrap().matches(&java));
+        assert!(!Filter::parse("cmd:--quiet").unwrap().matches(&java));
+
+        let empty = Filter::parse("  ").unwrap();
+        assert!(empty.is_empty());
+        assert!(empty.matches(&bash));
+    }
+
+    #[test]
+    fn test_filter_cgroup() {
+        let nginx = BrtProcess {
+            cgroup: Some("/system.slice/nginx.service".to_string()),
+            ..process("nginx", 1.0, 8 << 20)
+        };
+        let filter = Filter::parse("cgroup:/system.slice").unwrap();
+        assert!(filter.needs_cgroup());
+        assert!(filter.matches(&nginx));
+        assert!(Filter::parse("cgroup:system.slice/nginx.service/")
+            .unwrap()
+            .matches(&nginx));
+        assert!(Filter::parse("cgroup:/").unwrap().matches(&nginx));
+        assert!(!Filter::parse("cgroup:/system").unwrap().matches(&nginx));
+        // the cgroup wasn't read
+        assert!(!filter.matches(&process("nginx", 1.0, 8 << 20)));
+        assert!(!Filter::parse("cpu>5").unwrap().needs_cgroup());
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        assert_eq!(
+            Filter::parse("cpu>5 color:red"),
+            Err("color:red: unknown field color".to_string())
+        );
+        assert_eq!(
+            Filter::parse("size>5"),
+            Err("size>5: unknown field size".to_string())
+        );
+        assert_eq!(
+            Filter::parse("mem>lots"),
+            Err("mem>lots: lots is not a number".to_string())
+        );
+        assert_eq!(
+            Filter::parse("cpu:5"),
+            Err("cpu:5: unknown field cpu".to_string())
+        );
+    }
+}
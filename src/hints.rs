@@ -0,0 +1,145 @@
+//! The line of keys at the bottom of the screen, like the function key bar
+//! of htop: what the focused component does in its current state, followed
+//! by the global keybindings of the mode.
+
+use std::collections::HashMap;
+
+use crossterm::event::KeyEvent;
+use ratatui::prelude::*;
+
+use crate::action::Action;
+use crate::config::key_event_to_string;
+
+/// A key and what it does, e.g. `f filter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub key: String,
+    pub label: &'static str,
+}
+
+impl Hint {
+    pub fn new(key: impl Into<String>, label: &'static str) -> Self {
+        Self {
+            key: key.into(),
+            label,
+        }
+    }
+}
+
+/// The label of a globally bound action worth a hint; the others, like
+/// selecting a particular tab, would only crowd the line.
+fn label(action: &Action) -> Option<&'static str> {
+    let label = match action {
+        Action::Help | Action::ToggleShowHelp => "help",
+        Action::NextTab => "next tab",
+        Action::ToggleLogs => "logs",
+        Action::ToggleMemoryDetails => "memory",
+        Action::RunCommand(_) => "run",
+        Action::Suspend => "suspend",
+        Action::Quit => "quit",
+        _ => return None,
+    };
+    Some(label)
+}
+
+/// A hint for each labelled action of `keymap`, in the order of [`label`],
+/// with the shortest of the key sequences bound to it.
+pub fn binding_hints(keymap: &HashMap<Vec<KeyEvent>, Action>) -> Vec<Hint> {
+    let mut bindings: Vec<(&'static str, String)> = keymap
+        .iter()
+        .filter_map(|(keys, action)| {
+            let keys: Vec<_> = keys.iter().map(key_event_to_string).collect();
+            Some((label(action)?, keys.join(" ")))
+        })
+        .collect();
+    // ties are broken by the key so that the line doesn't change between
+    // frames with the iteration order of the map
+    bindings.sort_by(|(a, a_keys), (b, b_keys)| {
+        rank(a)
+            .cmp(&rank(b))
+            .then(a_keys.len().cmp(&b_keys.len()))
+            .then(a_keys.cmp(b_keys))
+    });
+    bindings.dedup_by_key(|(label, _)| *label);
+    bindings
+        .into_iter()
+        .map(|(label, key)| Hint::new(key, label))
+        .collect()
+}
+
+/// The position of `label` on the line.
+fn rank(label: &str) -> usize {
+    const ORDER: [&str; 7] = [
+        "help", "next tab", "logs", "memory", "run", "suspend", "quit",
+    ];
+    ORDER
+        .iter()
+        .position(|l| *l == label)
+        .unwrap_or(ORDER.len())
+}
+
+/// The hints that fit in `width`, keys highlighted; a hint that doesn't fit
+/// is left out with the ones after it rather than cut.
+pub fn line(hints: &[Hint], width: u16) -> Line<'static> {
+    let key_style = Style::default().add_modifier(Modifier::REVERSED);
+    let mut spans = vec![];
+    let mut used = 0;
+    for hint in hints {
+        let label = format!(" {} ", hint.label);
+        let length = hint.key.chars().count() + label.chars().count();
+        if used + length > usize::from(width) {
+            break;
+        }
+        used += length;
+        spans.push(Span::styled(hint.key.clone(), key_style));
+        spans.push(Span::raw(label));
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_key_sequence;
+
+    fn keymap(bindings: &[(&str, Action)]) -> HashMap<Vec<KeyEvent>, Action> {
+        bindings
+            .iter()
+            .map(|(keys, action)| (parse_key_sequence(keys).unwrap(), action.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_binding_hints() {
+        let keymap = keymap(&[
+            ("<Ctrl-c>", Action::Quit),
+            ("<q>", Action::Quit),
+            ("<Ctrl-d>", Action::Quit),
+            ("<Alt-1>", Action::SelectTab(1)),
+            ("<Tab>", Action::NextTab),
+            ("<l>", Action::ToggleLogs),
+        ]);
+        assert_eq!(
+            binding_hints(&keymap),
+            vec![
+                Hint::new("tab", "next tab"),
+                Hint::new("l", "logs"),
+                Hint::new("q", "quit"),
+            ]
+        );
+        assert_eq!(binding_hints(&HashMap::new()), vec![]);
+    }
+
+    #[test]
+    fn test_line() {
+        let hints = [Hint::new("/", "search"), Hint::new("q", "quit")];
+        assert_eq!(line(&hints, 80).to_string(), "/ search q quit ");
+        assert_eq!(
+            line(&hints, 80).spans[0].style,
+            Style::default().add_modifier(Modifier::REVERSED)
+        );
+        // the second hint needs 7 more columns
+        assert_eq!(line(&hints, 14).to_string(), "/ search ");
+        assert_eq!(line(&hints, 3), Line::default());
+    }
+}
@@ -0,0 +1,254 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+/// The locale chosen at startup; English until [`init`] is called.
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// The languages brt is translated to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    English,
+    Dutch,
+    German,
+    French,
+}
+
+/// The strings shown in the interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    Pid,
+    Program,
+    Command,
+    Threads,
+    User,
+    Memory,
+    Cpu,
+    Started,
+    Elapsed,
+    Nice,
+    Priority,
+    State,
+    Received,
+    Sent,
+    Capabilities,
+    OomScore,
+    OomScoreAdj,
+    ContextSwitches,
+    /// The units of a duration, e.g. `3d4h`.
+    Days,
+    Hours,
+    Minutes,
+    Seconds,
+    Battery,
+}
+
+/// The language of the interface and the locale of dates and times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    pub language: Language,
+    pub time: chrono::Locale,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            language: Language::English,
+            time: chrono::Locale::POSIX,
+        }
+    }
+}
+
+impl Locale {
+    /// Parses a locale like `nl_NL.UTF-8`; unknown locales, `C` and `POSIX`
+    /// are English.
+    pub fn parse(name: &str) -> Self {
+        let name = name.split(['.', '@']).next().unwrap_or_default();
+        let language = match name.split('_').next().unwrap_or_default() {
+            "nl" => Language::Dutch,
+            "de" => Language::German,
+            "fr" => Language::French,
+            _ => Language::English,
+        };
+        Self {
+            language,
+            time: chrono::Locale::try_from(name).unwrap_or(chrono::Locale::POSIX),
+        }
+    }
+
+    /// The configured locale, or the one of the environment.
+    pub fn select(configured: Option<&str>) -> Self {
+        configured
+            .map(str::to_string)
+            .or_else(|| {
+                ["LC_ALL", "LC_MESSAGES", "LANG"]
+                    .into_iter()
+                    .filter_map(|name| std::env::var(name).ok())
+                    .find(|value| !value.is_empty())
+            })
+            .map(|name| Self::parse(&name))
+            .unwrap_or_default()
+    }
+
+    pub fn text(&self, text: Text) -> &'static str {
+        let translated = match self.language {
+            Language::English => None,
+            Language::Dutch => dutch(text),
+            Language::German => german(text),
+            Language::French => french(text),
+        };
+        translated.unwrap_or_else(|| english(text))
+    }
+
+    /// Whether times are written with AM and PM, e.g. in `en_US`.
+    pub fn uses_12_hour_clock(&self) -> bool {
+        let afternoon = chrono::Utc.from_utc_datetime(&NaiveDateTime::new(
+            NaiveDate::default(),
+            NaiveTime::from_hms_opt(13, 0, 0).unwrap_or_default(),
+        ));
+        !afternoon
+            .format_localized("%X", self.time)
+            .to_string()
+            .contains("13")
+    }
+
+    /// Formats `time` in this locale, with `%p` and the like translated.
+    pub fn format_time<Tz: TimeZone>(&self, time: &DateTime<Tz>, format: &str) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        time.format_localized(format, self.time).to_string()
+    }
+}
+
+/// Chooses the locale from `configured` or the environment; only the first
+/// call has an effect.
+pub fn init(configured: Option<&str>) {
+    let _ = LOCALE.set(Locale::select(configured));
+}
+
+pub fn locale() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+}
+
+/// `text` in the language of the interface.
+pub fn tr(text: Text) -> &'static str {
+    locale().text(text)
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        Text::Pid => "Pid:",
+        Text::Program => "Program:",
+        Text::Command => "Command:",
+        Text::Threads => "Threads:",
+        Text::User => "User:",
+        Text::Memory => "MemB",
+        Text::Cpu => "Cpu%",
+        Text::Started => "Started:",
+        Text::Elapsed => "Elapsed:",
+        Text::Nice => "Ni:",
+        Text::Priority => "Pri:",
+        Text::State => "S",
+        Text::Received => "Rx/s",
+        Text::Sent => "Tx/s",
+        Text::Capabilities => "Cap",
+        Text::OomScore => "OOM",
+        Text::OomScoreAdj => "Adj",
+        Text::ContextSwitches => "Csw/s",
+        Text::Days => "d",
+        Text::Hours => "h",
+        Text::Minutes => "m",
+        Text::Seconds => "s",
+        Text::Battery => "BAT",
+    }
+}
+
+fn dutch(text: Text) -> Option<&'static str> {
+    Some(match text {
+        Text::Program => "Programma:",
+        Text::Command => "Commando:",
+        Text::User => "Gebruiker:",
+        Text::Started => "Gestart:",
+        Text::Elapsed => "Looptijd:",
+        Text::Hours => "u",
+        Text::Battery => "ACCU",
+        _ => return None,
+    })
+}
+
+fn german(text: Text) -> Option<&'static str> {
+    Some(match text {
+        Text::Program => "Programm:",
+        Text::Command => "Befehl:",
+        Text::User => "Benutzer:",
+        Text::Cpu => "CPU%",
+        Text::Started => "Gestartet:",
+        Text::Elapsed => "Laufzeit:",
+        Text::Days => "T",
+        Text::Battery => "AKKU",
+        _ => return None,
+    })
+}
+
+fn french(text: Text) -> Option<&'static str> {
+    Some(match text {
+        Text::Program => "Programme:",
+        Text::Command => "Commande:",
+        Text::User => "Utilisateur:",
+        Text::Memory => "Mém",
+        Text::Cpu => "CPU%",
+        Text::Started => "Démarré:",
+        Text::Elapsed => "Durée:",
+        Text::State => "É",
+        Text::Days => "j",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let dutch = Locale::parse("nl_NL.UTF-8");
+        assert_eq!(dutch.language, Language::Dutch);
+        assert_eq!(dutch.time, chrono::Locale::nl_NL);
+        assert_eq!(Locale::parse("de_DE@euro").language, Language::German);
+        assert_eq!(Locale::parse("C.UTF-8"), Locale::default());
+        assert_eq!(Locale::parse("xx_YY").language, Language::English);
+        assert_eq!(Locale::select(Some("fr_FR")).language, Language::French);
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(Locale::default().text(Text::Program), "Program:");
+        assert_eq!(Locale::parse("nl_NL").text(Text::Program), "Programma:");
+        // untranslated strings are English
+        assert_eq!(Locale::parse("nl_NL").text(Text::Pid), "Pid:");
+        assert_eq!(Locale::parse("de_DE").text(Text::Battery), "AKKU");
+        assert_eq!(Locale::parse("fr_FR").text(Text::Days), "j");
+    }
+
+    #[test]
+    fn test_clock() {
+        let time = NaiveDate::from_ymd_opt(2024, 5, 17)
+            .unwrap()
+            .and_hms_milli_opt(14, 5, 9, 250)
+            .unwrap()
+            .and_local_timezone(Local)
+            .unwrap();
+        let english = Locale::default();
+        assert!(!english.uses_12_hour_clock());
+        assert_eq!(english.format_time(&time, "%H:%M:%S%.3f"), "14:05:09.250");
+        let american = Locale::parse("en_US.UTF-8");
+        assert!(american.uses_12_hour_clock());
+        assert_eq!(american.format_time(&time, "%I:%M:%S %p"), "02:05:09 PM");
+        let german = Locale::parse("de_DE");
+        assert_eq!(german.format_time(&time, "%a %H:%M"), "Fr 14:05");
+    }
+}
@@ -0,0 +1,279 @@
+//! What the systemd unit of a process logged, read from the journal through
+//! `journalctl` when brt is built with the `journald` feature.
+//!
+//! `journalctl` is spawned rather than the journal read with `sd-journal`, so
+//! brt doesn't link to `libsystemd` and keeps running where there is none,
+//! and the journal's own rules decide which entries a user may see. Its JSON
+//! output is parsed by [`read_json`], for the kernel log as well.
+//!
+//! The units of a user are matched on `_SYSTEMD_USER_UNIT=` and `_UID=`
+//! rather than with `--user-unit`, which only finds the units of the user
+//! running `journalctl` and so not those of other users, even for root.
+
+use std::fmt::Debug;
+use std::io;
+
+use chrono::{Local, TimeZone};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{sanitize, truncate};
+
+/// The width the messages are cut at.
+const MESSAGE_WIDTH: usize = 100;
+
+/// The systemd unit a process runs in, e.g. `nginx.service`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Unit {
+    pub name: String,
+    /// The uid of the user whose service manager runs it, or `None` for the
+    /// service manager of the system.
+    pub user: Option<u32>,
+}
+
+impl Unit {
+    /// The innermost service or scope of `cgroup`, e.g. `nginx.service` of
+    /// `/system.slice/nginx.service`, or `None` outside of any.
+    pub fn from_cgroup(cgroup: &str) -> Option<Self> {
+        let name = cgroup
+            .split('/')
+            .rev()
+            .find(|part| part.ends_with(".service") || part.ends_with(".scope"))?;
+        // the units below `user@1000.service` are those of the user manager
+        let user = cgroup
+            .split('/')
+            .filter(|part| *part != name)
+            .find_map(|part| part.strip_prefix("user@")?.strip_suffix(".service"))
+            .and_then(|uid| uid.parse().ok());
+        Some(Self {
+            name: name.to_string(),
+            user,
+        })
+    }
+}
+
+/// An entry of the journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// From 0 for emergencies to 7 for debugging, as in syslog.
+    pub priority: u8,
+    /// Microseconds since the epoch.
+    pub timestamp: u64,
+    pub text: String,
+}
+
+impl Entry {
+    /// E.g. `14:02:11 Started nginx.`, red for errors, yellow for warnings
+    /// and dimmed for debugging, as in the kernel log.
+    pub fn line(&self) -> Line<'static> {
+        let style = match self.priority {
+            0..=3 => Style::default().fg(Color::Red),
+            4 => Style::default().fg(Color::Yellow),
+            7 => Style::default().dim(),
+            _ => Style::default(),
+        };
+        let time = i64::try_from(self.timestamp / 1_000_000)
+            .ok()
+            .and_then(|seconds| Local.timestamp_opt(seconds, 0).single())
+            .map_or("--:--:--".to_string(), |time| {
+                time.format("%H:%M:%S").to_string()
+            });
+        Line::from(vec![
+            Span::raw(format!("{time} ")).dim(),
+            Span::styled(truncate(&sanitize(&self.text), MESSAGE_WIDTH), style),
+        ])
+    }
+}
+
+/// The fields brt reads of an entry of `journalctl --output=json`.
+#[cfg(feature = "journald")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonEntry {
+    /// From 0 for emergencies to 7 for debugging; 6 when missing.
+    pub priority: u8,
+    /// Microseconds since the epoch.
+    pub realtime: Option<u64>,
+    /// Microseconds since boot.
+    pub monotonic: Option<u64>,
+    /// Where to read on from with `--after-cursor`.
+    pub cursor: Option<String>,
+    pub message: String,
+}
+
+/// Parses an entry of `journalctl --output=json`.
+#[cfg(feature = "journald")]
+pub fn parse_json(entry: &str) -> Option<JsonEntry> {
+    let entry: serde_json::Value = serde_json::from_str(entry).ok()?;
+    let field = |name: &str| entry.get(name)?.as_str();
+    Some(JsonEntry {
+        priority: field("PRIORITY").and_then(|p| p.parse().ok()).unwrap_or(6),
+        realtime: field("__REALTIME_TIMESTAMP").and_then(|t| t.parse().ok()),
+        monotonic: field("__MONOTONIC_TIMESTAMP").and_then(|t| t.parse().ok()),
+        cursor: field("__CURSOR").map(str::to_string),
+        // messages that aren't valid UTF-8 are arrays of bytes
+        message: field("MESSAGE").unwrap_or_default().to_string(),
+    })
+}
+
+/// Runs `journalctl` with `args` and parses the entries it prints, failing
+/// with what it printed on stderr when it fails.
+#[cfg(feature = "journald")]
+pub fn read_json<I, S>(args: I) -> io::Result<Vec<JsonEntry>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    let output = std::process::Command::new("journalctl")
+        .args(args)
+        .args(["--output=json", "--no-pager"])
+        .output()?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(error.trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_json)
+        .collect())
+}
+
+/// The `journalctl` arguments that select the entries of `unit`.
+#[cfg(feature = "journald")]
+fn unit_matches(unit: &Unit) -> Vec<String> {
+    match unit.user {
+        Some(uid) => vec![
+            format!("_SYSTEMD_USER_UNIT={}", unit.name),
+            format!("_UID={uid}"),
+        ],
+        None => vec![format!("--unit={}", unit.name)],
+    }
+}
+
+/// Something that tells the latest entries of a unit.
+pub trait UnitJournal: Debug + Send + Sync {
+    /// The last `lines` entries of `unit`, the oldest first; which can take
+    /// seconds on a large journal.
+    fn tail(&self, unit: &Unit, lines: usize) -> io::Result<Vec<Entry>>;
+}
+
+/// Reads the journal with `journalctl`, which shows the entries of other
+/// users to members of the `systemd-journal` group and to root only.
+#[derive(Debug, Default)]
+pub struct Journalctl;
+
+impl UnitJournal for Journalctl {
+    #[cfg(feature = "journald")]
+    fn tail(&self, unit: &Unit, lines: usize) -> io::Result<Vec<Entry>> {
+        let mut args = unit_matches(unit);
+        args.push(format!("--lines={lines}"));
+        Ok(read_json(args)?
+            .into_iter()
+            .filter_map(|entry| {
+                Some(Entry {
+                    priority: entry.priority,
+                    timestamp: entry.realtime?,
+                    text: entry.message,
+                })
+            })
+            .collect())
+    }
+
+    #[cfg(not(feature = "journald"))]
+    fn tail(&self, _unit: &Unit, _lines: usize) -> io::Result<Vec<Entry>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "brt is built without the journald feature",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_from_cgroup() {
+        let unit = |name: &str, user: Option<u32>| {
+            Some(Unit {
+                name: name.to_string(),
+                user,
+            })
+        };
+        assert_eq!(
+            Unit::from_cgroup("/system.slice/nginx.service"),
+            unit("nginx.service", None)
+        );
+        assert_eq!(
+            Unit::from_cgroup("/system.slice/docker-1a2b.scope/init"),
+            unit("docker-1a2b.scope", None)
+        );
+        assert_eq!(
+            Unit::from_cgroup(
+                "/user.slice/user-1000.slice/user@1000.service/app.slice/syncthing.service"
+            ),
+            unit("syncthing.service", Some(1000))
+        );
+        assert_eq!(
+            Unit::from_cgroup("/user.slice/user-1000.slice/user@1000.service"),
+            unit("user@1000.service", None)
+        );
+        assert_eq!(Unit::from_cgroup("/"), None);
+        assert_eq!(Unit::from_cgroup("/docker/web"), None);
+    }
+
+    #[cfg(feature = "journald")]
+    #[test]
+    fn test_parse_json() {
+        let entry = r#"{"__CURSOR":"s=1;i=2","__REALTIME_TIMESTAMP":"1700000000000000","__MONOTONIC_TIMESTAMP":"812345678","PRIORITY":"4","MESSAGE":"Slow request"}"#;
+        assert_eq!(
+            parse_json(entry),
+            Some(JsonEntry {
+                priority: 4,
+                realtime: Some(1_700_000_000_000_000),
+                monotonic: Some(812_345_678),
+                cursor: Some("s=1;i=2".to_string()),
+                message: "Slow request".to_string(),
+            })
+        );
+        let bytes = r#"{"MESSAGE":[104,105]}"#;
+        assert_eq!(
+            parse_json(bytes),
+            Some(JsonEntry {
+                priority: 6,
+                realtime: None,
+                monotonic: None,
+                cursor: None,
+                message: String::new(),
+            })
+        );
+        assert_eq!(parse_json("not json"), None);
+    }
+
+    #[cfg(feature = "journald")]
+    #[test]
+    fn test_unit_matches() {
+        let unit = |user| Unit {
+            name: "syncthing.service".to_string(),
+            user,
+        };
+        assert_eq!(unit_matches(&unit(None)), ["--unit=syncthing.service"]);
+        assert_eq!(
+            unit_matches(&unit(Some(1000))),
+            ["_SYSTEMD_USER_UNIT=syncthing.service", "_UID=1000"]
+        );
+    }
+
+    #[test]
+    fn test_entry_line() {
+        let entry = Entry {
+            priority: 4,
+            timestamp: 1_700_000_000_000_000,
+            text: "Slow\trequest".to_string(),
+        };
+        let line = entry.line();
+        // the time is local
+        assert!(line.to_string().ends_with(" Slow\\trequest"));
+        assert_eq!(line.spans[1].style.fg, Some(Color::Yellow));
+    }
+}
@@ -0,0 +1,229 @@
+//! The layout of the panes when it is configured instead of the default
+//! one, written as nested rows and columns, e.g.
+//! `rows(header, columns(main, side 30%), footer)`.
+//!
+//! Every part can be followed by its size: a percentage of its group, or a
+//! number of rows or columns. Without one the header and the footer take a
+//! row per component and anything else shares what is left.
+
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+use crate::app::Pane;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Size {
+    Percentage(u16),
+    Length(u16),
+}
+
+/// A pane, or a group of parts next to or below each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Pane(Pane),
+    Rows(Vec<(Node, Option<Size>)>),
+    Columns(Vec<(Node, Option<Size>)>),
+}
+
+impl Node {
+    /// The header, main, side and footer panes in `area`, with `headers` and
+    /// `footers` the number of components in them; the panes left out of the
+    /// layout are empty, and their components aren't drawn.
+    pub fn panes(&self, area: Rect, headers: u16, footers: u16) -> [Rect; 4] {
+        let mut panes = [Rect::default(); 4];
+        self.split(area, headers, footers, &mut panes);
+        panes
+    }
+
+    fn split(&self, area: Rect, headers: u16, footers: u16, panes: &mut [Rect; 4]) {
+        let (direction, parts) = match self {
+            Node::Pane(pane) => {
+                panes[*pane as usize] = area;
+                return;
+            }
+            Node::Rows(parts) => (Direction::Vertical, parts),
+            Node::Columns(parts) => (Direction::Horizontal, parts),
+        };
+        let constraints = parts.iter().map(|(node, size)| match (size, node) {
+            (Some(Size::Percentage(percentage)), _) => Constraint::Percentage(*percentage),
+            (Some(Size::Length(length)), _) => Constraint::Length(*length),
+            (None, Node::Pane(Pane::Header)) => Constraint::Length(headers),
+            (None, Node::Pane(Pane::Footer)) => Constraint::Length(footers),
+            (None, _) => Constraint::Fill(1),
+        });
+        let areas = Layout::new(direction, constraints).split(area);
+        for ((node, _), area) in parts.iter().zip(areas.iter()) {
+            node.split(*area, headers, footers, panes);
+        }
+    }
+}
+
+impl FromStr for Node {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            text: s,
+            chars: s.char_indices().peekable(),
+        };
+        let node = parser.node()?;
+        parser.skip_whitespace();
+        match parser.chars.peek() {
+            None => Ok(node),
+            Some((at, c)) => Err(format!("Unexpected `{c}` at {at} in the layout")),
+        }
+    }
+}
+
+/// Reads a layout from left to right.
+struct Parser<'a> {
+    text: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    /// The longest run of characters matching `accept`.
+    fn word(&mut self, accept: fn(char) -> bool) -> &'a str {
+        self.skip_whitespace();
+        let start = self.chars.peek().map_or(self.text.len(), |(at, _)| *at);
+        let mut end = start;
+        while let Some((at, c)) = self.chars.next_if(|(_, c)| accept(*c)) {
+            end = at + c.len_utf8();
+        }
+        &self.text[start..end]
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((at, c)) => Err(format!("Expected `{expected}` at {at} but got `{c}`")),
+            None => Err(format!("Expected `{expected}` at the end of the layout")),
+        }
+    }
+
+    fn node(&mut self) -> Result<Node, String> {
+        let name = self.word(|c| c.is_ascii_alphabetic()).to_lowercase();
+        let pane = match name.as_str() {
+            "rows" => return Ok(Node::Rows(self.parts()?)),
+            "columns" => return Ok(Node::Columns(self.parts()?)),
+            "header" => Pane::Header,
+            "main" => Pane::Main,
+            "side" => Pane::Side,
+            "footer" => Pane::Footer,
+            "" => return Err("Expected a pane, rows or columns in the layout".to_string()),
+            _ => return Err(format!("Unknown pane `{name}` in the layout")),
+        };
+        Ok(Node::Pane(pane))
+    }
+
+    /// The parts of a group, between parentheses and separated by commas.
+    fn parts(&mut self) -> Result<Vec<(Node, Option<Size>)>, String> {
+        self.expect('(')?;
+        let mut parts = vec![];
+        loop {
+            let node = self.node()?;
+            parts.push((node, self.size()?));
+            self.skip_whitespace();
+            if self.chars.next_if(|(_, c)| *c == ',').is_none() {
+                break;
+            }
+        }
+        self.expect(')')?;
+        Ok(parts)
+    }
+
+    fn size(&mut self) -> Result<Option<Size>, String> {
+        let digits = self.word(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return Ok(None);
+        }
+        let number: u16 = digits
+            .parse()
+            .map_err(|_| format!("The size {digits} in the layout is too large"))?;
+        if self.chars.next_if(|(_, c)| *c == '%').is_some() {
+            if number > 100 {
+                return Err(format!("The size {number}% in the layout is over 100%"));
+            }
+            return Ok(Some(Size::Percentage(number)));
+        }
+        Ok(Some(Size::Length(number)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let layout: Node = "rows(header, columns(main, Side 30%), footer 2)"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            layout,
+            Node::Rows(vec![
+                (Node::Pane(Pane::Header), None),
+                (
+                    Node::Columns(vec![
+                        (Node::Pane(Pane::Main), None),
+                        (Node::Pane(Pane::Side), Some(Size::Percentage(30))),
+                    ]),
+                    None
+                ),
+                (Node::Pane(Pane::Footer), Some(Size::Length(2))),
+            ])
+        );
+        assert_eq!("main".parse(), Ok(Node::Pane(Pane::Main)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |layout: &str| layout.parse::<Node>().unwrap_err();
+        assert_eq!(error("rows(main"), "Expected `)` at the end of the layout");
+        assert_eq!(error("rows(main side)"), "Expected `)` at 10 but got `s`");
+        assert_eq!(
+            error("columns(main, top)"),
+            "Unknown pane `top` in the layout"
+        );
+        assert_eq!(
+            error("rows(main 120%)"),
+            "The size 120% in the layout is over 100%"
+        );
+        assert_eq!(error("main)"), "Unexpected `)` at 4 in the layout");
+        assert_eq!(error(""), "Expected a pane, rows or columns in the layout");
+    }
+
+    #[test]
+    fn test_panes() {
+        let layout: Node = "columns(side 40, rows(header, main, footer))"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            layout.panes(Rect::new(0, 0, 100, 30), 2, 1),
+            [
+                Rect::new(40, 0, 60, 2),
+                Rect::new(40, 2, 60, 27),
+                Rect::new(0, 0, 40, 30),
+                Rect::new(40, 29, 60, 1),
+            ]
+        );
+        // without a side pane
+        let layout: Node = "rows(header, main 50%)".parse().unwrap();
+        assert_eq!(
+            layout.panes(Rect::new(0, 0, 100, 30), 1, 1),
+            [
+                Rect::new(0, 0, 100, 1),
+                Rect::new(0, 1, 100, 15),
+                Rect::default(),
+                Rect::default(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! brt as a library: the processes and their usage, the cpu, memory and
+//! network counters of the system, the widgets and components that draw them
+//! and the app that lays them out, for the `brt` and `processbar` binaries,
+//! the integration tests and other tools to reuse.
+
+pub mod action;
+pub mod app;
+pub mod cadence;
+pub mod capabilities;
+pub mod clipboard;
+pub mod collector;
+pub mod components;
+pub mod config;
+pub mod control;
+pub mod filter;
+pub mod hints;
+pub mod i18n;
+pub mod journal;
+pub mod layout;
+pub mod listing;
+pub mod logging;
+pub mod model;
+pub mod network;
+pub mod proc_events;
+pub mod recording;
+pub mod rpc;
+pub mod session;
+pub mod snapshot;
+pub mod source;
+pub mod staleness;
+pub mod statusline;
+pub mod symbols;
+pub mod system;
+pub mod text_dump;
+pub mod tui;
+pub mod utils;
+pub mod widgets;
@@ -0,0 +1,192 @@
+//! The ordered and filtered list of processes the process table shows, built
+//! on a thread of its own so sorting a long list never holds up the UI, which
+//! only swaps in the finished list.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use crate::components::process::Order::{
+    Capabilities, Command, ContextSwitches, Cpu, CpuRecent, Elapsed, Memory, Name, Nice,
+    NumberOfThreads, OomScore, OomScoreAdj, Pid, Priority, Received, Sent, Started, State, User,
+};
+use crate::components::process::SortKey;
+use crate::filter::Filter;
+use crate::model::BrtProcess;
+
+/// What a list is built from.
+#[derive(Debug, Clone)]
+pub struct Job {
+    /// The current and recently exited processes, in no particular order.
+    pub processes: Vec<BrtProcess>,
+    pub filter: Filter,
+    /// The sorted column first, then the tie breakers that can change the
+    /// order.
+    pub keys: Vec<SortKey>,
+    /// The number of cpu samples averaged for the recent cpu usage.
+    pub recent_cpu_samples: usize,
+}
+
+impl Job {
+    /// The processes that pass the filter, ordered by the keys.
+    pub fn list(self) -> Vec<BrtProcess> {
+        let mut processes: Vec<BrtProcess> = self
+            .processes
+            .into_iter()
+            .filter(|process| self.filter.matches(process))
+            .collect();
+        // the sorts are stable, so sorting by the last tie breaker first
+        // leaves the ties of each sort in the order of the ones before
+        for key in self.keys.iter().rev() {
+            sort_by(&mut processes, *key, self.recent_cpu_samples);
+        }
+        processes
+    }
+}
+
+fn sort_by(processes: &mut [BrtProcess], key: SortKey, recent_cpu_samples: usize) {
+    // reversed around the sort, the equal values stay in their order
+    if key.descending {
+        processes.reverse();
+    }
+    match key.order {
+        Pid => processes.sort_by_key(|a| a.pid),
+        Name => processes.sort_by(|a, b| a.program.cmp(&b.program)),
+        Command => processes.sort_by(|a, b| a.command.cmp(&b.command)),
+        NumberOfThreads => processes.sort_by_key(|a| a.number_of_threads),
+        User => processes.sort_by_cached_key(|a| a.username()),
+        Memory => processes.sort_by_key(|a| a.resident_memory),
+        Cpu => processes.sort_by(|a, b| a.cpu.total_cmp(&b.cpu)),
+        CpuRecent => processes.sort_by(|a, b| {
+            a.recent_cpu(recent_cpu_samples)
+                .total_cmp(&b.recent_cpu(recent_cpu_samples))
+        }),
+        Started => processes.sort_by_key(|a| a.start_time),
+        Elapsed => processes.sort_by_key(|a| a.elapsed()),
+        Nice => processes.sort_by_key(|a| a.nice),
+        Priority => processes.sort_by_key(|a| a.priority),
+        State => processes.sort_by_key(|a| a.state),
+        Received => processes.sort_by_key(|a| a.received),
+        Sent => processes.sort_by_key(|a| a.sent),
+        // the most privileged processes end up last
+        Capabilities => processes.sort_by_key(|a| (a.capabilities.count_ones(), a.capabilities)),
+        OomScore => processes.sort_by_key(|a| a.oom_score),
+        OomScoreAdj => processes.sort_by_key(|a| a.oom_score_adj),
+        ContextSwitches => processes.sort_by_key(|a| a.context_switch_rate),
+    }
+    if key.descending {
+        processes.reverse();
+    }
+}
+
+/// The thread building the lists, and the lists it built, numbered in the
+/// order they were asked for.
+#[derive(Debug)]
+pub struct Listing {
+    jobs: Sender<(u64, Job)>,
+    lists: Receiver<(u64, Arc<Vec<BrtProcess>>)>,
+    /// The number of the list asked for last.
+    requested: u64,
+    /// The number of the list received last.
+    received: u64,
+}
+
+impl Default for Listing {
+    fn default() -> Self {
+        Self::spawn()
+    }
+}
+
+impl Listing {
+    /// Starts the thread, which stops once the listing is dropped.
+    pub fn spawn() -> Self {
+        let (jobs, pending) = mpsc::channel::<(u64, Job)>();
+        let (done, lists) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(mut job) = pending.recv() {
+                // a newer job makes the ones before it moot
+                while let Ok(newer) = pending.try_recv() {
+                    job = newer;
+                }
+                let (number, job) = job;
+                if done.send((number, Arc::new(job.list()))).is_err() {
+                    return;
+                }
+            }
+        });
+        Self {
+            jobs,
+            lists,
+            requested: 0,
+            received: 0,
+        }
+    }
+
+    /// Has the list built again.
+    pub fn request(&mut self, job: Job) {
+        self.requested += 1;
+        // the thread only stops once the listing is dropped
+        let _ = self.jobs.send((self.requested, job));
+    }
+
+    /// The latest of the lists built since the last call, if any.
+    pub fn latest(&mut self) -> Option<Arc<Vec<BrtProcess>>> {
+        let (number, list) = self.lists.try_iter().last()?;
+        self.received = number;
+        Some(list)
+    }
+
+    /// Waits for the list asked for last, unless it was received already.
+    pub fn wait(&mut self) -> Option<Arc<Vec<BrtProcess>>> {
+        while self.received < self.requested {
+            let (number, list) = self.lists.recv().ok()?;
+            self.received = number;
+            if number == self.requested {
+                return Some(list);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, cpu: f64, program: &str) -> BrtProcess {
+        BrtProcess {
+            pid,
+            cpu,
+            program: program.to_string(),
+            ..BrtProcess::default()
+        }
+    }
+
+    #[test]
+    fn test_listing() {
+        let key = |order, descending| SortKey { order, descending };
+        let job = Job {
+            processes: vec![
+                process(3, 1.0, "vim"),
+                process(1, 5.0, "bash"),
+                process(2, 1.0, "bash"),
+            ],
+            filter: Filter::default(),
+            keys: vec![key(Cpu, true), key(Name, false), key(Pid, true)],
+            recent_cpu_samples: 1,
+        };
+        let pids = |list: &[BrtProcess]| list.iter().map(|p| p.pid).collect::<Vec<_>>();
+        assert_eq!(pids(&job.clone().list()), [1, 2, 3]);
+
+        let mut listing = Listing::spawn();
+        listing.request(job.clone());
+        listing.request(Job {
+            filter: Filter::parse("cpu<5").unwrap(),
+            ..job
+        });
+        // the list asked for last, whether or not the first was built
+        assert_eq!(pids(&listing.wait().unwrap()), [2, 3]);
+        assert!(listing.wait().is_none());
+        assert!(listing.latest().is_none());
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::Result;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Metadata};
+use tracing_error::ErrorLayer;
+use tracing_subscriber::filter::{EnvFilter, FilterExt};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::{Context, Filter, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use crate::utils::{get_data_dir, LOG_ENV, LOG_FILE, LOG_LEVEL_ENV};
+
+/// The number of distinct messages remembered before old ones are forgotten.
+const MAX_TRACKED_MESSAGES: usize = 1024;
+/// Identical messages logged more often than this are dropped.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(10);
+const RATE_LIMIT_BURST: u32 = 3;
+
+/// The filter directives: `RUST_LOG` as is, or a level for the binaries and
+/// `brt_core` from `BRT_LOG_LEVEL` or `BRT_LOGLEVEL`, e.g. `debug`.
+fn directives(rust_log: Option<String>, level: Option<String>) -> String {
+    match (rust_log, level) {
+        (Some(directives), _) => directives,
+        (None, Some(level)) if level.contains('=') => level,
+        (None, level) => {
+            let level = level.as_deref().unwrap_or("info");
+            format!("brt={level},brt_core={level}")
+        }
+    }
+}
+
+/// Logs to `brt.log` in the data directory and, with the `journald` feature,
+/// to the journal. Events of the `log` crate are forwarded as well.
+pub fn initialize_logging() -> Result<()> {
+    let directory = get_data_dir();
+    std::fs::create_dir_all(directory.clone())?;
+    let log_file = std::fs::File::create(directory.join(LOG_FILE.clone()))?;
+    let level = std::env::var(LOG_LEVEL_ENV.clone())
+        .or_else(|_| std::env::var(LOG_ENV.clone()))
+        .ok();
+    let directives = directives(std::env::var("RUST_LOG").ok(), level);
+
+    let file_layer = tracing_subscriber::fmt::layer()
+        .with_file(true)
+        .with_line_number(true)
+        .with_writer(log_file)
+        .with_target(false)
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_filter(
+            EnvFilter::new(&directives).and(RateLimit::new(RATE_LIMIT_WINDOW, RATE_LIMIT_BURST)),
+        );
+
+    #[cfg(feature = "journald")]
+    let journald_layer = tracing_journald::layer()
+        .ok()
+        .map(|layer| layer.with_filter(EnvFilter::new(&directives)));
+    #[cfg(not(feature = "journald"))]
+    let journald_layer: Option<tracing_subscriber::layer::Identity> = None;
+
+    tracing_subscriber::registry()
+        .with(file_layer)
+        .with(journald_layer)
+        .with(ErrorLayer::default())
+        .init();
+    Ok(())
+}
+
+/// Drops repetitions of the same message: at most `burst` identical events
+/// are let through per `window`, so loops that log on every tick don't flood
+/// the log file.
+#[derive(Debug)]
+pub struct RateLimit {
+    window: Duration,
+    burst: u32,
+    seen: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimit {
+    pub fn new(window: Duration, burst: u32) -> Self {
+        Self {
+            window,
+            burst,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts an occurrence of `key` and tells whether it may be logged.
+    fn allow(&self, key: String, now: Instant) -> bool {
+        let Ok(mut seen) = self.seen.lock() else {
+            return true;
+        };
+        if seen.len() >= MAX_TRACKED_MESSAGES {
+            seen.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+        }
+        let (start, count) = seen.entry(key).or_insert((now, 0));
+        if now.duration_since(*start) >= self.window {
+            *start = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= self.burst
+    }
+}
+
+/// Renders all fields of an event, so identical messages get identical keys,
+/// also for the events forwarded from the `log` crate, which share a callsite.
+#[derive(Default)]
+struct Key(String);
+
+impl Visit for Key {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let _ = write!(self.0, "{}={:?};", field.name(), value);
+    }
+}
+
+impl<S> Filter<S> for RateLimit {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        let mut key = Key(format!("{}:", event.metadata().target()));
+        event.record(&mut key);
+        self.allow(key.0, Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directives() {
+        assert_eq!(directives(None, None), "brt=info,brt_core=info");
+        assert_eq!(
+            directives(None, Some("debug".to_string())),
+            "brt=debug,brt_core=debug"
+        );
+        assert_eq!(
+            directives(None, Some("brt=trace,procfs=warn".to_string())),
+            "brt=trace,procfs=warn"
+        );
+        assert_eq!(
+            directives(Some("warn".to_string()), Some("debug".to_string())),
+            "warn"
+        );
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let limit = RateLimit::new(Duration::from_secs(10), 2);
+        let start = Instant::now();
+        assert!(limit.allow("tick".to_string(), start));
+        assert!(limit.allow("tick".to_string(), start));
+        assert!(!limit.allow("tick".to_string(), start + Duration::from_secs(1)));
+        assert!(limit.allow("other".to_string(), start + Duration::from_secs(1)));
+        assert!(limit.allow("tick".to_string(), start + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_rate_limit_forgets_old_messages() {
+        let limit = RateLimit::new(Duration::from_secs(1), 1);
+        let start = Instant::now();
+        for i in 0..MAX_TRACKED_MESSAGES {
+            limit.allow(i.to_string(), start);
+        }
+        limit.allow("new".to_string(), start + Duration::from_secs(1));
+        assert_eq!(limit.seen.lock().unwrap().len(), 1);
+    }
+}
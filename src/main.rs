@@ -1,29 +1,86 @@
-pub mod action;
-pub mod app;
+pub mod bench;
 pub mod cli;
-pub mod components;
-pub mod config;
-pub mod model;
-pub mod tui;
-pub mod utils;
-pub mod widgets;
+
+pub use brt_core::{
+    action, app, cadence, capabilities, clipboard, collector, components, config, control, filter,
+    hints, i18n, journal, layout, listing, logging, model, network, proc_events, recording, rpc,
+    session, snapshot, source, staleness, statusline, symbols, system, text_dump, tui, utils,
+    widgets,
+};
+
+use std::io::Write;
+use std::time::Duration;
 
 use clap::Parser;
-use cli::Cli;
+use cli::{Cli, Command};
 use color_eyre::eyre::Result;
 
 use crate::{
     app::App,
-    utils::{initialize_logging, initialize_panic_handler},
+    components::battery::SystemPowerSource,
+    logging::initialize_logging,
+    recording::{ActionRecorder, ActionReplay},
+    snapshot::{Snapshot, CPU_SAMPLE_INTERVAL},
+    source::system_source,
+    utils::initialize_panic_handler,
 };
 
+#[cfg(any(test, feature = "bench"))]
+#[global_allocator]
+static ALLOCATOR: bench::CountingAllocator = bench::CountingAllocator;
+
 async fn tokio_main() -> Result<()> {
     initialize_logging()?;
 
     initialize_panic_handler()?;
 
     let args = Cli::parse();
-    let mut app = App::new(args.tick_rate, args.frame_rate, args.debug)?;
+    if let Some(Command::Bench { runs }) = args.command {
+        println!("{}", bench::run(system_source().as_ref(), runs));
+        return Ok(());
+    }
+    if args.statusline {
+        symbols::init(args.ascii);
+        let interval = args.interval.map(Duration::from_secs_f64);
+        return statusline::run(
+            system_source().as_ref(),
+            &mut SystemPowerSource::new(),
+            interval,
+            &mut std::io::stdout(),
+        );
+    }
+    if args.once {
+        let snapshot = Snapshot::take(
+            system_source().as_ref(),
+            &mut SystemPowerSource::new(),
+            CPU_SAMPLE_INTERVAL,
+        )?;
+        let output = snapshot.render(args.format)?;
+        if let Err(e) = writeln!(std::io::stdout(), "{output}") {
+            // a closed pipe, as in `brt --once | head`, is not an error
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+        return Ok(());
+    }
+
+    let mut app = App::new(
+        args.tick_rate,
+        args.frame_rate,
+        args.debug,
+        args.record,
+        args.replay,
+        args.ascii,
+        args.socket
+            .map(|path| path.unwrap_or_else(rpc::default_socket_path)),
+    )?;
+    if let Some(path) = args.record_actions {
+        app = app.with_action_recorder(ActionRecorder::new(&path)?);
+    }
+    if let Some(path) = args.replay_actions {
+        app = app.with_action_replay(ActionReplay::from_file(&path)?);
+    }
     app.run().await?;
 
     Ok(())
@@ -32,6 +89,7 @@ async fn tokio_main() -> Result<()> {
 #[tokio::main]
 async fn main() -> Result<()> {
     if let Err(e) = tokio_main().await {
+        tracing::error!("Exiting after an error: {e:?}");
         eprintln!("{} error: Something went wrong", env!("CARGO_PKG_NAME"));
         Err(e)
     } else {
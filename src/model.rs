@@ -1,161 +1,721 @@
 use battery::Battery;
+use chrono::{DateTime, Local};
 use humansize::{format_size, FormatSizeOptions, BINARY};
-use log::{debug, warn};
-use procfs::process::Process;
-use procfs::{ticks_per_second, CpuInfo, Current, Uptime};
-use ratatui::layout::Alignment;
-use ratatui::style::{Color, Style};
+use procfs_core::process::Stat;
+use procfs_core::ProcError;
+use ratatui::layout::{Alignment, Constraint};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::Line;
 use ratatui::widgets::{Cell, Row};
-use std::collections::{HashMap, VecDeque};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 use uzers::{get_user_by_uid, User};
 
+use crate::capabilities;
+use crate::i18n::{tr, Text};
+use crate::source::ProcSource;
+use crate::symbols::symbols;
+use crate::widgets::cpu_graph::CpuGraph;
+use crate::widgets::sparkline::MAX_DOTS;
+
 pub fn get_battery() -> Battery {
     let manager = battery::Manager::new().unwrap();
     manager.batteries().unwrap().next().unwrap().unwrap()
 }
 
-pub fn create_rows<'a>(processes: &Vec<BrtProcess>) -> Vec<Row<'a>> {
-    let mut rows = Vec::new();
-    for process in processes {
-        let row = create_row(process);
-        rows.push(row);
-    }
-    rows
+/// A column of the process table.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Column {
+    Pid,
+    Program,
+    Command,
+    Threads,
+    User,
+    Memory,
+    CpuGraph,
+    Cpu,
+    Started,
+    Elapsed,
+    Nice,
+    Priority,
+    State,
+    /// Bytes received per second over TCP, with the `network` feature.
+    Received,
+    /// Bytes sent per second over TCP, with the `network` feature.
+    Sent,
+    /// `⚷` for processes with `CAP_SYS_ADMIN`, `+` for other capabilities.
+    Capabilities,
+    /// The badness the OOM killer gives the process, the most likely killed
+    /// in bold red when memory runs low.
+    OomScore,
+    /// The adjustment of the OOM score, from -1000 to 1000.
+    OomScoreAdj,
+    /// Voluntary and involuntary context switches per second; many tell of
+    /// lock contention or a chatty process.
+    ContextSwitches,
 }
 
-pub fn create_row<'a>(process: &BrtProcess) -> Row<'a> {
-    let user = process.user.clone();
-    let username = if user.is_some() {
-        #[allow(clippy::unnecessary_unwrap)]
-        user.unwrap().name().to_os_string().into_string().unwrap()
-    } else {
-        "unknown".to_string()
-    };
+pub const DEFAULT_COLUMNS: [Column; 8] = [
+    Column::Pid,
+    Column::Program,
+    Column::Command,
+    Column::Threads,
+    Column::User,
+    Column::Memory,
+    Column::CpuGraph,
+    Column::Cpu,
+];
+
+impl Column {
+    fn label(&self) -> &'static str {
+        let text = match self {
+            Column::Pid => Text::Pid,
+            Column::Program => Text::Program,
+            Column::Command => Text::Command,
+            Column::Threads => Text::Threads,
+            Column::User => Text::User,
+            Column::Memory => Text::Memory,
+            Column::CpuGraph => return "",
+            Column::Cpu => Text::Cpu,
+            Column::Started => Text::Started,
+            Column::Elapsed => Text::Elapsed,
+            Column::Nice => Text::Nice,
+            Column::Priority => Text::Priority,
+            Column::State => Text::State,
+            Column::Received => Text::Received,
+            Column::Sent => Text::Sent,
+            Column::Capabilities => Text::Capabilities,
+            Column::OomScore => Text::OomScore,
+            Column::OomScoreAdj => Text::OomScoreAdj,
+            Column::ContextSwitches => Text::ContextSwitches,
+        };
+        tr(text)
+    }
+
+    fn is_right_aligned(&self) -> bool {
+        matches!(
+            self,
+            Column::Pid
+                | Column::Threads
+                | Column::Elapsed
+                | Column::Nice
+                | Column::Priority
+                | Column::Received
+                | Column::Sent
+                | Column::OomScore
+                | Column::OomScoreAdj
+                | Column::ContextSwitches
+        )
+    }
+
+    /// The header cell, with `indicator` after the label, e.g. `▲` on the
+    /// sorted column.
+    pub fn header<'a>(&self, indicator: Option<&str>) -> Cell<'a> {
+        let label = format!("{}{}", self.label(), indicator.unwrap_or_default());
+        if self.is_right_aligned() {
+            Cell::new(Line::from(label).alignment(Alignment::Right))
+        } else {
+            Cell::new(label)
+        }
+    }
+
+    /// The columns that are left out first when space runs short.
+    pub fn is_optional(&self) -> bool {
+        matches!(self, Column::Threads | Column::User | Column::CpuGraph)
+    }
+
+    /// Whether the column stays in view while the others scroll sideways, so
+    /// it remains clear which process a row is.
+    pub fn is_frozen(&self) -> bool {
+        matches!(self, Column::Pid | Column::Program)
+    }
+
+    /// Whether the column needs the OOM scores to be read.
+    pub fn is_oom(&self) -> bool {
+        matches!(self, Column::OomScore | Column::OomScoreAdj)
+    }
+
+    /// Whether the column needs the network to be sampled.
+    pub fn is_network(&self) -> bool {
+        matches!(self, Column::Received | Column::Sent)
+    }
+
+    pub fn width(&self) -> Constraint {
+        match self {
+            Column::Pid => Constraint::Percentage(5),
+            Column::Program => Constraint::Percentage(15),
+            Column::Command => Constraint::Fill(1),
+            Column::Threads => Constraint::Length(8),
+            Column::User => Constraint::Percentage(5),
+            Column::Memory => Constraint::Length(5),
+            Column::CpuGraph => Constraint::Length(5),
+            Column::Cpu => Constraint::Length(5),
+            Column::Started => Constraint::Length(8),
+            Column::Elapsed => Constraint::Length(8),
+            Column::Nice => Constraint::Length(3),
+            Column::Priority => Constraint::Length(4),
+            Column::State => Constraint::Length(1),
+            Column::Received | Column::Sent => Constraint::Length(7),
+            Column::Capabilities => Constraint::Length(3),
+            Column::OomScore => Constraint::Length(4),
+            Column::OomScoreAdj => Constraint::Length(5),
+            Column::ContextSwitches => Constraint::Length(6),
+        }
+    }
 
-    let special_style = Style::default().fg(Color::Rgb(0x0D, 0xE7, 0x56));
+    /// The cell of `process` in this column, `width` cells wide.
+    fn cell<'a>(
+        &self,
+        process: &BrtProcess,
+        width: usize,
+        thresholds: &[f64; MAX_DOTS],
+    ) -> Cell<'a> {
+        let special_style = Style::default().fg(Color::Rgb(0x0D, 0xE7, 0x56));
+        match self {
+            Column::Pid => {
+                Cell::new(Line::from(process.pid.to_string()).alignment(Alignment::Right))
+            }
+            Column::Program => Cell::new(truncate(&process.program, width)).style(special_style),
+            Column::Command => Cell::new(truncate(&process.command, width)),
+            Column::Threads => Cell::new(
+                Line::from(process.number_of_threads.to_string())
+                    .alignment(Alignment::Right)
+                    .style(special_style),
+            ),
+            Column::User => Cell::new(process.username()),
+            Column::Memory => Cell::new(format_bytes(process.resident_memory)).style(special_style),
+            Column::CpuGraph => {
+                Cell::new(CpuGraph::new(&process.cpus).thresholds(*thresholds).line())
+            }
+            Column::Cpu => Cell::new(format!("{:.2}", process.cpu)).style(special_style),
+            Column::Started => Cell::new(format_start_time(process.start_time)),
+            Column::Elapsed => {
+                Cell::new(Line::from(format_elapsed(process.elapsed())).alignment(Alignment::Right))
+                    .style(special_style)
+            }
+            Column::Nice => {
+                let style = match process.nice {
+                    nice if nice < 0 => Style::default().fg(Color::Red),
+                    nice if nice > 0 => Style::default().add_modifier(Modifier::DIM),
+                    _ => Style::default(),
+                };
+                Cell::new(Line::from(process.nice.to_string()).alignment(Alignment::Right))
+                    .style(style)
+            }
+            Column::Priority => {
+                let style = if process.is_realtime() {
+                    Style::default().fg(Color::Magenta)
+                } else {
+                    Style::default()
+                };
+                Cell::new(Line::from(process.priority.to_string()).alignment(Alignment::Right))
+                    .style(style)
+            }
+            Column::State => {
+                let color = match process.state {
+                    'R' => Color::Green,
+                    'D' | 'Z' => Color::Red,
+                    'T' | 't' => Color::Yellow,
+                    _ => Color::Reset,
+                };
+                Cell::new(process.state.to_string()).style(Style::default().fg(color))
+            }
+            Column::Received => {
+                Cell::new(Line::from(format_bytes(process.received)).alignment(Alignment::Right))
+            }
+            Column::Sent => {
+                Cell::new(Line::from(format_bytes(process.sent)).alignment(Alignment::Right))
+            }
+            Column::Capabilities => {
+                let style = if process.capabilities & capabilities::CAP_SYS_ADMIN != 0 {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default().fg(Color::Yellow)
+                };
+                Cell::new(capabilities::indicator(process.capabilities)).style(style)
+            }
+            Column::OomScore => {
+                let style = if process.oom_risk {
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                Cell::new(Line::from(process.oom_score.to_string()).alignment(Alignment::Right))
+                    .style(style)
+            }
+            Column::OomScoreAdj => {
+                // more likely to be killed, or protected from it
+                let style = match process.oom_score_adj {
+                    adj if adj > 0 => Style::default().fg(Color::Yellow),
+                    adj if adj < 0 => Style::default().add_modifier(Modifier::DIM),
+                    _ => Style::default(),
+                };
+                Cell::new(Line::from(process.oom_score_adj.to_string()).alignment(Alignment::Right))
+                    .style(style)
+            }
+            Column::ContextSwitches => Cell::new(
+                Line::from(format_rate(process.context_switch_rate)).alignment(Alignment::Right),
+            ),
+        }
+    }
+}
 
-    let humansize_options: FormatSizeOptions = FormatSizeOptions::from(BINARY)
+/// E.g. `1.5Ki`.
+pub fn format_bytes(bytes: u64) -> String {
+    let options: FormatSizeOptions = FormatSizeOptions::from(BINARY)
         .space_after_value(false)
         .decimal_places(1)
         .decimal_zeroes(0);
+    format_size(bytes, options)
+}
 
-    Row::new([
-        Cell::new(Line::from(process.pid.to_string()).alignment(Alignment::Right)),
-        Cell::new(process.program.to_string()).style(special_style),
-        Cell::new(process.command.to_string()),
-        Cell::new(
-            Line::from(process.number_of_threads.to_string())
-                .alignment(Alignment::Right)
-                .style(special_style),
-        ),
-        Cell::new(username),
-        Cell::new(format_size(process.resident_memory, humansize_options)).style(special_style),
-        Cell::new(process.cpu_graph.to_string()),
-        Cell::new(format!("{:.2}", process.cpu)).style(special_style),
-    ])
-}
-
-fn between(status: &f64, min: f64, max: f64) -> bool {
-    status >= &min && status < &max
-}
-
-fn get_points(cpu: &f64) -> i32 {
-    match cpu {
-        status if between(status, 0_f64, 0.001_f64) => 0,
-        status if between(status, 0.001_f64, 0.2_f64) => 1,
-        status if between(status, 0.2_f64, 0.5_f64) => 2,
-        status if between(status, 0.5_f64, 0.7_f64) => 3,
-        status if between(status, 0.7_f64, 100_f64) => 4,
-        _ => {
-            warn!("Invalid cpu found: {}; setting to zero.", cpu);
-            0
-        }
+/// E.g. `950`, `12.3k` or `1.2M`.
+pub fn format_rate(per_second: u64) -> String {
+    match per_second {
+        rate if rate >= 1_000_000 => format!("{:.1}M", rate as f64 / 1_000_000.0),
+        rate if rate >= 1_000 => format!("{:.1}k", rate as f64 / 1_000.0),
+        rate => rate.to_string(),
     }
 }
 
-pub fn get_cpu_graph(cpus: &VecDeque<f64>) -> String {
-    let blocks: HashMap<&str, &str> = HashMap::from([
-        ("00", " "),
-        ("01", "⢀"),
-        ("02", "⢠"),
-        ("03", "⢰"),
-        ("04", "⢸"),
-        ("10", "⡀"),
-        ("11", "⣀"),
-        ("12", "⣠"),
-        ("13", "⣰"),
-        ("14", "⣸"),
-        ("20", "⡄"),
-        ("21", "⣄"),
-        ("22", "⣤"),
-        ("23", "⣴"),
-        ("24", "⣼"),
-        ("30", "⡆"),
-        ("31", "⣆"),
-        ("32", "⣦"),
-        ("33", "⣶"),
-        ("34", "⣾"),
-        ("40", "⡇"),
-        ("41", "⣇"),
-        ("42", "⣧"),
-        ("43", "⣷"),
-        ("44", "⣿"),
-    ]);
-    let mut graph = "".to_string();
-    let v: Vec<&f64> = cpus.iter().collect();
-    for cpu in v.chunks(2) {
-        let first = get_points(cpu[0]);
-        let second = get_points(cpu[1]);
-        let slot = blocks.get(format!("{}{}", first, second).as_str()).unwrap();
-        graph += slot;
-    }
-    graph
-}
-
-#[derive(Default, Clone, Debug)]
+/// Whether a process started or exited since one of the last refreshes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lifecycle {
+    Started,
+    Exited,
+}
+
+/// What the rows of the process table are tinted by.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Usage {
+    #[default]
+    Cpu,
+    Memory,
+}
+
+/// Styles the rows of the processes using much cpu or memory, so that they
+/// stand out without sorting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowTint {
+    pub by: Usage,
+    /// The usage in percent from which a row takes a style, from the lowest
+    /// usage up; the highest step reached applies.
+    pub steps: Vec<(f64, Style)>,
+}
+
+impl RowTint {
+    /// The style of `process`, with `memory_total` the memory of the system
+    /// in bytes.
+    pub fn style(&self, process: &BrtProcess, memory_total: u64) -> Option<Style> {
+        let usage = match self.by {
+            Usage::Cpu => process.cpu,
+            Usage::Memory if memory_total == 0 => return None,
+            Usage::Memory => process.resident_memory as f64 * 100.0 / memory_total as f64,
+        };
+        self.steps
+            .iter()
+            .rev()
+            .find(|(from, _)| usage >= *from)
+            .map(|(_, style)| *style)
+    }
+}
+
+/// The rows of `processes`, with `widths` the widths of the `columns` and
+/// `thresholds` those of the cpu graph. `tint` gives the style of a row
+/// before it is marked, started or exited. Only the processes in view are
+/// passed, so a frame builds no more rows than it shows.
+pub fn rows(
+    processes: &[BrtProcess],
+    columns: &[Column],
+    widths: &[u16],
+    thresholds: &[f64; MAX_DOTS],
+    marked: &HashSet<i32>,
+    lifecycles: &HashMap<i32, Lifecycle>,
+    tint: impl Fn(&BrtProcess) -> Option<Style>,
+) -> Vec<Row<'static>> {
+    processes
+        .iter()
+        .map(|process| {
+            create_row(
+                process,
+                columns,
+                widths,
+                thresholds,
+                marked.contains(&process.pid),
+                lifecycles.get(&process.pid).copied(),
+                tint(process),
+            )
+        })
+        .collect()
+}
+
+/// Marked processes are yellow, started ones green and exited ones red, on
+/// top of the `tint` of their usage.
+fn create_row<'a>(
+    process: &BrtProcess,
+    columns: &[Column],
+    widths: &[u16],
+    thresholds: &[f64; MAX_DOTS],
+    marked: bool,
+    lifecycle: Option<Lifecycle>,
+    tint: Option<Style>,
+) -> Row<'a> {
+    // shown in the first column of the processes that are marked
+    let marker = Cell::new(if marked { symbols().marker } else { "" });
+    let cells = columns
+        .iter()
+        .zip(widths)
+        .map(|(column, width)| column.cell(process, *width as usize, thresholds));
+    let row = Row::new(std::iter::once(marker).chain(cells));
+    let style = tint.unwrap_or_default();
+    row.style(match lifecycle {
+        _ if marked => style.fg(Color::Yellow),
+        _ if process.restricted => style.add_modifier(Modifier::DIM),
+        Some(Lifecycle::Started) => style.fg(Color::Green),
+        Some(Lifecycle::Exited) => style.fg(Color::Red).add_modifier(Modifier::DIM),
+        None => style,
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Shows the time of day for processes started today and the date otherwise.
+pub fn format_start_time(start_time: u64) -> String {
+    let Some(started) = DateTime::from_timestamp(start_time as i64, 0) else {
+        return "?".to_string();
+    };
+    let started = started.with_timezone(&Local);
+    if started.date_naive() == Local::now().date_naive() {
+        started.format("%H:%M").to_string()
+    } else {
+        started.format("%b%d").to_string()
+    }
+}
+
+/// Formats a number of seconds using its two most significant units, e.g. `3d4h`.
+pub fn format_elapsed(seconds: u64) -> String {
+    let days = seconds / 86_400;
+    let hours = seconds % 86_400 / 3_600;
+    let minutes = seconds % 3_600 / 60;
+    let seconds = seconds % 60;
+    let (d, h, m, s) = (
+        tr(Text::Days),
+        tr(Text::Hours),
+        tr(Text::Minutes),
+        tr(Text::Seconds),
+    );
+    if days > 0 {
+        format!("{days}{d}{hours}{h}")
+    } else if hours > 0 {
+        format!("{hours}{h}{minutes}{m}")
+    } else if minutes > 0 {
+        format!("{minutes}{m}{seconds}{s}")
+    } else {
+        format!("{seconds}{s}")
+    }
+}
+
+/// The cpu usage in percent from which a column of the cpu graph has one,
+/// two, three and four dots.
+pub const GRAPH_THRESHOLDS: [f64; MAX_DOTS] = [0.1, 20.0, 50.0, 70.0];
+
+/// The dots of a cpu usage in percent in a column of the graph: one for
+/// every threshold it reaches.
+pub fn get_points(cpu: f64, thresholds: &[f64; MAX_DOTS]) -> usize {
+    thresholds
+        .iter()
+        .filter(|threshold| cpu >= **threshold)
+        .count()
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct BrtProcess {
     pub pid: i32,
     pub ppid: i32,
     pub program: String,
     pub command: String,
     pub number_of_threads: i64,
-    pub user: Option<User>,
+    /// The real user id; recordings keep the name of the user with it.
+    #[serde(rename = "user", with = "user")]
+    pub uid: Option<u32>,
     pub resident_memory: u64,
     pub cpus: VecDeque<f64>,
-    pub cpu_graph: String,
     pub cpu: f64,
+    /// Start time in seconds since the epoch.
+    #[serde(default)]
+    pub start_time: u64,
+    /// From -20, the least nice, to 19.
+    #[serde(default)]
+    pub nice: i64,
+    /// The kernel's scheduling priority; below zero for real-time processes.
+    #[serde(default)]
+    pub priority: i64,
+    /// The state letter of `/proc/<pid>/stat`, e.g. `R` for running.
+    #[serde(default = "unknown_state")]
+    pub state: char,
+    /// Bytes per second received and sent over TCP.
+    #[serde(default)]
+    pub received: u64,
+    #[serde(default)]
+    pub sent: u64,
+    /// The effective capabilities, only read with the `Capabilities` column.
+    #[serde(default)]
+    pub capabilities: u64,
+    /// Only read with the OOM columns.
+    #[serde(default)]
+    pub oom_score: u32,
+    #[serde(default)]
+    pub oom_score_adj: i32,
+    /// Among the processes the OOM killer would pick first, while memory
+    /// runs low.
+    #[serde(skip)]
+    pub oom_risk: bool,
+    /// The voluntary and involuntary context switches since it started, and
+    /// per second; only read with the `ContextSwitches` column.
+    #[serde(default)]
+    pub context_switches: u64,
+    #[serde(default)]
+    pub context_switch_rate: u64,
+    /// Its `/proc/<pid>/stat` couldn't be read, e.g. because of `hidepid`,
+    /// which leaves little more than the pid and the user.
+    #[serde(default)]
+    pub restricted: bool,
+    /// The cgroup v2 path, only read while the processes are filtered by it.
+    #[serde(default)]
+    pub cgroup: Option<String>,
+    /// Read from a recording, so its user is one of the recorded ones rather
+    /// than one of this machine.
+    #[serde(skip)]
+    pub replayed: bool,
+}
+
+fn unknown_state() -> char {
+    '?'
 }
 
 impl BrtProcess {
     pub fn new() -> BrtProcess {
         BrtProcess {
             cpus: VecDeque::from(vec![0_f64; 10]),
+            state: unknown_state(),
             ..Default::default()
         }
     }
+
+    /// A process whose stat brt isn't allowed to read.
+    pub fn restricted(source: &dyn ProcSource, pid: i32) -> BrtProcess {
+        BrtProcess {
+            pid,
+            program: "?".to_string(),
+            uid: source.uid(pid).ok(),
+            restricted: true,
+            ..BrtProcess::new()
+        }
+    }
+
+    pub fn username(&self) -> String {
+        let lookup = if self.replayed {
+            recorded_username
+        } else {
+            username
+        };
+        self.uid
+            .and_then(lookup)
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// The average of the last `samples` cpu readings.
+    pub fn recent_cpu(&self, samples: usize) -> f64 {
+        let samples = samples.clamp(1, self.cpus.len().max(1));
+        self.cpus.iter().rev().take(samples).sum::<f64>() / samples as f64
+    }
+
+    pub fn is_realtime(&self) -> bool {
+        self.priority < 0
+    }
+
+    /// Number of seconds the process has been running.
+    pub fn elapsed(&self) -> u64 {
+        now().saturating_sub(self.start_time)
+    }
+}
+
+/// The users by uid, looked up once and shared by all processes; `None` for
+/// uids without a user.
+static USERS: Mutex<BTreeMap<u32, Option<User>>> = Mutex::new(BTreeMap::new());
+
+/// Looks up the users of the uids that weren't seen before, so a refresh
+/// only asks the user database about new ones.
+pub fn resolve_users(uids: impl IntoIterator<Item = u32>) {
+    let mut users = USERS.lock().unwrap_or_else(PoisonError::into_inner);
+    for uid in uids {
+        users.entry(uid).or_insert_with(|| get_user_by_uid(uid));
+    }
+}
+
+/// The name of the user with this uid, if there is one.
+pub fn username(uid: u32) -> Option<String> {
+    resolve_users([uid]);
+    let users = USERS.lock().unwrap_or_else(PoisonError::into_inner);
+    let user = users.get(&uid)?.as_ref()?;
+    Some(user.name().to_string_lossy().to_string())
+}
+
+/// The users of the recorded processes by uid, kept apart from [`USERS`] so
+/// a replay doesn't rename the users of this machine.
+static RECORDED_USERS: Mutex<BTreeMap<u32, User>> = Mutex::new(BTreeMap::new());
+
+/// The name of the user with this uid in the recording being replayed.
+fn recorded_username(uid: u32) -> Option<String> {
+    let users = RECORDED_USERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    Some(users.get(&uid)?.name().to_string_lossy().to_string())
+}
+
+/// (De)serializes a uid as the uid, name and primary group of its user, so
+/// recorded processes can be replayed on a machine that doesn't know those
+/// users. The recorded users are kept besides the known ones.
+mod user {
+    use std::sync::PoisonError;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use uzers::User;
+
+    use super::{resolve_users, RECORDED_USERS, USERS};
+
+    #[derive(Serialize, Deserialize)]
+    struct SerializedUser {
+        uid: u32,
+        name: String,
+        gid: u32,
+    }
+
+    pub fn serialize<S: Serializer>(uid: &Option<u32>, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(uid) = uid {
+            resolve_users([*uid]);
+        }
+        let users = USERS.lock().unwrap_or_else(PoisonError::into_inner);
+        let recorded = RECORDED_USERS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        // a replayed process that is recorded again keeps its user
+        uid.and_then(|uid| users.get(&uid)?.as_ref().or_else(|| recorded.get(&uid)))
+            .map(|u| SerializedUser {
+                uid: u.uid(),
+                name: u.name().to_string_lossy().to_string(),
+                gid: u.primary_group_id(),
+            })
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u32>, D::Error> {
+        let Some(user) = Option::<SerializedUser>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        let mut users = RECORDED_USERS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        users.insert(user.uid, User::new(user.uid, &user.name, user.gid));
+        Ok(Some(user.uid))
+    }
+}
+
+/// Cuts `text` to at most `width` terminal cells, ending it with an ellipsis
+/// when it was cut. Wide characters, like CJK ones and most emoji, take two
+/// cells.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let ellipsis = symbols().ellipsis;
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let c_width = c.width().unwrap_or_default();
+        if used + c_width + ellipsis.width() > width {
+            break;
+        }
+        truncated.push(c);
+        used += c_width;
+    }
+    if width > 0 {
+        truncated.push_str(ellipsis);
+    }
+    truncated
+}
+
+/// Escapes the characters that would mess up the table: control characters,
+/// like newlines and terminal escape sequences, and the bidirectional
+/// overrides that reorder the text around them.
+pub fn sanitize(text: &str) -> String {
+    let mut sanitized = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\t' => sanitized.push_str("\\t"),
+            '\n' => sanitized.push_str("\\n"),
+            '\r' => sanitized.push_str("\\r"),
+            c if c.is_control() && (c as u32) < 0x100 => {
+                sanitized.push_str(&format!("\\x{:02x}", c as u32))
+            }
+            '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}' => {
+                sanitized.push_str(&format!("\\u{{{:x}}}", c as u32))
+            }
+            c => sanitized.push(c),
+        }
+    }
+    sanitized
 }
 
 fn create_command(cmdline: &[String]) -> String {
     let mut command = "".to_string();
     for part in cmdline.iter() {
-        command += format!("{} ", part).as_str();
+        command += format!("{} ", sanitize(part)).as_str();
     }
     command
 }
 
-pub fn to_brt_process(process: &Process) -> Option<BrtProcess> {
+/// Reads all processes that can be read, and then the users that weren't
+/// seen before.
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn get_processes(source: &dyn ProcSource) -> HashMap<i32, BrtProcess> {
+    let processes: HashMap<i32, BrtProcess> = source
+        .pids()
+        .expect("Can't read /proc")
+        .into_iter()
+        .filter_map(|pid| Some((pid, to_brt_process(source, pid)?)))
+        .collect();
+    resolve_users(processes.values().filter_map(|process| process.uid));
+    processes
+}
+
+pub fn to_brt_process(source: &dyn ProcSource, pid: i32) -> Option<BrtProcess> {
     let mut brt_process: BrtProcess = BrtProcess::new();
-    let stat_result = process.stat();
+    let stat_result = source.stat(pid);
     match stat_result {
         Ok(stat) => {
             brt_process.pid = stat.pid;
             brt_process.ppid = stat.ppid;
-            brt_process.program = stat.comm;
+            brt_process.program = sanitize(&stat.comm);
             brt_process.number_of_threads = stat.num_threads;
+            brt_process.nice = stat.nice;
+            brt_process.priority = stat.priority;
+            brt_process.state = stat.state;
+            brt_process.start_time = get_start_time(source, stat.starttime);
 
             // command
-            let cmd_result = process.cmdline();
+            let cmd_result = source.cmdline(pid);
             match cmd_result {
                 Ok(cmd) => {
                     brt_process.command = create_command(&cmd);
@@ -165,71 +725,331 @@ pub fn to_brt_process(process: &Process) -> Option<BrtProcess> {
                 }
             }
 
-            // user
-            let uid_result = process.uid();
+            // user, looked up by the caller
+            let uid_result = source.uid(pid);
             match uid_result {
                 Ok(uid) => {
-                    brt_process.user = get_user_by_uid(uid);
+                    brt_process.uid = Some(uid);
                 }
                 Err(_e) => {
-                    warn!("No user found for process {}.", process.pid().to_string());
-                    brt_process.user = None;
+                    warn!("No user found for process {}.", pid);
+                    brt_process.uid = None;
                 }
             }
 
             // memory
-            let resident_memory = get_memory(process);
+            let resident_memory = get_memory(source, pid);
             brt_process.resident_memory = resident_memory;
 
             // cpu(s)
-            let cpu = get_cpu(process);
+            let cpu = get_cpu(source, &stat);
             brt_process.cpu = cpu;
             brt_process.cpus.push_back(cpu);
             brt_process.cpus.pop_front();
-            brt_process.cpu_graph = get_cpu_graph(&brt_process.cpus);
+        }
+        Err(ProcError::PermissionDenied(_)) => {
+            debug!("Not allowed to read the stat of process {}.", pid);
+            return Some(BrtProcess::restricted(source, pid));
         }
         Err(_e) => {
-            warn!("Stat not found for process {}.", process.pid().to_string());
+            warn!("Stat not found for process {}.", pid);
             return None;
         }
     }
     Some(brt_process)
 }
 
-pub fn get_memory(process: &Process) -> u64 {
-    let statm = process.statm().unwrap(); // TODO: this can be: NotFound(Some("/proc/3955386/statm"))
-    let page_size = procfs::page_size();
-    statm.resident * page_size
+/// The resident memory in bytes, or zero when statm can't be read.
+pub fn get_memory(source: &dyn ProcSource, pid: i32) -> u64 {
+    match source.statm(pid) {
+        Ok(statm) => statm.resident * source.page_size(),
+        Err(e) => {
+            debug!("No memory found for process {}: {}", pid, e);
+            0
+        }
+    }
 }
 
-fn get_cpu(process: &Process) -> f64 {
-    let stat = process.stat().unwrap();
-
-    let usage = stat.utime / ticks_per_second() + stat.stime / ticks_per_second();
-    debug!("usage: {}s", usage);
+/// Converts the start time in clock ticks after boot to seconds since the epoch.
+fn get_start_time(source: &dyn ProcSource, starttime: u64) -> u64 {
+    let boot_time = source.boot_time().unwrap_or_default();
+    boot_time + starttime / source.ticks_per_second()
+}
 
-    let uptime = Uptime::current().unwrap().uptime_duration().as_secs();
+/// The seconds the process runs, since it started after boot.
+pub fn get_runtime(source: &dyn ProcSource, stat: &Stat) -> u64 {
+    let uptime = source.uptime().unwrap_or_default();
     debug!("Uptime: {}s", uptime);
 
-    let starttime = stat.starttime / ticks_per_second();
+    let starttime = stat.starttime / source.ticks_per_second();
     debug!("start time: {}s", starttime);
 
-    let runtime = uptime - starttime;
+    uptime.saturating_sub(starttime)
+}
+
+pub fn get_cpu(source: &dyn ProcSource, stat: &Stat) -> f64 {
+    let ticks_per_second = source.ticks_per_second();
+
+    let usage = stat.utime / ticks_per_second + stat.stime / ticks_per_second;
+    debug!("usage: {}s", usage);
+
+    let runtime = get_runtime(source, stat);
     debug!("runtime: {}s", runtime);
+    if runtime == 0 {
+        return 0.0;
+    }
 
-    let num_cores = CpuInfo::current().unwrap().num_cores();
-    debug!("Uptime: {}s", uptime);
+    let num_cores = source.num_cores();
+    debug!("num cores: {}", num_cores);
 
     usage as f64 * 100.0 / runtime as f64 / num_cores as f64
 }
 
 #[cfg(test)]
 mod tests {
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::widgets::Table;
+
+    use super::*;
+    use crate::source::fake::{FakeProcSource, FakeProcess, BOOT_TIME, PAGE_SIZE};
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(0), "0s");
+        assert_eq!(format_elapsed(59), "59s");
+        assert_eq!(format_elapsed(61), "1m1s");
+        assert_eq!(format_elapsed(3_600 + 120), "1h2m");
+        assert_eq!(format_elapsed(3 * 86_400 + 4 * 3_600 + 5), "3d4h");
+    }
+
+    #[test]
+    fn test_to_brt_process() {
+        let source = FakeProcSource::default()
+            .with(1, FakeProcess::running(1, "init", 4_000, 100))
+            .with(2, FakeProcess::zombie(2, "defunct"))
+            .with(3, FakeProcess::forbidden());
+
+        let init = to_brt_process(&source, 1).unwrap();
+        assert_eq!(init.program, "init");
+        assert_eq!(init.command, "/usr/bin/init --flag ");
+        assert_eq!(init.resident_memory, 512 * PAGE_SIZE);
+        assert_eq!(init.start_time, BOOT_TIME + 1);
+        // 40s of cpu over 999s of runtime on 4 cores
+        assert!((init.cpu - 4000.0 / 999.0 / 4.0).abs() < 1e-9);
+        assert_eq!((init.nice, init.priority, init.state), (0, 20, 'S'));
+        assert!(!init.is_realtime());
+
+        let zombie = to_brt_process(&source, 2).unwrap();
+        assert_eq!(zombie.state, 'Z');
+        assert_eq!(zombie.command, "");
+        assert_eq!(zombie.resident_memory, 0);
+        assert_eq!(zombie.cpu, 0.0);
+
+        // not allowed to read, but still there
+        let forbidden = to_brt_process(&source, 3).unwrap();
+        assert!(forbidden.restricted);
+        assert_eq!((forbidden.pid, forbidden.program.as_str()), (3, "?"));
+        assert_eq!(forbidden.state, '?');
+        assert!(to_brt_process(&source, 4).is_none());
+    }
+
+    #[test]
+    fn test_users() {
+        let source = FakeProcSource::default().with(1, FakeProcess::running(1, "init", 0, 0));
+        let init = &get_processes(&source)[&1];
+        assert_eq!(init.uid, Some(0));
+        assert!(USERS.lock().unwrap().contains_key(&0));
+        assert_eq!(init.username(), "root");
+        assert_eq!(BrtProcess::new().username(), "unknown");
+
+        // a recorded user is only the user of the replayed processes
+        let recorded = r#"{"pid": 7, "ppid": 1, "program": "job", "command": "job",
+            "number_of_threads": 1, "user": {"uid": 0, "name": "recorded", "gid": 100},
+            "resident_memory": 0, "cpus": [], "cpu": 0.0}"#;
+        let mut job: BrtProcess = serde_json::from_str(recorded).unwrap();
+        assert_eq!((job.uid, job.username()), (Some(0), "root".to_string()));
+        assert_eq!(init.username(), "root");
+        job.replayed = true;
+        assert_eq!(job.username(), "recorded");
+
+        // recorded again, a user unknown here keeps its name
+        let recorded = recorded.replace("\"uid\": 0", "\"uid\": 4242");
+        let job: BrtProcess = serde_json::from_str(&recorded).unwrap();
+        let json = serde_json::to_value(&job).unwrap();
+        assert_eq!(json["user"]["name"], "recorded");
+        assert_eq!(json["user"]["gid"], 100);
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("/usr/bin/vim"), "/usr/bin/vim");
+        assert_eq!(sanitize("naïve 日本"), "naïve 日本");
+        assert_eq!(sanitize("a\tb\nc\r"), "a\\tb\\nc\\r");
+        assert_eq!(
+            sanitize("\x1b[2J\x1b]0;pwned\x07"),
+            "\\x1b[2J\\x1b]0;pwned\\x07"
+        );
+        assert_eq!(sanitize("\u{7f}\u{9b}"), "\\x7f\\x9b");
+        assert_eq!(sanitize("evil\u{202e}txt.exe"), "evil\\u{202e}txt.exe");
+        assert_eq!(sanitize("\u{fffd}"), "\u{fffd}");
+    }
+
+    #[test]
+    fn test_adversarial_process() {
+        let mut process = FakeProcess::running(6, "x\x1b[31my", 0, 0);
+        process.cmdline = Some(vec![
+            "/tmp/\x1b[2Jevil".to_string(),
+            "line\nbreak".to_string(),
+            "caf\u{fffd}".to_string(),
+        ]);
+        let source = FakeProcSource::default().with(6, process);
+        let process = to_brt_process(&source, 6).unwrap();
+        assert_eq!(process.program, "x\\x1b[31my");
+        assert_eq!(
+            process.command,
+            "/tmp/\\x1b[2Jevil line\\nbreak caf\u{fffd} "
+        );
+        assert!(!process.command.chars().any(char::is_control));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("vim", 5), "vim");
+        assert_eq!(truncate("firefox", 5), "fire…");
+        // each of these takes two cells, so only one fits before the ellipsis
+        assert_eq!(truncate("日本語", 4), "日…");
+        assert_eq!(truncate("日本語", 5), "日本…");
+        assert_eq!(truncate("🦀🦀🦀", 3), "🦀…");
+        assert_eq!(truncate("a日", 2), "a…");
+        assert_eq!(truncate("abc", 0), "");
+    }
+
+    #[test]
+    fn test_create_row_with_wide_characters() {
+        let process = BrtProcess {
+            pid: 7,
+            program: "日本語のプログラム".to_string(),
+            command: "/usr/bin/🦀 --crab".to_string(),
+            ..BrtProcess::default()
+        };
+        let columns = [Column::Pid, Column::Program, Column::Command];
+        let row = create_row(
+            &process,
+            &columns,
+            &[5, 8, 8],
+            &GRAPH_THRESHOLDS,
+            false,
+            None,
+            None,
+        );
+        let table = Table::new([row], [1, 5, 8, 8].map(Constraint::Length));
+        let mut buf = Buffer::empty(Rect::new(0, 0, 25, 1));
+        ratatui::widgets::Widget::render(table, buf.area, &mut buf);
+        let text: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        // the wide characters take two cells, the second of which is blank
+        assert_eq!(text, "      7 日 本 語 …  /usr/bi…");
+        assert_eq!(buf.get(17, 0).symbol(), "/");
+    }
+
+    #[test]
+    fn test_rows() {
+        let process = |pid| BrtProcess {
+            pid,
+            ..BrtProcess::default()
+        };
+        let built = rows(
+            &[process(1), process(2)],
+            &[Column::Pid, Column::Cpu],
+            &[5, 5],
+            &GRAPH_THRESHOLDS,
+            &HashSet::from([2]),
+            &HashMap::from([(1, Lifecycle::Started)]),
+            |_| None,
+        );
+        assert_eq!(built.len(), 2);
+        assert_eq!(
+            built[0],
+            create_row(
+                &process(1),
+                &[Column::Pid, Column::Cpu],
+                &[5, 5],
+                &GRAPH_THRESHOLDS,
+                false,
+                Some(Lifecycle::Started),
+                None
+            )
+        );
+        assert_eq!(
+            built[1],
+            create_row(
+                &process(2),
+                &[Column::Pid, Column::Cpu],
+                &[5, 5],
+                &GRAPH_THRESHOLDS,
+                true,
+                None,
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn test_row_tint() {
+        let orange = Style::default().fg(Color::Indexed(208));
+        let red = Style::default().bg(Color::Red);
+        let mut tint = RowTint {
+            by: Usage::Cpu,
+            steps: vec![(50.0, orange), (90.0, red)],
+        };
+        let process = |cpu, resident_memory| BrtProcess {
+            cpu,
+            resident_memory,
+            ..BrtProcess::default()
+        };
+        assert_eq!(tint.style(&process(10.0, 0), 0), None);
+        assert_eq!(tint.style(&process(50.0, 0), 0), Some(orange));
+        assert_eq!(tint.style(&process(250.0, 0), 0), Some(red));
+
+        tint.by = Usage::Memory;
+        assert_eq!(tint.style(&process(250.0, 600), 1000), Some(orange));
+        assert_eq!(tint.style(&process(0.0, 950), 1000), Some(red));
+        // before the memory of the system was read
+        assert_eq!(tint.style(&process(0.0, 950), 0), None);
+
+        // a marked process keeps the background of its usage
+        let row = create_row(
+            &process(95.0, 0),
+            &[],
+            &[],
+            &GRAPH_THRESHOLDS,
+            true,
+            None,
+            Some(red),
+        );
+        assert_eq!(row, Row::new([Cell::new("●")]).style(red.fg(Color::Yellow)));
+    }
+
+    #[test]
+    fn test_realtime_process() {
+        let mut audio = FakeProcess::running(5, "pipewire", 0, 0);
+        audio.stat = audio.stat.map(|s| s.replace(" 20 0 1 0 ", " -51 -11 1 0 "));
+        let source = FakeProcSource::default().with(5, audio);
+        let audio = to_brt_process(&source, 5).unwrap();
+        assert_eq!((audio.nice, audio.priority), (-11, -51));
+        assert!(audio.is_realtime());
+        assert_eq!(
+            Column::Nice.cell(&audio, 3, &GRAPH_THRESHOLDS),
+            Cell::new(Line::from("-11").alignment(Alignment::Right))
+                .style(Style::default().fg(Color::Red))
+        );
+    }
 
     #[test]
-    fn test_get_all_processes() {
-        // let all_processes = get_all_processes();
-        // assert_eq!(all_processes.is_empty(), false)
-        assert_eq!(false, false)
+    fn test_get_memory_permission_denied() {
+        let mut forbidden = FakeProcess::running(1, "secret", 0, 0);
+        forbidden.statm = None;
+        let source = FakeProcSource::default().with(1, forbidden);
+        assert_eq!(get_memory(&source, 1), 0);
     }
 }
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// The bytes per second a process received and sent over TCP.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Throughput {
+    pub received: u64,
+    pub sent: u64,
+}
+
+/// The bytes received and sent by each socket, by inode.
+type Counters = HashMap<u64, (u64, u64)>;
+
+/// Estimates the network throughput of each process from the byte counters
+/// the kernel keeps for every TCP socket, attributed to the processes that
+/// have the socket open. UDP and sockets of processes that can't be read are
+/// not counted, so this is a lower bound.
+#[derive(Debug, Default)]
+pub struct NetworkSampler {
+    counters: Counters,
+    sampled: Option<Instant>,
+}
+
+impl NetworkSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The throughput since the previous sample; the first sample only
+    /// remembers the counters.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub fn sample(&mut self) -> HashMap<i32, Throughput> {
+        let counters = match read_counters() {
+            Ok(counters) => counters,
+            Err(e) => {
+                tracing::warn!("Unable to read the socket counters: {e}");
+                return HashMap::new();
+            }
+        };
+        let now = Instant::now();
+        let throughput = match self.sampled {
+            Some(sampled) => throughput(
+                &self.counters,
+                &counters,
+                &socket_owners(),
+                now.duration_since(sampled).as_secs_f64(),
+            ),
+            None => HashMap::new(),
+        };
+        self.counters = counters;
+        self.sampled = Some(now);
+        throughput
+    }
+}
+
+/// Adds up the bytes each socket moved between two samples per owning
+/// process. Sockets that are new in `current` count in full.
+fn throughput(
+    previous: &Counters,
+    current: &Counters,
+    owners: &HashMap<u64, i32>,
+    seconds: f64,
+) -> HashMap<i32, Throughput> {
+    let mut bytes: HashMap<i32, (u64, u64)> = HashMap::new();
+    for (inode, (received, sent)) in current {
+        let Some(pid) = owners.get(inode) else {
+            continue;
+        };
+        let (previous_received, previous_sent) = previous.get(inode).copied().unwrap_or_default();
+        let total = bytes.entry(*pid).or_default();
+        total.0 += received.saturating_sub(previous_received);
+        total.1 += sent.saturating_sub(previous_sent);
+    }
+    if seconds <= 0.0 {
+        return HashMap::new();
+    }
+    bytes
+        .into_iter()
+        .map(|(pid, (received, sent))| {
+            let throughput = Throughput {
+                received: (received as f64 / seconds) as u64,
+                sent: (sent as f64 / seconds) as u64,
+            };
+            (pid, throughput)
+        })
+        .collect()
+}
+
+#[cfg(all(feature = "network", target_os = "linux"))]
+use diag::{read_counters, socket_owners};
+
+#[cfg(not(all(feature = "network", target_os = "linux")))]
+fn read_counters() -> std::io::Result<Counters> {
+    Ok(HashMap::new())
+}
+
+#[cfg(not(all(feature = "network", target_os = "linux")))]
+fn socket_owners() -> HashMap<u64, i32> {
+    HashMap::new()
+}
+
+/// Dumps the TCP sockets with the `sock_diag` netlink interface, like `ss`.
+#[cfg(all(feature = "network", target_os = "linux"))]
+mod diag {
+    use std::collections::HashMap;
+    use std::io;
+    use std::mem;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    use procfs::process::{all_processes, FDTarget};
+
+    use super::Counters;
+
+    const SOCK_DIAG_BY_FAMILY: u16 = 20;
+    const INET_DIAG_INFO: u16 = 2;
+    const HEADER_LENGTH: usize = 16;
+    /// The length of `struct inet_diag_req_v2`.
+    const REQUEST_LENGTH: usize = 56;
+    /// The length of `struct inet_diag_msg` and the offset of its inode.
+    const MESSAGE_LENGTH: usize = 72;
+    const INODE_OFFSET: usize = 68;
+    /// The offsets of `tcpi_bytes_acked` and `tcpi_bytes_received` in
+    /// `struct tcp_info`.
+    const BYTES_ACKED_OFFSET: usize = 120;
+    const BYTES_RECEIVED_OFFSET: usize = 128;
+
+    fn align(length: usize) -> usize {
+        (length + 3) & !3
+    }
+
+    fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+        u16::from_ne_bytes([bytes[offset], bytes[offset + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        let mut value = [0; 4];
+        value.copy_from_slice(&bytes[offset..offset + 4]);
+        u32::from_ne_bytes(value)
+    }
+
+    fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+        let mut value = [0; 8];
+        value.copy_from_slice(&bytes[offset..offset + 8]);
+        u64::from_ne_bytes(value)
+    }
+
+    /// A dump request for all TCP sockets of `family`, with their `tcp_info`.
+    fn request(family: u8) -> Vec<u8> {
+        let length = (HEADER_LENGTH + REQUEST_LENGTH) as u32;
+        let flags = (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16;
+        let mut request = Vec::with_capacity(length as usize);
+        request.extend(length.to_ne_bytes());
+        request.extend(SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+        request.extend(flags.to_ne_bytes());
+        // sequence number and port id
+        request.extend([0; 8]);
+        let extensions = 1 << (INET_DIAG_INFO - 1);
+        request.extend([family, libc::IPPROTO_TCP as u8, extensions, 0]);
+        // all states
+        request.extend(u32::MAX.to_ne_bytes());
+        // any socket
+        request.extend([0; 48]);
+        request
+    }
+
+    /// The inode and the received and sent bytes of a single socket.
+    fn parse_socket(message: &[u8]) -> Option<(u64, (u64, u64))> {
+        if message.len() < MESSAGE_LENGTH {
+            return None;
+        }
+        let inode = u32_at(message, INODE_OFFSET) as u64;
+        let mut offset = MESSAGE_LENGTH;
+        while offset + 4 <= message.len() {
+            let length = u16_at(message, offset) as usize;
+            if length < 4 || offset + length > message.len() {
+                break;
+            }
+            let info = &message[offset + 4..offset + length];
+            if u16_at(message, offset + 2) == INET_DIAG_INFO && info.len() >= 136 {
+                let received = u64_at(info, BYTES_RECEIVED_OFFSET);
+                let sent = u64_at(info, BYTES_ACKED_OFFSET);
+                return (inode != 0).then_some((inode, (received, sent)));
+            }
+            offset += align(length);
+        }
+        None
+    }
+
+    /// Adds the sockets in a netlink response to `counters`; tells whether
+    /// the dump is complete.
+    fn parse(buffer: &[u8], counters: &mut Counters) -> io::Result<bool> {
+        let mut offset = 0;
+        while offset + HEADER_LENGTH <= buffer.len() {
+            let length = u32_at(buffer, offset) as usize;
+            if length < HEADER_LENGTH || offset + length > buffer.len() {
+                break;
+            }
+            let message = &buffer[offset + HEADER_LENGTH..offset + length];
+            match u16_at(buffer, offset + 4) {
+                kind if kind == libc::NLMSG_DONE as u16 => return Ok(true),
+                kind if kind == libc::NLMSG_ERROR as u16 => {
+                    let errno = if message.len() >= 4 {
+                        u32_at(message, 0) as i32
+                    } else {
+                        0
+                    };
+                    if errno != 0 {
+                        return Err(io::Error::from_raw_os_error(-errno));
+                    }
+                    return Ok(true);
+                }
+                SOCK_DIAG_BY_FAMILY => counters.extend(parse_socket(message)),
+                _ => (),
+            }
+            offset += align(length);
+        }
+        Ok(false)
+    }
+
+    fn dump(socket: &OwnedFd, family: u8, counters: &mut Counters) -> io::Result<()> {
+        let request = request(family);
+        // SAFETY: an all zero sockaddr_nl addresses the kernel.
+        let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as u16;
+        // SAFETY: the request and the address outlive the call.
+        let sent = unsafe {
+            libc::sendto(
+                socket.as_raw_fd(),
+                request.as_ptr().cast(),
+                request.len(),
+                0,
+                (&address as *const libc::sockaddr_nl).cast(),
+                mem::size_of::<libc::sockaddr_nl>() as u32,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut buffer = vec![0; 32 * 1024];
+        loop {
+            // SAFETY: the buffer is valid for its length.
+            let received = unsafe {
+                libc::recv(
+                    socket.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                    0,
+                )
+            };
+            if received < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if received == 0 || parse(&buffer[..received as usize], counters)? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// The byte counters of all IPv4 and IPv6 TCP sockets.
+    pub fn read_counters() -> io::Result<Counters> {
+        // SAFETY: socket has no memory safety requirements.
+        let socket = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_SOCK_DIAG,
+            )
+        };
+        if socket < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the descriptor was just created and is owned by nobody else.
+        let socket = unsafe { OwnedFd::from_raw_fd(socket) };
+        let mut counters = HashMap::new();
+        for family in [libc::AF_INET, libc::AF_INET6] {
+            dump(&socket, family as u8, &mut counters)?;
+        }
+        Ok(counters)
+    }
+
+    /// The process that has each socket open, for the processes whose file
+    /// descriptors can be read.
+    pub fn socket_owners() -> HashMap<u64, i32> {
+        let Ok(processes) = all_processes() else {
+            return HashMap::new();
+        };
+        let mut owners = HashMap::new();
+        for process in processes.flatten() {
+            let Ok(fds) = process.fd() else {
+                continue;
+            };
+            for fd in fds.flatten() {
+                if let FDTarget::Socket(inode) = fd.target {
+                    owners.insert(inode, process.pid);
+                }
+            }
+        }
+        owners
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::io::{Read, Write};
+        use std::net::{TcpListener, TcpStream};
+        use std::os::fd::AsRawFd;
+
+        use super::*;
+
+        fn inode(stream: &TcpStream) -> u64 {
+            // SAFETY: stat is plain old data and the descriptor is open.
+            let mut stat: libc::stat = unsafe { mem::zeroed() };
+            assert_eq!(unsafe { libc::fstat(stream.as_raw_fd(), &mut stat) }, 0);
+            stat.st_ino
+        }
+
+        #[test]
+        fn test_parse_done_and_error() {
+            let mut done = vec![];
+            done.extend(20_u32.to_ne_bytes());
+            done.extend((libc::NLMSG_DONE as u16).to_ne_bytes());
+            done.extend([0; 14]);
+            let mut counters = HashMap::new();
+            assert!(parse(&done, &mut counters).unwrap());
+
+            let mut error = done.clone();
+            error[4..6].copy_from_slice(&(libc::NLMSG_ERROR as u16).to_ne_bytes());
+            error[16..20].copy_from_slice(&(-libc::EACCES).to_ne_bytes());
+            let e = parse(&error, &mut counters).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::PermissionDenied);
+            assert!(counters.is_empty());
+        }
+
+        #[test]
+        fn test_tcp_counters() {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+            let (mut server, _) = listener.accept().unwrap();
+            client.write_all(&[1; 4096]).unwrap();
+            let mut received = [0; 4096];
+            server.read_exact(&mut received).unwrap();
+            server.write_all(&[2; 1000]).unwrap();
+            client.read_exact(&mut received[..1000]).unwrap();
+
+            let counters = read_counters().unwrap();
+            // the acknowledged bytes can include the SYN
+            let (received, sent) = counters[&inode(&client)];
+            assert_eq!(received, 1000);
+            assert!((4096..=4097).contains(&sent));
+            let (received, sent) = counters[&inode(&server)];
+            assert_eq!(received, 4096);
+            assert!((1000..=1001).contains(&sent));
+            let owners = socket_owners();
+            assert_eq!(owners[&inode(&client)], std::process::id() as i32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throughput() {
+        let previous = HashMap::from([(100, (1_000, 500)), (101, (0, 0))]);
+        let current = HashMap::from([
+            (100, (3_000, 700)),
+            (101, (400, 100)),
+            // opened since the previous sample
+            (102, (600, 0)),
+            // not owned by a process that can be read
+            (103, (1_000_000, 0)),
+        ]);
+        let owners = HashMap::from([(100, 1), (101, 1), (102, 2)]);
+        let throughput = throughput(&previous, &current, &owners, 2.0);
+        assert_eq!(
+            throughput[&1],
+            Throughput {
+                received: 1_200,
+                sent: 150
+            }
+        );
+        assert_eq!(
+            throughput[&2],
+            Throughput {
+                received: 300,
+                sent: 0
+            }
+        );
+        assert_eq!(throughput.len(), 2);
+    }
+}
@@ -0,0 +1,259 @@
+//! Fork, exec and exit events from the kernel's proc connector, so that
+//! processes appear and exit between two refreshes of the process list.
+//! Listening takes `CAP_NET_ADMIN`; without it, or without the
+//! `proc-connector` feature, brt only samples.
+
+use std::io;
+use std::sync::mpsc::{self, Receiver};
+
+/// What happened to a process, by pid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcEvent {
+    /// A new process, not a new thread.
+    Fork(i32),
+    /// The process runs another program.
+    Exec(i32),
+    /// The process exited; it may linger as a zombie until reaped.
+    Exit(i32),
+}
+
+/// The events received on a thread of their own.
+#[derive(Debug)]
+pub struct ProcEvents {
+    receiver: Receiver<ProcEvent>,
+}
+
+impl ProcEvents {
+    /// Subscribes to the events.
+    pub fn listen() -> io::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        connector::listen(sender)?;
+        Ok(Self::from_receiver(receiver))
+    }
+
+    /// The events sent through a channel, e.g. by a test.
+    pub fn from_receiver(receiver: Receiver<ProcEvent>) -> Self {
+        Self { receiver }
+    }
+
+    /// The events since the previous call.
+    pub fn drain(&self) -> Vec<ProcEvent> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+#[cfg(not(all(feature = "proc-connector", target_os = "linux")))]
+mod connector {
+    use std::io;
+    use std::sync::mpsc::Sender;
+
+    use super::ProcEvent;
+
+    pub fn listen(_sender: Sender<ProcEvent>) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "brt was built without the proc-connector feature",
+        ))
+    }
+}
+
+/// The netlink side of the connector, `linux/cn_proc.h`.
+#[cfg(all(feature = "proc-connector", target_os = "linux"))]
+mod connector {
+    use std::io;
+    use std::mem;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::sync::mpsc::Sender;
+
+    use super::ProcEvent;
+
+    const NETLINK_CONNECTOR: i32 = 11;
+    /// The index and value of the proc connector, also its multicast group.
+    const CN_IDX_PROC: u32 = 1;
+    const CN_VAL_PROC: u32 = 1;
+    const PROC_CN_MCAST_LISTEN: u32 = 1;
+    const PROC_EVENT_FORK: u32 = 0x1;
+    const PROC_EVENT_EXEC: u32 = 0x2;
+    const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+    const HEADER_LENGTH: usize = 16;
+    /// The length of `struct cn_msg` before its data.
+    const CN_MSG_LENGTH: usize = 20;
+    /// The offset of the event data in `struct proc_event`, after what
+    /// happened, the cpu and a timestamp.
+    const EVENT_DATA_OFFSET: usize = 16;
+
+    fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+        let value = bytes.get(offset..offset + 4)?;
+        Some(u32::from_ne_bytes(value.try_into().ok()?))
+    }
+
+    fn i32_at(bytes: &[u8], offset: usize) -> Option<i32> {
+        u32_at(bytes, offset).map(|value| value as i32)
+    }
+
+    /// The message that subscribes to the events.
+    pub(super) fn subscription() -> Vec<u8> {
+        let length = (HEADER_LENGTH + CN_MSG_LENGTH + 4) as u32;
+        let mut message = Vec::with_capacity(length as usize);
+        message.extend(length.to_ne_bytes());
+        message.extend((libc::NLMSG_DONE as u16).to_ne_bytes());
+        // flags, sequence number and port id
+        message.extend([0; 10]);
+        message.extend(CN_IDX_PROC.to_ne_bytes());
+        message.extend(CN_VAL_PROC.to_ne_bytes());
+        // sequence number and acknowledgement
+        message.extend([0; 8]);
+        message.extend(4_u16.to_ne_bytes());
+        // flags
+        message.extend([0; 2]);
+        message.extend(PROC_CN_MCAST_LISTEN.to_ne_bytes());
+        message
+    }
+
+    /// The event of a single netlink message; events of threads, and those
+    /// brt doesn't need, are left out.
+    pub(super) fn parse_event(message: &[u8]) -> Option<ProcEvent> {
+        let event = message.get(HEADER_LENGTH + CN_MSG_LENGTH..)?;
+        let data = EVENT_DATA_OFFSET;
+        let (pid, tgid, kind): (_, _, fn(i32) -> ProcEvent) = match u32_at(event, 0)? {
+            // the parent's pid and tgid come first
+            PROC_EVENT_FORK => (
+                i32_at(event, data + 8)?,
+                i32_at(event, data + 12)?,
+                ProcEvent::Fork,
+            ),
+            PROC_EVENT_EXEC => (
+                i32_at(event, data)?,
+                i32_at(event, data + 4)?,
+                ProcEvent::Exec,
+            ),
+            PROC_EVENT_EXIT => (
+                i32_at(event, data)?,
+                i32_at(event, data + 4)?,
+                ProcEvent::Exit,
+            ),
+            _ => return None,
+        };
+        (pid == tgid).then_some(kind(pid))
+    }
+
+    pub fn listen(sender: Sender<ProcEvent>) -> io::Result<()> {
+        // SAFETY: socket has no memory safety requirements.
+        let socket = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                NETLINK_CONNECTOR,
+            )
+        };
+        if socket < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the descriptor was just created and is owned by nobody else.
+        let socket = unsafe { OwnedFd::from_raw_fd(socket) };
+        // SAFETY: an all zero sockaddr_nl is valid; the kernel picks the
+        // port id.
+        let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as u16;
+        address.nl_groups = CN_IDX_PROC;
+        // SAFETY: the address outlives the call.
+        let bound = unsafe {
+            libc::bind(
+                socket.as_raw_fd(),
+                (&address as *const libc::sockaddr_nl).cast(),
+                mem::size_of::<libc::sockaddr_nl>() as u32,
+            )
+        };
+        if bound < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let subscription = subscription();
+        // SAFETY: the message outlives the call.
+        let sent = unsafe {
+            libc::send(
+                socket.as_raw_fd(),
+                subscription.as_ptr().cast(),
+                subscription.len(),
+                0,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        std::thread::spawn(move || {
+            let mut buffer = vec![0; 4096];
+            loop {
+                // SAFETY: the buffer is valid for its length.
+                let received = unsafe {
+                    libc::recv(
+                        socket.as_raw_fd(),
+                        buffer.as_mut_ptr().cast(),
+                        buffer.len(),
+                        0,
+                    )
+                };
+                if received < 0 {
+                    let error = io::Error::last_os_error();
+                    // events were dropped while the buffer was full, which
+                    // the next refresh catches up with
+                    if matches!(error.raw_os_error(), Some(libc::ENOBUFS | libc::EINTR)) {
+                        continue;
+                    }
+                    tracing::warn!("Stopped listening to process events: {error}");
+                    return;
+                }
+                let Some(event) = parse_event(&buffer[..received as usize]) else {
+                    continue;
+                };
+                // brt is done with the events
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// A netlink message with `what` and the event data.
+        fn message(what: u32, data: &[i32]) -> Vec<u8> {
+            let mut message = vec![0; HEADER_LENGTH + CN_MSG_LENGTH];
+            message.extend(what.to_ne_bytes());
+            // the cpu and the timestamp
+            message.extend([0; 12]);
+            for value in data {
+                message.extend(value.to_ne_bytes());
+            }
+            message
+        }
+
+        #[test]
+        fn test_subscription() {
+            let subscription = subscription();
+            assert_eq!(subscription.len(), 40);
+            assert_eq!(u32_at(&subscription, 0), Some(40));
+            assert_eq!(u32_at(&subscription, HEADER_LENGTH), Some(CN_IDX_PROC));
+            assert_eq!(u32_at(&subscription, 36), Some(PROC_CN_MCAST_LISTEN));
+        }
+
+        #[test]
+        fn test_parse_event() {
+            let fork = message(PROC_EVENT_FORK, &[1, 1, 42, 42]);
+            assert_eq!(parse_event(&fork), Some(ProcEvent::Fork(42)));
+            let exec = message(PROC_EVENT_EXEC, &[42, 42]);
+            assert_eq!(parse_event(&exec), Some(ProcEvent::Exec(42)));
+            let exit = message(PROC_EVENT_EXIT, &[42, 42, 0, 17, 1, 1]);
+            assert_eq!(parse_event(&exit), Some(ProcEvent::Exit(42)));
+
+            // a new thread of process 42
+            let thread = message(PROC_EVENT_FORK, &[42, 42, 43, 42]);
+            assert_eq!(parse_event(&thread), None);
+            // the acknowledgement of the subscription
+            assert_eq!(parse_event(&message(0, &[0, 0])), None);
+            assert_eq!(parse_event(&fork[..HEADER_LENGTH]), None);
+        }
+    }
+}
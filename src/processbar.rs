@@ -1,54 +1,244 @@
-pub mod model;
+use std::collections::{HashMap, VecDeque};
 
 use anyhow::{Context, Result};
+use brt_core::model::{format_bytes, format_elapsed, get_cpu, get_memory, get_runtime, username};
+use brt_core::source::{system_source, ProcSource};
 use clap::Parser;
-use log::debug;
-use model::get_memory;
 use owo_colors::OwoColorize;
-use procfs::process::Process;
-use procfs::{page_size, ticks_per_second, CpuInfo, Current, Uptime};
+use serde::Serialize;
+use tracing::debug;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
+    /// The processes to sample, e.g. `--pid 1 --pid 42` or `--pid 1,42`.
+    #[arg(long, required = true, value_delimiter = ',')]
+    pid: Vec<i32>,
+    /// Also sample the children of the processes, and theirs in turn.
     #[arg(long)]
+    all_children: bool,
+    /// Print the sampled fields as a JSON object per process instead of a
+    /// sentence or a table.
+    #[arg(long)]
+    json: bool,
+}
+
+/// What is read of the process.
+#[derive(Debug, PartialEq, Serialize)]
+struct Sample {
     pid: i32,
+    comm: String,
+    /// The cpu used since the process started, in percent of all cores.
+    cpu: f64,
+    /// The resident memory in bytes.
+    rss: u64,
+    /// `None` when the uid can't be read or has no user.
+    user: Option<String>,
+    /// The seconds since the process started.
+    uptime: u64,
+}
+
+fn sample(source: &dyn ProcSource, pid: i32) -> Result<Sample> {
+    let stat = source
+        .stat(pid)
+        .with_context(|| format!("Pid {pid} not found."))?;
+    Ok(Sample {
+        pid,
+        cpu: get_cpu(source, &stat),
+        rss: get_memory(source, pid),
+        user: source.uid(pid).ok().and_then(username),
+        uptime: get_runtime(source, &stat),
+        comm: stat.comm,
+    })
+}
+
+/// `pids`, each followed by its descendants, breadth first, and every pid
+/// once.
+fn with_descendants(source: &dyn ProcSource, pids: &[i32]) -> Result<Vec<i32>> {
+    let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+    for pid in source.pids().context("Unable to list the processes.")? {
+        if let Ok(stat) = source.stat(pid) {
+            children.entry(stat.ppid).or_default().push(pid);
+        }
+    }
+    let mut all = vec![];
+    for &pid in pids {
+        let mut queue = VecDeque::from([pid]);
+        while let Some(pid) = queue.pop_front() {
+            if all.contains(&pid) {
+                continue;
+            }
+            all.push(pid);
+            if let Some(children) = children.get_mut(&pid) {
+                children.sort_unstable();
+                queue.extend(children.iter());
+            }
+        }
+    }
+    Ok(all)
+}
+
+/// The samples of the `requested` processes and, with `all_children`, of
+/// their descendants. A descendant may well have exited since the processes
+/// were listed and is left out, but a requested process that can't be read
+/// is an error.
+fn sample_all(
+    source: &dyn ProcSource,
+    requested: &[i32],
+    all_children: bool,
+) -> Result<Vec<Sample>> {
+    let pids = if all_children {
+        with_descendants(source, requested)?
+    } else {
+        requested.to_vec()
+    };
+    debug!("Checking pids {:?}...", pids);
+    let mut samples = vec![];
+    for pid in pids {
+        match sample(source, pid) {
+            Ok(sample) => samples.push(sample),
+            Err(e) if !requested.contains(&pid) => debug!("{e}"),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(samples)
+}
+
+/// A row per sample under a header, the columns padded before they are
+/// colored so they line up.
+fn table(samples: &[Sample]) -> Vec<String> {
+    let width = samples
+        .iter()
+        .map(|sample| sample.comm.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("COMM".len());
+    let user_width = samples
+        .iter()
+        .map(|sample| sample.user.as_deref().unwrap_or("?").chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("USER".len());
+    let mut lines = vec![format!(
+        "{:>7} {:<width$} {:<user_width$} {:>6} {:>8} {:>7}",
+        "PID", "COMM", "USER", "CPU%", "RSS", "UPTIME"
+    )];
+    for sample in samples {
+        lines.push(format!(
+            "{} {} {:<user_width$} {} {} {:>7}",
+            format!("{:>7}", sample.pid).yellow(),
+            format!("{:<width$}", sample.comm).green(),
+            sample.user.as_deref().unwrap_or("?"),
+            format!("{:>6.2}", sample.cpu).yellow(),
+            format!("{:>8}", format_bytes(sample.rss)).yellow(),
+            format_elapsed(sample.uptime),
+        ));
+    }
+    lines
 }
 
-#[allow(dead_code)]
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    let pid = args.pid;
-
-    debug!("Checking pid {}...", pid);
-    let process = Process::new(pid).with_context(|| format!("Pid {pid} not found."))?;
-    let stat = process.stat().unwrap();
-
-    debug!("ticks per second: {}", ticks_per_second());
-    debug!("pagesize: {}", page_size());
-    let usage = stat.utime / ticks_per_second() + stat.stime / ticks_per_second();
-    debug!("usage {} ", usage);
-
-    let uptime = Uptime::current().unwrap().uptime_duration().as_secs();
-    debug!("Uptime: {}", uptime);
-    let starttime = stat.starttime / ticks_per_second();
-    debug!("Starttime: {}", starttime);
-    let runtime = uptime - starttime;
-    debug!("runtime: {}", runtime);
-    let num_cores = CpuInfo::current().unwrap().num_cores();
-    debug!("num cores: {}", num_cores);
-    let percentage = usage as f64 * 100.0 / runtime as f64 / num_cores as f64;
-
-    let memory = get_memory(&process);
-
-    println!(
-        "Process {} ({}) has used {:.2}% of the cpu and is using {} bytes of memory.",
-        stat.comm.green(),
-        pid.yellow(),
-        percentage.yellow(),
-        memory.yellow(),
-    );
+    let samples = sample_all(system_source().as_ref(), &args.pid, args.all_children)?;
+
+    if args.json {
+        for sample in &samples {
+            println!("{}", serde_json::to_string(sample)?);
+        }
+        return Ok(());
+    }
+    if let [sample] = samples.as_slice() {
+        println!(
+            "Process {} ({}) has used {:.2}% of the cpu and is using {} bytes of memory.",
+            sample.comm.green(),
+            sample.pid.yellow(),
+            sample.cpu.yellow(),
+            sample.rss.yellow(),
+        );
+        return Ok(());
+    }
+    for line in table(&samples) {
+        println!("{line}");
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use brt_core::source::fake::{FakeProcSource, FakeProcess};
+
+    use super::*;
+
+    #[test]
+    fn test_sample() {
+        let source =
+            FakeProcSource::default().with(7, FakeProcess::running(7, "sleep", 500, 50_000));
+        let sample = sample(&source, 7).unwrap();
+        assert_eq!(sample.comm, "sleep");
+        // five seconds of cpu in the 500 seconds since it started, on 4 cores
+        assert_eq!(sample.cpu, 0.25);
+        assert_eq!(sample.rss, 512 * 4096);
+        assert_eq!(sample.uptime, 500);
+        let json = serde_json::to_value(&sample).unwrap();
+        assert_eq!(
+            json.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["comm", "cpu", "pid", "rss", "uptime", "user"]
+        );
+        assert_eq!(json["pid"], 7);
+        assert!(super::sample(&source, 8).is_err());
+    }
+
+    #[test]
+    fn test_with_descendants() {
+        let process = |pid, ppid| FakeProcess::running(pid, "sh", 0, 0).with_ppid(ppid);
+        let source = FakeProcSource::default()
+            .with(1, process(1, 0))
+            .with(10, process(10, 1))
+            .with(20, process(20, 10))
+            .with(21, process(21, 10))
+            .with(30, process(30, 1))
+            .with(40, process(40, 2));
+        assert_eq!(with_descendants(&source, &[10, 20]).unwrap(), [10, 20, 21]);
+        assert_eq!(
+            with_descendants(&source, &[1]).unwrap(),
+            [1, 10, 30, 20, 21]
+        );
+
+        let pids = |samples: Vec<Sample>| samples.iter().map(|s| s.pid).collect::<Vec<_>>();
+        assert_eq!(
+            pids(sample_all(&source, &[10], true).unwrap()),
+            [10, 20, 21]
+        );
+        assert_eq!(pids(sample_all(&source, &[10], false).unwrap()), [10]);
+        // a mistyped pid isn't skipped like a child that exited
+        let error = sample_all(&source, &[10, 8], true).unwrap_err();
+        assert_eq!(error.to_string(), "Pid 8 not found.");
+    }
+
+    #[test]
+    fn test_table() {
+        let sample = |pid, comm: &str, user: Option<&str>| Sample {
+            pid,
+            comm: comm.to_string(),
+            cpu: 1.5,
+            rss: 2048,
+            user: user.map(str::to_string),
+            uptime: 90,
+        };
+        let lines: Vec<String> =
+            table(&[sample(1, "init", Some("root")), sample(42, "sleep", None)])
+                .iter()
+                .map(|line| String::from_utf8(strip_ansi_escapes::strip(line)).unwrap())
+                .collect();
+        assert_eq!(
+            lines,
+            [
+                "    PID COMM  USER   CPU%      RSS  UPTIME",
+                "      1 init  root   1.50     2KiB   1m30s",
+                "     42 sleep ?      1.50     2KiB   1m30s",
+            ]
+        );
+    }
+}
@@ -0,0 +1,416 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use crossterm::event::KeyEvent;
+use serde::{Deserialize, Serialize};
+
+use crate::action::Action;
+use crate::model::BrtProcess;
+use crate::symbols::symbols;
+
+const SEEK_STEP: Duration = Duration::from_secs(10);
+const SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+/// One refresh of the process list, as written to a recording file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sample {
+    /// Milliseconds since the start of the recording.
+    pub elapsed: u64,
+    pub processes: Vec<BrtProcess>,
+}
+
+/// Writes every refreshed process list as a json line to a file.
+pub struct Recorder {
+    start: Instant,
+    writer: BufWriter<File>,
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder")
+            .field("start", &self.start)
+            .finish()
+    }
+}
+
+impl Recorder {
+    pub fn new(path: &Path) -> Result<Self> {
+        let file = File::create(path).wrap_err_with(|| format!("Unable to create {path:?}"))?;
+        Ok(Self {
+            start: Instant::now(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, processes: &HashMap<i32, BrtProcess>) -> Result<()> {
+        let sample = Sample {
+            elapsed: self.start.elapsed().as_millis() as u64,
+            processes: processes.values().cloned().collect(),
+        };
+        serde_json::to_writer(&mut self.writer, &sample)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Plays back a recording made by the [`Recorder`].
+#[derive(Debug)]
+pub struct Replay {
+    samples: Vec<Sample>,
+    position: Duration,
+    last_instant: Instant,
+    speed: usize,
+    pub paused: bool,
+}
+
+impl Replay {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = File::open(path).wrap_err_with(|| format!("Unable to open {path:?}"))?;
+        let mut samples = Vec::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let sample: Sample = serde_json::from_str(&line)
+                .wrap_err_with(|| format!("Invalid sample on line {} of {path:?}", number + 1))?;
+            samples.push(sample);
+        }
+        Self::new(samples)
+    }
+
+    pub fn new(mut samples: Vec<Sample>) -> Result<Self> {
+        if samples.is_empty() {
+            return Err(eyre!("The recording does not contain any samples"));
+        }
+        samples.sort_by_key(|s| s.elapsed);
+        for process in samples.iter_mut().flat_map(|s| s.processes.iter_mut()) {
+            process.replayed = true;
+        }
+        Ok(Self {
+            samples,
+            position: Duration::ZERO,
+            last_instant: Instant::now(),
+            speed: 2,
+            paused: false,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.samples.last().map(|s| s.elapsed).unwrap_or_default())
+    }
+
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    pub fn speed(&self) -> f64 {
+        SPEEDS[self.speed]
+    }
+
+    /// Moves the playback position forward by the wall clock time since the
+    /// previous call, scaled by the current speed.
+    pub fn advance(&mut self) {
+        let now = Instant::now();
+        if !self.paused {
+            let delta = now.duration_since(self.last_instant).mul_f64(self.speed());
+            self.position = (self.position + delta).min(self.duration());
+        }
+        self.last_instant = now;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.advance();
+        self.paused = !self.paused;
+    }
+
+    pub fn seek_forward(&mut self) {
+        self.advance();
+        self.position = (self.position + SEEK_STEP).min(self.duration());
+    }
+
+    pub fn seek_backward(&mut self) {
+        self.advance();
+        self.position = self.position.saturating_sub(SEEK_STEP);
+    }
+
+    pub fn speed_up(&mut self) {
+        self.advance();
+        self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn speed_down(&mut self) {
+        self.advance();
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    /// The most recent sample at the current playback position.
+    pub fn current(&self) -> &Sample {
+        let position = self.position.as_millis() as u64;
+        let index = self
+            .samples
+            .partition_point(|s| s.elapsed <= position)
+            .saturating_sub(1);
+        &self.samples[index]
+    }
+
+    pub fn current_processes(&self) -> HashMap<i32, BrtProcess> {
+        self.current()
+            .processes
+            .iter()
+            .map(|p| (p.pid, p.clone()))
+            .collect()
+    }
+
+    pub fn status(&self) -> String {
+        let symbols = symbols();
+        let state = if self.paused {
+            symbols.paused
+        } else {
+            symbols.playing
+        };
+        format!(
+            "{} {}/{} {}{}",
+            state,
+            format_duration(self.position),
+            format_duration(self.duration()),
+            symbols.times,
+            self.speed()
+        )
+    }
+}
+
+/// What is written to an action log: the keys as typed, and the actions
+/// the app handled.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LoggedEvent {
+    Key(KeyEvent),
+    Action(Action),
+}
+
+/// An event of an action log, as a json line.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Milliseconds since the start of the log.
+    pub elapsed: u64,
+    pub event: LoggedEvent,
+}
+
+/// Writes the keys and the actions of a session to a file, to attach to a
+/// bug report. The ticks and frames are left out, as they only tell the
+/// time.
+pub struct ActionRecorder {
+    start: Instant,
+    writer: BufWriter<File>,
+}
+
+impl std::fmt::Debug for ActionRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ActionRecorder")
+            .field("start", &self.start)
+            .finish()
+    }
+}
+
+impl ActionRecorder {
+    pub fn new(path: &Path) -> Result<Self> {
+        let file = File::create(path).wrap_err_with(|| format!("Unable to create {path:?}"))?;
+        Ok(Self {
+            start: Instant::now(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, event: LoggedEvent) -> Result<()> {
+        if let LoggedEvent::Action(Action::Tick | Action::Render | Action::Sampled(_)) = event {
+            return Ok(());
+        }
+        let entry = LogEntry {
+            elapsed: self.start.elapsed().as_millis() as u64,
+            event,
+        };
+        serde_json::to_writer(&mut self.writer, &entry)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Types the keys of an action log again at the pace they were typed. The
+/// logged actions are not sent themselves, as the keys bring them about
+/// again; they are in the log to be read.
+#[derive(Debug)]
+pub struct ActionReplay {
+    keys: VecDeque<(Duration, KeyEvent)>,
+    /// When the first key was asked for.
+    start: Option<Instant>,
+}
+
+impl ActionReplay {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = File::open(path).wrap_err_with(|| format!("Unable to open {path:?}"))?;
+        let mut entries = Vec::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: LogEntry = serde_json::from_str(&line)
+                .wrap_err_with(|| format!("Invalid entry on line {} of {path:?}", number + 1))?;
+            entries.push(entry);
+        }
+        Ok(Self::new(entries))
+    }
+
+    pub fn new(entries: Vec<LogEntry>) -> Self {
+        let mut keys: Vec<(Duration, KeyEvent)> = entries
+            .into_iter()
+            .filter_map(|entry| match entry.event {
+                LoggedEvent::Key(key) => Some((Duration::from_millis(entry.elapsed), key)),
+                LoggedEvent::Action(_) => None,
+            })
+            .collect();
+        keys.sort_by_key(|(elapsed, _)| *elapsed);
+        Self {
+            keys: keys.into(),
+            start: None,
+        }
+    }
+
+    /// The keys typed by `now`, counting from the first call.
+    pub fn due(&mut self, now: Instant) -> Vec<KeyEvent> {
+        let elapsed = now.saturating_duration_since(*self.start.get_or_insert(now));
+        let count = self
+            .keys
+            .iter()
+            .take_while(|(typed, _)| *typed <= elapsed)
+            .count();
+        self.keys.drain(..count).map(|(_, key)| key).collect()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+
+    use super::*;
+
+    fn sample(elapsed: u64, pid: i32) -> Sample {
+        let mut process = BrtProcess::new();
+        process.pid = pid;
+        Sample {
+            elapsed,
+            processes: vec![process],
+        }
+    }
+
+    fn replay() -> Replay {
+        Replay::new(vec![sample(0, 1), sample(5_000, 2), sample(30_000, 3)]).unwrap()
+    }
+
+    #[test]
+    fn test_replay_empty() {
+        assert!(Replay::new(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_replay_seek() {
+        let mut replay = replay();
+        replay.paused = true;
+        assert_eq!(replay.current().processes[0].pid, 1);
+        assert!(replay.current_processes()[&1].replayed);
+        replay.seek_forward();
+        assert_eq!(replay.position(), Duration::from_secs(10));
+        assert_eq!(replay.current().processes[0].pid, 2);
+        replay.seek_forward();
+        replay.seek_forward();
+        assert_eq!(replay.position(), replay.duration());
+        assert_eq!(replay.current().processes[0].pid, 3);
+        replay.seek_backward();
+        replay.seek_backward();
+        replay.seek_backward();
+        replay.seek_backward();
+        assert_eq!(replay.position(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_replay_speed() {
+        let mut replay = replay();
+        assert_eq!(replay.speed(), 1.0);
+        replay.speed_up();
+        assert_eq!(replay.speed(), 2.0);
+        for _ in 0..10 {
+            replay.speed_down();
+        }
+        assert_eq!(replay.speed(), 0.25);
+    }
+
+    #[test]
+    fn test_record_and_replay() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("brt-recording-{}.jsonl", std::process::id()));
+        let mut recorder = Recorder::new(&path)?;
+        let mut processes = HashMap::new();
+        processes.insert(42, sample(0, 42).processes[0].clone());
+        recorder.record(&processes)?;
+        recorder.record(&processes)?;
+        let replay = Replay::from_file(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(replay.samples.len(), 2);
+        assert!(replay.current_processes().contains_key(&42));
+        Ok(())
+    }
+
+    #[test]
+    fn test_action_log() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("brt-actions-{}.jsonl", std::process::id()));
+        let mut recorder = ActionRecorder::new(&path)?;
+        let key = KeyEvent::from(KeyCode::Char('k'));
+        recorder.record(LoggedEvent::Key(key))?;
+        recorder.record(LoggedEvent::Action(Action::Tick))?;
+        recorder.record(LoggedEvent::Action(Action::Terminate))?;
+        let text = std::fs::read_to_string(&path)?;
+        let replay = ActionReplay::from_file(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(text.lines().count(), 2);
+        assert!(text
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with(r#""event":{"Action":"Terminate"}}"#));
+        assert_eq!(replay.keys.len(), 1);
+
+        let entry = |elapsed, key| LogEntry {
+            elapsed,
+            event: LoggedEvent::Key(KeyEvent::from(key)),
+        };
+        let mut replay = ActionReplay::new(vec![
+            entry(500, KeyCode::Down),
+            entry(0, KeyCode::Down),
+            entry(1_500, KeyCode::Enter),
+        ]);
+        let start = Instant::now();
+        assert_eq!(replay.due(start), [KeyEvent::from(KeyCode::Down)]);
+        assert_eq!(replay.due(start + Duration::from_millis(600)).len(), 1);
+        assert!(!replay.is_done());
+        assert_eq!(
+            replay.due(start + Duration::from_secs(2)),
+            [KeyEvent::from(KeyCode::Enter)]
+        );
+        assert!(replay.is_done());
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+//! A Unix domain socket through which other programs drive a running brt,
+//! with JSON-RPC 2.0 requests and responses of a line each, e.g.
+//!
+//! ```text
+//! {"jsonrpc": "2.0", "id": 1, "method": "set_sort", "params": {"order": "Cpu", "descending": true}}
+//! {"jsonrpc":"2.0","id":1,"result":null}
+//! ```
+//!
+//! The methods are `get_processes`, `set_sort`, `export` and `quit`.
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream as StdUnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc::UnboundedSender, watch};
+use tracing::{debug, warn};
+
+use crate::action::Action;
+use crate::components::process::Order;
+use crate::model::BrtProcess;
+
+const SOCKET_FILE: &str = "brt.sock";
+
+/// The error codes of the JSON-RPC specification.
+const PARSE_ERROR: i32 = -32700;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const INTERNAL_ERROR: i32 = -32603;
+
+/// `$XDG_RUNTIME_DIR/brt.sock`, or a socket of the user in the temporary
+/// directory when there is no runtime directory.
+pub fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(directory) => PathBuf::from(directory).join(SOCKET_FILE),
+        None => std::env::temp_dir().join(format!("brt-{}.sock", uzers::get_current_uid())),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct SortParams {
+    order: Order,
+    #[serde(default)]
+    descending: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    path: PathBuf,
+}
+
+/// Answers the requests on the socket with the processes the process table
+/// publishes, and turns the commands into actions.
+#[derive(Debug)]
+pub struct RpcServer {
+    path: PathBuf,
+    processes: watch::Receiver<Arc<Vec<BrtProcess>>>,
+    /// Whether the socket at `path` is ours to remove.
+    bound: bool,
+}
+
+impl RpcServer {
+    pub fn new(path: PathBuf, processes: watch::Receiver<Arc<Vec<BrtProcess>>>) -> Self {
+        Self {
+            path,
+            processes,
+            bound: false,
+        }
+    }
+
+    /// Binds the socket and answers its connections in the background. A
+    /// socket left behind by a brt that didn't exit cleanly is replaced.
+    pub fn listen(&mut self, action_tx: UnboundedSender<Action>) -> Result<()> {
+        if self.path.exists() {
+            if StdUnixStream::connect(&self.path).is_ok() {
+                return Err(eyre!("Another brt listens on {}", self.path.display()));
+            }
+            fs::remove_file(&self.path)
+                .wrap_err_with(|| format!("Unable to remove {}", self.path.display()))?;
+        }
+        let listener = UnixListener::bind(&self.path)
+            .wrap_err_with(|| format!("Unable to listen on {}", self.path.display()))?;
+        self.bound = true;
+        // whoever may connect may kill and renice the processes of the user
+        if let Err(e) = fs::set_permissions(&self.path, fs::Permissions::from_mode(0o600)) {
+            self.close();
+            return Err(e).wrap_err_with(|| {
+                format!("Unable to restrict the access to {}", self.path.display())
+            });
+        }
+        let processes = self.processes.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(serve(stream, processes.clone(), action_tx.clone()));
+                    }
+                    Err(e) => {
+                        warn!("Unable to accept a connection: {e}");
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Removes the socket, when it was bound.
+    pub fn close(&mut self) {
+        if std::mem::take(&mut self.bound) {
+            if let Err(e) = fs::remove_file(&self.path) {
+                warn!("Unable to remove {}: {e}", self.path.display());
+            }
+        }
+    }
+}
+
+/// Answers the requests of a connection until it is closed.
+async fn serve(
+    stream: UnixStream,
+    processes: watch::Receiver<Arc<Vec<BrtProcess>>>,
+    action_tx: UnboundedSender<Action>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                debug!("Closing the connection: {e}");
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut response = respond(&line, &processes.borrow(), &action_tx).to_string();
+        response.push('\n');
+        if let Err(e) = writer.write_all(response.as_bytes()).await {
+            debug!("Closing the connection: {e}");
+            break;
+        }
+    }
+}
+
+/// The response to a single request.
+fn respond(line: &str, processes: &[BrtProcess], action_tx: &UnboundedSender<Action>) -> Value {
+    let request: Request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return error(Value::Null, PARSE_ERROR, e.to_string()),
+    };
+    debug!(method = request.method, "Handling a request.");
+    let id = request.id;
+    let send = |action: Action| match action_tx.send(action) {
+        Ok(()) => success(id.clone(), Value::Null),
+        Err(e) => error(id.clone(), INTERNAL_ERROR, e.to_string()),
+    };
+    match request.method.as_str() {
+        "get_processes" => match serde_json::to_value(processes) {
+            Ok(processes) => success(id, processes),
+            Err(e) => error(id, INTERNAL_ERROR, e.to_string()),
+        },
+        "set_sort" => match serde_json::from_value::<SortParams>(request.params) {
+            Ok(params) => send(Action::OrderBy(params.order, params.descending)),
+            Err(e) => error(id, INVALID_PARAMS, e.to_string()),
+        },
+        "export" => match serde_json::from_value::<ExportParams>(request.params) {
+            Ok(params) => match export(&params.path, processes) {
+                Ok(()) => success(id, json!({ "processes": processes.len() })),
+                Err(e) => error(id, INTERNAL_ERROR, e.to_string()),
+            },
+            Err(e) => error(id, INVALID_PARAMS, e.to_string()),
+        },
+        "quit" => send(Action::Quit),
+        method => error(id, METHOD_NOT_FOUND, format!("Unknown method {method}")),
+    }
+}
+
+/// Writes the processes as JSON to `path`.
+fn export(path: &Path, processes: &[BrtProcess]) -> io::Result<()> {
+    let text = serde_json::to_string_pretty(processes)?;
+    fs::write(path, text)
+}
+
+fn success(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error(id: Value, code: i32, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn processes() -> Vec<BrtProcess> {
+        [(1, "init"), (42, "sshd")]
+            .into_iter()
+            .map(|(pid, program)| BrtProcess {
+                pid,
+                program: program.to_string(),
+                ..BrtProcess::new()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_respond() {
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        let processes = processes();
+        let respond = |line: &str| respond(line, &processes, &action_tx);
+
+        let response = respond(r#"{"jsonrpc": "2.0", "id": 1, "method": "get_processes"}"#);
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"][1]["program"], "sshd");
+
+        let response = respond(
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "set_sort", "params": {"order": "Cpu", "descending": true}}"#,
+        );
+        assert_eq!(
+            response,
+            json!({ "jsonrpc": "2.0", "id": 2, "result": null })
+        );
+        assert_eq!(action_rx.try_recv(), Ok(Action::OrderBy(Order::Cpu, true)));
+
+        let response = respond(r#"{"id": 3, "method": "set_sort", "params": {"order": "Size"}}"#);
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        respond(r#"{"id": 4, "method": "quit"}"#);
+        assert_eq!(action_rx.try_recv(), Ok(Action::Quit));
+
+        let response = respond(r#"{"id": 5, "method": "restart"}"#);
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(respond("{")["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn test_export() {
+        let (action_tx, _action_rx) = mpsc::unbounded_channel();
+        let path = std::env::temp_dir().join(format!("brt-export-{}.json", std::process::id()));
+        let request = json!({ "id": 1, "method": "export", "params": { "path": path } });
+        let response = respond(&request.to_string(), &processes(), &action_tx);
+        assert_eq!(response["result"]["processes"], 2);
+        let exported: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(exported[0]["pid"], 1);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_socket() {
+        let path = std::env::temp_dir().join(format!("brt-rpc-{}.sock", std::process::id()));
+        let (publisher, processes) = watch::channel(Arc::new(processes()));
+        let (action_tx, _action_rx) = mpsc::unbounded_channel();
+        let mut server = RpcServer::new(path.clone(), processes);
+        server.listen(action_tx.clone()).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(RpcServer::new(path.clone(), publisher.subscribe())
+            .listen(action_tx)
+            .is_err());
+
+        publisher.send_replace(Arc::default());
+        let mut stream = UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"{\"id\": 7, \"method\": \"get_processes\"}\n")
+            .await
+            .unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        let response: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(response["result"], json!([]));
+
+        server.close();
+        assert!(!path.exists());
+    }
+}
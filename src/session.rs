@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::app::Tab;
+use crate::components::process::{Order, SortKey};
+use crate::utils::get_data_dir;
+
+const SESSION_FILE: &str = "session.json";
+
+/// The state of the interface, saved on quit and restored on the next
+/// launch so brt opens the way it was left.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub order: Order,
+    pub descending: bool,
+    /// Set in the sorter popup, overriding the configured ones.
+    pub tie_breakers: Vec<SortKey>,
+    pub show_logs: bool,
+    /// The filter expression applied to the processes, empty for none.
+    pub filter: String,
+    /// The tab shown last.
+    pub tab: Tab,
+}
+
+impl Session {
+    pub fn path() -> PathBuf {
+        get_data_dir().join(SESSION_FILE)
+    }
+
+    /// The saved session, or the default one when there is none or it can't
+    /// be read.
+    pub fn load(path: &Path) -> Self {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                warn!("Unable to read {}: {e}", path.display());
+                return Self::default();
+            }
+        };
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            warn!("Ignoring the session in {}: {e}", path.display());
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let text = serde_json::to_string_pretty(self)?;
+        fs::write(path, text).wrap_err_with(|| format!("Unable to write {path:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_round_trip() {
+        let directory = std::env::temp_dir().join(format!("brt-session-{}", std::process::id()));
+        let path = directory.join(SESSION_FILE);
+        assert_eq!(Session::load(&path), Session::default());
+
+        let session = Session {
+            order: Order::Memory,
+            descending: true,
+            tie_breakers: vec![SortKey {
+                order: Order::Cpu,
+                descending: true,
+            }],
+            show_logs: true,
+            filter: "user:root cpu>5".to_string(),
+            tab: Tab::Network,
+        };
+        session.save(&path).unwrap();
+        assert_eq!(Session::load(&path), session);
+
+        fs::write(&path, r#"{"order": "Cpu", "unknown": 1}"#).unwrap();
+        let partial = Session::load(&path);
+        assert_eq!(partial.order, Order::Cpu);
+        assert!(!partial.descending);
+
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(Session::load(&path), Session::default());
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use color_eyre::eyre::Result;
+use humansize::{format_size, FormatSizeOptions, BINARY};
+use serde::Serialize;
+
+use crate::components::battery::{PowerReading, PowerSource};
+use crate::model::{format_elapsed, get_processes, BrtProcess};
+use crate::source::ProcSource;
+use crate::system::{CpuTicks, MemoryUsage};
+
+/// The time between the two cpu readings of a snapshot.
+pub const CPU_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatterySnapshot {
+    pub state: String,
+    pub percentage: u32,
+}
+
+impl From<PowerReading> for BatterySnapshot {
+    fn from(reading: PowerReading) -> Self {
+        Self {
+            state: reading.state.to_string().to_lowercase(),
+            percentage: (reading.state_of_charge * 100.0).round() as u32,
+        }
+    }
+}
+
+/// A single sample of the whole system.
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    /// Seconds since boot, if the system tells.
+    pub uptime: Option<u64>,
+    /// Percentage of the cpus that was busy.
+    pub cpu: f64,
+    pub memory: MemoryUsage,
+    pub battery: Option<BatterySnapshot>,
+    pub processes: Vec<BrtProcess>,
+}
+
+impl Snapshot {
+    /// Samples the system; the cpu usage is measured over `interval`.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub fn take(
+        source: &dyn ProcSource,
+        power: &mut dyn PowerSource,
+        interval: Duration,
+    ) -> Result<Self> {
+        let before = CpuTicks::read(source)?;
+        std::thread::sleep(interval);
+        let after = CpuTicks::read(source)?;
+
+        let mut processes: Vec<BrtProcess> = get_processes(source).into_values().collect();
+        processes.sort_by_key(|p| p.pid);
+
+        Ok(Self {
+            uptime: source.uptime().ok(),
+            cpu: after.usage_since(&before),
+            memory: MemoryUsage::read(source)?,
+            battery: power.read().map(BatterySnapshot::from),
+            processes,
+        })
+    }
+
+    pub fn render(&self, format: OutputFormat) -> Result<String> {
+        match format {
+            OutputFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            OutputFormat::Table => Ok(self.table()),
+        }
+    }
+
+    fn table(&self) -> String {
+        let size_options: FormatSizeOptions = FormatSizeOptions::from(BINARY)
+            .space_after_value(false)
+            .decimal_places(1)
+            .decimal_zeroes(0);
+
+        let mut table = String::new();
+        let _ = write!(
+            table,
+            "uptime {}  cpu {:.1}%  mem {}/{} ({:.1}%)",
+            self.uptime
+                .map(format_elapsed)
+                .unwrap_or_else(|| "n/a".to_string()),
+            self.cpu,
+            format_size(self.memory.used(), size_options),
+            format_size(self.memory.total, size_options),
+            self.memory.percentage()
+        );
+        if let Some(battery) = &self.battery {
+            let _ = write!(table, "  battery {}% {}", battery.percentage, battery.state);
+        }
+        let _ = writeln!(table);
+        let _ = writeln!(
+            table,
+            "{:>7} {:<16} {:>7} {:<10} {:>7} {:>6}  COMMAND",
+            "PID", "PROGRAM", "THREADS", "USER", "MEM", "CPU%"
+        );
+        for process in &self.processes {
+            let _ = writeln!(
+                table,
+                "{:>7} {:<16} {:>7} {:<10} {:>7} {:>6.2}  {}",
+                process.pid,
+                process.program,
+                process.number_of_threads,
+                process.username(),
+                format_size(process.resident_memory, size_options),
+                process.cpu,
+                process.command.trim_end()
+            );
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use battery::State;
+
+    use super::*;
+    use crate::source::fake::{FakeProcSource, FakeProcess};
+
+    #[derive(Debug)]
+    struct FakePowerSource;
+
+    impl PowerSource for FakePowerSource {
+        fn read(&mut self) -> Option<PowerReading> {
+            Some(PowerReading {
+                state: State::Charging,
+                state_of_charge: 0.5,
+                charge_limit: None,
+                time_left: None,
+            })
+        }
+    }
+
+    fn snapshot() -> Snapshot {
+        let source = FakeProcSource::default()
+            .with(7, FakeProcess::running(7, "sshd", 0, 0))
+            .with(1, FakeProcess::running(1, "init", 0, 0));
+        Snapshot::take(&source, &mut FakePowerSource, Duration::ZERO).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_take() {
+        let snapshot = snapshot();
+        assert_eq!(snapshot.uptime, Some(1_000));
+        assert_eq!(snapshot.memory.percentage(), 25.0);
+        assert_eq!(
+            snapshot.battery,
+            Some(BatterySnapshot {
+                state: "charging".to_string(),
+                percentage: 50
+            })
+        );
+        let pids: Vec<i32> = snapshot.processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![1, 7]);
+    }
+
+    #[test]
+    fn test_snapshot_table() {
+        let table = snapshot().render(OutputFormat::Table).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("uptime 16m40s  cpu 0.0%  mem 4GiB/16GiB (25.0%)"));
+        assert!(lines[0].ends_with("battery 50% charging"));
+        assert!(lines[2].contains("init"));
+        assert!(lines[3].ends_with("/usr/bin/sshd --flag"));
+    }
+
+    #[test]
+    fn test_snapshot_without_uptime() {
+        let source = FakeProcSource {
+            uptime: None,
+            ..FakeProcSource::default()
+        };
+        let snapshot = Snapshot::take(&source, &mut FakePowerSource, Duration::ZERO).unwrap();
+        assert_eq!(snapshot.uptime, None);
+        let table = snapshot.render(OutputFormat::Table).unwrap();
+        assert!(table.starts_with("uptime n/a  cpu"));
+    }
+
+    #[test]
+    fn test_snapshot_json() {
+        let json = snapshot().render(OutputFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["uptime"], 1_000);
+        assert_eq!(value["battery"]["percentage"], 50);
+        assert_eq!(value["processes"][1]["program"], "sshd");
+    }
+}
@@ -0,0 +1,743 @@
+use std::fmt::Debug;
+
+#[cfg(target_os = "linux")]
+use procfs::{
+    boot_time_secs, page_size,
+    process::{all_processes, Process},
+    ticks_per_second, CpuInfo, Current, CurrentSI, FromReadSI, LoadAverage, Uptime,
+};
+use procfs_core::process::{Stat, StatM};
+use procfs_core::{KernelStats, Meminfo, ProcError, ProcResult};
+#[cfg(target_os = "linux")]
+use tracing::warn;
+
+#[cfg(feature = "sysinfo")]
+pub mod sysinfo;
+
+#[cfg(not(any(target_os = "linux", feature = "sysinfo")))]
+compile_error!("brt reads /proc; enable the sysinfo feature on systems without it");
+
+/// Everything brt reads from `/proc`, so the collectors can run against
+/// synthetic data in tests.
+pub trait ProcSource: Debug + Send {
+    /// The pids of all processes that are currently running.
+    fn pids(&self) -> ProcResult<Vec<i32>>;
+    fn stat(&self, pid: i32) -> ProcResult<Stat>;
+    fn statm(&self, pid: i32) -> ProcResult<StatM>;
+    fn cmdline(&self, pid: i32) -> ProcResult<Vec<String>>;
+    fn uid(&self, pid: i32) -> ProcResult<u32>;
+    /// The effective capabilities, `CapEff` of `/proc/<pid>/status`.
+    fn capabilities(&self, pid: i32) -> ProcResult<u64>;
+    /// The badness the OOM killer gives a process, `/proc/<pid>/oom_score`,
+    /// and the adjustment of it, `oom_score_adj`, from -1000 to 1000.
+    fn oom_score(&self, pid: i32) -> ProcResult<(u32, i32)>;
+    /// The SELinux or AppArmor label, `/proc/<pid>/attr/current`.
+    fn security_label(&self, pid: i32) -> ProcResult<String>;
+    /// The memory policy and nodes of each mapping, `/proc/<pid>/numa_maps`.
+    fn numa_maps(&self, pid: i32) -> ProcResult<String>;
+    /// The memory of all mappings summed up, `/proc/<pid>/smaps_rollup`;
+    /// only the owner of the process and root may read it.
+    fn smaps_rollup(&self, pid: i32) -> ProcResult<String>;
+    /// The cgroup v2 path, the `0::` line of `/proc/<pid>/cgroup`.
+    fn cgroup(&self, pid: i32) -> ProcResult<String>;
+    /// The voluntary and involuntary context switches of
+    /// `/proc/<pid>/status`.
+    fn context_switches(&self, pid: i32) -> ProcResult<(u64, u64)>;
+    /// The times the process was woken up, `nr_wakeups` of
+    /// `/proc/<pid>/sched`; only kernels with scheduler statistics tell.
+    fn wakeups(&self, pid: i32) -> ProcResult<u64>;
+    /// A file of a cgroup v2 directory, e.g. `memory.max`, relative to
+    /// `/sys/fs/cgroup`.
+    fn cgroup_file(&self, cgroup: &str, name: &str) -> ProcResult<String>;
+    /// The cgroups right below `cgroup`, e.g. `/system.slice` of `/`, by
+    /// their path.
+    fn cgroup_children(&self, cgroup: &str) -> ProcResult<Vec<String>>;
+    /// Seconds since boot.
+    fn uptime(&self) -> ProcResult<u64>;
+    /// Seconds since the epoch at which the system booted.
+    fn boot_time(&self) -> ProcResult<u64>;
+    /// The load averages over 1, 5 and 15 minutes, `/proc/loadavg`.
+    fn load_average(&self) -> ProcResult<[f32; 3]>;
+    /// E.g. `6.8.0-45-generic`, `/proc/sys/kernel/osrelease`.
+    fn kernel_release(&self) -> ProcResult<String>;
+    /// `/proc/sys/kernel/hostname`.
+    fn hostname(&self) -> ProcResult<String>;
+    fn num_cores(&self) -> usize;
+    fn ticks_per_second(&self) -> u64;
+    fn page_size(&self) -> u64;
+    /// The system wide counters of `/proc/stat`.
+    fn kernel_stats(&self) -> ProcResult<KernelStats>;
+    /// The counters of `/proc/stat` with the interrupts, from a single read.
+    fn system_stat(&self) -> ProcResult<SystemStat>;
+    fn meminfo(&self) -> ProcResult<Meminfo>;
+    /// The kernel's object caches, `/proc/slabinfo`; usually only root may
+    /// read it.
+    fn slabinfo(&self) -> ProcResult<String>;
+}
+
+/// The system wide counters of `/proc/stat`.
+#[derive(Debug, Clone)]
+pub struct SystemStat {
+    pub kernel: KernelStats,
+    /// The number of interrupts since boot, the total of the `intr` line,
+    /// which procfs leaves out; `None` where there is no such line.
+    pub interrupts: Option<u64>,
+}
+
+/// The total of the `intr` line of a `/proc/stat`.
+pub fn parse_interrupts(stat: &str) -> Option<u64> {
+    stat.lines()
+        .find_map(|line| line.strip_prefix("intr "))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// The cgroup v2 path of a `/proc/<pid>/cgroup`; v1 hierarchies have a
+/// controller in the middle field and are left out.
+pub fn parse_cgroup(cgroup: &str) -> ProcResult<String> {
+    cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(str::to_string)
+        .ok_or_else(|| ProcError::NotFound(Some("cgroup v2".into())))
+}
+
+/// The `nr_wakeups` of a `/proc/<pid>/sched`, which older kernels prefix
+/// with `se.statistics.`.
+pub fn parse_wakeups(sched: &str) -> ProcResult<u64> {
+    sched
+        .lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let key = key.trim();
+            (key == "nr_wakeups" || key.ends_with(".nr_wakeups"))
+                .then(|| value.trim().parse().ok())?
+        })
+        .ok_or_else(|| ProcError::NotFound(Some("nr_wakeups".into())))
+}
+
+/// Reads the live system: through `/proc` on Linux and through `sysinfo`
+/// elsewhere.
+pub fn system_source() -> Box<dyn ProcSource> {
+    #[cfg(target_os = "linux")]
+    return Box::new(ProcfsSource);
+    #[cfg(not(target_os = "linux"))]
+    return Box::new(sysinfo::SysinfoSource::new());
+}
+
+/// Splits the NUL separated arguments of `/proc/<pid>/cmdline`, replacing
+/// invalid UTF-8 with `U+FFFD`.
+#[cfg(target_os = "linux")]
+pub fn split_cmdline(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .split(|b| *b == 0)
+        .filter(|part| !part.is_empty())
+        .map(|part| String::from_utf8_lossy(part).into_owned())
+        .collect()
+}
+
+/// Where the cgroup v2 hierarchy is mounted.
+#[cfg(target_os = "linux")]
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Reads the live system through `procfs`.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProcfsSource;
+
+#[cfg(target_os = "linux")]
+impl ProcSource for ProcfsSource {
+    fn pids(&self) -> ProcResult<Vec<i32>> {
+        let pids = all_processes()?
+            .filter_map(|p| match p {
+                Ok(p) => Some(p.pid),
+                Err(e) => match e {
+                    procfs_core::ProcError::NotFound(_) => None,
+                    procfs_core::ProcError::Io(_e, _path) => None,
+                    x => {
+                        warn!("Can't read process due to error {x:?}");
+                        None
+                    }
+                },
+            })
+            .collect();
+        Ok(pids)
+    }
+
+    fn stat(&self, pid: i32) -> ProcResult<Stat> {
+        Process::new(pid)?.stat()
+    }
+
+    fn statm(&self, pid: i32) -> ProcResult<StatM> {
+        Process::new(pid)?.statm()
+    }
+
+    fn cmdline(&self, pid: i32) -> ProcResult<Vec<String>> {
+        // procfs refuses command lines that aren't valid UTF-8
+        let bytes = std::fs::read(format!("/proc/{pid}/cmdline"))?;
+        Ok(split_cmdline(&bytes))
+    }
+
+    fn uid(&self, pid: i32) -> ProcResult<u32> {
+        Process::new(pid)?.uid()
+    }
+
+    fn capabilities(&self, pid: i32) -> ProcResult<u64> {
+        Ok(Process::new(pid)?.status()?.capeff)
+    }
+
+    fn oom_score(&self, pid: i32) -> ProcResult<(u32, i32)> {
+        let read = |name: &str| std::fs::read_to_string(format!("/proc/{pid}/{name}"));
+        let parse = |name: &str| -> ProcResult<i32> {
+            read(name)?
+                .trim()
+                .parse()
+                .map_err(|_| ProcError::Incomplete(Some(name.into())))
+        };
+        Ok((parse("oom_score")?.max(0) as u32, parse("oom_score_adj")?))
+    }
+
+    fn security_label(&self, pid: i32) -> ProcResult<String> {
+        let label = std::fs::read_to_string(format!("/proc/{pid}/attr/current"))?;
+        Ok(label.trim_end_matches(['\0', '\n']).to_string())
+    }
+
+    fn numa_maps(&self, pid: i32) -> ProcResult<String> {
+        Ok(std::fs::read_to_string(format!("/proc/{pid}/numa_maps"))?)
+    }
+
+    fn smaps_rollup(&self, pid: i32) -> ProcResult<String> {
+        Ok(std::fs::read_to_string(format!(
+            "/proc/{pid}/smaps_rollup"
+        ))?)
+    }
+
+    fn cgroup(&self, pid: i32) -> ProcResult<String> {
+        parse_cgroup(&std::fs::read_to_string(format!("/proc/{pid}/cgroup"))?)
+    }
+
+    fn context_switches(&self, pid: i32) -> ProcResult<(u64, u64)> {
+        let status = Process::new(pid)?.status()?;
+        match (
+            status.voluntary_ctxt_switches,
+            status.nonvoluntary_ctxt_switches,
+        ) {
+            (Some(voluntary), Some(involuntary)) => Ok((voluntary, involuntary)),
+            _ => Err(ProcError::Incomplete(Some("status".into()))),
+        }
+    }
+
+    fn wakeups(&self, pid: i32) -> ProcResult<u64> {
+        parse_wakeups(&std::fs::read_to_string(format!("/proc/{pid}/sched"))?)
+    }
+
+    fn cgroup_file(&self, cgroup: &str, name: &str) -> ProcResult<String> {
+        let path = std::path::Path::new(CGROUP_ROOT)
+            .join(cgroup.trim_start_matches('/'))
+            .join(name);
+        Ok(std::fs::read_to_string(path)?)
+    }
+
+    fn cgroup_children(&self, cgroup: &str) -> ProcResult<Vec<String>> {
+        let directory = std::path::Path::new(CGROUP_ROOT).join(cgroup.trim_start_matches('/'));
+        let parent = cgroup.trim_end_matches('/');
+        let mut children: Vec<String> = std::fs::read_dir(directory)?
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+            .filter_map(|entry| Some(format!("{parent}/{}", entry.file_name().to_str()?)))
+            .collect();
+        children.sort();
+        Ok(children)
+    }
+
+    /// Falls back on the kernel clocks in containers without `/proc/uptime`.
+    fn uptime(&self) -> ProcResult<u64> {
+        Uptime::current()
+            .map(|uptime| uptime.uptime_duration().as_secs())
+            .or_else(|error| {
+                warn!("Can't read the uptime: {error}");
+                boot_clock_uptime().or_else(sysinfo_uptime).ok_or(error)
+            })
+    }
+
+    fn boot_time(&self) -> ProcResult<u64> {
+        boot_time_secs()
+    }
+
+    fn load_average(&self) -> ProcResult<[f32; 3]> {
+        LoadAverage::current().map(|load| [load.one, load.five, load.fifteen])
+    }
+
+    fn kernel_release(&self) -> ProcResult<String> {
+        Ok(std::fs::read_to_string("/proc/sys/kernel/osrelease")?
+            .trim_end()
+            .to_string())
+    }
+
+    fn hostname(&self) -> ProcResult<String> {
+        Ok(std::fs::read_to_string("/proc/sys/kernel/hostname")?
+            .trim_end()
+            .to_string())
+    }
+
+    fn num_cores(&self) -> usize {
+        CpuInfo::current().map(|c| c.num_cores()).unwrap_or(1)
+    }
+
+    fn ticks_per_second(&self) -> u64 {
+        ticks_per_second()
+    }
+
+    fn page_size(&self) -> u64 {
+        page_size()
+    }
+
+    fn kernel_stats(&self) -> ProcResult<KernelStats> {
+        KernelStats::current()
+    }
+
+    fn system_stat(&self) -> ProcResult<SystemStat> {
+        let stat = std::fs::read_to_string("/proc/stat")?;
+        Ok(SystemStat {
+            kernel: KernelStats::from_read(stat.as_bytes(), procfs::current_system_info())?,
+            interrupts: parse_interrupts(&stat),
+        })
+    }
+
+    fn meminfo(&self) -> ProcResult<Meminfo> {
+        Meminfo::current()
+    }
+
+    fn slabinfo(&self) -> ProcResult<String> {
+        Ok(std::fs::read_to_string("/proc/slabinfo")?)
+    }
+}
+
+/// The seconds since boot, suspended time included.
+#[cfg(target_os = "linux")]
+fn boot_clock_uptime() -> Option<u64> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: time is a valid timespec to write to.
+    let result = unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut time) };
+    (result == 0).then_some(time.tv_sec as u64)
+}
+
+/// The seconds since boot according to the sysinfo syscall.
+#[cfg(target_os = "linux")]
+fn sysinfo_uptime() -> Option<u64> {
+    // SAFETY: an all zero sysinfo is valid.
+    let mut info: libc::sysinfo = unsafe { std::mem::zeroed() };
+    // SAFETY: info is a valid sysinfo to write to.
+    let result = unsafe { libc::sysinfo(&mut info) };
+    (result == 0).then_some(info.uptime as u64)
+}
+
+/// Made up processes and system files, for the tests of brt and of the
+/// tools built on this library, which enable the `test-util` feature.
+#[cfg(any(test, feature = "test-util"))]
+pub mod fake {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use procfs_core::{ExplicitSystemInfo, FromRead, FromReadSI, ProcError};
+
+    use super::*;
+
+    pub const TICKS_PER_SECOND: u64 = 100;
+    pub const PAGE_SIZE: u64 = 4096;
+    pub const BOOT_TIME: u64 = 1_700_000_000;
+
+    /// `/proc/stat` of a two core system that was busy 25% of the time.
+    pub const KERNEL_STATS: &str = "cpu  1500 0 500 6000 0 0 0 0 0 0
+cpu0 750 0 250 3000 0 0 0 0 0 0
+cpu1 750 0 250 3000 0 0 0 0 0 0
+intr 1000 0 0
+ctxt 20000
+btime 1700000000
+processes 4000
+procs_running 2
+procs_blocked 0
+";
+
+    /// `/proc/meminfo` of a system with 16GiB of memory of which 4GiB is used.
+    pub const MEMINFO: &str = "MemTotal:       16777216 kB
+MemFree:         8388608 kB
+MemAvailable:   12582912 kB
+Buffers:          524288 kB
+Cached:          3145728 kB
+SwapCached:            0 kB
+Active:          4194304 kB
+Inactive:        2097152 kB
+SwapTotal:       2097152 kB
+SwapFree:        2097152 kB
+Dirty:              1024 kB
+Writeback:             0 kB
+Mapped:           262144 kB
+Slab:             262144 kB
+Committed_AS:    6291456 kB
+VmallocTotal:   34359738367 kB
+VmallocUsed:       65536 kB
+VmallocChunk:          0 kB
+";
+
+    /// The rollup of a process with 2MiB resident, like its `statm`, a
+    /// quarter of which is shared with another process.
+    pub const SMAPS_ROLLUP: &str =
+        "00400000-7ffd0000 ---p 00000000 00:00 0                          [rollup]
+Rss:                2048 kB
+Pss:                1792 kB
+Pss_Anon:           1536 kB
+Pss_File:            256 kB
+Shared_Clean:        512 kB
+Shared_Dirty:          0 kB
+Private_Clean:       256 kB
+Private_Dirty:      1280 kB
+Referenced:         2048 kB
+Anonymous:          1536 kB
+Swap:                128 kB
+SwapPss:             128 kB
+Locked:                0 kB
+";
+
+    /// The files of a single fake process; `None` means the file can't be read.
+    #[derive(Debug, Clone, Default)]
+    pub struct FakeProcess {
+        pub stat: Option<String>,
+        pub statm: Option<String>,
+        pub cmdline: Option<Vec<String>>,
+        pub uid: Option<u32>,
+        pub capabilities: Option<u64>,
+        pub oom_score: Option<(u32, i32)>,
+        pub security_label: Option<String>,
+        pub numa_maps: Option<String>,
+        pub cgroup: Option<String>,
+        pub context_switches: Option<(u64, u64)>,
+        pub sched: Option<String>,
+        pub smaps_rollup: Option<String>,
+    }
+
+    impl FakeProcess {
+        /// A running process that used `cpu_ticks` of cpu time since it
+        /// started `start_ticks` after boot.
+        pub fn running(pid: i32, comm: &str, cpu_ticks: u64, start_ticks: u64) -> Self {
+            Self {
+                stat: Some(stat_line(pid, comm, 'S', cpu_ticks, start_ticks)),
+                statm: Some("2048 512 128 1 0 256 0".to_string()),
+                cmdline: Some(vec![format!("/usr/bin/{comm}"), "--flag".to_string()]),
+                uid: Some(0),
+                capabilities: Some(0),
+                oom_score: Some((0, 0)),
+                security_label: Some("unconfined".to_string()),
+                numa_maps: Some(format!(
+                    "00400000 default file=/usr/bin/{comm} mapped=512 N0=512 kernelpagesize_kB=4\n"
+                )),
+                cgroup: Some("/".to_string()),
+                context_switches: Some((120, 4)),
+                sched: Some(format!(
+                    "{comm} ({pid}, #threads: 1)\nse.exec_start : 1000.0\nnr_wakeups : 250\n"
+                )),
+                smaps_rollup: Some(SMAPS_ROLLUP.to_string()),
+            }
+        }
+
+        /// A zombie has a stat, but no command line or memory.
+        pub fn zombie(pid: i32, comm: &str) -> Self {
+            Self {
+                stat: Some(stat_line(pid, comm, 'Z', 0, 0)),
+                statm: Some("0 0 0 0 0 0 0".to_string()),
+                cmdline: Some(vec![]),
+                uid: Some(1000),
+                capabilities: Some(0),
+                oom_score: Some((0, 0)),
+                security_label: None,
+                numa_maps: None,
+                cgroup: None,
+                context_switches: Some((0, 0)),
+                sched: None,
+                smaps_rollup: None,
+            }
+        }
+
+        /// A process of another user that can't be read under `hidepid`.
+        pub fn forbidden() -> Self {
+            Self::default()
+        }
+
+        /// The same process, started by `ppid` rather than init.
+        pub fn with_ppid(mut self, ppid: i32) -> Self {
+            self.stat = self.stat.map(|stat| {
+                let (head, tail) = stat.rsplit_once(") ").expect("a stat line");
+                let mut fields: Vec<String> = tail.split(' ').map(str::to_string).collect();
+                fields[1] = ppid.to_string();
+                format!("{head}) {}", fields.join(" "))
+            });
+            self
+        }
+
+        /// The same process, in the process group `pgrp` rather than its own.
+        pub fn with_pgrp(mut self, pgrp: i32) -> Self {
+            self.stat = self.stat.map(|stat| {
+                let (head, tail) = stat.rsplit_once(") ").expect("a stat line");
+                let mut fields: Vec<String> = tail.split(' ').map(str::to_string).collect();
+                fields[2] = pgrp.to_string();
+                format!("{head}) {}", fields.join(" "))
+            });
+            self
+        }
+    }
+
+    /// A `/proc/<pid>/stat` line with the given fields, all others zero.
+    pub fn stat_line(
+        pid: i32,
+        comm: &str,
+        state: char,
+        cpu_ticks: u64,
+        start_ticks: u64,
+    ) -> String {
+        format!(
+            "{pid} ({comm}) {state} 1 {pid} {pid} 0 -1 4194560 0 0 0 0 {cpu_ticks} 0 0 0 20 0 1 0 \
+             {start_ticks} 8388608 512 18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 17 0 0 0 0 0 0 \
+             0 0 0 0 0 0 0 0"
+        )
+    }
+
+    /// A fixture of processes and system wide values.
+    #[derive(Debug, Clone)]
+    pub struct FakeProcSource {
+        pub processes: BTreeMap<i32, FakeProcess>,
+        /// `None` for a system that doesn't tell.
+        pub uptime: Option<u64>,
+        pub num_cores: usize,
+        pub kernel_stats: String,
+        pub meminfo: String,
+        /// The cgroup files by their path, e.g. `/docker/web/memory.max`.
+        pub cgroup_files: BTreeMap<String, String>,
+        /// `None` when it can't be read, as by anyone but root.
+        pub slabinfo: Option<String>,
+        /// `None` for a system that doesn't tell.
+        pub load_average: Option<[f32; 3]>,
+    }
+
+    impl Default for FakeProcSource {
+        fn default() -> Self {
+            Self {
+                processes: BTreeMap::new(),
+                uptime: Some(1_000),
+                num_cores: 4,
+                kernel_stats: KERNEL_STATS.to_string(),
+                meminfo: MEMINFO.to_string(),
+                cgroup_files: BTreeMap::new(),
+                slabinfo: None,
+                load_average: Some([1.5, 1.0, 0.5]),
+            }
+        }
+    }
+
+    impl FakeProcSource {
+        pub fn with(mut self, pid: i32, process: FakeProcess) -> Self {
+            self.processes.insert(pid, process);
+            self
+        }
+
+        pub fn without(mut self, pid: i32) -> Self {
+            self.processes.remove(&pid);
+            self
+        }
+
+        fn process(&self, pid: i32) -> ProcResult<&FakeProcess> {
+            self.processes.get(&pid).ok_or(ProcError::NotFound(None))
+        }
+    }
+
+    fn readable<T: Clone>(value: &Option<T>) -> ProcResult<T> {
+        value.clone().ok_or(ProcError::PermissionDenied(None))
+    }
+
+    impl ProcSource for FakeProcSource {
+        fn pids(&self) -> ProcResult<Vec<i32>> {
+            Ok(self.processes.keys().copied().collect())
+        }
+
+        fn stat(&self, pid: i32) -> ProcResult<Stat> {
+            Stat::from_read(readable(&self.process(pid)?.stat)?.as_bytes())
+        }
+
+        fn statm(&self, pid: i32) -> ProcResult<StatM> {
+            StatM::from_read(readable(&self.process(pid)?.statm)?.as_bytes())
+        }
+
+        fn cmdline(&self, pid: i32) -> ProcResult<Vec<String>> {
+            readable(&self.process(pid)?.cmdline)
+        }
+
+        fn uid(&self, pid: i32) -> ProcResult<u32> {
+            readable(&self.process(pid)?.uid)
+        }
+
+        fn capabilities(&self, pid: i32) -> ProcResult<u64> {
+            readable(&self.process(pid)?.capabilities)
+        }
+
+        fn oom_score(&self, pid: i32) -> ProcResult<(u32, i32)> {
+            readable(&self.process(pid)?.oom_score)
+        }
+
+        fn security_label(&self, pid: i32) -> ProcResult<String> {
+            readable(&self.process(pid)?.security_label)
+        }
+
+        fn numa_maps(&self, pid: i32) -> ProcResult<String> {
+            readable(&self.process(pid)?.numa_maps)
+        }
+
+        fn smaps_rollup(&self, pid: i32) -> ProcResult<String> {
+            readable(&self.process(pid)?.smaps_rollup)
+        }
+
+        fn cgroup(&self, pid: i32) -> ProcResult<String> {
+            readable(&self.process(pid)?.cgroup)
+        }
+
+        fn context_switches(&self, pid: i32) -> ProcResult<(u64, u64)> {
+            readable(&self.process(pid)?.context_switches)
+        }
+
+        fn wakeups(&self, pid: i32) -> ProcResult<u64> {
+            super::parse_wakeups(&readable(&self.process(pid)?.sched)?)
+        }
+
+        fn cgroup_file(&self, cgroup: &str, name: &str) -> ProcResult<String> {
+            let path = format!("{}/{name}", cgroup.trim_end_matches('/'));
+            self.cgroup_files
+                .get(&path)
+                .cloned()
+                .ok_or(ProcError::NotFound(None))
+        }
+
+        /// The directories the cgroup files are in.
+        fn cgroup_children(&self, cgroup: &str) -> ProcResult<Vec<String>> {
+            let parent = format!("{}/", cgroup.trim_end_matches('/'));
+            let children: BTreeSet<String> = self
+                .cgroup_files
+                .keys()
+                .filter_map(|path| {
+                    let (child, _) = path.strip_prefix(&parent)?.split_once('/')?;
+                    Some(format!("{parent}{child}"))
+                })
+                .collect();
+            Ok(children.into_iter().collect())
+        }
+
+        fn uptime(&self) -> ProcResult<u64> {
+            self.uptime.ok_or(ProcError::NotFound(None))
+        }
+
+        fn boot_time(&self) -> ProcResult<u64> {
+            Ok(BOOT_TIME)
+        }
+
+        fn load_average(&self) -> ProcResult<[f32; 3]> {
+            self.load_average.ok_or(ProcError::NotFound(None))
+        }
+
+        fn kernel_release(&self) -> ProcResult<String> {
+            Ok("6.8.0-brt".to_string())
+        }
+
+        fn hostname(&self) -> ProcResult<String> {
+            Ok("brt-host".to_string())
+        }
+
+        fn num_cores(&self) -> usize {
+            self.num_cores
+        }
+
+        fn ticks_per_second(&self) -> u64 {
+            TICKS_PER_SECOND
+        }
+
+        fn page_size(&self) -> u64 {
+            PAGE_SIZE
+        }
+
+        fn kernel_stats(&self) -> ProcResult<KernelStats> {
+            let system_info = ExplicitSystemInfo {
+                boot_time_secs: BOOT_TIME,
+                ticks_per_second: TICKS_PER_SECOND,
+                page_size: PAGE_SIZE,
+                is_little_endian: cfg!(target_endian = "little"),
+            };
+            KernelStats::from_read(self.kernel_stats.as_bytes(), &system_info)
+        }
+
+        fn system_stat(&self) -> ProcResult<SystemStat> {
+            Ok(SystemStat {
+                kernel: self.kernel_stats()?,
+                interrupts: super::parse_interrupts(&self.kernel_stats),
+            })
+        }
+
+        fn meminfo(&self) -> ProcResult<Meminfo> {
+            Meminfo::from_read(self.meminfo.as_bytes())
+        }
+
+        fn slabinfo(&self) -> ProcResult<String> {
+            readable(&self.slabinfo)
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_cmdline() {
+        assert_eq!(
+            split_cmdline(b"/usr/bin/vim\0-u\0NONE\0"),
+            vec!["/usr/bin/vim", "-u", "NONE"]
+        );
+        assert_eq!(split_cmdline(b""), Vec::<String>::new());
+        assert_eq!(
+            split_cmdline(b"caf\xe9\0\xff\xfe\0"),
+            vec!["caf\u{fffd}", "\u{fffd}\u{fffd}"]
+        );
+    }
+
+    #[test]
+    fn test_parse_cgroup() {
+        assert_eq!(
+            parse_cgroup("0::/system.slice/nginx.service\n").unwrap(),
+            "/system.slice/nginx.service"
+        );
+        let hybrid = "12:memory:/docker/web\n1:name=systemd:/docker/web\n0::/docker/web\n";
+        assert_eq!(parse_cgroup(hybrid).unwrap(), "/docker/web");
+        assert!(parse_cgroup("4:memory:/docker/web\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_wakeups() {
+        let sched = "sshd (42, #threads: 1)\n\
+                     -------------------------------------------------------------------\n\
+                     se.exec_start                                :      51234567.123456\n\
+                     nr_voluntary_switches                        :                  120\n\
+                     nr_wakeups                                   :                  250\n\
+                     nr_wakeups_sync                              :                    3\n";
+        assert_eq!(parse_wakeups(sched).unwrap(), 250);
+        let older = "se.statistics.nr_wakeups : 17\n";
+        assert_eq!(parse_wakeups(older).unwrap(), 17);
+        // without scheduler statistics
+        assert!(parse_wakeups("se.exec_start : 1.0\n").is_err());
+    }
+
+    #[test]
+    fn test_uptime_fallbacks() {
+        let boot_clock = boot_clock_uptime().unwrap();
+        let sysinfo = sysinfo_uptime().unwrap();
+        assert!(boot_clock.abs_diff(sysinfo) <= 1);
+    }
+}
@@ -0,0 +1,354 @@
+use std::sync::Mutex;
+use std::time::Instant;
+
+use ::sysinfo::{
+    Pid, Process, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, System, UpdateKind,
+};
+use procfs_core::process::{Stat, StatM};
+use procfs_core::{
+    ExplicitSystemInfo, FromRead, FromReadSI, KernelStats, Meminfo, ProcError, ProcResult,
+};
+
+use super::{ProcSource, SystemStat};
+
+/// The resolution of the synthesized tick counters.
+const TICKS_PER_SECOND: u64 = 100;
+
+/// Reads the system through `sysinfo`, for systems without `/proc`.
+///
+/// `sysinfo` reports values where `/proc` has files, so the files brt reads
+/// are rebuilt from those values. Details `sysinfo` doesn't know about, like
+/// the niceness, are left at their defaults.
+#[derive(Debug)]
+pub struct SysinfoSource {
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    system: System,
+    /// Busy and total ticks of each cpu since brt started, accumulated from
+    /// the usage between refreshes.
+    cpus: Vec<(u64, u64)>,
+    refreshed: Instant,
+}
+
+impl Default for SysinfoSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SysinfoSource {
+    pub fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_cpu_usage();
+        system.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh_kind());
+        let cpus = vec![(0, 0); system.cpus().len()];
+        Self {
+            state: Mutex::new(State {
+                system,
+                cpus,
+                refreshed: Instant::now(),
+            }),
+        }
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut State) -> ProcResult<T>) -> ProcResult<T> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| ProcError::Other("The sysinfo state is poisoned".to_string()))?;
+        f(&mut state)
+    }
+
+    /// Reads a process that isn't known yet, e.g. one that started after the
+    /// last call to `pids`.
+    fn with_process<T>(&self, pid: i32, f: impl FnOnce(&Process) -> T) -> ProcResult<T> {
+        self.with_state(|state| {
+            let pid = Pid::from_u32(pid as u32);
+            if state.system.process(pid).is_none() {
+                state.system.refresh_processes_specifics(
+                    ProcessesToUpdate::Some(&[pid]),
+                    false,
+                    refresh_kind(),
+                );
+            }
+            let process = state.system.process(pid).ok_or(ProcError::NotFound(None))?;
+            Ok(f(process))
+        })
+    }
+}
+
+/// Everything the collectors need; the command line and user don't change.
+fn refresh_kind() -> ProcessRefreshKind {
+    ProcessRefreshKind::nothing()
+        .with_memory()
+        .with_cpu()
+        .with_tasks()
+        .with_cmd(UpdateKind::OnlyIfNotSet)
+        .with_user(UpdateKind::OnlyIfNotSet)
+}
+
+fn system_info() -> ExplicitSystemInfo {
+    ExplicitSystemInfo {
+        boot_time_secs: System::boot_time(),
+        ticks_per_second: TICKS_PER_SECOND,
+        page_size: page_size(),
+        is_little_endian: cfg!(target_endian = "little"),
+    }
+}
+
+fn page_size() -> u64 {
+    // SAFETY: sysconf has no memory safety requirements.
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as u64,
+        _ => 4096,
+    }
+}
+
+fn state_letter(status: ProcessStatus) -> char {
+    match status {
+        ProcessStatus::Run => 'R',
+        ProcessStatus::Sleep | ProcessStatus::Idle => 'S',
+        ProcessStatus::UninterruptibleDiskSleep => 'D',
+        ProcessStatus::Stop => 'T',
+        ProcessStatus::Tracing => 't',
+        ProcessStatus::Zombie => 'Z',
+        ProcessStatus::Dead => 'X',
+        _ => 'S',
+    }
+}
+
+/// A `/proc/<pid>/stat` line with what `sysinfo` knows about a process.
+fn stat_line(pid: i32, process: &Process) -> String {
+    let comm = process.name().to_string_lossy().replace(')', "");
+    let state = state_letter(process.status());
+    let ppid = process.parent().map(|p| p.as_u32()).unwrap_or_default();
+    let cpu_ticks = process.accumulated_cpu_time() * TICKS_PER_SECOND / 1000;
+    let threads = process.tasks().map(|t| t.len().max(1)).unwrap_or(1);
+    let start_ticks = process.start_time().saturating_sub(System::boot_time()) * TICKS_PER_SECOND;
+    let vsize = process.virtual_memory();
+    let rss = process.memory() / page_size();
+    format!(
+        "{pid} ({comm}) {state} {ppid} {pid} {pid} 0 -1 0 0 0 0 0 {cpu_ticks} 0 0 0 20 0 \
+         {threads} 0 {start_ticks} {vsize} {rss} 18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 \
+         17 0 0 0 0 0 0 0 0 0 0 0 0 0 0"
+    )
+}
+
+impl ProcSource for SysinfoSource {
+    fn pids(&self) -> ProcResult<Vec<i32>> {
+        self.with_state(|state| {
+            state
+                .system
+                .refresh_processes_specifics(ProcessesToUpdate::All, true, refresh_kind());
+            Ok(state
+                .system
+                .processes()
+                .keys()
+                .map(|pid| pid.as_u32() as i32)
+                .collect())
+        })
+    }
+
+    fn stat(&self, pid: i32) -> ProcResult<Stat> {
+        let line = self.with_process(pid, |process| stat_line(pid, process))?;
+        Stat::from_read(line.as_bytes())
+    }
+
+    fn statm(&self, pid: i32) -> ProcResult<StatM> {
+        let line = self.with_process(pid, |process| {
+            let page_size = page_size();
+            format!(
+                "{} {} 0 0 0 0 0",
+                process.virtual_memory() / page_size,
+                process.memory() / page_size
+            )
+        })?;
+        StatM::from_read(line.as_bytes())
+    }
+
+    fn cmdline(&self, pid: i32) -> ProcResult<Vec<String>> {
+        self.with_process(pid, |process| {
+            process
+                .cmd()
+                .iter()
+                .map(|part| part.to_string_lossy().to_string())
+                .collect()
+        })
+    }
+
+    fn uid(&self, pid: i32) -> ProcResult<u32> {
+        self.with_process(pid, |process| process.user_id().map(|uid| **uid))?
+            .ok_or(ProcError::PermissionDenied(None))
+    }
+
+    fn capabilities(&self, _pid: i32) -> ProcResult<u64> {
+        Err(ProcError::NotFound(None))
+    }
+
+    fn oom_score(&self, _pid: i32) -> ProcResult<(u32, i32)> {
+        Err(ProcError::NotFound(None))
+    }
+
+    fn security_label(&self, _pid: i32) -> ProcResult<String> {
+        Err(ProcError::NotFound(None))
+    }
+
+    fn numa_maps(&self, _pid: i32) -> ProcResult<String> {
+        Err(ProcError::NotFound(None))
+    }
+
+    fn smaps_rollup(&self, _pid: i32) -> ProcResult<String> {
+        Err(ProcError::NotFound(None))
+    }
+
+    fn context_switches(&self, _pid: i32) -> ProcResult<(u64, u64)> {
+        Err(ProcError::NotFound(None))
+    }
+
+    fn wakeups(&self, _pid: i32) -> ProcResult<u64> {
+        Err(ProcError::NotFound(None))
+    }
+
+    fn cgroup(&self, _pid: i32) -> ProcResult<String> {
+        Err(ProcError::NotFound(None))
+    }
+
+    fn cgroup_file(&self, _cgroup: &str, _name: &str) -> ProcResult<String> {
+        Err(ProcError::NotFound(None))
+    }
+
+    fn cgroup_children(&self, _cgroup: &str) -> ProcResult<Vec<String>> {
+        Err(ProcError::NotFound(None))
+    }
+
+    fn uptime(&self) -> ProcResult<u64> {
+        Ok(System::uptime())
+    }
+
+    fn boot_time(&self) -> ProcResult<u64> {
+        Ok(System::boot_time())
+    }
+
+    fn load_average(&self) -> ProcResult<[f32; 3]> {
+        let load = System::load_average();
+        Ok([load.one as f32, load.five as f32, load.fifteen as f32])
+    }
+
+    fn kernel_release(&self) -> ProcResult<String> {
+        System::kernel_version().ok_or(ProcError::NotFound(None))
+    }
+
+    fn hostname(&self) -> ProcResult<String> {
+        System::host_name().ok_or(ProcError::NotFound(None))
+    }
+
+    fn num_cores(&self) -> usize {
+        self.with_state(|state| Ok(state.system.cpus().len().max(1)))
+            .unwrap_or(1)
+    }
+
+    fn ticks_per_second(&self) -> u64 {
+        TICKS_PER_SECOND
+    }
+
+    fn page_size(&self) -> u64 {
+        page_size()
+    }
+
+    fn kernel_stats(&self) -> ProcResult<KernelStats> {
+        let text = self.with_state(|state| {
+            state.system.refresh_cpu_usage();
+            let elapsed = state.refreshed.elapsed();
+            state.refreshed = Instant::now();
+            let ticks = (elapsed.as_secs_f64() * TICKS_PER_SECOND as f64) as u64;
+            state.cpus.resize(state.system.cpus().len(), (0, 0));
+            for (cpu, (busy, total)) in state.system.cpus().iter().zip(state.cpus.iter_mut()) {
+                *busy += (ticks as f64 * cpu.cpu_usage() as f64 / 100.0) as u64;
+                *total += ticks;
+            }
+            let line = |name: String, (busy, total): (u64, u64)| {
+                format!("{name} {busy} 0 0 {} 0 0 0 0 0 0\n", total - busy)
+            };
+            let sum = state
+                .cpus
+                .iter()
+                .fold((0, 0), |(b, t), (busy, total)| (b + busy, t + total));
+            let mut text = line("cpu ".to_string(), sum);
+            for (i, ticks) in state.cpus.iter().enumerate() {
+                text += &line(format!("cpu{i}"), *ticks);
+            }
+            text += &format!(
+                "ctxt 0\nbtime {}\nprocesses 0\nprocs_running 0\nprocs_blocked 0\n",
+                System::boot_time()
+            );
+            Ok(text)
+        })?;
+        KernelStats::from_read(text.as_bytes(), &system_info())
+    }
+
+    fn system_stat(&self) -> ProcResult<SystemStat> {
+        Ok(SystemStat {
+            kernel: self.kernel_stats()?,
+            interrupts: None,
+        })
+    }
+
+    fn meminfo(&self) -> ProcResult<Meminfo> {
+        let text = self.with_state(|state| {
+            state.system.refresh_memory();
+            let kb = |bytes: u64| bytes / 1024;
+            let system = &state.system;
+            Ok(format!(
+                "MemTotal: {} kB\nMemFree: {} kB\nMemAvailable: {} kB\nBuffers: 0 kB\n\
+                 Cached: 0 kB\nSwapCached: 0 kB\nActive: 0 kB\nInactive: 0 kB\n\
+                 SwapTotal: {} kB\nSwapFree: {} kB\nDirty: 0 kB\nWriteback: 0 kB\n\
+                 Mapped: 0 kB\nSlab: 0 kB\nCommitted_AS: 0 kB\nVmallocTotal: 0 kB\n\
+                 VmallocUsed: 0 kB\nVmallocChunk: 0 kB\n",
+                kb(system.total_memory()),
+                kb(system.free_memory()),
+                kb(system.available_memory()),
+                kb(system.total_swap()),
+                kb(system.free_swap()),
+            ))
+        })?;
+        Meminfo::from_read(text.as_bytes())
+    }
+
+    fn slabinfo(&self) -> ProcResult<String> {
+        Err(ProcError::NotFound(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::to_brt_process;
+
+    #[test]
+    fn test_sysinfo_source_reads_itself() {
+        let source = SysinfoSource::new();
+        let pid = std::process::id() as i32;
+        assert!(source.pids().unwrap().contains(&pid));
+        let process = to_brt_process(&source, pid).unwrap();
+        assert_eq!(process.pid, pid);
+        assert!(process.resident_memory > 0);
+        assert!(!process.command.is_empty());
+    }
+
+    #[test]
+    fn test_sysinfo_source_system() {
+        let source = SysinfoSource::new();
+        let meminfo = source.meminfo().unwrap();
+        assert!(meminfo.mem_total > 0);
+        assert!(meminfo.mem_available.unwrap() <= meminfo.mem_total);
+        let cpu = source.kernel_stats().unwrap().total;
+        assert!(cpu.user <= cpu.user + cpu.idle);
+        assert_eq!(
+            source.kernel_stats().unwrap().cpu_time.len(),
+            source.num_cores()
+        );
+    }
+}
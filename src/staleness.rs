@@ -0,0 +1,110 @@
+//! The line at the bottom telling how old the data of each pane is, e.g.
+//! `proc 1.2s ago cpu 0.4s ago`, to spot a collector that stopped sampling.
+
+use std::time::{Duration, Instant};
+
+use ratatui::prelude::*;
+
+use crate::cadence::Cadence;
+use crate::model::format_elapsed;
+
+/// Past this many periods without a sample the data is late, and past the
+/// second stalled.
+const LATE_PERIODS: u32 = 2;
+const STALLED_PERIODS: u32 = 5;
+
+/// When a component or collector last sampled, and how often it should.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    /// Short, e.g. `proc`.
+    pub name: String,
+    pub at: Instant,
+    pub period: Duration,
+}
+
+impl Sample {
+    /// The last sample that succeeded, at `at`, of something sampled as
+    /// often as `cadence` allows; a sample `cadence` allowed may have failed.
+    pub fn of(name: impl Into<String>, at: Option<Instant>, cadence: &Cadence) -> Option<Self> {
+        Some(Self {
+            name: name.into(),
+            at: at?,
+            period: cadence.period(),
+        })
+    }
+
+    /// Yellow when late and red when stalled; the period counts as a second
+    /// at least, as the samples are taken on the ticks.
+    fn style(&self, age: Duration) -> Style {
+        let period = self.period.max(Duration::from_secs(1));
+        if age > period * STALLED_PERIODS {
+            Style::default().fg(Color::Red)
+        } else if age > period * LATE_PERIODS {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().dim()
+        }
+    }
+}
+
+/// E.g. `1.2s ago`, or `3m12s ago` from a minute.
+fn format_age(age: Duration) -> String {
+    if age < Duration::from_secs(60) {
+        format!("{:.1}s ago", age.as_secs_f64())
+    } else {
+        format!("{} ago", format_elapsed(age.as_secs()))
+    }
+}
+
+/// The age of each sample at `now`, colored by how late it is.
+pub fn line(samples: &[Sample], now: Instant) -> Line<'static> {
+    let spans: Vec<Span> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| {
+            let age = now.saturating_duration_since(sample.at);
+            let separator = if i == 0 { "" } else { " " };
+            Span::styled(
+                format!("{separator}{} {}", sample.name, format_age(age)),
+                sample.style(age),
+            )
+        })
+        .collect();
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        let now = Instant::now();
+        let sample = |name: &str, millis, period| Sample {
+            name: name.to_string(),
+            at: now - Duration::from_millis(millis),
+            period: Duration::from_millis(period),
+        };
+        let line = line(
+            &[
+                sample("proc", 1_200, 1_000),
+                sample("cpu", 2_500, 1_000),
+                sample("net", 190_000, 2_000),
+                sample("load", 200, 0),
+            ],
+            now,
+        );
+        assert_eq!(
+            line.to_string(),
+            "proc 1.2s ago cpu 2.5s ago net 3m10s ago load 0.2s ago"
+        );
+        let colors: Vec<_> = line.spans.iter().map(|span| span.style.fg).collect();
+        assert_eq!(colors, [None, Some(Color::Yellow), Some(Color::Red), None]);
+        let cadence = Cadence::from_millis(1_000);
+        assert_eq!(Sample::of("proc", None, &cadence), None);
+        assert_eq!(
+            Sample::of("proc", Some(now), &cadence).map(|sample| sample.period),
+            Some(Duration::from_secs(1))
+        );
+    }
+}
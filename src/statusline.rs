@@ -0,0 +1,168 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use color_eyre::eyre::Result;
+
+use crate::components::battery::PowerSource;
+use crate::model::format_elapsed;
+use crate::snapshot::{BatterySnapshot, CPU_SAMPLE_INTERVAL};
+use crate::source::ProcSource;
+use crate::symbols::symbols;
+use crate::system::{CpuTicks, MemoryUsage};
+
+/// The number of glyphs in a bar.
+const BAR_LENGTH: usize = 5;
+
+/// What fits in a status bar like the one of tmux or i3bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusLine {
+    /// Percentage of the cpus that was busy.
+    pub cpu: f64,
+    pub memory: MemoryUsage,
+    pub load_average: Option<[f32; 3]>,
+    pub battery: Option<BatterySnapshot>,
+    /// Seconds since boot.
+    pub uptime: Option<u64>,
+    /// Percentage of the cpus that was busy since boot.
+    pub busy: f64,
+}
+
+impl StatusLine {
+    /// Reads the system; the cpu usage is that since `before`.
+    pub fn read(
+        source: &dyn ProcSource,
+        power: &mut dyn PowerSource,
+        before: &CpuTicks,
+        after: &CpuTicks,
+    ) -> Result<Self> {
+        Ok(Self {
+            cpu: after.usage_since(before),
+            memory: MemoryUsage::read(source)?,
+            load_average: source.load_average().ok(),
+            battery: power.read().map(BatterySnapshot::from),
+            uptime: source.uptime().ok(),
+            busy: after.usage_since(&CpuTicks::default()),
+        })
+    }
+
+    /// E.g. `cpu ■□□□□ 12% mem ■■■□□ 61% load 0.52 0.48 0.40 bat ■■■■□ 80% up 3d4h busy 9%`;
+    /// what the system doesn't tell is left out.
+    pub fn line(&self) -> String {
+        let mut line = format!(
+            "cpu {} {:.0}% mem {} {:.0}%",
+            text_bar(self.cpu / 100.0),
+            self.cpu,
+            text_bar(self.memory.percentage() / 100.0),
+            self.memory.percentage()
+        );
+        if let Some([one, five, fifteen]) = self.load_average {
+            let _ = write!(line, " load {one:.2} {five:.2} {fifteen:.2}");
+        }
+        if let Some(battery) = &self.battery {
+            let fraction = f64::from(battery.percentage) / 100.0;
+            let _ = write!(line, " bat {} {}%", text_bar(fraction), battery.percentage);
+        }
+        if let Some(uptime) = self.uptime {
+            let _ = write!(
+                line,
+                " up {} busy {:.0}%",
+                format_elapsed(uptime),
+                self.busy
+            );
+        }
+        line
+    }
+}
+
+/// A bar filled according to `fraction` (0.0 to 1.0), with hollow glyphs
+/// for the unfilled part since status bars may not have colors to dim it.
+fn text_bar(fraction: f64) -> String {
+    let symbols = symbols();
+    let filled = (fraction.clamp(0.0, 1.0) * BAR_LENGTH as f64).round() as usize;
+    symbols.bar.repeat(filled) + &symbols.hollow.repeat(BAR_LENGTH - filled)
+}
+
+/// Prints a status line to `output`, and another one every `interval` when
+/// there is one.
+pub fn run(
+    source: &dyn ProcSource,
+    power: &mut dyn PowerSource,
+    interval: Option<Duration>,
+    output: &mut impl Write,
+) -> Result<()> {
+    let mut before = CpuTicks::read(source)?;
+    std::thread::sleep(CPU_SAMPLE_INTERVAL);
+    loop {
+        let after = CpuTicks::read(source)?;
+        let line = StatusLine::read(source, power, &before, &after)?.line();
+        if let Err(e) = writeln!(output, "{line}").and_then(|()| output.flush()) {
+            // the status bar went away
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                return Ok(());
+            }
+            return Err(e.into());
+        }
+        let Some(interval) = interval else {
+            return Ok(());
+        };
+        before = after;
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use battery::State;
+
+    use super::*;
+    use crate::components::battery::PowerReading;
+    use crate::source::fake::FakeProcSource;
+
+    #[derive(Debug)]
+    struct FakePowerSource(Option<PowerReading>);
+
+    impl PowerSource for FakePowerSource {
+        fn read(&mut self) -> Option<PowerReading> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_text_bar() {
+        assert_eq!(text_bar(0.0), "□□□□□");
+        assert_eq!(text_bar(0.61), "■■■□□");
+        assert_eq!(text_bar(1.5), "■■■■■");
+    }
+
+    #[test]
+    fn test_status_line() {
+        let source = FakeProcSource::default();
+        let mut power = FakePowerSource(Some(PowerReading {
+            state: State::Discharging,
+            state_of_charge: 0.8,
+            charge_limit: None,
+            time_left: None,
+        }));
+        let ticks = CpuTicks::read(&source).unwrap();
+        let status = StatusLine::read(&source, &mut power, &ticks, &ticks).unwrap();
+        assert_eq!(
+            status.line(),
+            "cpu □□□□□ 0% mem ■□□□□ 25% load 1.50 1.00 0.50 bat ■■■■□ 80% up 16m40s busy 25%"
+        );
+        // an average over the time since boot
+        assert_eq!(status.busy, 25.0);
+
+        let source = FakeProcSource {
+            uptime: None,
+            load_average: None,
+            ..FakeProcSource::default()
+        };
+        let mut output = vec![];
+        run(&source, &mut FakePowerSource(None), None, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "cpu □□□□□ 0% mem ■□□□□ 25%\n"
+        );
+    }
+}
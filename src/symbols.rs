@@ -0,0 +1,191 @@
+//! The symbols the interface is drawn with: Unicode by default, or only
+//! ASCII for terminals and fonts that lack the glyphs and for screen readers.
+//! The borders stay box drawing characters either way.
+
+use std::sync::OnceLock;
+
+use ratatui::symbols::bar;
+
+static ASCII: OnceLock<bool> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbols {
+    /// The filled and the unfilled glyphs of a bar; Unicode dims the same
+    /// glyph.
+    pub bar: &'static str,
+    pub bar_empty: &'static str,
+    /// The unfilled glyph where there are no colors to dim it with.
+    pub hollow: &'static str,
+    /// From the lowest to the highest value of a sparkline.
+    pub sparks: [char; 8],
+    /// The glyphs of a sparkline widget more than a line high.
+    pub levels: bar::Set,
+    /// From none to all dots of a column of the cpu graph, for ASCII,
+    /// which has no braille to put two columns in a character.
+    pub graph: Option<[&'static str; 5]>,
+    /// For sorting in either direction and a battery charging or
+    /// discharging.
+    pub rising: &'static str,
+    pub falling: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+    pub left: &'static str,
+    pub right: &'static str,
+    /// In front of a marked process.
+    pub marker: &'static str,
+    /// Ends text that was cut to fit.
+    pub ellipsis: &'static str,
+    /// Between the ancestors of a process.
+    pub separator: &'static str,
+    /// Between the tabs and other items on a line.
+    pub divider: &'static str,
+    /// For a full battery and one in an unknown state.
+    pub full: &'static str,
+    pub unknown: &'static str,
+    pub paused: &'static str,
+    pub playing: &'static str,
+    /// Before a speed, e.g. `×2`.
+    pub times: &'static str,
+    /// Before a limit, e.g. `≤80%`.
+    pub at_most: &'static str,
+    /// For processes that can administer the system.
+    pub admin: &'static str,
+    /// On mains power.
+    pub plugged: &'static str,
+}
+
+pub const UNICODE: Symbols = Symbols {
+    bar: "■",
+    bar_empty: "■",
+    hollow: "□",
+    sparks: ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'],
+    levels: bar::NINE_LEVELS,
+    graph: None,
+    rising: "▲",
+    falling: "▼",
+    up: "↑",
+    down: "↓",
+    left: "←",
+    right: "→",
+    marker: "●",
+    ellipsis: "…",
+    separator: "›",
+    divider: "│",
+    full: "■",
+    unknown: "○",
+    paused: "⏸",
+    playing: "▶",
+    times: "×",
+    at_most: "≤",
+    admin: "⚷",
+    plugged: "⚡",
+};
+
+pub const ASCII_SYMBOLS: Symbols = Symbols {
+    bar: "#",
+    bar_empty: "-",
+    hollow: "-",
+    sparks: ['_', '.', ':', '-', '=', '+', '*', '#'],
+    levels: bar::Set {
+        full: "#",
+        seven_eighths: "#",
+        three_quarters: "*",
+        five_eighths: "+",
+        half: "=",
+        three_eighths: "-",
+        one_quarter: ":",
+        one_eighth: ".",
+        empty: " ",
+    },
+    graph: Some([" ", ".", "-", "=", "#"]),
+    rising: "^",
+    falling: "v",
+    up: "^",
+    down: "v",
+    left: "<",
+    right: ">",
+    marker: "*",
+    ellipsis: "~",
+    separator: ">",
+    divider: "|",
+    full: "=",
+    unknown: "?",
+    paused: "||",
+    playing: ">",
+    times: "x",
+    at_most: "<=",
+    admin: "!",
+    plugged: "~",
+};
+
+/// Chooses ASCII or Unicode; only the first call has an effect.
+pub fn init(ascii: bool) {
+    let _ = ASCII.set(ascii);
+}
+
+pub fn symbols() -> &'static Symbols {
+    if ASCII.get().copied().unwrap_or_default() {
+        &ASCII_SYMBOLS
+    } else {
+        &UNICODE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_symbols() {
+        let Symbols {
+            bar,
+            bar_empty,
+            hollow,
+            sparks,
+            levels,
+            graph,
+            rising,
+            falling,
+            up,
+            down,
+            left,
+            right,
+            marker,
+            ellipsis,
+            separator,
+            divider,
+            full,
+            unknown,
+            paused,
+            playing,
+            times,
+            at_most,
+            admin,
+            plugged,
+        } = ASCII_SYMBOLS;
+        let texts = [
+            bar, bar_empty, hollow, rising, falling, up, down, left, right, marker, ellipsis,
+            separator, divider, full, unknown, paused, playing, times, at_most, admin, plugged,
+        ];
+        assert!(texts.concat().is_ascii());
+        assert!(sparks.iter().all(char::is_ascii));
+        assert!(graph.unwrap().concat().is_ascii());
+        assert!([
+            levels.full,
+            levels.seven_eighths,
+            levels.three_quarters,
+            levels.five_eighths,
+            levels.half,
+            levels.three_eighths,
+            levels.one_quarter,
+            levels.one_eighth,
+            levels.empty,
+        ]
+        .concat()
+        .is_ascii());
+        // truncating counts on an ellipsis as wide as the Unicode one
+        assert_eq!(ellipsis.len(), 1);
+        // tests see the Unicode symbols
+        assert_eq!(symbols(), &UNICODE);
+    }
+}
@@ -0,0 +1,498 @@
+use procfs_core::{CpuTime, KernelStats, ProcError, ProcResult};
+use serde::Serialize;
+
+use crate::source::{ProcSource, SystemStat};
+
+/// Busy and total cpu ticks of the whole system since boot.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTicks {
+    pub busy: u64,
+    pub total: u64,
+}
+
+impl CpuTicks {
+    pub fn read(source: &dyn ProcSource) -> ProcResult<Self> {
+        Ok(Self::from(&source.kernel_stats()?.total))
+    }
+
+    /// The ticks of every core, ordered by number.
+    pub fn read_cores(source: &dyn ProcSource) -> ProcResult<Vec<Self>> {
+        Ok(Self::cores(&source.kernel_stats()?))
+    }
+
+    pub fn cores(stats: &KernelStats) -> Vec<Self> {
+        stats.cpu_time.iter().map(Self::from).collect()
+    }
+
+    /// The percentage of time the cpus were busy since an earlier reading.
+    pub fn usage_since(&self, earlier: &CpuTicks) -> f64 {
+        let total = self.total.saturating_sub(earlier.total);
+        if total == 0 {
+            return 0.0;
+        }
+        self.busy.saturating_sub(earlier.busy) as f64 * 100.0 / total as f64
+    }
+}
+
+impl From<&CpuTime> for CpuTicks {
+    fn from(cpu: &CpuTime) -> Self {
+        let idle = cpu.idle + cpu.iowait.unwrap_or_default();
+        let busy = cpu.user
+            + cpu.nice
+            + cpu.system
+            + cpu.irq.unwrap_or_default()
+            + cpu.softirq.unwrap_or_default()
+            + cpu.steal.unwrap_or_default();
+        Self {
+            busy,
+            total: busy + idle,
+        }
+    }
+}
+
+/// The context switches and interrupts of the whole system since boot.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ActivityCounters {
+    pub context_switches: u64,
+    pub interrupts: u64,
+}
+
+impl ActivityCounters {
+    /// `None` where the interrupts aren't counted.
+    pub fn of(stat: &SystemStat) -> Option<Self> {
+        Some(Self {
+            context_switches: stat.kernel.ctxt,
+            interrupts: stat.interrupts?,
+        })
+    }
+
+    /// The context switches and interrupts per second since an earlier
+    /// reading, `seconds` ago.
+    pub fn rates_since(&self, earlier: &ActivityCounters, seconds: f64) -> (f64, f64) {
+        if seconds <= 0.0 {
+            return (0.0, 0.0);
+        }
+        (
+            self.context_switches
+                .saturating_sub(earlier.context_switches) as f64
+                / seconds,
+            self.interrupts.saturating_sub(earlier.interrupts) as f64 / seconds,
+        )
+    }
+}
+
+/// Memory usage of the whole system, in bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MemoryUsage {
+    pub total: u64,
+    pub available: u64,
+}
+
+impl MemoryUsage {
+    pub fn read(source: &dyn ProcSource) -> ProcResult<Self> {
+        let meminfo = source.meminfo()?;
+        let available = meminfo
+            .mem_available
+            .unwrap_or(meminfo.mem_free + meminfo.buffers + meminfo.cached);
+        Ok(Self {
+            total: meminfo.mem_total,
+            available,
+        })
+    }
+
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.used() as f64 * 100.0 / self.total as f64
+    }
+}
+
+/// The memory the kernel sets aside for huge pages and keeps in its own
+/// caches, in bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KernelMemory {
+    /// The huge pages in the pool, those not allocated yet and those promised
+    /// to a mapping, counted in pages.
+    pub hugepages_total: u64,
+    pub hugepages_free: u64,
+    pub hugepages_reserved: u64,
+    pub hugepage_size: u64,
+    /// Transparent huge pages backing anonymous memory.
+    pub anon_hugepages: u64,
+    pub slab: u64,
+    /// The part of the slab the kernel can free under pressure.
+    pub slab_reclaimable: u64,
+}
+
+impl KernelMemory {
+    pub fn read(source: &dyn ProcSource) -> ProcResult<Self> {
+        let meminfo = source.meminfo()?;
+        Ok(Self {
+            hugepages_total: meminfo.hugepages_total.unwrap_or_default(),
+            hugepages_free: meminfo.hugepages_free.unwrap_or_default(),
+            hugepages_reserved: meminfo.hugepages_rsvd.unwrap_or_default(),
+            hugepage_size: meminfo.hugepagesize.unwrap_or_default(),
+            anon_hugepages: meminfo.anon_hugepages.unwrap_or_default(),
+            slab: meminfo.slab,
+            slab_reclaimable: meminfo.s_reclaimable.unwrap_or_default(),
+        })
+    }
+
+    /// The huge pages that are allocated, in pages.
+    pub fn hugepages_used(&self) -> u64 {
+        self.hugepages_total.saturating_sub(self.hugepages_free)
+    }
+}
+
+/// A cache of kernel objects of one kind, e.g. `dentry`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlabCache {
+    pub name: String,
+    /// The bytes of all its objects, in use or not.
+    pub bytes: u64,
+}
+
+impl SlabCache {
+    /// The `count` largest caches, the largest first.
+    pub fn read_largest(source: &dyn ProcSource, count: usize) -> ProcResult<Vec<Self>> {
+        let mut caches = parse_slabinfo(&source.slabinfo()?)?;
+        caches.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+        caches.truncate(count);
+        Ok(caches)
+    }
+}
+
+/// The caches of a `/proc/slabinfo`, where lines look like
+/// `dentry 81270 81270 192 21 1 : tunables 0 0 0 : slabdata 3870 3870 0`.
+fn parse_slabinfo(slabinfo: &str) -> ProcResult<Vec<SlabCache>> {
+    slabinfo
+        .lines()
+        .filter(|line| !line.starts_with("slabinfo") && !line.starts_with('#'))
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |index: usize| {
+                fields
+                    .get(index)
+                    .and_then(|field| field.parse::<u64>().ok())
+            };
+            match (fields.first(), number(2), number(3)) {
+                (Some(name), Some(objects), Some(size)) => Ok(SlabCache {
+                    name: name.to_string(),
+                    bytes: objects * size,
+                }),
+                _ => Err(ProcError::Incomplete(Some("/proc/slabinfo".into()))),
+            }
+        })
+        .collect()
+}
+
+/// The memory of a process beyond its resident pages, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessMemory {
+    pub rss: u64,
+    pub shared: u64,
+    /// The rest is only known from `smaps_rollup`: the proportional share
+    /// of the resident memory, which splits the shared pages between the
+    /// processes mapping them.
+    pub pss: Option<u64>,
+    pub private: Option<u64>,
+    pub swap: Option<u64>,
+}
+
+impl ProcessMemory {
+    /// Reads `/proc/<pid>/smaps_rollup`, or `statm` when that can't be read.
+    pub fn read(source: &dyn ProcSource, pid: i32) -> ProcResult<Self> {
+        if let Ok(memory) = source
+            .smaps_rollup(pid)
+            .and_then(|rollup| parse_smaps_rollup(&rollup))
+        {
+            return Ok(memory);
+        }
+        let statm = source.statm(pid)?;
+        let page_size = source.page_size();
+        Ok(Self {
+            rss: statm.resident * page_size,
+            shared: statm.shared * page_size,
+            ..Self::default()
+        })
+    }
+
+    /// Whether it was read from `smaps_rollup`.
+    pub fn is_complete(&self) -> bool {
+        self.pss.is_some()
+    }
+}
+
+/// The sums of a `/proc/<pid>/smaps_rollup`, whose values are in kB.
+pub fn parse_smaps_rollup(rollup: &str) -> ProcResult<ProcessMemory> {
+    let field = |name: &str| {
+        rollup.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            let kilobytes: u64 = value.trim().trim_end_matches("kB").trim().parse().ok()?;
+            Some(kilobytes * 1024)
+        })
+    };
+    let incomplete = || ProcError::Incomplete(Some("smaps_rollup".into()));
+    let sum = |a: &str, b: &str| Some(field(a)? + field(b)?);
+    Ok(ProcessMemory {
+        rss: field("Rss").ok_or_else(incomplete)?,
+        shared: sum("Shared_Clean", "Shared_Dirty").ok_or_else(incomplete)?,
+        pss: Some(field("Pss").ok_or_else(incomplete)?),
+        private: sum("Private_Clean", "Private_Dirty"),
+        swap: field("Swap"),
+    })
+}
+
+/// From this percentage of its limit on, a cgroup is near it.
+const CGROUP_NEAR_LIMIT: f64 = 90.0;
+
+/// The memory a process's cgroup v2 uses and may use, in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CgroupMemory {
+    pub cgroup: String,
+    pub current: u64,
+    /// `None` when the cgroup is not limited, `max` in `memory.max`.
+    pub max: Option<u64>,
+}
+
+impl CgroupMemory {
+    /// Fails for processes in the root cgroup, which has no memory files.
+    pub fn read(source: &dyn ProcSource, pid: i32) -> ProcResult<Self> {
+        let cgroup = source.cgroup(pid)?;
+        let parse = |name: &str| -> ProcResult<Option<u64>> {
+            let value = source.cgroup_file(&cgroup, name)?;
+            match value.trim() {
+                "max" => Ok(None),
+                bytes => bytes
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| ProcError::Incomplete(Some(name.into()))),
+            }
+        };
+        let current = parse("memory.current")?.unwrap_or_default();
+        let max = parse("memory.max")?;
+        Ok(Self {
+            cgroup,
+            current,
+            max,
+        })
+    }
+
+    pub fn percentage(&self) -> Option<f64> {
+        match self.max {
+            Some(0) | None => None,
+            Some(max) => Some(self.current as f64 * 100.0 / max as f64),
+        }
+    }
+
+    pub fn is_near_limit(&self) -> bool {
+        self.percentage()
+            .is_some_and(|percentage| percentage >= CGROUP_NEAR_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::{FakeProcSource, FakeProcess};
+
+    #[test]
+    fn test_cpu_ticks() {
+        let source = FakeProcSource::default();
+        let ticks = CpuTicks::read(&source).unwrap();
+        assert_eq!(
+            ticks,
+            CpuTicks {
+                busy: 2000,
+                total: 8000
+            }
+        );
+        assert_eq!(ticks.usage_since(&CpuTicks::default()), 25.0);
+        assert_eq!(ticks.usage_since(&ticks), 0.0);
+
+        let later = CpuTicks {
+            busy: 2100,
+            total: 8200,
+        };
+        assert_eq!(later.usage_since(&ticks), 50.0);
+    }
+
+    #[test]
+    fn test_core_ticks() {
+        let cores = CpuTicks::read_cores(&FakeProcSource::default()).unwrap();
+        assert_eq!(
+            cores,
+            vec![
+                CpuTicks {
+                    busy: 1000,
+                    total: 4000
+                };
+                2
+            ]
+        );
+    }
+
+    #[test]
+    fn test_activity_counters() {
+        let source = FakeProcSource::default();
+        let counters = ActivityCounters::of(&source.system_stat().unwrap()).unwrap();
+        assert_eq!(
+            counters,
+            ActivityCounters {
+                context_switches: 20_000,
+                interrupts: 1_000,
+            }
+        );
+        let later = ActivityCounters {
+            context_switches: 21_000,
+            interrupts: 1_500,
+        };
+        assert_eq!(later.rates_since(&counters, 0.5), (2_000.0, 1_000.0));
+        assert_eq!(later.rates_since(&counters, 0.0), (0.0, 0.0));
+        assert_eq!(crate::source::parse_interrupts("ctxt 1\n"), None);
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let source = FakeProcSource::default();
+        let memory = MemoryUsage::read(&source).unwrap();
+        assert_eq!(memory.total, 16 * 1024 * 1024 * 1024);
+        assert_eq!(memory.used(), 4 * 1024 * 1024 * 1024);
+        assert_eq!(memory.percentage(), 25.0);
+        assert_eq!(MemoryUsage::default().percentage(), 0.0);
+    }
+
+    #[test]
+    fn test_kernel_memory() {
+        let mut source = FakeProcSource::default();
+        source.meminfo += "SReclaimable:     196608 kB
+AnonHugePages:    131072 kB
+HugePages_Total:     512
+HugePages_Free:      510
+HugePages_Rsvd:        4
+Hugepagesize:       2048 kB
+";
+        let memory = KernelMemory::read(&source).unwrap();
+        assert_eq!(
+            memory,
+            KernelMemory {
+                hugepages_total: 512,
+                hugepages_free: 510,
+                hugepages_reserved: 4,
+                hugepage_size: 2 << 20,
+                anon_hugepages: 128 << 20,
+                slab: 256 << 20,
+                slab_reclaimable: 192 << 20,
+            }
+        );
+        assert_eq!(memory.hugepages_used(), 2);
+        assert_eq!(
+            KernelMemory::read(&FakeProcSource::default())
+                .unwrap()
+                .hugepages_total,
+            0
+        );
+    }
+
+    #[test]
+    fn test_slab_caches() {
+        let mut source = FakeProcSource::default();
+        assert!(matches!(
+            SlabCache::read_largest(&source, 2),
+            Err(ProcError::PermissionDenied(_))
+        ));
+
+        source.slabinfo = Some(
+            "slabinfo - version: 2.1
+# name            <active_objs> <num_objs> <objsize> <objperslab> <pagesperslab> : tunables <limit> <batchcount> <sharedfactor> : slabdata <active_slabs> <num_slabs> <sharedavail>
+kmalloc-64         12032  12032     64   64    1 : tunables    0    0    0 : slabdata    188    188      0
+dentry             81270  81438    192   21    1 : tunables    0    0    0 : slabdata   3878   3878      0
+inode_cache        20000  20034    624   26    4 : tunables    0    0    0 : slabdata    771    771      0
+"
+            .to_string(),
+        );
+        let caches = SlabCache::read_largest(&source, 2).unwrap();
+        assert_eq!(
+            caches,
+            vec![
+                SlabCache {
+                    name: "dentry".to_string(),
+                    bytes: 81_438 * 192,
+                },
+                SlabCache {
+                    name: "inode_cache".to_string(),
+                    bytes: 20_034 * 624,
+                },
+            ]
+        );
+        assert!(parse_slabinfo("dentry 1\n").is_err());
+    }
+
+    #[test]
+    fn test_process_memory() {
+        let mut source = FakeProcSource::default().with(7, FakeProcess::running(7, "db", 0, 0));
+        let memory = ProcessMemory::read(&source, 7).unwrap();
+        assert_eq!(
+            memory,
+            ProcessMemory {
+                rss: 2048 * 1024,
+                shared: 512 * 1024,
+                pss: Some(1792 * 1024),
+                private: Some(1536 * 1024),
+                swap: Some(128 * 1024),
+            }
+        );
+        assert!(memory.is_complete());
+
+        // another user's process, whose statm can still be read
+        source.processes.get_mut(&7).unwrap().smaps_rollup = None;
+        let memory = ProcessMemory::read(&source, 7).unwrap();
+        assert_eq!(
+            memory,
+            ProcessMemory {
+                rss: 2048 * 1024,
+                shared: 512 * 1024,
+                ..ProcessMemory::default()
+            }
+        );
+        assert!(!memory.is_complete());
+        assert!(ProcessMemory::read(&FakeProcSource::default(), 7).is_err());
+        assert!(parse_smaps_rollup("Rss: 4 kB\n").is_err());
+    }
+
+    #[test]
+    fn test_cgroup_memory() {
+        let mut web = FakeProcess::running(7, "web", 0, 0);
+        web.cgroup = Some("/docker/web".to_string());
+        let mut source = FakeProcSource::default()
+            .with(7, web)
+            .with(8, FakeProcess::running(8, "init", 0, 0));
+        for (name, value) in [
+            ("memory.current", "1000000000\n"),
+            ("memory.max", "1073741824\n"),
+        ] {
+            source
+                .cgroup_files
+                .insert(format!("/docker/web/{name}"), value.to_string());
+        }
+        let memory = CgroupMemory::read(&source, 7).unwrap();
+        assert_eq!(memory.cgroup, "/docker/web");
+        assert_eq!((memory.current, memory.max), (1_000_000_000, Some(1 << 30)));
+        assert!(memory.is_near_limit());
+
+        source
+            .cgroup_files
+            .insert("/docker/web/memory.max".to_string(), "max\n".to_string());
+        let memory = CgroupMemory::read(&source, 7).unwrap();
+        assert_eq!((memory.max, memory.percentage()), (None, None));
+        assert!(!memory.is_near_limit());
+
+        // the root cgroup has no memory files
+        assert!(CgroupMemory::read(&source, 8).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! The current view as plain sentences rather than a grid, written to a file
+//! or piped to a command such as a screen reader.
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crate::config::TextDumpConfig;
+use crate::utils::get_data_dir;
+
+/// Writes `text` where `config` says, returning what was done for a toast.
+/// A command is left running, and is sent the text on a thread, as it may
+/// read it slowly or not at all; its output is discarded, as it would garble
+/// the screen.
+pub fn dump(text: &str, config: &TextDumpConfig) -> io::Result<String> {
+    if let Some((program, args)) = config.command.split_first() {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let (stdin, text, reader) = (child.stdin.take(), text.to_string(), program.clone());
+        std::thread::spawn(move || {
+            if let Some(mut stdin) = stdin {
+                if let Err(e) = stdin.write_all(text.as_bytes()) {
+                    tracing::warn!("Unable to send the view to {reader}: {e}");
+                }
+                // closed, so the reader sees the end of the text
+            }
+            // reaped once it is done reading
+            child.wait()
+        });
+        return Ok(format!("Sent the view to {program}"));
+    }
+    let path = config
+        .path
+        .clone()
+        .unwrap_or_else(|| get_data_dir().join("view.txt"));
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    std::fs::write(&path, text)?;
+    Ok(format!("Wrote the view to {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump() {
+        let path = std::env::temp_dir().join(format!("brt-view-{}.txt", std::process::id()));
+        let config = TextDumpConfig {
+            command: vec![],
+            path: Some(path.clone()),
+        };
+        assert_eq!(
+            dump("34 processes.\n", &config).unwrap(),
+            format!("Wrote the view to {}", path.display())
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "34 processes.\n");
+        std::fs::remove_file(&path).unwrap();
+
+        let config = TextDumpConfig {
+            command: vec!["brt-no-such-reader".to_string()],
+            path: None,
+        };
+        assert_eq!(
+            dump("", &config).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        // more than fits in the pipe, to a reader that doesn't read
+        let config = TextDumpConfig {
+            command: vec!["sleep".to_string(), "2".to_string()],
+            path: None,
+        };
+        let started = std::time::Instant::now();
+        let text = "34 processes.\n".repeat(100_000);
+        assert_eq!(dump(&text, &config).unwrap(), "Sent the view to sleep");
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+}
@@ -1,13 +1,18 @@
 use std::{
+    io::Write,
     ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering},
     time::Duration,
 };
 
 use color_eyre::eyre::Result;
 use crossterm::{
     cursor,
-    event::{Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent},
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+    event::{
+        DisableFocusChange, EnableFocusChange, Event as CrosstermEvent, KeyEvent, KeyEventKind,
+        MouseEvent,
+    },
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, SetTitle},
 };
 use futures::{FutureExt, StreamExt};
 use ratatui::backend::CrosstermBackend as Backend;
@@ -20,6 +25,11 @@ use tokio_util::sync::CancellationToken;
 
 pub type Frame<'a> = ratatui::Frame<'a>;
 
+/// Whether the title of the terminal window was changed, the previous one
+/// being on the terminal's stack of titles until [`restore`], which the
+/// panic hook calls too.
+static TITLED: AtomicBool = AtomicBool::new(false);
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Event {
     Init,
@@ -43,6 +53,8 @@ pub struct Tui {
     pub event_rx: UnboundedReceiver<Event>,
     pub event_tx: UnboundedSender<Event>,
     pub frame_rate: f64,
+    /// The frame rate while the terminal doesn't have the focus.
+    pub unfocused_frame_rate: f64,
     pub tick_rate: f64,
 }
 
@@ -61,6 +73,7 @@ impl Tui {
             event_rx,
             event_tx,
             frame_rate,
+            unfocused_frame_rate: frame_rate,
             tick_rate,
         })
     }
@@ -73,9 +86,17 @@ impl Tui {
         self.frame_rate = frame_rate;
     }
 
+    /// Never faster than the frame rate, nor slower than a frame a minute.
+    /// A rate that isn't a number, or is infinite, is ignored.
+    pub fn unfocused_frame_rate(&mut self, frame_rate: f64) {
+        self.unfocused_frame_rate = unfocused_frame_rate(frame_rate, self.frame_rate);
+    }
+
     pub fn start(&mut self) {
         let tick_delay = std::time::Duration::from_secs_f64(1.0 / self.tick_rate);
-        let render_delay = std::time::Duration::from_secs_f64(1.0 / self.frame_rate);
+        let focused_render_delay = std::time::Duration::from_secs_f64(1.0 / self.frame_rate);
+        let unfocused_render_delay =
+            std::time::Duration::from_secs_f64(1.0 / self.unfocused_frame_rate);
         self.cancel();
         self.cancellation_token = CancellationToken::new();
         let _cancellation_token = self.cancellation_token.clone();
@@ -83,7 +104,7 @@ impl Tui {
         self.task = tokio::spawn(async move {
             let mut reader = crossterm::event::EventStream::new();
             let mut tick_interval = tokio::time::interval(tick_delay);
-            let mut render_interval = tokio::time::interval(render_delay);
+            let mut render_interval = tokio::time::interval(focused_render_delay);
             _event_tx.send(Event::Init).unwrap();
             loop {
                 let tick_delay = tick_interval.tick();
@@ -109,9 +130,13 @@ impl Tui {
                             _event_tx.send(Event::Resize(x, y)).unwrap();
                           },
                           CrosstermEvent::FocusLost => {
+                            // nobody is looking, so there's no need to draw
+                            // as often
+                            render_interval = tokio::time::interval(unfocused_render_delay);
                             _event_tx.send(Event::FocusLost).unwrap();
                           },
                           CrosstermEvent::FocusGained => {
+                            render_interval = tokio::time::interval(focused_render_delay);
                             _event_tx.send(Event::FocusGained).unwrap();
                           },
                           CrosstermEvent::Paste(s) => {
@@ -146,7 +171,7 @@ impl Tui {
                 self.task.abort();
             }
             if counter > 100 {
-                log::error!("Failed to abort task in 100 milliseconds for unknown reason");
+                tracing::error!("Failed to abort task in 100 milliseconds for unknown reason");
                 break;
             }
         }
@@ -155,19 +180,34 @@ impl Tui {
 
     pub fn enter(&mut self) -> Result<()> {
         crossterm::terminal::enable_raw_mode()?;
-        crossterm::execute!(std::io::stderr(), EnterAlternateScreen, cursor::Hide)?;
+        crossterm::execute!(
+            std::io::stderr(),
+            EnterAlternateScreen,
+            EnableFocusChange,
+            cursor::Hide
+        )?;
         self.start();
         Ok(())
     }
 
+    /// Sets the title of the terminal window, saving the previous one first.
+    pub fn set_title(&mut self, title: &str) -> Result<()> {
+        let mut stderr = std::io::stderr();
+        if !TITLED.load(Ordering::Relaxed) {
+            // XTWINOPS: push the window title on the stack
+            write!(stderr, "\x1b[22;2t")?;
+            TITLED.store(true, Ordering::Relaxed);
+        }
+        crossterm::execute!(stderr, SetTitle(title))?;
+        Ok(())
+    }
+
     pub fn exit(&mut self) -> Result<()> {
         self.stop()?;
         if crossterm::terminal::is_raw_mode_enabled()? {
             self.flush()?;
-            crossterm::execute!(std::io::stderr(), LeaveAlternateScreen, cursor::Show)?;
-            crossterm::terminal::disable_raw_mode()?;
         }
-        Ok(())
+        restore()
     }
 
     pub fn cancel(&self) {
@@ -191,6 +231,39 @@ impl Tui {
     }
 }
 
+/// `unfocused` within a frame a minute and the `focused` frame rate, or the
+/// latter when `unfocused` isn't finite.
+fn unfocused_frame_rate(unfocused: f64, focused: f64) -> f64 {
+    const SLOWEST: f64 = 1.0 / 60.0;
+    let fastest = focused.max(SLOWEST);
+    if unfocused.is_finite() {
+        unfocused.clamp(SLOWEST, fastest)
+    } else {
+        tracing::warn!("Ignoring the unfocused frame rate {unfocused}.");
+        fastest
+    }
+}
+
+/// Leaves the alternate screen and raw mode and puts the title of the
+/// terminal window back, also without a [`Tui`], e.g. from the panic hook,
+/// which may run outside of the runtime.
+pub fn restore() -> Result<()> {
+    if TITLED.swap(false, Ordering::Relaxed) {
+        // XTWINOPS: pop the window title from the stack
+        write!(std::io::stderr(), "\x1b[23;2t")?;
+    }
+    if crossterm::terminal::is_raw_mode_enabled()? {
+        crossterm::execute!(
+            std::io::stderr(),
+            DisableFocusChange,
+            LeaveAlternateScreen,
+            cursor::Show
+        )?;
+        crossterm::terminal::disable_raw_mode()?;
+    }
+    Ok(())
+}
+
 impl Deref for Tui {
     type Target = ratatui::Terminal<Backend<std::io::Stderr>>;
 
@@ -210,3 +283,18 @@ impl Drop for Tui {
         self.exit().unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unfocused_frame_rate() {
+        assert_eq!(unfocused_frame_rate(4.0, 60.0), 4.0);
+        assert_eq!(unfocused_frame_rate(120.0, 60.0), 60.0);
+        assert_eq!(unfocused_frame_rate(0.0, 60.0), 1.0 / 60.0);
+        for rate in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(unfocused_frame_rate(rate, 60.0), 60.0);
+        }
+    }
+}
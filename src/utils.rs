@@ -4,13 +4,9 @@ use color_eyre::eyre::Result;
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
 use tracing::error;
-use tracing_error::ErrorLayer;
-use tracing_subscriber::{
-    self, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt, Layer,
-};
 
 lazy_static! {
-    pub static ref PROJECT_NAME: String = env!("CARGO_CRATE_NAME").to_uppercase().to_string();
+    pub static ref PROJECT_NAME: String = env!("CARGO_PKG_NAME").to_uppercase().to_string();
     pub static ref DATA_FOLDER: Option<PathBuf> =
         std::env::var(format!("{}_DATA", PROJECT_NAME.clone()))
             .ok()
@@ -20,6 +16,7 @@ lazy_static! {
             .ok()
             .map(PathBuf::from);
     pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
+    pub static ref LOG_LEVEL_ENV: String = format!("{}_LOG_LEVEL", PROJECT_NAME.clone());
     pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
 
@@ -39,21 +36,25 @@ pub fn initialize_panic_handler() -> Result<()> {
         .into_hooks();
     eyre_hook.install()?;
     std::panic::set_hook(Box::new(move |panic_info| {
-        if let Ok(mut t) = crate::tui::Tui::new() {
-            if let Err(r) = t.exit() {
-                error!("Unable to exit Terminal: {:?}", r);
-            }
+        // the report is unreadable in raw mode on the alternate screen, and
+        // the shell is left broken
+        if let Err(e) = crate::tui::restore() {
+            error!("Unable to restore the terminal: {e:?}");
         }
+        let msg = format!("{}", panic_hook.panic_report(panic_info));
+        error!("Panicked: {}", strip_ansi_escapes::strip_str(msg));
 
         #[cfg(not(debug_assertions))]
         {
-            use human_panic::{metadata, setup_panic};
-            setup_panic!(metadata!()
+            use human_panic::{handle_dump, metadata, print_msg};
+            let meta = metadata!()
                 .authors(env!("CARGO_PKG_AUTHORS").replace(':', ", "))
-                .homepage(env!("CARGO_PKG_HOMEPAGE")));
+                .homepage(env!("CARGO_PKG_HOMEPAGE"));
+            let dump = handle_dump(&meta, panic_info);
+            if let Err(e) = print_msg(dump, &meta) {
+                error!("Unable to print the panic report: {e}");
+            }
         }
-        let msg = format!("{}", panic_hook.panic_report(panic_info));
-        log::error!("Error: {}", strip_ansi_escapes::strip_str(msg));
 
         #[cfg(debug_assertions)]
         {
@@ -92,31 +93,6 @@ pub fn get_config_dir() -> PathBuf {
     directory
 }
 
-pub fn initialize_logging() -> Result<()> {
-    let directory = get_data_dir();
-    std::fs::create_dir_all(directory.clone())?;
-    let log_path = directory.join(LOG_FILE.clone());
-    let log_file = std::fs::File::create(log_path)?;
-    std::env::set_var(
-        "RUST_LOG",
-        std::env::var("RUST_LOG")
-            .or_else(|_| std::env::var(LOG_ENV.clone()))
-            .unwrap_or_else(|_| format!("{}=info", env!("CARGO_CRATE_NAME"))),
-    );
-    let file_subscriber = tracing_subscriber::fmt::layer()
-        .with_file(true)
-        .with_line_number(true)
-        .with_writer(log_file)
-        .with_target(false)
-        .with_ansi(false)
-        .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
-    tracing_subscriber::registry()
-        .with(file_subscriber)
-        .with(ErrorLayer::default())
-        .init();
-    Ok(())
-}
-
 /// Similar to the `std::dbg!` macro, but generates `tracing` events rather
 /// than printing to stdout.
 ///
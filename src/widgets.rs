@@ -1 +1,5 @@
+pub mod bar;
 pub mod cpu_graph;
+pub mod heatmap;
+pub mod popup;
+pub mod sparkline;
@@ -0,0 +1,149 @@
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+use crate::symbols::symbols;
+
+pub const EMPTY_COLOR: Color = Color::DarkGray;
+
+/// From red to green, for values where more is better, like the battery charge.
+pub const RED_TO_GREEN: [Color; 10] = [
+    Color::Rgb(0xd4, 0x54, 0x54),
+    Color::Rgb(0xd8, 0x6a, 0x4c),
+    Color::Rgb(0xdc, 0x80, 0x44),
+    Color::Rgb(0xe0, 0x96, 0x3c),
+    Color::Rgb(0xe4, 0xac, 0x34),
+    Color::Rgb(0xc4, 0xb8, 0x3c),
+    Color::Rgb(0x9c, 0xc0, 0x44),
+    Color::Rgb(0x74, 0xc8, 0x4c),
+    Color::Rgb(0x44, 0xd4, 0x52),
+    Color::Rgb(0x0d, 0xe7, 0x56),
+];
+
+/// From green to red, for values where less is better, like the cpu load.
+pub const GREEN_TO_RED: [Color; 10] = [
+    Color::Rgb(0x0d, 0xe7, 0x56),
+    Color::Rgb(0x44, 0xd4, 0x52),
+    Color::Rgb(0x74, 0xc8, 0x4c),
+    Color::Rgb(0x9c, 0xc0, 0x44),
+    Color::Rgb(0xc4, 0xb8, 0x3c),
+    Color::Rgb(0xe4, 0xac, 0x34),
+    Color::Rgb(0xe0, 0x96, 0x3c),
+    Color::Rgb(0xdc, 0x80, 0x44),
+    Color::Rgb(0xd8, 0x6a, 0x4c),
+    Color::Rgb(0xd4, 0x54, 0x54),
+];
+
+/// A bar with one glyph per gradient color, filled according to `fraction`
+/// (0.0 to 1.0); the unfilled glyphs are dimmed.
+pub fn bar(fraction: f32, gradient: &[Color]) -> Line<'static> {
+    let symbols = symbols();
+    gradient_bar(
+        fraction,
+        gradient.len(),
+        gradient,
+        (symbols.bar, symbols.bar_empty),
+    )
+}
+
+/// A bar of `length` glyphs, the filled and the unfilled one of `glyphs`,
+/// filled according to `fraction` (0.0 to 1.0). The gradient is stretched
+/// or squeezed to the length, so the first glyph has its first color and the
+/// last glyph its last.
+pub fn gradient_bar(
+    fraction: f32,
+    length: usize,
+    gradient: &[Color],
+    (glyph, empty_glyph): (&str, &str),
+) -> Line<'static> {
+    let filled = (fraction.clamp(0.0, 1.0) * length as f32).round() as usize;
+    let last = gradient.len().saturating_sub(1);
+    let spans = (0..length)
+        .map(|i| {
+            if i >= filled {
+                return Span::styled(empty_glyph.to_string(), Style::default().fg(EMPTY_COLOR));
+            }
+            // rounded to the nearest color
+            let index = (i * last + (length - 1) / 2)
+                .checked_div(length - 1)
+                .unwrap_or(last);
+            let color = gradient.get(index).copied().unwrap_or_default();
+            Span::styled(glyph.to_string(), Style::default().fg(color))
+        })
+        .collect::<Vec<_>>();
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(fraction: f32) -> usize {
+        bar(fraction, &RED_TO_GREEN)
+            .spans
+            .iter()
+            .filter(|s| s.style.fg != Some(EMPTY_COLOR))
+            .count()
+    }
+
+    #[test]
+    fn test_bar_boundaries() {
+        assert_eq!(bar(0.5, &RED_TO_GREEN).spans.len(), 10);
+        assert_eq!(filled(-0.1), 0);
+        assert_eq!(filled(0.0), 0);
+        assert_eq!(filled(0.04), 0);
+        assert_eq!(filled(0.5), 5);
+        assert_eq!(filled(1.0), 10);
+        assert_eq!(filled(1.2), 10);
+    }
+
+    #[test]
+    fn test_gradient_bar() {
+        let colors = |line: Line| -> Vec<Option<Color>> {
+            line.spans.iter().map(|span| span.style.fg).collect()
+        };
+        let gradient = [Color::Red, Color::Yellow, Color::Green];
+        let line = gradient_bar(1.0, 5, &gradient, ("=", "."));
+        assert_eq!(line.to_string(), "=====");
+        assert_eq!(
+            colors(line),
+            [
+                Color::Red,
+                Color::Yellow,
+                Color::Yellow,
+                Color::Green,
+                Color::Green
+            ]
+            .map(Some)
+        );
+        let line = gradient_bar(0.5, 20, &RED_TO_GREEN, ("=", "."));
+        assert_eq!(
+            line.to_string(),
+            format!("{}{}", "=".repeat(10), ".".repeat(10))
+        );
+        assert_eq!(line.spans[9].style.fg, Some(RED_TO_GREEN[4]));
+
+        // squeezed, the last glyph still has the last color
+        let line = gradient_bar(1.0, 3, &RED_TO_GREEN, ("=", "."));
+        assert_eq!(
+            colors(line),
+            [RED_TO_GREEN[0], RED_TO_GREEN[5], RED_TO_GREEN[9]].map(Some)
+        );
+        assert_eq!(
+            colors(gradient_bar(1.0, 1, &gradient, ("=", "."))),
+            [Some(Color::Green)]
+        );
+        assert_eq!(gradient_bar(0.0, 0, &gradient, ("=", ".")).spans.len(), 0);
+        assert_eq!(
+            gradient_bar(1.0, 2, &[], ("=", ".")).spans[0].style.fg,
+            Some(Color::Reset)
+        );
+    }
+
+    #[test]
+    fn test_bar_gradient() {
+        let line = bar(0.2, &GREEN_TO_RED[..5]);
+        assert_eq!(line.spans.len(), 5);
+        assert_eq!(line.spans[0].style.fg, Some(GREEN_TO_RED[0]));
+        assert_eq!(line.spans[1].style.fg, Some(EMPTY_COLOR));
+    }
+}
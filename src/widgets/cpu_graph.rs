@@ -1,59 +1,126 @@
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
-use ratatui::{prelude::*, widgets::*};
+use ratatui::prelude::*;
 use std::collections::VecDeque;
 
+use crate::model::{get_points, GRAPH_THRESHOLDS};
+use crate::widgets::bar::GREEN_TO_RED;
+use crate::widgets::sparkline::{Sparkline, MAX_DOTS};
+
+/// The recent cpu usage of a process, in percent, as braille dots, two
+/// samples per character, each character colored from green to red by its
+/// highest sample.
 #[derive(Debug, Clone, PartialEq)]
 pub struct CpuGraph<'a> {
-    block: Option<Block<'a>>,
-    data: VecDeque<u64>,
+    cpus: &'a VecDeque<f64>,
+    thresholds: [f64; MAX_DOTS],
 }
 
-impl<'a> Default for CpuGraph<'a> {
-    fn default() -> CpuGraph<'a> {
-        CpuGraph {
-            block: None,
-            data: VecDeque::from(vec![0_u64, 25]),
+impl<'a> CpuGraph<'a> {
+    pub fn new(cpus: &'a VecDeque<f64>) -> Self {
+        Self {
+            cpus,
+            thresholds: GRAPH_THRESHOLDS,
         }
     }
-}
 
-impl<'a> CpuGraph<'a> {
-    /// Surrounds the `CpuGraph` with a [`Block`].
+    /// The usage from which a column has one, two, three and four dots.
     #[must_use = "method moves the value of self and returns the modified value"]
-    pub fn block(mut self, block: Block<'a>) -> Self {
-        self.block = Some(block);
+    pub fn thresholds(mut self, thresholds: [f64; MAX_DOTS]) -> Self {
+        self.thresholds = thresholds;
         self
     }
 
-    #[must_use = "method moves the value of self and returns the modified value"]
-    pub fn update(mut self, point: u64) -> Self {
-        self.data.push_back(point);
-        self.data.pop_front();
-        self
+    /// The graph as a line, e.g. for a table cell.
+    pub fn line(&self) -> Line<'static> {
+        let dots = self
+            .cpus
+            .iter()
+            .map(|cpu| get_points(*cpu, &self.thresholds))
+            .collect();
+        Sparkline::from_dots(dots).gradient(&GREEN_TO_RED).line()
     }
 }
 
 impl Widget for CpuGraph<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        self.render_ref(area, buf);
+        self.line().render(area, buf);
     }
 }
 
-impl WidgetRef for CpuGraph<'_> {
-    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
-        self.block.render_ref(area, buf);
-        let inner = self.block.inner_if_some(area);
-        self.render_cpu_graph(inner, buf);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_graph_colors() {
+        let cpus = VecDeque::from(vec![0.0, 0.0, 5.0, 0.0, 30.0, 60.0, 0.0, 90.0]);
+        let line = CpuGraph::new(&cpus).line();
+        let glyphs: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(glyphs, " ⡀⣴⢸");
+        let colors: Vec<_> = line.spans.iter().map(|s| s.style.fg.unwrap()).collect();
+        assert_eq!(
+            colors,
+            vec![
+                GREEN_TO_RED[0],
+                GREEN_TO_RED[0],
+                GREEN_TO_RED[6],
+                GREEN_TO_RED[9]
+            ]
+        );
     }
-}
 
-impl CpuGraph<'_> {
-    fn render_cpu_graph(&self, gpu_graph_area: Rect, buf: &mut Buffer) {
-        if gpu_graph_area.is_empty() {
-            return;
-        }
-        let label = Span::raw(format!("{}", self.data.len()));
-        buf.set_span(0, 0, &label, 1);
+    #[test]
+    fn test_cpu_graph_widget() {
+        let cpus = VecDeque::from(vec![90.0, 90.0, 0.5]);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 3, 1));
+        CpuGraph::new(&cpus).render(buf.area, &mut buf);
+        assert_eq!(buf.get(0, 0).symbol(), "⣿");
+        assert_eq!(buf.get(0, 0).fg, GREEN_TO_RED[9]);
+        assert_eq!(buf.get(1, 0).symbol(), "⡀");
+        assert_eq!(buf.get(2, 0).symbol(), " ");
+    }
+
+    fn glyphs(cpus: &[f64], thresholds: [f64; MAX_DOTS]) -> String {
+        let cpus = VecDeque::from(cpus.to_vec());
+        CpuGraph::new(&cpus)
+            .thresholds(thresholds)
+            .line()
+            .to_string()
+    }
+
+    #[test]
+    fn test_usage_series() {
+        // an idle daemon, rarely above a fraction of a percent
+        assert_eq!(
+            glyphs(&[0.0, 0.05, 0.3, 0.0, 0.0, 0.8], GRAPH_THRESHOLDS),
+            " ⡀⢀"
+        );
+        // an editor, busy while typing
+        assert_eq!(
+            glyphs(&[1.0, 4.0, 12.0, 25.0, 8.0, 2.0], GRAPH_THRESHOLDS),
+            "⣀⣠⣀"
+        );
+        // a build, pegged on some samples but not all
+        assert_eq!(
+            glyphs(&[35.0, 55.0, 80.0, 100.0, 65.0, 45.0], GRAPH_THRESHOLDS),
+            "⣴⣿⣦"
+        );
+        // a multithreaded process can use more than a core
+        assert_eq!(glyphs(&[350.0, 0.0], GRAPH_THRESHOLDS), "⡇");
+        // lower thresholds for a machine that is mostly idle
+        assert_eq!(glyphs(&[1.0, 4.0, 12.0, 25.0], [0.1, 2.0, 5.0, 10.0]), "⣠⣿");
+    }
+
+    #[test]
+    fn test_get_points() {
+        assert_eq!(get_points(0.0, &GRAPH_THRESHOLDS), 0);
+        assert_eq!(get_points(0.1, &GRAPH_THRESHOLDS), 1);
+        assert_eq!(get_points(19.9, &GRAPH_THRESHOLDS), 1);
+        assert_eq!(get_points(20.0, &GRAPH_THRESHOLDS), 2);
+        assert_eq!(get_points(69.9, &GRAPH_THRESHOLDS), 3);
+        assert_eq!(get_points(70.0, &GRAPH_THRESHOLDS), 4);
+        assert_eq!(get_points(-1.0, &GRAPH_THRESHOLDS), 0);
+        assert_eq!(get_points(f64::NAN, &GRAPH_THRESHOLDS), 0);
     }
 }
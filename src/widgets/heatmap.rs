@@ -0,0 +1,121 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::prelude::*;
+
+use crate::symbols::symbols;
+use crate::widgets::bar::GREEN_TO_RED;
+
+/// The width of a cell, its glyph and a space.
+const CELL_WIDTH: u16 = 2;
+
+/// The load of many cores as a grid of glyphs, left to right and top to
+/// bottom, each colored from green to red by the usage of its core. The
+/// cores that don't fit are counted at the end instead, e.g. `+12`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heatmap<'a> {
+    /// The usage of every core, in percent.
+    usages: &'a [u64],
+}
+
+impl<'a> Heatmap<'a> {
+    pub fn new(usages: &'a [u64]) -> Self {
+        Self { usages }
+    }
+
+    /// The number of cells in a row of this width.
+    pub fn columns(width: u16) -> usize {
+        (width / CELL_WIDTH).max(1) as usize
+    }
+}
+
+/// Spreads the usages over the gradient, from green when idle to red when
+/// fully busy.
+fn color(usage: u64) -> Color {
+    let index = usage.min(100) as usize * GREEN_TO_RED.len() / 101;
+    GREEN_TO_RED[index]
+}
+
+/// The number of cores shown of `cores` in `cells` cells, and the marker
+/// counting the others in the cells left, if any.
+fn overflow(cores: usize, cells: usize) -> (usize, Option<String>) {
+    if cores <= cells {
+        return (cores, None);
+    }
+    // the marker takes more cells as the number it shows grows
+    let mut taken = 1;
+    loop {
+        let shown = cells.saturating_sub(taken);
+        let marker = format!("+{}", cores - shown);
+        let needed = marker.len().div_ceil(CELL_WIDTH as usize);
+        if needed <= taken || shown == 0 {
+            return (shown, Some(marker));
+        }
+        taken = needed;
+    }
+}
+
+impl Widget for Heatmap<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let columns = Self::columns(area.width);
+        let cells = columns * area.height as usize;
+        let position = |i: usize| {
+            let x = area.x + (i % columns) as u16 * CELL_WIDTH;
+            let y = area.y + (i / columns) as u16;
+            (x, y)
+        };
+        let (shown, marker) = overflow(self.usages.len(), cells);
+        for (i, usage) in self.usages.iter().take(shown).enumerate() {
+            let (x, y) = position(i);
+            buf.set_string(x, y, symbols().bar, Style::default().fg(color(*usage)));
+        }
+        if let Some(marker) = marker {
+            let (x, y) = position(shown);
+            let width = area.right().saturating_sub(x) as usize;
+            buf.set_stringn(x, y, marker, width, Style::default().dim());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heatmap_colors() {
+        assert_eq!(color(0), GREEN_TO_RED[0]);
+        assert_eq!(color(50), GREEN_TO_RED[4]);
+        assert_eq!(color(100), GREEN_TO_RED[9]);
+        assert_eq!(color(120), GREEN_TO_RED[9]);
+    }
+
+    #[test]
+    fn test_heatmap_widget() {
+        let usages = [0, 100, 50, 0, 100];
+        let mut buf = Buffer::empty(Rect::new(0, 0, 5, 2));
+        Heatmap::new(&usages).render(buf.area, &mut buf);
+        let row = |y: u16| -> String { (0..5).map(|x| buf.get(x, y).symbol()).collect() };
+        assert_eq!(row(0), "■ ■  ");
+        // the fourth and fifth cores don't fit
+        assert_eq!(row(1), "■ +2 ");
+        assert_eq!(buf.get(2, 0).fg, GREEN_TO_RED[9]);
+        assert_eq!(buf.get(0, 1).fg, GREEN_TO_RED[4]);
+
+        let usages = [0; 3];
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 1));
+        Heatmap::new(&usages).render(buf.area, &mut buf);
+        assert_eq!(
+            buf.content().iter().map(|c| c.symbol()).collect::<String>(),
+            "■ ■ ■ "
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(overflow(4, 4), (4, None));
+        assert_eq!(overflow(5, 4), (3, Some("+2".to_string())));
+        // a marker of three characters takes two cells
+        assert_eq!(overflow(100, 16), (14, Some("+86".to_string())));
+        assert_eq!(overflow(20, 8), (6, Some("+14".to_string())));
+        assert_eq!(overflow(3, 0), (0, Some("+3".to_string())));
+    }
+}
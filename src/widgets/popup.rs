@@ -0,0 +1,75 @@
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+
+/// A bordered box, sized to its text and centered over the area it is
+/// rendered in.
+#[derive(Debug, Clone)]
+pub struct Popup<'a> {
+    title: Line<'a>,
+    text: Text<'a>,
+    style: Style,
+}
+
+impl<'a> Popup<'a> {
+    pub fn new(title: impl Into<Line<'a>>, text: impl Into<Text<'a>>) -> Self {
+        Self {
+            title: title.into(),
+            text: text.into(),
+            style: Style::default(),
+        }
+    }
+
+    /// The style of the borders and the title, e.g. red for a warning.
+    #[must_use = "method moves the value of self and returns the modified value"]
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// The centered area, including the borders and a space of padding.
+    fn area(&self, area: Rect) -> Rect {
+        let width = (self.text.width().max(self.title.width()) as u16 + 4).min(area.width);
+        let height = (self.text.height() as u16 + 2).min(area.height);
+        Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        }
+    }
+}
+
+impl Widget for Popup<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = self.area(area);
+        let block = Block::default()
+            .title(self.title.alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(self.style)
+            .padding(ratatui::widgets::Padding::horizontal(1));
+        Clear.render(area, buf);
+        Paragraph::new(self.text).block(block).render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_popup_is_centered() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 5));
+        Popup::new("ok?", "yes").render(buf.area, &mut buf);
+        assert_eq!(
+            buf,
+            Buffer::with_lines(vec![
+                "                    ",
+                "      ╭─ok?─╮       ",
+                "      │ yes │       ",
+                "      ╰─────╯       ",
+                "                    ",
+            ])
+        );
+    }
+}
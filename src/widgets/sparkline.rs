@@ -0,0 +1,205 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::prelude::*;
+
+use crate::symbols::symbols;
+
+/// The number of dots in a full column of a braille character.
+pub const MAX_DOTS: usize = 4;
+
+/// The braille characters by the dots in their left and their right column,
+/// filled from the bottom.
+const BRAILLE: [[&str; MAX_DOTS + 1]; MAX_DOTS + 1] = [
+    [" ", "⢀", "⢠", "⢰", "⢸"],
+    ["⡀", "⣀", "⣠", "⣰", "⣸"],
+    ["⡄", "⣄", "⣤", "⣴", "⣼"],
+    ["⡆", "⣆", "⣦", "⣶", "⣾"],
+    ["⡇", "⣇", "⣧", "⣷", "⣿"],
+];
+
+/// The braille character with `first` dots in the left column and `second`
+/// in the right one, both up to [`MAX_DOTS`]; in ASCII the most dots of the
+/// two.
+pub fn braille(first: usize, second: usize) -> &'static str {
+    let (first, second) = (first.min(MAX_DOTS), second.min(MAX_DOTS));
+    match symbols().graph {
+        Some(graph) => graph[first.max(second)],
+        None => BRAILLE[first][second],
+    }
+}
+
+/// The dots of `value` scaled between `min` and `max`: none at or below
+/// `min` and at least one above it, so that small values still show.
+pub fn dots(value: f64, min: f64, max: f64) -> usize {
+    if value.is_nan() || value <= min {
+        return 0;
+    }
+    let range = max - min;
+    if range <= 0.0 {
+        return MAX_DOTS;
+    }
+    let fraction = ((value - min) / range).min(1.0);
+    ((fraction * MAX_DOTS as f64).ceil() as usize).clamp(1, MAX_DOTS)
+}
+
+/// A series of any length as braille dots, two values per character. Each
+/// character can be colored by its highest column, spreading the gradient
+/// from a single dot to a full column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sparkline<'a> {
+    /// The dots of every value, up to [`MAX_DOTS`].
+    dots: Vec<usize>,
+    gradient: &'a [Color],
+}
+
+impl<'a> Sparkline<'a> {
+    /// Scales the values between `min` and `max`.
+    pub fn new(values: &[f64], min: f64, max: f64) -> Self {
+        Self::from_dots(values.iter().map(|value| dots(*value, min, max)).collect())
+    }
+
+    /// Scales the values between zero and the highest of them.
+    pub fn scaled(values: &[f64]) -> Self {
+        let max = values.iter().copied().fold(0.0, f64::max);
+        Self::new(values, 0.0, max)
+    }
+
+    /// For values that were already turned into dots.
+    pub fn from_dots(dots: Vec<usize>) -> Self {
+        Self {
+            dots: dots.into_iter().map(|dots| dots.min(MAX_DOTS)).collect(),
+            gradient: &[],
+        }
+    }
+
+    #[must_use = "method moves the value of self and returns the modified value"]
+    pub fn gradient(mut self, gradient: &'a [Color]) -> Self {
+        self.gradient = gradient;
+        self
+    }
+
+    /// The color of a character with `dots` in its highest column.
+    fn color(&self, dots: usize) -> Option<Color> {
+        let last = self.gradient.len().checked_sub(1)?;
+        let index = (dots.max(1) - 1) * last / (MAX_DOTS - 1);
+        Some(self.gradient[index.min(last)])
+    }
+
+    /// The sparkline as a line, e.g. for a table cell.
+    pub fn line(&self) -> Line<'static> {
+        let spans = self
+            .dots
+            .chunks(2)
+            .map(|pair| {
+                let first = pair[0];
+                let second = pair.get(1).copied().unwrap_or_default();
+                let style = match self.color(first.max(second)) {
+                    Some(color) => Style::default().fg(color),
+                    None => Style::default(),
+                };
+                Span::styled(braille(first, second), style)
+            })
+            .collect::<Vec<_>>();
+        Line::from(spans)
+    }
+}
+
+impl Widget for Sparkline<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.line().render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widgets::bar::GREEN_TO_RED;
+
+    fn glyphs(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_braille() {
+        assert_eq!(braille(0, 0), " ");
+        assert_eq!(braille(0, 4), "⢸");
+        assert_eq!(braille(4, 0), "⡇");
+        assert_eq!(braille(2, 3), "⣴");
+        assert_eq!(braille(4, 4), "⣿");
+        // more dots than fit
+        assert_eq!(braille(9, 1), "⣇");
+    }
+
+    #[test]
+    fn test_braille_table() {
+        // the dots are the bits of the braille block starting at U+2800,
+        // here from the bottom of each column up
+        let bits = |c: &str| c.chars().next().unwrap() as u32 - 0x2800;
+        let left = [0, 0x40, 0x44, 0x46, 0x47];
+        let right = [0, 0x80, 0xa0, 0xb0, 0xb8];
+        for first in 0..=MAX_DOTS {
+            for second in 0..=MAX_DOTS {
+                let glyph = BRAILLE[first][second];
+                let expected = left[first] | right[second];
+                if expected == 0 {
+                    assert_eq!(glyph, " ");
+                } else {
+                    assert_eq!(bits(glyph), expected, "{first} {second}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dots() {
+        assert_eq!(dots(0.0, 0.0, 100.0), 0);
+        assert_eq!(dots(-5.0, 0.0, 100.0), 0);
+        assert_eq!(dots(0.1, 0.0, 100.0), 1);
+        assert_eq!(dots(25.0, 0.0, 100.0), 1);
+        assert_eq!(dots(26.0, 0.0, 100.0), 2);
+        assert_eq!(dots(100.0, 0.0, 100.0), 4);
+        assert_eq!(dots(250.0, 0.0, 100.0), 4);
+        assert_eq!(dots(15.0, 10.0, 20.0), 2);
+        assert_eq!(dots(f64::NAN, 0.0, 100.0), 0);
+        // a flat series above the minimum
+        assert_eq!(dots(5.0, 0.0, 0.0), 4);
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let line = Sparkline::new(&[0.0, 100.0, 50.0, 50.0, 10.0], 0.0, 100.0).line();
+        assert_eq!(glyphs(&line), "⢸⣤⡀");
+        assert!(line.spans.iter().all(|span| span.style.fg.is_none()));
+
+        let line = Sparkline::scaled(&[1.0, 2.0, 4.0, 8.0]).line();
+        assert_eq!(glyphs(&line), "⣀⣼");
+        assert_eq!(Sparkline::scaled(&[]).line(), Line::default());
+        assert_eq!(glyphs(&Sparkline::scaled(&[0.0, 0.0]).line()), " ");
+    }
+
+    #[test]
+    fn test_sparkline_colors() {
+        let line = Sparkline::from_dots(vec![0, 0, 1, 0, 2, 3, 0, 4])
+            .gradient(&GREEN_TO_RED)
+            .line();
+        assert_eq!(glyphs(&line), " ⡀⣴⢸");
+        let colors: Vec<_> = line.spans.iter().map(|s| s.style.fg.unwrap()).collect();
+        assert_eq!(
+            colors,
+            vec![
+                GREEN_TO_RED[0],
+                GREEN_TO_RED[0],
+                GREEN_TO_RED[6],
+                GREEN_TO_RED[9]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sparkline_widget() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 2, 1));
+        Sparkline::from_dots(vec![4, 4, 1]).render(buf.area, &mut buf);
+        assert_eq!(buf.get(0, 0).symbol(), "⣿");
+        assert_eq!(buf.get(1, 0).symbol(), "⡀");
+    }
+}
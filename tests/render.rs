@@ -0,0 +1,215 @@
+//! Renders the components, and the whole app, with fake sources to a
+//! [`TestBackend`] and compares the result with the snapshots in
+//! `tests/snapshots`. Run `cargo insta review` after an intended change.
+
+use std::time::{Duration, SystemTime};
+
+use battery::State;
+use insta::assert_snapshot;
+use ratatui::{backend::TestBackend, Terminal};
+use tokio::sync::mpsc;
+
+use brt_core::action::{Action, TopProcesses, UpdatePayload};
+use brt_core::app::{App, Mode, Pane, Tab};
+use brt_core::collector::Collectors;
+use brt_core::components::battery::{Battery, PowerReading, PowerSource};
+use brt_core::components::fps::{FpsCounter, SelfUsage};
+use brt_core::components::header::Header;
+use brt_core::components::process::Process;
+use brt_core::components::tabs::TabBar;
+use brt_core::components::Component;
+use brt_core::config::Config;
+use brt_core::source::fake::{FakeProcSource, FakeProcess};
+use brt_core::tui::Frame;
+
+#[derive(Debug)]
+struct FakePowerSource(PowerReading);
+
+impl PowerSource for FakePowerSource {
+    fn read(&mut self) -> Option<PowerReading> {
+        Some(self.0)
+    }
+}
+
+fn power(state: State, state_of_charge: f32) -> Box<FakePowerSource> {
+    Box::new(FakePowerSource(PowerReading {
+        state,
+        state_of_charge,
+        charge_limit: None,
+        time_left: None,
+    }))
+}
+
+fn source() -> FakeProcSource {
+    // the user of uid 1000 differs between machines
+    let zombie = FakeProcess {
+        uid: Some(0),
+        ..FakeProcess::zombie(2, "defunct")
+    };
+    let mut source = FakeProcSource::default().with(2, zombie);
+    for (pid, comm) in [(10, "init"), (11, "sshd"), (12, "postgres"), (13, "nginx")] {
+        source = source.with(pid, FakeProcess::running(pid, comm, pid as u64 * 100, 0));
+    }
+    source
+}
+
+fn render(width: u16, height: u16, draw: impl FnOnce(&mut Frame<'_>)) -> TestBackend {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(draw).unwrap();
+    terminal.backend().clone()
+}
+
+/// Hides what changes between runs: the clock, the version and the boot
+/// time in the local time zone.
+macro_rules! assert_screen {
+    ($name:expr, $backend:expr) => {
+        insta::with_settings!({filters => vec![
+            (r"\d{4}-\d\d-\d\d \d\d:\d\d", "[boot time]"),
+            (r"\d\d:\d\d:\d\d(\.\d{3})?", "[clock]"),
+            (env!("CARGO_PKG_VERSION"), "[version]"),
+        ]}, {
+            assert_snapshot!($name, $backend);
+        });
+    };
+}
+
+#[test]
+fn test_header() {
+    let mut header = Header::with_sources(Box::new(source()), power(State::Discharging, 0.8));
+    header.init().unwrap();
+    assert_screen!(
+        "header",
+        render(120, 1, |f| header.draw(f, f.size()).unwrap())
+    );
+    assert_screen!(
+        "compact_header",
+        render(60, 1, |f| header.draw(f, f.size()).unwrap())
+    );
+}
+
+#[test]
+fn test_battery_line() {
+    for (name, state, charge) in [
+        ("battery_charging", State::Charging, 0.42),
+        ("battery_low", State::Discharging, 0.15),
+    ] {
+        let mut battery = Battery::with_source(power(state, charge));
+        battery.update(Action::Tick).unwrap();
+        let backend = render(20, 1, |f| f.render_widget(battery.line(), f.size()));
+        assert_snapshot!(name, backend);
+    }
+}
+
+#[test]
+fn test_process_table() {
+    let mut process = Process::with_source(Box::new(source()));
+    process.list_processes();
+    process.wait_for_list();
+    process.update(Action::Down).unwrap();
+    process.update(Action::ToggleMark).unwrap();
+    assert_snapshot!(
+        "process_table",
+        render(100, 10, |f| process.draw(f, f.size()).unwrap())
+    );
+
+    process.update(Action::SortBy(2)).unwrap();
+    process.update(Action::SortBy(2)).unwrap();
+    process.wait_for_list();
+    process.update(Action::Kill).unwrap();
+    assert_snapshot!(
+        "process_table_kill",
+        render(100, 10, |f| process.draw(f, f.size()).unwrap())
+    );
+}
+
+#[test]
+fn test_footer() {
+    let mut footer = FpsCounter::with_usage(SelfUsage::with_source(Box::new(source()), 10));
+    footer
+        .update(Action::Update(UpdatePayload {
+            timestamp: SystemTime::now(),
+            duration: Duration::from_micros(4_300),
+            processes: 5,
+            started: 2,
+            exited: 1,
+            restricted: 0,
+            top: TopProcesses::default(),
+        }))
+        .unwrap();
+    assert_snapshot!(
+        "footer",
+        render(100, 1, |f| footer.draw(f, f.size()).unwrap())
+    );
+}
+
+#[test]
+fn test_app() {
+    let mut app = App {
+        config: Config::default(),
+        tick_rate: 4.0,
+        frame_rate: 60.0,
+        components: vec![
+            (
+                Pane::Header,
+                None,
+                Box::new(Header::with_sources(
+                    Box::new(source()),
+                    power(State::Full, 1.0),
+                )),
+            ),
+            (Pane::Header, None, Box::new(TabBar::new())),
+        ],
+        tab: Tab::Processes,
+        dirty: true,
+        should_quit: false,
+        should_suspend: false,
+        mode: Mode::Process,
+        last_tick_key_events: vec![],
+        rpc: None,
+        title: None,
+        print_on_exit: vec![],
+        collectors: Collectors::new(),
+        action_recorder: None,
+        action_replay: None,
+    };
+    for (_, _, component) in app.components.iter_mut() {
+        component.init().unwrap();
+        for action in [Action::Tick, Action::Right, Action::Down] {
+            component.update(action).unwrap();
+        }
+    }
+    // the table shows the sorted list before the selection moves
+    let mut process = Process::with_source(Box::new(source()));
+    process.init().unwrap();
+    process.update(Action::Tick).unwrap();
+    process.update(Action::Right).unwrap();
+    process.wait_for_list();
+    process.update(Action::Down).unwrap();
+    app.components
+        .push((Pane::Main, Some(Tab::Processes), Box::new(process)));
+    let (action_tx, _action_rx) = mpsc::unbounded_channel();
+    assert_screen!("app", render(100, 12, |f| app.draw(f, &action_tx)));
+
+    // above the keys, with an age that depends on how fast the test runs
+    app.config.config.staleness = true;
+    let backend = render(100, 12, |f| app.draw(f, &action_tx));
+    let rows: Vec<String> = backend
+        .buffer()
+        .content()
+        .chunks(100)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+        .collect();
+    assert!(rows[10].starts_with("proc 0."), "{}", rows[10]);
+    assert!(rows[11].starts_with("enter details"));
+
+    // the process table isn't drawn without the main pane
+    app.config.config.layout = Some("rows(header, footer)".parse().unwrap());
+    let backend = render(100, 12, |f| app.draw(f, &action_tx));
+    let text: String = backend
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect();
+    assert!(!text.contains("Pid:"), "{text}");
+}